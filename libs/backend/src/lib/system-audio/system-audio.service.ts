@@ -48,7 +48,12 @@ export class SystemAudioService extends EventEmitter implements OnModuleDestroy
     let chunkCount = 0;
     try {
       nativeAudio.startCapture((err: Error | null, chunk: Buffer) => {
-        if (err || !chunk) return;
+        if (err) {
+          // Non-fatal warnings from the native side (e.g. sustained clipping)
+          console.warn('[SystemAudio] Native warning:', err.message);
+          return;
+        }
+        if (!chunk) return;
         chunkCount++;
         const samples = new Int16Array(
           chunk.buffer,
@@ -8,10 +8,23 @@ fn main() {
     println!("cargo:rustc-link-lib=framework=AudioToolbox");
     println!("cargo:rustc-link-lib=framework=CoreFoundation");
     println!("cargo:rustc-link-lib=framework=CoreGraphics");
-    println!("cargo:rustc-link-lib=framework=ScreenCaptureKit");
+    // Weak-linked so the module still loads where ScreenCaptureKit is missing;
+    // start_capture then reports FrameworkUnavailable instead of dyld aborting
+    println!("cargo:rustc-link-arg=-Wl,-weak_framework,ScreenCaptureKit");
     println!("cargo:rustc-link-lib=framework=CoreMedia");
     println!("cargo:rustc-link-lib=framework=Foundation");
 
+    // Record the SDK version for build_info()
+    if let Ok(output) = std::process::Command::new("xcrun")
+        .args(["--sdk", "macosx", "--show-sdk-version"])
+        .output()
+    {
+        if output.status.success() {
+            let version = String::from_utf8_lossy(&output.stdout);
+            println!("cargo:rustc-env=VOXTAPE_SDK_VERSION={}", version.trim());
+        }
+    }
+
     // Compile ObjC bridge for safe CATapDescription creation
     cc::Build::new()
         .file("src/objc_bridge.m")
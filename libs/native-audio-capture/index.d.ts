@@ -1,5 +1,590 @@
 /* auto-generated by NAPI-RS */
 /* eslint-disable */
+/**
+ * Add an output to the running capture, e.g. 48kHz stereo for archival next
+ * to the main 16kHz mono stream. The raw input is resampled separately for
+ * each output; `callback` receives interleaved samples in the requested
+ * format. Outputs end with the capture. Returns an id for
+ * `remove_capture_output`.
+ */
+export declare function addCaptureOutput(callback: ((err: Error | null, arg: Buffer) => any), config: OutputConfig): number
+
+/** An audio device that can be captured with `backend: "device"` */
+export interface AudioDeviceInfo {
+  /**
+   * CoreAudio device id, for `CaptureOptions.deviceId`. Only valid until
+   * the device is unplugged or the system restarts; use `uid` to remember it
+   */
+  id: number
+  /** Persistent identifier */
+  uid: string
+  /** Device name as shown in Sound settings */
+  name: string
+  /**
+   * Transport, as in `OutputDeviceInfo.transport` ("virtual" for most
+   * loopback drivers, "aggregate" for aggregate devices)
+   */
+  transport: string
+  inputChannels: number
+  outputChannels: number
+  /** Nominal sample rate in Hz (0 if unknown) */
+  sampleRate: number
+  /** Whether this is the system default for its direction */
+  isDefault: boolean
+}
+
+/**
+ * Let the crate start and stop capture on its own based on meeting-app
+ * presence. `callback` receives audio exactly as with `start_capture`;
+ * `events` receives lifecycle events. Call `stop_auto_capture` to end it.
+ */
+export declare function autoCapture(callback: ((err: Error | null, arg: Buffer) => any), events: ((err: Error | null, arg: AutoCaptureEvent) => any), options?: AutoCaptureOptions | undefined | null): void
+
+/** Lifecycle event emitted by `auto_capture` */
+export interface AutoCaptureEvent {
+  /** "started", "stopped" or "error" */
+  kind: string
+  /** Bundle identifier of the meeting app that triggered the event, if any */
+  bundleId?: string
+  /** Human-readable app name, if any */
+  name?: string
+  /** Error description for "error" events */
+  message?: string
+  /**
+   * Capture session started or stopped by this event. Every start gets a
+   * new id, so consumers can split recordings at each restart instead of
+   * splicing sessions together
+   */
+  sessionId?: number
+}
+
+/** Settings for `auto_capture` */
+export interface AutoCaptureOptions {
+  /** How often to check for meeting apps (default 1000ms) */
+  pollIntervalMs?: number
+  /** How long a meeting app must be present before capture starts (default 2000ms) */
+  startDelayMs?: number
+  /** How long no meeting app may be present before capture stops (default 10000ms) */
+  stopGraceMs?: number
+  /** Only count meeting apps that are frontmost (default true) */
+  requireForeground?: boolean
+  /**
+   * Options applied to each capture session. Recorded files are titled
+   * after the meeting app unless `capture.metadata.title` is set
+   */
+  capture?: CaptureOptions
+}
+
+/** Report which build of the native module is loaded. */
+export declare function buildInfo(): BuildInfo
+
+/** Identifies the loaded native binary */
+export interface BuildInfo {
+  /** Crate version */
+  version: string
+  /** macOS SDK version the bridge was compiled against, if known */
+  sdkVersion?: string
+  /** Capture backends compiled in ("sck", "tap" and "device" on macOS) */
+  backends: Array<string>
+  /** Whether this is a debug build */
+  debug: boolean
+}
+
+/**
+ * Check whether `start_capture` can run right now without macOS showing a
+ * permission prompt: the OS supports system audio capture and Screen
+ * Recording access is already granted. Uses only the preflight check, which
+ * never shows UI, so it's safe for a silent capability probe at launch
+ * (e.g. to decide whether to show a rationale screen before requesting).
+ */
+export declare function canCaptureWithoutPrompt(): boolean
+
+/**
+ * Report which capture features this system supports, based on the OS
+ * version and on which ScreenCaptureKit / CoreAudio APIs are present.
+ */
+export declare function capabilities(): Capabilities
+
+/** Feature availability on the running system */
+export interface Capabilities {
+  /** macOS version as "major.minor" (empty if unknown) */
+  osVersion: string
+  /** Whole-system audio capture (`start_capture`; macOS 14.2+) */
+  systemAudio: boolean
+  /** Per-application audio capture via process taps (CATapDescription) */
+  perAppAudio: boolean
+  /** ScreenCaptureKit can mix the microphone into the stream (macOS 15+) */
+  microphoneMix: boolean
+}
+
+/**
+ * Get the host-clock reference for the active capture.
+ * Returns null when not capturing or before the first buffer arrives.
+ * Sample positions are exact up to the resampler's filter delay (~7 input samples).
+ */
+export declare function captureClockInfo(): ClockInfo | null
+
+/** Something that happened to a capture session outside the caller's control */
+export interface CaptureEvent {
+  /**
+   * - "consumerGone": the audio callback was released or aborted by JS
+   *   while capturing, so the session was stopped
+   * - "reTapped": capture was re-established after the default output
+   *   device changed (`retapOnDeviceChange`)
+   * - "reTapFailed": that restart failed; the session delivers nothing
+   *   more until restarted
+   * - "formatChange": the input sample rate or channel count changed
+   *   mid-session (output is still 16kHz mono)
+   * - "lowDiskSpace": free space on the recording's volume dropped below
+   *   `lowDiskSpaceMb`
+   * - "lowDiskSpaceStopped": it got so low that the session was stopped,
+   *   with its files finalized
+   */
+  kind: string
+  /** Session the event concerns */
+  sessionId?: number
+  /** Human-readable description */
+  message?: string
+}
+
+/** Optional settings for `start_capture`. */
+export interface CaptureOptions {
+  /** Also encode the resampled stream to this file, alongside the PCM callback */
+  encodedPath?: string
+  /** Codec for `encoded_path`: "aac" (.m4a, default) or "opus" (.caf) */
+  encodedFormat?: string
+  /** Deliver fixed-size chunks every N ms from a timer thread instead of as SCK bursts arrive */
+  pacedIntervalMs?: number
+  /** Maximum audio buffered for paced delivery before the oldest is dropped (default 1000ms) */
+  pacedBufferMs?: number
+  /**
+   * Deliver exactly this many samples per callback, for block-based
+   * consumers (FFT, ML inference), holding the remainder for the next
+   * one. The final chunk when capture stops may be shorter. Not combinable
+   * with `pacedIntervalMs`
+   */
+  framesPerCallback?: number
+  /**
+   * Hold chunks until at least this much audio has built up and deliver
+   * them as one callback, trading up to that much latency for fewer JS
+   * wakeups, which matters on battery during long recordings (default
+   * 20ms, at most 1000; 0 delivers every chunk as it arrives). Chunks are
+   * never split, so sizes vary. Ignored with `framesPerCallback` or
+   * `pacedIntervalMs`, which set their own sizes
+   */
+  coalesceMs?: number
+  /** Also write the resampled stream to this WAV file (16kHz mono 16-bit) */
+  wavPath?: string
+  /**
+   * Split `wavPath` into numbered files of this many ms each
+   * (`meeting-001.wav`, `meeting-002.wav`, ...) instead of one growing file
+   */
+  wavSegmentDurationMs?: number
+  /**
+   * With `wavPath` or `encodedPath`: once free space on the recording's
+   * volume drops below this many MB, emit a "lowDiskSpace" capture
+   * event; below 100MB (or this, if lower) stop the capture, finalizing
+   * the files, before the disk fills. Starting with less than that fails
+   * with an error starting with `LowDiskSpace:`. Checked every 5s
+   * (default 500; 0 turns the guard off)
+   */
+  lowDiskSpaceMb?: number
+  /** Keep the last N ms of audio as Opus in memory, for `save_rolling_buffer` */
+  rollingRetentionMs?: number
+  /**
+   * Keep the last N ms of 16kHz Int16 in memory (32KB per second, at
+   * most 600000ms), for `snapshot_recent` to clip from while streaming
+   */
+  recentRetentionMs?: number
+  /**
+   * Also stream the resampled audio to a socket, "unix:/path/to.sock" or
+   * "tcp:host:port", as framed chunks (32-byte header with sequence
+   * number and host time, then PCM; see the README). Frames are dropped,
+   * and the next one flagged, if the reader falls behind
+   */
+  socketSink?: string
+  /** Suppress delivery of audio quieter than this level (dBFS, e.g. -50) */
+  silenceThresholdDb?: number
+  /** Time the level must stay above the threshold to open the gate (default 20ms) */
+  gateAttackMs?: number
+  /** Time the level must stay below the threshold to close the gate (default 400ms) */
+  gateHangoverMs?: number
+  /**
+   * Stereo→mono fold: "average" (default), "peak" or its alias "louder"
+   * (the louder channel per frame, for speakers hard-panned L/R) or "rms"
+   * (equal-power)
+   */
+  mixdownMode?: string
+  /**
+   * Run the audio callback on a real-time (time-constraint) thread to avoid
+   * dropouts under load. The scheduler reserves CPU for it every 10ms, so
+   * keep per-chunk work (encoders, gates) light or other threads may starve.
+   */
+  realtimePriority?: boolean
+  /**
+   * Enable a downward expander on the 16kHz output: audio below this level
+   * (dBFS) is attenuated by `expanderRatio` instead of hard-gated
+   */
+  expanderThresholdDb?: number
+  /** dB of output drop per dB below threshold (default 2.0, must be >= 1) */
+  expanderRatio?: number
+  /** Envelope attack time (default 5ms) */
+  expanderAttackMs?: number
+  /** Envelope release time (default 100ms) */
+  expanderReleaseMs?: number
+  /**
+   * Normalize the 16kHz output toward this peak level (dBFS, -60 to 0),
+   * with DC removed. Slow-tracking, for a consistent recording level:
+   * the gain drops instantly on a peak that would overshoot and rises
+   * over seconds, so unlike the expander it leaves the dynamics within
+   * a phrase alone. Applied after the expander; gain is capped at +30dB
+   */
+  normalizeToTarget?: number
+  /**
+   * Mix the default microphone into the 16kHz stream (macOS 15+;
+   * see `capabilities().microphoneMix`). Extra outputs stay system-only
+   */
+  captureMicrophone?: boolean
+  /**
+   * Fade the microphone in over this long at the start of the session
+   * instead of starting at full gain (default 0)
+   */
+  micRampMs?: number
+  /**
+   * Duck the system audio by this many dB while the microphone carries
+   * speech, for narrating over content (0 to 60; needs `captureMicrophone`).
+   * A sidechain compressor keyed by the mic's level, held through short
+   * pauses between words
+   */
+  duckSystemDb?: number
+  /** Mic level in dBFS that triggers ducking (default -40) */
+  duckThresholdDb?: number
+  /** Time to duck once speech starts (default 50ms) */
+  duckAttackMs?: number
+  /** Time to recover once speech ends (default 500ms) */
+  duckReleaseMs?: number
+  /**
+   * Run the pipeline for this long after start without delivering anything,
+   * so the filter state and SCStream have settled (default 0). Warmup
+   * audio is discarded from every output, including the rolling buffer,
+   * which starts retaining only once warmup ends
+   */
+  warmupMs?: number
+  /**
+   * Resample in Q15 fixed point instead of float (within ±1 LSB of the
+   * float output). Saves CPU with many streams; ignored while the expander,
+   * normalization or dither is enabled
+   */
+  fixedPoint?: boolean
+  /**
+   * Keep the expander and normalization in float end to end, rounding to
+   * Int16 only once as the last step (default true). With false, each
+   * stage's output is rounded before the next, so the rounding error
+   * compounds; only useful to compare with or bit-match an Int16 pipeline
+   */
+  processInFloat?: boolean
+  /**
+   * Detect stereo whose channels are inverted copies of each other (which
+   * would sum to silence) and pick up the left channel alone while it
+   * lasts. Warns once when it engages (default false)
+   */
+  phaseProtection?: boolean
+  /**
+   * Capture backend: "sck" (ScreenCaptureKit, default) or "tap" (CoreAudio
+   * process tap, macOS 14.2+). The tap needs no Screen Recording access
+   * (macOS asks for audio capture permission instead) and has less
+   * overhead, but doesn't support content filters or `captureMicrophone`.
+   * "device" records one CoreAudio device chosen with `deviceId`
+   */
+  backend?: string
+  /**
+   * Device to record with `backend: "device"`: an `id` from
+   * `listInputDevices()` (a loopback driver such as BlackHole, or an
+   * aggregate device, whose input is recorded) or `listOutputDevices()`
+   * (an output-only device, recorded through a process tap on it)
+   */
+  deviceId?: number
+  /**
+   * Deliver `{ seq, samples }` objects instead of bare Buffers, where
+   * `seq` counts delivered chunks from 0 for each session, so gaps or
+   * reordering downstream are detectable (default false)
+   */
+  sequenceNumbers?: boolean
+  /**
+   * Decimate with a plain block average instead of the anti-alias filter,
+   * for live monitoring. Cuts the resampler's group delay (see
+   * `captureStatus().processingLatencyMs`) at the cost of audible aliasing
+   * from content above 8kHz; keep it off for transcription (default false)
+   */
+  lowLatency?: boolean
+  /** Tags to embed in the `wavPath` and `encodedPath` files */
+  metadata?: RecordingMetadata
+  /**
+   * Swap or invert L/R before mixdown, to work around a driver that
+   * delivers them that way (an inverted channel cancels the mix to
+   * near-silence). Applies to the main 16kHz stream
+   */
+  channelMap?: ChannelMapOptions
+  /**
+   * Deliver each chunk's little-endian Int16 bytes as a base64 string
+   * instead of a Buffer, ready for a JSON or HTTP transport (default
+   * false). Combines with `sequenceNumbers` (`samples` is the string)
+   */
+  base64?: boolean
+  /**
+   * Most audio held in native memory by each internal buffer (default
+   * 30000ms): the paced queue (also capped by `pacedBufferMs`) and the
+   * chunks queued for a busy JS thread, for the main callback and
+   * `addCaptureOutput` outputs together. When the paced queue is full its
+   * oldest audio is dropped; chunks already queued for JS can't be
+   * recalled, so new chunks are dropped instead until JS catches up.
+   * Either way the callback gets a warning (at most every 10s). Also caps
+   * the length of a `start_memory_capture` recording. The rolling buffer
+   * is bounded by `rollingRetentionMs` instead
+   */
+  maxBufferedMs?: number
+  /**
+   * Ask ScreenCaptureKit for mono audio, so the resampler gets one
+   * channel and skips the stereo mixdown (and its phase issues) entirely.
+   * `sck` backend only; `capture_status().monoInputHonored` reports
+   * whether SCK delivered mono (default false)
+   */
+  requestMonoInput?: boolean
+  /**
+   * Retries after a transient start failure, e.g. right after Screen
+   * Recording was granted (default 2, 0 disables). Permanent failures
+   * such as missing permission are never retried
+   */
+  startRetryAttempts?: number
+  /**
+   * Wait before the first retry, doubling for each one after (default
+   * 250ms). `start_capture` blocks while it waits
+   */
+  startRetryDelayMs?: number
+  /**
+   * Keep this app's own audio out of the capture, including its helper
+   * processes (bundle IDs under this app's, e.g. Electron's
+   * "<id>.helper"), which is where TTS and notification sounds usually
+   * play from. The current process alone is always excluded. Not
+   * applied to a prebuilt `ContentFilter.handle` (default false)
+   */
+  excludeSelf?: boolean
+  /**
+   * Re-establish capture when the default output device changes (e.g.
+   * headphones unplugged), keeping the session id and the pipeline's
+   * state; each re-tap is reported as a "reTapped" lifecycle event.
+   * Not for `backend: "device"`, which is pinned to its device (default false)
+   */
+  retapOnDeviceChange?: boolean
+  /**
+   * Add ±1 LSB triangular dither before rounding to Int16, trading
+   * quantization distortion on quiet audio for benign noise (default false)
+   */
+  dither?: boolean
+  /**
+   * Seed the dither so the same input gives byte-identical output, for
+   * tests and reproducible offline processing (default: random per session)
+   */
+  ditherSeed?: number
+  /**
+   * Decimate with this FIR instead of the built-in anti-alias filter.
+   * Must sum to 1 (unity DC gain), at most 1024 taps; applies to 48kHz
+   * and 32kHz input. See `filterCoefficients()` for the default
+   */
+  filterCoefficients?: Array<number>
+  /**
+   * The previous session's final input samples (mono float, -1..1, at
+   * the input rate, at most 48000), run through the resampler before the
+   * first buffer so a restarted capture continues without a seam. The
+   * built-in resampler only needs the last few hundred; ignored by a
+   * custom resampler
+   */
+  primeTail?: Array<number>
+}
+
+/**
+ * Report which bundle the permission APIs are evaluated against.
+ * Useful when a grant appears not to apply (e.g. it was given to a different
+ * helper or to the unpackaged Electron binary).
+ */
+export declare function capturePermissionContext(): PermissionContext | null
+
+/** A contiguous run of delivered audio between pauses */
+export interface CaptureSegment {
+  /** Start on the session timeline (ms since the first SCK buffer) */
+  startMs: number
+  /** End on the session timeline (ms since the first SCK buffer) */
+  endMs: number
+  /** Index of the segment's first sample in the delivered 16kHz stream */
+  sampleOffset: number
+  /** Number of 16kHz samples in the segment */
+  sampleCount: number
+}
+
+/** Result of `start_capture_async` */
+export interface CaptureStarted {
+  /** Id of the new session (as in `capture_status().sessionId`) */
+  sessionId: number
+  /**
+   * Whether audio was flowing before the promise resolved. False means the
+   * stream started but nothing arrived within 3s (e.g. no audio device).
+   */
+  audioFlowing: boolean
+}
+
+/** Get the current capture status. */
+export declare function captureStatus(): CaptureStatus
+
+/** Snapshot of the capture session's health */
+export interface CaptureStatus {
+  /** Whether a capture session is active */
+  isCapturing: boolean
+  /** Id of the active session, shared by every window in this process */
+  sessionId?: number
+  /** When the active session started (ms since the Unix epoch) */
+  startedAtMs?: number
+  /** Output samples clamped to the Int16 range since capture started */
+  clippedSampleCount: number
+  /** Whether the silence gate is passing audio (None if the gate is disabled) */
+  gateOpen?: boolean
+  /**
+   * Whether the default output device is known to be capturable
+   * (None if unknown); see `output_device_info`
+   */
+  outputDeviceCapturable?: boolean
+  /** Backend of the active session ("sck", "tap" or "device") */
+  backend?: string
+  /** Device being recorded, with `backend: "device"` */
+  device?: AudioDeviceInfo
+  /**
+   * Group delay the resampler adds, in ms (None before the first buffer
+   * or with a custom resampler)
+   */
+  processingLatencyMs?: number
+  /** Mean time between audio callbacks this session (about 10ms normally) */
+  callbackIntervalMs?: number
+  /**
+   * Standard deviation of the time between audio callbacks. Persistently
+   * high values point at thread scheduling; try `realtimePriority`
+   */
+  callbackJitterMs?: number
+  /** Longest gap between two audio callbacks this session */
+  callbackMaxIntervalMs?: number
+  /**
+   * Ms per second spent processing audio in the capture callbacks
+   * (resampling, file sinks, extra outputs), i.e. roughly the module's
+   * CPU cost: 10 means 1% of one core
+   */
+  processingCpuMsPerSec?: number
+  /**
+   * Whether SCK delivered mono after `requestMonoInput` (None if it
+   * wasn't requested or before the first buffer)
+   */
+  monoInputHonored?: boolean
+  /**
+   * Input frames (at the input rate) ScreenCaptureKit dropped before
+   * handing audio to the module this session, going by gaps in its
+   * buffer timestamps: the OS falling behind, as opposed to delivery to
+   * JS falling behind (the overflow warnings). None unless capturing
+   * with the "sck" backend
+   */
+  sckReportedDrops?: number
+  /** Whether `set_muted(true)` is in effect (None if not capturing) */
+  muted?: boolean
+}
+
+/**
+ * Manual L/R corrections for capture drivers that deliver the channels
+ * swapped or with one of them inverted
+ */
+export interface ChannelMapOptions {
+  /** Exchange L and R (applied before the inversions) */
+  swapLR?: boolean
+  /** Flip the polarity of L */
+  invertL?: boolean
+  /** Flip the polarity of R */
+  invertR?: boolean
+}
+
+/** Remove the lifecycle event listener. Returns whether one was set. */
+export declare function clearCaptureEventListener(): boolean
+
+/** Remove the frame processor. Returns false if not capturing or none was set. */
+export declare function clearFrameProcessor(): boolean
+
+/** Remove the raw tap. Returns false if not capturing or none was set. */
+export declare function clearRawTap(): boolean
+
+/** Host-clock reference for aligning captured audio with other recorders */
+export interface ClockInfo {
+  /** mach_timebase_info numerator (ticks * numer / denom = nanoseconds) */
+  timebaseNumer: number
+  /** mach_timebase_info denominator */
+  timebaseDenom: number
+  /** Host time (mach ticks) of the first delivered sample */
+  anchorHostTime: number
+  /** Host time (mach ticks) of the most recent SCK buffer */
+  latestHostTime: number
+  /** Index in the 16kHz output stream of the sample stamped `latest_host_time` */
+  latestSampleIndex: number
+}
+
+/**
+ * Content selection for `start_capture_with_filter`: either an existing
+ * `SCContentFilter` handle, or the parameters to build one.
+ */
+export interface ContentFilter {
+  /**
+   * Address of an `SCContentFilter *` created by another native module.
+   * Borrowed: the stream retains the filter itself, so the caller keeps
+   * ownership and only has to keep it alive until the start call returns.
+   */
+  handle?: bigint
+  /** CGDirectDisplayID to attach to (default: main display) */
+  displayId?: number
+  /** Only capture audio from these apps */
+  includedBundleIds?: Array<string>
+  /** Capture all audio except these apps */
+  excludedBundleIds?: Array<string>
+}
+
+/**
+ * Everything support needs in one JSON string: OS and build, capabilities,
+ * permissions, output device, the active session's status, config and
+ * formats, running meeting apps, and the most recent warnings and errors
+ * (kept across sessions). Never throws; anything that can't be determined
+ * is null.
+ */
+export declare function diagnosticsJson(): string
+
+/**
+ * Deliver a `duration_ms` sine at `freq_hz` to `callback` as 16kHz mono
+ * Int16 Buffers, exactly as a capture session would. Independent of any
+ * capture session. Returns the number of samples queued for delivery.
+ */
+export declare function emitTestTone(callback: ((err: Error | null, arg: Buffer) => any), freqHz: number, durationMs: number): number
+
+/**
+ * Snapshot the active capture's resampler state (filter histories, phase,
+ * expander, normalization and dither state) as an opaque Buffer, to
+ * persist or to hand the stream to another capture with
+ * `import_resampler_state`. Fails with a custom resampler.
+ */
+export declare function exportResamplerState(): Buffer
+
+/**
+ * The anti-alias FIR the active capture decimates with, or the built-in
+ * one when not capturing, oldest-sample tap first.
+ */
+export declare function filterCoefficients(): Array<number>
+
+/**
+ * The active session's options as `start_capture` applied them, with
+ * every default it filled in spelled out, so a reloaded UI can rehydrate
+ * its controls. Returns null when not capturing.
+ */
+export declare function getCaptureConfig(): CaptureOptions | null
+
 /**
  * Get list of currently running meeting applications.
  * Returns an array of MeetingAppInfo for any detected meeting apps.
@@ -9,12 +594,131 @@ export declare function getRunningMeetingApps(): Array<MeetingAppInfo>
 /** Check if the app has Screen Capture (Screen Recording) access. */
 export declare function hasScreenCaptureAccess(): boolean
 
+/**
+ * Restore a state from `export_resampler_state` into the active capture,
+ * which continues as if it had processed the exporting capture's audio.
+ * The capture must resample the same way: same `filterCoefficients`,
+ * expander, `normalizeToTarget` and dither settings. Audio processed
+ * before the import used fresh state, so call it right after starting.
+ */
+export declare function importResamplerState(state: Buffer): void
+
+/**
+ * Whether the app with `bundle_id` (or one of its helper processes, e.g.
+ * `com.google.Chrome.helper`) is playing audio right now. Finer than
+ * meeting-app presence or focus: a muted, idle call reports false.
+ * Returns null where the system can't tell (before macOS 14, or off macOS).
+ */
+export declare function isAppProducingAudio(bundleId: string): boolean | null
+
 /**
  * Check if system audio capture is supported on this platform.
  * Requires macOS 14.2+ (Sonoma).
  */
 export declare function isSupported(): boolean
 
+/** A smoothed level reading of the 16kHz output, in dBFS (-100 for silence) */
+export interface LevelEvent {
+  rmsDb: number
+  peakDb: number
+  /**
+   * With `spectralFlatness` enabled: how noise-like the interval's audio
+   * was, from near 0 (tonal, e.g. music) to near 1 (noise, ambience).
+   * Speech sits in between and varies quickly. Absent while silent or
+   * before 16ms of audio has been analyzed
+   */
+  spectralFlatness?: number
+}
+
+/** Meter ballistics for `start_level_meter` */
+export interface LevelMeterConfig {
+  /** Time constant for rising levels (default 10ms) */
+  attackMs?: number
+  /**
+   * Time constant for falling levels (default 300ms). A long release
+   * (~1500ms) with a short attack behaves like a PPM; attack and release
+   * both at 300ms like a VU meter
+   */
+  releaseMs?: number
+  /** Time between readings (default 50ms) */
+  intervalMs?: number
+  /**
+   * Also report `spectralFlatness` with each reading (default false; runs
+   * a small FFT per 16ms of audio)
+   */
+  spectralFlatness?: boolean
+}
+
+/**
+ * List every app ScreenCaptureKit can capture that is playing audio right
+ * now (including through helper processes), for a "select source" UI
+ * feeding `ContentFilter.includedBundleIds`. Not limited to meeting apps;
+ * this app itself is left out. Returns an empty list without Screen
+ * Recording permission.
+ */
+export declare function listCapturableAudioApps(): Array<MeetingAppInfo>
+
+/**
+ * Devices with input channels. Capturing one records its input, which for
+ * loopback drivers and aggregate devices is whatever is routed into them.
+ */
+export declare function listInputDevices(): Array<AudioDeviceInfo>
+
+/**
+ * Devices with output channels. Capturing an output-only device records
+ * what plays through it, through a process tap on that device (macOS 14.2+).
+ */
+export declare function listOutputDevices(): Array<AudioDeviceInfo>
+
+/** Verbosity of the native module's logging, least to most chatty */
+export declare enum LogLevel {
+  /** Failures that lose audio or stop a capture */
+  Error = 'error',
+  /** Recoverable problems (the warnings also sent to JS) */
+  Warn = 'warn',
+  /** Capture lifecycle: starts, stops, route changes (the default) */
+  Info = 'info',
+  /** Format and size of every buffer */
+  Debug = 'debug',
+  /** Per-buffer timing on top of Debug */
+  Trace = 'trace'
+}
+
+/**
+ * A BS.1770 loudness reading of the 16kHz output, in LUFS (-100 for
+ * silence)
+ */
+export interface LoudnessEvent {
+  /** Over the last 400ms; absent for the first 400ms */
+  momentary?: number
+  /** Over the last 3s; absent for the first 3s */
+  shortTerm?: number
+  /**
+   * Since the meter started, gated: 400ms blocks below -70 LUFS, then
+   * those more than 10 LU below the loudness of the rest, are left out,
+   * so pauses and quiet passages don't pull it down. Absent until a
+   * block passes the gates
+   */
+  integrated?: number
+}
+
+/** Options for `start_loudness_meter` */
+export interface LoudnessMeterConfig {
+  /** Time between readings, a multiple of 100ms (default 100ms) */
+  intervalMs?: number
+}
+
+/**
+ * How many captures with `backend` ("sck" by default, "tap" or "device")
+ * can run at once on this system; 0 where the backend isn't available.
+ * `start_capture` runs one session at a time whatever the backend, so this
+ * is at most 1; starting another while one runs or is starting fails with
+ * an error starting with `AlreadyCapturing:`. Per-app tracks
+ * (`start_app_tracks`) are separate streams, limited to 8 at a time; going
+ * over that fails with an error starting with `TooManyCaptures:`.
+ */
+export declare function maxConcurrentCaptures(backend?: string | undefined | null): number
+
 /** Information about a detected meeting application */
 export interface MeetingAppInfo {
   /** Bundle identifier (e.g., "us.zoom.xos") */
@@ -27,17 +731,453 @@ export interface MeetingAppInfo {
   isActive: boolean
 }
 
+/**
+ * Open System Settings at Privacy & Security > Screen Recording.
+ * Returns false if the settings pane could not be opened.
+ */
+export declare function openScreenRecordingSettings(): boolean
+
+/** Format of an additional output stream */
+export interface OutputConfig {
+  /**
+   * Output sample rate in Hz: a divisor or integer multiple of the capture
+   * rate (e.g. 16000 or 48000 for a 48kHz stream). 8000 uses a
+   * telephony-grade filter, flat to 3.4kHz
+   */
+  sampleRate: number
+  /** 1 (mono mixdown, default) or 2 (stereo) */
+  channels?: number
+  /** "linear" (Int16 PCM, default), "mulaw" or "alaw" (8-bit G.711) */
+  encoding?: string
+}
+
+/** Describe the current default output device (None if it can't be read). */
+export declare function outputDeviceInfo(): OutputDeviceInfo | null
+
+/** The current default output device */
+export interface OutputDeviceInfo {
+  /** Device name as shown in Sound settings */
+  name: string
+  /**
+   * "builtin", "usb", "hdmi", "displayport", "thunderbolt", "pci",
+   * "firewire", "bluetooth", "airplay", "virtual", "aggregate" or "unknown"
+   */
+  transport: string
+  /**
+   * Whether system audio capture is known to work on this route
+   * (None if not known either way)
+   */
+  capturable?: boolean
+}
+
+/**
+ * Pause delivery. The stream keeps running (no permission or startup cost on
+ * resume); audio arriving while paused is dropped from every output.
+ */
+export declare function pauseCapture(): void
+
+/** The identity macOS evaluates Screen Recording permission against */
+export interface PermissionContext {
+  /** Bundle identifier of the running process (None for unbundled binaries) */
+  bundleId?: string
+  /** Path of the main bundle (the .app, or the binary's directory if unbundled) */
+  bundlePath: string
+  /** Path of the executable that is actually running */
+  executablePath: string
+  /** Process ID */
+  pid: number
+  /**
+   * How the process is hosted: "app", "agent" (LSUIElement), "background"
+   * (background-only) or "nogui" (no GUI login session, e.g. a LaunchDaemon)
+   */
+  session: string
+}
+
+/** Outcome of `request_capture_permission` */
+export declare enum PermissionRequestOutcome {
+  /** Access is granted */
+  Granted = 'granted',
+  /** The system dialog was shown; the user hasn't granted access (yet) */
+  Prompted = 'prompted',
+  /**
+   * Access was denied earlier. macOS never re-prompts after a denial, so
+   * the user has to enable it in System Settings (`open_screen_recording_settings`)
+   */
+  Denied = 'denied',
+  /** Not running on macOS */
+  Unsupported = 'unsupported'
+}
+
+/** Values the permission functions return while stubbed */
+export interface PermissionStub {
+  /**
+   * Answer for `has_screen_capture_access` and the start precheck, and
+   * the result of `request_screen_capture_access` /
+   * `request_audio_capture_permission`
+   */
+  screenCaptureAccess: boolean
+  /**
+   * Outcome of `request_capture_permission` (default: "granted" if
+   * `screenCaptureAccess`, else "denied")
+   */
+  requestOutcome?: PermissionRequestOutcome
+}
+
+/** Raw capture chunk handed to a custom resampler */
+export interface RawAudioChunk {
+  /** Interleaved float32 frames as delivered by ScreenCaptureKit */
+  samples: Float32Array
+  /** Channels per frame */
+  channels: number
+  /** Input sample rate in Hz */
+  sampleRate: number
+}
+
+/**
+ * Tags embedded in recorded files: a LIST/INFO chunk in WAV output, the
+ * file's info dictionary in encoded output
+ */
+export interface RecordingMetadata {
+  /** e.g. the meeting title */
+  title?: string
+  /** e.g. the participant or organizer */
+  artist?: string
+  comment?: string
+}
+
+/** Result of a `record_to_wav` call */
+export interface RecordingResult {
+  /** Number of 16kHz samples written to the file */
+  samples: number
+  /** Duration of the recorded audio in milliseconds */
+  durationMs: number
+}
+
+/**
+ * Record system audio for `duration_ms` to a 16kHz mono WAV file at `path`.
+ * Resolves once the file is finalized; rejects if permission is missing, a
+ * capture is already running, or the capture is stopped before the time is
+ * up. `metadata` is embedded in the file.
+ */
+export declare function recordToWav(path: string, durationMs: number, metadata?: RecordingMetadata | undefined | null): Promise<RecordingResult>
+
+/**
+ * Remove an output added with `add_capture_output`.
+ * Returns false if no such output is registered.
+ */
+export declare function removeCaptureOutput(id: number): boolean
+
 /** Request Screen & System Audio Recording permission via ScreenCaptureKit. */
 export declare function requestAudioCapturePermission(): boolean
 
+/**
+ * Request Screen Recording access and report what happened.
+ * Unlike `request_screen_capture_access`, this distinguishes a first-time
+ * prompt from an earlier denial, which macOS cannot be forced to re-prompt.
+ */
+export declare function requestCapturePermission(): PermissionRequestOutcome
+
 /** Request Screen Capture access (triggers macOS permission dialog). */
 export declare function requestScreenCaptureAccess(): boolean
 
+/**
+ * Convert a recorded PCM buffer to mono at `output_rate` with the same
+ * resampler live capture uses (built-in filter, average mixdown), e.g. to
+ * batch-convert 48kHz recordings consistently with live transcription.
+ * `input` holds interleaved `channels`-channel samples; formats are
+ * "s16le" (Int16) or "f32le" (Float32, -1..1). Each call starts from a
+ * fresh filter state, so convert a recording in one call rather than in
+ * pieces. Runs synchronously; convert very long recordings from a worker.
+ */
+export declare function resamplePcm(input: Buffer, inputRate: number, channels: number, inputFormat: string, outputRate: number, outputFormat: string): Buffer
+
+/** Resume delivery after `pause_capture`. */
+export declare function resumeCapture(): ResumeInfo
+
+/** Returned by `resume_capture`: where the next audio lands relative to the pause */
+export interface ResumeInfo {
+  /** How long the capture was paused, in milliseconds */
+  pauseDurationMs: number
+  /** Index in the delivered 16kHz stream of the first sample after resuming */
+  sampleOffset: number
+}
+
+/**
+ * Write the currently retained rolling window to an Opus .caf file.
+ * Capture keeps running. Returns the duration saved in milliseconds.
+ */
+export declare function saveRollingBuffer(path: string): number
+
+/**
+ * Receive lifecycle events for every capture session, independent of the
+ * audio callback (which is exactly what a "consumerGone" event reports
+ * losing). Replaces any listener already set.
+ */
+export declare function setCaptureEventListener(callback: ((err: Error | null, arg: CaptureEvent) => any)): void
+
+/**
+ * Run `processor` on every chunk of the active capture's resampled audio,
+ * after the expander and normalization and just before the conversion to
+ * Int16, e.g. for a custom filter or a watermark. It receives 16kHz mono
+ * float samples (-1..1) and must synchronously return as many processed
+ * samples. Each chunk is a round trip to the main thread that the audio
+ * thread waits on, adding its latency to every chunk; a busy event loop
+ * stalls capture for up to 50ms per chunk, after which the chunk goes on
+ * unprocessed. Keep it cheap. Replaces any processor already set; not
+ * available with a custom resampler.
+ */
+export declare function setFrameProcessor(processor: ((err: Error | null, arg: Float32Array) => Float32Array)): void
+
+/**
+ * How much the native module prints to stderr (default "info"): "error"
+ * and "warn" quiet lifecycle messages, "debug" adds the format and size of
+ * every buffer and "trace" its processing time too. Those two print from
+ * the audio thread, so leave them for debugging sessions. Warnings still
+ * reach JS and `diagnostics_json` whatever the level.
+ */
+export declare function setLogLevel(level: LogLevel): void
+
+/**
+ * Mute or unmute the active capture. Unlike `pause_capture`, a muted
+ * capture keeps delivering audio, timestamps and sequence numbers, only
+ * silent (to the callback, extra outputs, the raw tap and every file and
+ * stream alike), so recordings keep a muted span of the right length
+ * instead of a gap. Gain fades over 10ms on each change to avoid clicks.
+ */
+export declare function setMuted(muted: boolean): void
+
+/**
+ * Script the permission functions, or restore the real checks with null.
+ * Only present in builds with the `permission-stub` feature
+ * (`npm run build:permission-stub`).
+ */
+export declare function setPermissionStub(stub?: PermissionStub | undefined | null): void
+
+/**
+ * Also send `callback` the active capture's audio as it left the
+ * resampler, before the expander, normalization, frame processor, dither,
+ * microphone mix, mute and silence gate: 16kHz mono float samples (-1..1),
+ * one chunk per capture buffer, to A/B against the processed delivery
+ * when tuning those options. Chunks line up with the processed stream
+ * before gating (same sample counts, not sent while paused or warming
+ * up), but don't share its chunking. Opt-in, as it costs a second
+ * callback per buffer and disables `fixedPoint`. Replaces any tap already
+ * set; not available with a custom resampler.
+ */
+export declare function setRawTap(callback: ((err: Error | null, arg: Float32Array) => any)): void
+
+/**
+ * Release everything the module holds for the life of the process, for
+ * embedders that unload or reload it (hot reload, test runners): stops
+ * auto-capture, per-app tracks and any running capture (finalizing their
+ * files and joining the pacer, socket and free-space threads), removes the
+ * output device observer and the capture event listener, frees pooled
+ * buffers, clears the diagnostics event log and restores the default log
+ * level. Callbacks are released, so Node can exit or unload the addon.
+ * Safe to call with nothing running or more than once; capture can be
+ * started again afterwards.
+ */
+export declare function shutdown(): void
+
+/**
+ * Copy up to the last `ms` of audio (16kHz mono Int16) from the ring kept
+ * with `recentRetentionMs`, e.g. to clip the last 30 seconds on a user
+ * action. Delivery carries on uninterrupted; the copy is shorter if less
+ * has been captured or retained.
+ */
+export declare function snapshotRecent(ms: number): Buffer
+
+/**
+ * Capture each app in `bundle_ids` as its own track. `callback` receives
+ * chunks labeled with the app they came from. Apps that aren't running are
+ * skipped; returns the bundle ids that did start. Call `stop_app_tracks` to end.
+ * At most 8 apps; more fail with an error starting with `TooManyCaptures:`.
+ */
+export declare function startAppTracks(bundleIds: Array<string>, callback: ((err: Error | null, arg: TrackChunk) => any)): Array<string>
+
 /**
  * Start capturing system audio via ScreenCaptureKit.
- * The callback receives Buffer chunks of 16kHz mono Int16 PCM data.
+ * The callback receives Buffer chunks of 16kHz mono Int16 PCM data; it is
+ * called with an error (and no chunk) for non-fatal warnings such as clipping.
+ * If `options.encodedPath` is set, the same samples are also encoded to that file.
+ * If `options.pacedIntervalMs` is set, chunks are re-timed to a steady cadence.
  */
-export declare function startCapture(callback: ((err: Error | null, arg: Buffer) => any)): void
+export declare function startCapture(callback: ((err: Error | null, arg: Buffer) => any), options?: CaptureOptions | undefined | null): void
 
-/** Stop capturing system audio. Cleans up all resources. */
-export declare function stopCapture(): void
+/**
+ * `start_capture` without blocking the calling thread. Resolves once the
+ * stream is running and the first buffer has arrived (or after 3s with
+ * `audioFlowing: false`); rejects with the same errors as `start_capture`,
+ * or if the capture is stopped before audio arrives.
+ */
+export declare function startCaptureAsync(callback: ((err: Error | null, arg: Buffer) => any), options?: CaptureOptions | undefined | null): Promise<CaptureStarted>
+
+/**
+ * Start capturing system audio against a specific ScreenCaptureKit filter,
+ * bypassing the default main-display filter. Lets a process that already
+ * captures video reuse the same filter instead of running competing streams.
+ * Chunks and options behave exactly as in `start_capture`.
+ */
+export declare function startCaptureWithFilter(callback: ((err: Error | null, arg: Buffer) => any), filter: ContentFilter, options?: CaptureOptions | undefined | null): void
+
+/**
+ * Start capturing system audio with a custom resampler in place of the
+ * built-in one. `resampler` receives each raw chunk and must synchronously
+ * return 16kHz mono Int16 samples; it runs on the main thread and chunks it
+ * doesn't answer within 50ms are dropped. `mixdownMode`, the expander
+ * options, `normalizeToTarget` and dither are ignored.
+ */
+export declare function startCaptureWithResampler(callback: ((err: Error | null, arg: Buffer) => any), resampler: ((err: Error | null, arg: RawAudioChunk) => Int16Array), options?: CaptureOptions | undefined | null): void
+
+/**
+ * Meter the running capture's 16kHz output (before the silence gate),
+ * smoothed per sample with attack/release ballistics so readings don't
+ * jump with the chunk size. `callback` receives a reading every
+ * `intervalMs` of audio. Replaces any meter already running.
+ */
+export declare function startLevelMeter(callback: ((err: Error | null, arg: LevelEvent) => any), config?: LevelMeterConfig | undefined | null): void
+
+/**
+ * Measure the running capture's 16kHz output (before the silence gate)
+ * per ITU-R BS.1770: K-weighted momentary, short-term and gated integrated
+ * loudness, e.g. to normalize a recording to -16 LUFS afterwards.
+ * `callback` receives a reading every `intervalMs` of audio. Replaces any
+ * loudness meter already running, restarting the integrated measurement.
+ */
+export declare function startLoudnessMeter(callback: ((err: Error | null, arg: LoudnessEvent) => any), config?: LoudnessMeterConfig | undefined | null): void
+
+/**
+ * Start a capture that accumulates the 16kHz mono Int16 output in native
+ * memory instead of streaming it; `stop_memory_capture` returns all of it.
+ * Recording stops growing at `maxBufferedMs` (default 30000ms), keeping the
+ * beginning; later audio is dropped with a warning logged to stderr.
+ * Other options apply as for `start_capture`.
+ */
+export declare function startMemoryCapture(options?: CaptureOptions | undefined | null): void
+
+/**
+ * Start an always-on capture that keeps only the last `retention_ms` of
+ * audio, Opus-compressed in native memory. No JS callback is involved;
+ * use `save_rolling_buffer` to dump the window and `stop_capture` to end it.
+ */
+export declare function startRollingCapture(retentionMs: number): void
+
+/**
+ * Run voice activity detection (level and zero-crossing rate per 20ms
+ * frame) on the running capture's 16kHz output, independent of the
+ * silence gate. `callback` receives the stream labeled as alternating
+ * speech and non-speech segments, each once it has ended; the last one is
+ * reported when VAD or the capture stops. Replaces any VAD already running.
+ */
+export declare function startVad(callback: ((err: Error | null, arg: VadEvent) => any), config?: VadConfig | undefined | null): void
+
+/** Stop all app tracks. */
+export declare function stopAppTracks(): void
+
+/** Stop auto-capture mode, ending any capture session it started. */
+export declare function stopAutoCapture(): void
+
+/**
+ * Stop capturing system audio. Cleans up all resources.
+ * Returns the delivered segments (one per run between pauses).
+ */
+export declare function stopCapture(): Array<CaptureSegment>
+
+/**
+ * Stop capturing and resolve once every chunk has been delivered to JS,
+ * including whatever paced delivery still held. Each callback receives one
+ * final empty Buffer marking the end of the stream. Resolves with the same
+ * segments as `stop_capture`.
+ */
+export declare function stopCaptureAndDrain(): Promise<Array<CaptureSegment>>
+
+/** Stop level metering. Returns false if no meter was running. */
+export declare function stopLevelMeter(): boolean
+
+/** Stop loudness metering. Returns false if no loudness meter was running. */
+export declare function stopLoudnessMeter(): boolean
+
+/**
+ * Stop a capture started with `start_memory_capture` and return the whole
+ * recording as one Buffer of 16kHz mono Int16 (little-endian).
+ */
+export declare function stopMemoryCapture(): Buffer
+
+/**
+ * Stop voice activity detection, reporting the segment still open.
+ * Returns false if VAD wasn't running.
+ */
+export declare function stopVad(): boolean
+
+/** A chunk of one app's audio */
+export interface TrackChunk {
+  /** Bundle identifier of the app the audio came from */
+  bundleId: string
+  /** 16kHz mono Int16 PCM */
+  samples: Buffer
+}
+
+/** Voice activity detector settings for `start_vad` */
+export interface VadConfig {
+  /** Level (dBFS) below which audio is never speech (default -45) */
+  thresholdDb?: number
+  /** Speech must last this long to start a speech segment (default 60ms) */
+  minSpeechMs?: number
+  /**
+   * Non-speech must last this long to end one (default 300ms), so pauses
+   * between words stay inside the segment
+   */
+  hangoverMs?: number
+}
+
+/**
+ * A closed run of speech or non-speech. Times are positions in the
+ * delivered 16kHz stream (paused audio isn't counted), in ms.
+ */
+export interface VadEvent {
+  isSpeech: boolean
+  startMs: number
+  endMs: number
+}
+
+/**
+ * Check whether a capture with `options` (and, for
+ * `start_capture_with_filter`, `filter`; for `add_capture_output`,
+ * `outputs`) could start on this machine, without starting anything,
+ * prompting or creating files: option values, a capture already running,
+ * OS and backend support, Screen Recording access, the device, recording
+ * paths and free space, the encoder, and included apps. Options with
+ * nothing to check beyond their values are only listed if they fail.
+ */
+export declare function validateCaptureConfig(options?: CaptureOptions | undefined | null, filter?: ContentFilter | undefined | null, outputs?: Array<OutputConfig> | undefined | null): ValidationReport
+
+/** One check made by `validate_capture_config` */
+export interface ValidationCheck {
+  /**
+   * The option checked ("wavPath", "outputs[0]", ...), or "session",
+   * "platform", "permission" or "filter" for checks of the system
+   */
+  option: string
+  status: ValidationStatus
+  /**
+   * What's wrong, for a warning or failure. Failures carry the error
+   * starting the capture would throw, prefix included
+   */
+  message?: string
+}
+
+/** Result of `validate_capture_config` */
+export interface ValidationReport {
+  /** No check failed */
+  ok: boolean
+  checks: Array<ValidationCheck>
+}
+
+/** Outcome of one check in a `ValidationReport` */
+export declare enum ValidationStatus {
+  Pass = 'pass',
+  /** Capture would start, but maybe not as intended */
+  Warn = 'warn',
+  /** Capture would fail to start */
+  Fail = 'fail'
+}
@@ -576,10 +576,69 @@ if (!nativeBinding) {
 }
 
 module.exports = nativeBinding
+module.exports.LogLevel = nativeBinding.LogLevel
+module.exports.ValidationStatus = nativeBinding.ValidationStatus
+module.exports.addCaptureOutput = nativeBinding.addCaptureOutput
+module.exports.autoCapture = nativeBinding.autoCapture
+module.exports.buildInfo = nativeBinding.buildInfo
+module.exports.canCaptureWithoutPrompt = nativeBinding.canCaptureWithoutPrompt
+module.exports.capabilities = nativeBinding.capabilities
+module.exports.captureClockInfo = nativeBinding.captureClockInfo
+module.exports.capturePermissionContext = nativeBinding.capturePermissionContext
+module.exports.captureStatus = nativeBinding.captureStatus
+module.exports.clearCaptureEventListener = nativeBinding.clearCaptureEventListener
+module.exports.clearFrameProcessor = nativeBinding.clearFrameProcessor
+module.exports.clearRawTap = nativeBinding.clearRawTap
+module.exports.diagnosticsJson = nativeBinding.diagnosticsJson
+module.exports.emitTestTone = nativeBinding.emitTestTone
+module.exports.exportResamplerState = nativeBinding.exportResamplerState
+module.exports.filterCoefficients = nativeBinding.filterCoefficients
+module.exports.getCaptureConfig = nativeBinding.getCaptureConfig
 module.exports.getRunningMeetingApps = nativeBinding.getRunningMeetingApps
 module.exports.hasScreenCaptureAccess = nativeBinding.hasScreenCaptureAccess
+module.exports.importResamplerState = nativeBinding.importResamplerState
+module.exports.isAppProducingAudio = nativeBinding.isAppProducingAudio
 module.exports.isSupported = nativeBinding.isSupported
+module.exports.listCapturableAudioApps = nativeBinding.listCapturableAudioApps
+module.exports.listInputDevices = nativeBinding.listInputDevices
+module.exports.listOutputDevices = nativeBinding.listOutputDevices
+module.exports.maxConcurrentCaptures = nativeBinding.maxConcurrentCaptures
+module.exports.openScreenRecordingSettings = nativeBinding.openScreenRecordingSettings
+module.exports.outputDeviceInfo = nativeBinding.outputDeviceInfo
+module.exports.pauseCapture = nativeBinding.pauseCapture
+module.exports.PermissionRequestOutcome = nativeBinding.PermissionRequestOutcome
+module.exports.recordToWav = nativeBinding.recordToWav
+module.exports.removeCaptureOutput = nativeBinding.removeCaptureOutput
 module.exports.requestAudioCapturePermission = nativeBinding.requestAudioCapturePermission
+module.exports.requestCapturePermission = nativeBinding.requestCapturePermission
 module.exports.requestScreenCaptureAccess = nativeBinding.requestScreenCaptureAccess
+module.exports.resamplePcm = nativeBinding.resamplePcm
+module.exports.resumeCapture = nativeBinding.resumeCapture
+module.exports.saveRollingBuffer = nativeBinding.saveRollingBuffer
+module.exports.setCaptureEventListener = nativeBinding.setCaptureEventListener
+module.exports.setFrameProcessor = nativeBinding.setFrameProcessor
+module.exports.setLogLevel = nativeBinding.setLogLevel
+module.exports.setMuted = nativeBinding.setMuted
+module.exports.setPermissionStub = nativeBinding.setPermissionStub
+module.exports.setRawTap = nativeBinding.setRawTap
+module.exports.shutdown = nativeBinding.shutdown
+module.exports.snapshotRecent = nativeBinding.snapshotRecent
+module.exports.startAppTracks = nativeBinding.startAppTracks
 module.exports.startCapture = nativeBinding.startCapture
+module.exports.startCaptureAsync = nativeBinding.startCaptureAsync
+module.exports.startCaptureWithFilter = nativeBinding.startCaptureWithFilter
+module.exports.startCaptureWithResampler = nativeBinding.startCaptureWithResampler
+module.exports.startLevelMeter = nativeBinding.startLevelMeter
+module.exports.startLoudnessMeter = nativeBinding.startLoudnessMeter
+module.exports.startMemoryCapture = nativeBinding.startMemoryCapture
+module.exports.startRollingCapture = nativeBinding.startRollingCapture
+module.exports.startVad = nativeBinding.startVad
+module.exports.stopAppTracks = nativeBinding.stopAppTracks
+module.exports.stopAutoCapture = nativeBinding.stopAutoCapture
 module.exports.stopCapture = nativeBinding.stopCapture
+module.exports.stopCaptureAndDrain = nativeBinding.stopCaptureAndDrain
+module.exports.stopLevelMeter = nativeBinding.stopLevelMeter
+module.exports.stopLoudnessMeter = nativeBinding.stopLoudnessMeter
+module.exports.stopMemoryCapture = nativeBinding.stopMemoryCapture
+module.exports.stopVad = nativeBinding.stopVad
+module.exports.validateCaptureConfig = nativeBinding.validateCaptureConfig
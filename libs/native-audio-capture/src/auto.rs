@@ -0,0 +1,226 @@
+//! Auto-capture: start/stop system audio capture as meeting apps come and go
+//!
+//! A watcher thread polls the running meeting apps. Capture starts once a
+//! meeting app has been frontmost (or just running, if foreground isn't
+//! required) for `startDelayMs`, and stops once none has been for
+//! `stopGraceMs`, so briefly switching windows mid-call doesn't split the
+//! recording.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use napi_derive::napi;
+
+use crate::logging::native_log;
+use crate::pool::PooledBuffer;
+use crate::{active_session_id, get_running_meeting_apps, sck_audio_callback};
+use crate::{start_capture_with, stop_capture, CaptureOptions, MeetingAppInfo};
+
+/// Wait before retrying after a failed start
+const RETRY_DELAY: Duration = Duration::from_secs(10);
+
+/// Settings for `auto_capture`
+#[napi(object)]
+#[derive(Default)]
+pub struct AutoCaptureOptions {
+    /// How often to check for meeting apps (default 1000ms)
+    pub poll_interval_ms: Option<u32>,
+    /// How long a meeting app must be present before capture starts (default 2000ms)
+    pub start_delay_ms: Option<u32>,
+    /// How long no meeting app may be present before capture stops (default 10000ms)
+    pub stop_grace_ms: Option<u32>,
+    /// Only count meeting apps that are frontmost (default true)
+    pub require_foreground: Option<bool>,
+    /// Options applied to each capture session. Recorded files are titled
+    /// after the meeting app unless `capture.metadata.title` is set
+    pub capture: Option<CaptureOptions>,
+}
+
+/// Lifecycle event emitted by `auto_capture`
+#[napi(object)]
+pub struct AutoCaptureEvent {
+    /// "started", "stopped" or "error"
+    pub kind: String,
+    /// Bundle identifier of the meeting app that triggered the event, if any
+    pub bundle_id: Option<String>,
+    /// Human-readable app name, if any
+    pub name: Option<String>,
+    /// Error description for "error" events
+    pub message: Option<String>,
+    /// Capture session started or stopped by this event. Every start gets a
+    /// new id, so consumers can split recordings at each restart instead of
+    /// splicing sessions together
+    pub session_id: Option<u32>,
+}
+
+struct AutoCaptureHandle {
+    running: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+static AUTO_CAPTURE: OnceLock<Mutex<Option<AutoCaptureHandle>>> = OnceLock::new();
+
+fn auto_mutex() -> &'static Mutex<Option<AutoCaptureHandle>> {
+    AUTO_CAPTURE.get_or_init(|| Mutex::new(None))
+}
+
+enum WatchState {
+    Idle,
+    /// Meeting app seen; waiting out the start delay
+    Pending(Instant),
+    Capturing,
+    /// Meeting app gone while capturing; waiting out the grace period
+    Leaving(Instant),
+    /// Start failed; don't retry before this instant
+    Backoff(Instant),
+}
+
+/// Whether `session_id` is still the running capture session.
+fn is_current(session_id: Option<u32>) -> bool {
+    session_id.is_some() && active_session_id() == session_id
+}
+
+/// Capture options for a session triggered by `app`: recorded files are
+/// titled after the app unless the caller set a title.
+fn labeled_options(options: Option<CaptureOptions>, app: &MeetingAppInfo) -> CaptureOptions {
+    let mut options = options.unwrap_or_default();
+    if options.wav_path.is_some() || options.encoded_path.is_some() {
+        let metadata = options.metadata.get_or_insert_with(Default::default);
+        metadata.title.get_or_insert_with(|| format!("{} meeting", app.name));
+    }
+    options
+}
+
+/// Let the crate start and stop capture on its own based on meeting-app
+/// presence. `callback` receives audio exactly as with `start_capture`;
+/// `events` receives lifecycle events. Call `stop_auto_capture` to end it.
+#[napi]
+pub fn auto_capture(
+    callback: ThreadsafeFunction<PooledBuffer>,
+    events: ThreadsafeFunction<AutoCaptureEvent>,
+    options: Option<AutoCaptureOptions>,
+) -> Result<()> {
+    let mut guard = auto_mutex()
+        .lock()
+        .map_err(|e| Error::from_reason(format!("Failed to acquire auto-capture lock: {}", e)))?;
+    if guard.is_some() {
+        return Err(Error::from_reason("Auto-capture is already running"));
+    }
+
+    let options = options.unwrap_or_default();
+    let poll_interval = Duration::from_millis(options.poll_interval_ms.unwrap_or(1000).max(100) as u64);
+    let start_delay = Duration::from_millis(options.start_delay_ms.unwrap_or(2000) as u64);
+    let stop_grace = Duration::from_millis(options.stop_grace_ms.unwrap_or(10_000) as u64);
+    let require_foreground = options.require_foreground.unwrap_or(true);
+    let capture_options = options.capture;
+
+    let callback = Arc::new(callback);
+    let running = Arc::new(AtomicBool::new(true));
+    let thread_running = Arc::clone(&running);
+
+    let thread = std::thread::spawn(move || {
+        let emit = |kind: &str,
+                    app: Option<&MeetingAppInfo>,
+                    message: Option<String>,
+                    session_id: Option<u32>| {
+            events.call(
+                Ok(AutoCaptureEvent {
+                    kind: kind.to_string(),
+                    bundle_id: app.map(|a| a.bundle_id.clone()),
+                    name: app.map(|a| a.name.clone()),
+                    message,
+                    session_id,
+                }),
+                ThreadsafeFunctionCallMode::NonBlocking,
+            );
+        };
+
+        let mut state = WatchState::Idle;
+        let mut trigger_app: Option<MeetingAppInfo> = None;
+        let mut session_id: Option<u32> = None;
+
+        while thread_running.load(Ordering::Acquire) {
+            let app = get_running_meeting_apps()
+                .into_iter()
+                .find(|a| a.is_active || !require_foreground);
+            let now = Instant::now();
+
+            state = match (state, app) {
+                (WatchState::Idle, Some(_)) => WatchState::Pending(now),
+                (WatchState::Pending(since), Some(app)) if now - since >= start_delay => {
+                    let result = start_capture_with(
+                        Some(Arc::clone(&callback)),
+                        Some(labeled_options(capture_options.clone(), &app)),
+                        |user_data| unsafe { crate::voxtape_sck_start_capture(sck_audio_callback, user_data) },
+                    );
+                    match result {
+                        Ok(()) => {
+                            native_log!(Info, "Auto-capture started for {}", app.name);
+                            session_id = active_session_id();
+                            emit("started", Some(&app), None, session_id);
+                            trigger_app = Some(app);
+                            WatchState::Capturing
+                        }
+                        Err(e) => {
+                            emit("error", Some(&app), Some(e.reason.clone()), None);
+                            WatchState::Backoff(now + RETRY_DELAY)
+                        }
+                    }
+                }
+                (WatchState::Pending(since), Some(_)) => WatchState::Pending(since),
+                (WatchState::Pending(_), None) => WatchState::Idle,
+                (WatchState::Capturing, Some(_)) => WatchState::Capturing,
+                (WatchState::Capturing, None) => WatchState::Leaving(now),
+                (WatchState::Leaving(_), Some(_)) => WatchState::Capturing,
+                (WatchState::Leaving(since), None) if now - since >= stop_grace => {
+                    // Someone else may have stopped our session, and maybe
+                    // started their own; leave theirs running
+                    if is_current(session_id) {
+                        if let Err(e) = stop_capture() {
+                            emit("error", trigger_app.as_ref(), Some(e.reason.clone()), session_id);
+                        }
+                        native_log!(Info, "Auto-capture stopped");
+                        emit("stopped", trigger_app.take().as_ref(), None, session_id.take());
+                    } else {
+                        native_log!(Info, "Auto-capture session already ended");
+                        (trigger_app, session_id) = (None, None);
+                    }
+                    WatchState::Idle
+                }
+                (WatchState::Leaving(since), None) => WatchState::Leaving(since),
+                (WatchState::Backoff(until), _) if now >= until => WatchState::Idle,
+                (state @ WatchState::Backoff(_), _) => state,
+                (WatchState::Idle, None) => WatchState::Idle,
+            };
+
+            std::thread::sleep(poll_interval);
+        }
+
+        // Shutting down: end a session we started, if it's still running
+        if matches!(state, WatchState::Capturing | WatchState::Leaving(_)) && is_current(session_id) {
+            let _ = stop_capture();
+            emit("stopped", trigger_app.as_ref(), None, session_id);
+        }
+    });
+
+    *guard = Some(AutoCaptureHandle { running, thread });
+    Ok(())
+}
+
+/// Stop auto-capture mode, ending any capture session it started.
+#[napi]
+pub fn stop_auto_capture() -> Result<()> {
+    let handle = auto_mutex()
+        .lock()
+        .map_err(|e| Error::from_reason(format!("Failed to acquire auto-capture lock: {}", e)))?
+        .take();
+    if let Some(handle) = handle {
+        handle.running.store(false, Ordering::Release);
+        let _ = handle.thread.join();
+    }
+    Ok(())
+}
@@ -0,0 +1,129 @@
+//! Hard cap on audio waiting in native memory for JS
+//!
+//! Chunks handed to a ThreadsafeFunction sit in its queue until the JS
+//! thread gets to them, and that queue is unbounded: a stalled event loop
+//! would grow it until the process runs out of memory. Each queued chunk
+//! holds a `BacklogToken` for its samples, released once JS has received
+//! it; new chunks that would take the total past the cap are dropped.
+//!
+//! The cap is counted in samples of the 16kHz mono output. Extra outputs at
+//! other rates and channel counts share it, their chunks converted by
+//! duration with `base_samples`.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Rate the backlog is counted at (the 16kHz mono output)
+const SAMPLE_RATE: u64 = 16000;
+/// Samples per millisecond at that rate
+const SAMPLES_PER_MS: u64 = SAMPLE_RATE / 1000;
+/// Minimum time between overflow warnings
+const WARNING_INTERVAL: Duration = Duration::from_secs(10);
+
+pub struct Backlog {
+    capacity: u64,
+    queued: AtomicU64,
+    dropped: AtomicU64,
+    last_warning: Mutex<Option<Instant>>,
+}
+
+impl Backlog {
+    pub fn new(max_ms: u32) -> Arc<Self> {
+        Arc::new(Self {
+            capacity: max_ms as u64 * SAMPLES_PER_MS,
+            queued: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            last_warning: Mutex::new(None),
+        })
+    }
+
+    /// Claim room for a chunk of `samples`, or `None` if it doesn't fit.
+    pub fn reserve(self: &Arc<Self>, samples: usize) -> Option<BacklogToken> {
+        let samples = samples as u64;
+        self.queued
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |queued| {
+                (queued + samples <= self.capacity).then_some(queued + samples)
+            })
+            .ok()?;
+        Some(BacklogToken {
+            backlog: Arc::clone(self),
+            samples,
+        })
+    }
+
+    /// Count `samples` dropped for lack of room (here or in another bounded
+    /// buffer). Returns a warning to surface, at most every 10s.
+    pub fn record_drop(&self, samples: usize) -> Option<String> {
+        let total = self.dropped.fetch_add(samples as u64, Ordering::Relaxed) + samples as u64;
+        let mut last = self.last_warning.lock().ok()?;
+        if last.is_some_and(|t| t.elapsed() < WARNING_INTERVAL) {
+            return None;
+        }
+        *last = Some(Instant::now());
+        Some(format!(
+            "Audio is backing up faster than it's consumed; dropped {}ms so far \
+             (maxBufferedMs is {}). Check that the JS thread isn't blocked.",
+            total / SAMPLES_PER_MS,
+            self.capacity / SAMPLES_PER_MS
+        ))
+    }
+}
+
+/// Length of a chunk of `samples` interleaved samples at `sample_rate` with
+/// `channels` channels, in the backlog's 16kHz mono samples.
+pub fn base_samples(samples: usize, sample_rate: u32, channels: u32) -> usize {
+    let per_second = sample_rate as u64 * channels.max(1) as u64;
+    (samples as u64 * SAMPLE_RATE).div_ceil(per_second.max(1)) as usize
+}
+
+/// Room held in a `Backlog` until dropped.
+pub struct BacklogToken {
+    backlog: Arc<Backlog>,
+    samples: u64,
+}
+
+impl Drop for BacklogToken {
+    fn drop(&mut self) {
+        self.backlog.queued.fetch_sub(self.samples, Ordering::AcqRel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reserve_until_full_then_release() {
+        let backlog = Backlog::new(30);
+        let first = backlog.reserve(320).unwrap();
+        let _second = backlog.reserve(160).unwrap();
+        // 480 samples = 30ms, the cap
+        assert!(backlog.reserve(1).is_none());
+        drop(first);
+        assert!(backlog.reserve(320).is_some());
+    }
+
+    #[test]
+    fn test_drop_warnings_are_rate_limited() {
+        let backlog = Backlog::new(100);
+        let warning = backlog.record_drop(1600).unwrap();
+        assert!(warning.contains("dropped 100ms"), "{}", warning);
+        assert!(backlog.record_drop(1600).is_none());
+        assert_eq!(backlog.dropped.load(Ordering::Relaxed), 3200);
+    }
+
+    #[test]
+    fn test_other_formats_count_by_duration() {
+        // 10ms at 48kHz stereo, 8kHz mono and 16kHz mono
+        assert_eq!(base_samples(960, 48000, 2), 160);
+        assert_eq!(base_samples(80, 8000, 1), 160);
+        assert_eq!(base_samples(160, 16000, 1), 160);
+        assert_eq!(base_samples(1, 44100, 2), 1);
+
+        let backlog = Backlog::new(20);
+        let _main = backlog.reserve(160).unwrap();
+        let _stereo = backlog.reserve(base_samples(960, 48000, 2)).unwrap();
+        assert!(backlog.reserve(base_samples(2, 48000, 2)).is_none());
+    }
+}
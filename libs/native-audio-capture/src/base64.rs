@@ -0,0 +1,50 @@
+//! Standard (RFC 4648) base64 with padding, for `base64` chunk delivery
+//!
+//! Encoding in Rust saves JS a `Buffer.toString('base64')` pass per chunk
+//! when audio is headed for a JSON or HTTP transport.
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub fn encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for group in data.chunks(3) {
+        let b = [group[0], *group.get(1).unwrap_or(&0), *group.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= group.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rfc4648_vectors() {
+        let cases = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(encode(input.as_bytes()), expected);
+        }
+    }
+
+    #[test]
+    fn test_encodes_pcm_bytes() {
+        // Int16 samples 1, -1 as little-endian bytes
+        assert_eq!(encode(&[0x01, 0x00, 0xff, 0xff]), "AQD//w==");
+        assert_eq!(encode(&[0xfb, 0xff, 0xbf]), "+/+/");
+    }
+}
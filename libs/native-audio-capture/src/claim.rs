@@ -0,0 +1,105 @@
+//! One capture start at a time
+//!
+//! Starting a capture takes a while (opening sinks, starting the stream,
+//! retries) and the session state is only stored once the stream runs, so
+//! checking the state on entry isn't enough: two starts racing through the
+//! check would both start a stream, and the second would overwrite the
+//! first's callback context. A start first claims the slot instead, which
+//! checks for a running session and marks a start in progress under the
+//! state lock in one step. The claim is released when dropped, after the
+//! state is stored or when the start fails.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Held by the start in progress; dropping it lets the next start in.
+pub struct StartClaim<'a> {
+    starting: &'a AtomicBool,
+}
+
+impl Drop for StartClaim<'_> {
+    fn drop(&mut self) {
+        self.starting.store(false, Ordering::Release);
+    }
+}
+
+/// Claim the right to start a session, unless `state` holds a running one
+/// (identified by `session_id`) or another start holds `starting`. Call
+/// with the lock guarding `state` held, so the check and the claim are one
+/// step.
+pub fn claim_start<'a, T>(
+    state: &Option<T>,
+    starting: &'a AtomicBool,
+    session_id: impl Fn(&T) -> u32,
+) -> Result<StartClaim<'a>, String> {
+    if let Some(running) = state {
+        return Err(format!(
+            "Already capturing system audio (session {}); only one capture runs at a time",
+            session_id(running)
+        ));
+    }
+    if starting.swap(true, Ordering::AcqRel) {
+        return Err("Another capture is starting; only one capture runs at a time".to_string());
+    }
+    Ok(StartClaim { starting })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Barrier, Mutex};
+    use std::time::Duration;
+
+    #[test]
+    fn test_concurrent_starts_have_exactly_one_winner() {
+        const THREADS: usize = 32;
+        let state: Arc<Mutex<Option<u32>>> = Arc::new(Mutex::new(None));
+        let starting = Arc::new(AtomicBool::new(false));
+        // Stands in for the callback context: only the winner may store it
+        let context: Arc<Mutex<Option<Arc<()>>>> = Arc::new(Mutex::new(None));
+        let live = Arc::new(());
+        let barrier = Arc::new(Barrier::new(THREADS));
+
+        let handles: Vec<_> = (0..THREADS as u32)
+            .map(|id| {
+                let (state, starting, context, barrier) =
+                    (Arc::clone(&state), Arc::clone(&starting), Arc::clone(&context), Arc::clone(&barrier));
+                let ctx = Arc::clone(&live);
+                std::thread::spawn(move || {
+                    barrier.wait();
+                    let _claim = claim_start(&state.lock().unwrap(), &starting, |&id| id)?;
+                    *context.lock().unwrap() = Some(ctx);
+                    // Starting the stream takes a while
+                    std::thread::sleep(Duration::from_millis(20));
+                    *state.lock().unwrap() = Some(id);
+                    Ok::<u32, String>(id)
+                })
+            })
+            .collect();
+        let results: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+        let winners: Vec<u32> = results.iter().filter_map(|r| r.as_ref().ok().copied()).collect();
+        assert_eq!(winners.len(), 1, "{:?}", results);
+        assert_eq!(*state.lock().unwrap(), Some(winners[0]));
+        assert!(results
+            .iter()
+            .filter_map(|r| r.as_ref().err())
+            .all(|e| e.starts_with("Already capturing") || e.starts_with("Another capture is starting")));
+        // Only the winner's context is held, besides our own reference
+        assert_eq!(Arc::strong_count(&live), 2);
+        assert!(!starting.load(Ordering::Acquire));
+    }
+
+    #[test]
+    fn test_failed_start_releases_the_claim() {
+        let mut state: Option<u32> = None;
+        let starting = AtomicBool::new(false);
+        let claim = claim_start(&state, &starting, |&id| id).unwrap();
+        assert!(claim_start(&state, &starting, |&id| id).err().unwrap().starts_with("Another capture"));
+        drop(claim);
+
+        let claim = claim_start(&state, &starting, |&id| id).unwrap();
+        state = Some(7);
+        drop(claim);
+        assert!(claim_start(&state, &starting, |&id| id).err().unwrap().contains("session 7"));
+    }
+}
@@ -0,0 +1,59 @@
+//! Host-clock bookkeeping for aligning the audio stream with other recorders
+//!
+//! SCK stamps every buffer with a presentation time on the mach host clock,
+//! the same clock ScreenCaptureKit video frames and AVFoundation use. Pairing
+//! those stamps with positions in the 16kHz output lets a consumer map any
+//! delivered sample onto that shared timebase.
+
+use std::sync::OnceLock;
+
+extern "C" {
+    fn voxtape_host_timebase(numer: *mut u32, denom: *mut u32);
+}
+
+/// Mapping between output sample positions and host time.
+#[derive(Default)]
+pub struct ClockTracker {
+    /// Host time of the first buffer (output sample 0)
+    anchor_host_time: Option<u64>,
+    /// Host time of the most recent buffer
+    latest_host_time: u64,
+    /// Output sample index corresponding to `latest_host_time`
+    latest_sample_index: u64,
+    /// Total 16kHz samples produced so far
+    samples_out: u64,
+}
+
+impl ClockTracker {
+    /// Record a buffer stamped `host_time` that produced `output_len` samples.
+    pub fn record(&mut self, host_time: u64, output_len: usize) {
+        if host_time != 0 {
+            self.anchor_host_time.get_or_insert(host_time);
+            self.latest_host_time = host_time;
+            self.latest_sample_index = self.samples_out;
+        }
+        self.samples_out += output_len as u64;
+    }
+
+    pub fn anchor_host_time(&self) -> Option<u64> {
+        self.anchor_host_time
+    }
+
+    pub fn latest(&self) -> (u64, u64) {
+        (self.latest_host_time, self.latest_sample_index)
+    }
+}
+
+/// Host clock timebase as (numer, denom): ticks * numer / denom = nanoseconds.
+pub fn host_timebase() -> (u32, u32) {
+    let (mut numer, mut denom) = (1, 1);
+    unsafe { voxtape_host_timebase(&mut numer, &mut denom) };
+    (numer, denom)
+}
+
+/// Convert host-clock ticks to nanoseconds.
+pub fn host_time_to_ns(ticks: u64) -> u64 {
+    static TIMEBASE: OnceLock<(u32, u32)> = OnceLock::new();
+    let (numer, denom) = *TIMEBASE.get_or_init(host_timebase);
+    (ticks as u128 * numer as u128 / denom.max(1) as u128) as u64
+}
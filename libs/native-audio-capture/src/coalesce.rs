@@ -0,0 +1,58 @@
+//! Callback coalescing for `coalesceMs`
+//!
+//! SCK can hand over many small buffers a second, and each delivery wakes
+//! the JS event loop. Chunks are held until at least the configured amount
+//! of audio has built up, then delivered together as one. Unlike
+//! `Framer`, sizes aren't fixed: a batch is every chunk accumulated so far,
+//! so no chunk is ever split.
+
+pub struct Coalescer {
+    min_samples: usize,
+    pending: Vec<i16>,
+}
+
+impl Coalescer {
+    /// Deliver once at least `min_samples` have accumulated.
+    pub fn new(min_samples: usize) -> Self {
+        Self {
+            min_samples,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Add a chunk, returning the batch if it is now due.
+    pub fn push(&mut self, samples: &[i16]) -> Option<Vec<i16>> {
+        if self.pending.is_empty() && samples.len() >= self.min_samples {
+            return Some(samples.to_vec());
+        }
+        self.pending.extend_from_slice(samples);
+        (self.pending.len() >= self.min_samples).then(|| std::mem::take(&mut self.pending))
+    }
+
+    /// Take whatever is still held, if anything.
+    pub fn flush(&mut self) -> Option<Vec<i16>> {
+        (!self.pending.is_empty()).then(|| std::mem::take(&mut self.pending))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small_chunks_are_batched_and_large_ones_pass() {
+        let mut coalescer = Coalescer::new(320);
+        assert_eq!(coalescer.push(&[1; 100]), None);
+        assert_eq!(coalescer.push(&[2; 100]), None);
+        let batch = coalescer.push(&[3; 200]).unwrap();
+        assert_eq!(batch.len(), 400);
+        assert_eq!((batch[0], batch[100], batch[399]), (1, 2, 3));
+
+        // A chunk already past the threshold goes straight through
+        assert_eq!(coalescer.push(&[4; 480]).map(|b| b.len()), Some(480));
+
+        assert_eq!(coalescer.push(&[5; 10]), None);
+        assert_eq!(coalescer.flush(), Some(vec![5; 10]));
+        assert_eq!(coalescer.flush(), None);
+    }
+}
@@ -0,0 +1,123 @@
+//! Offline PCM conversion for `resample_pcm`
+//!
+//! Runs a whole recorded buffer through the same `Resampler` live capture
+//! uses, so batch-converted audio matches what transcription sees live.
+//! Samples are little-endian Int16 or Float32, interleaved.
+
+use crate::resampler::Resampler;
+
+/// Sample format of a raw PCM buffer
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PcmFormat {
+    /// Int16 little-endian
+    S16,
+    /// Float32 little-endian, -1..1
+    F32,
+}
+
+impl PcmFormat {
+    /// Parse a format name: "s16le" (also "s16", "int16") or "f32le" (also
+    /// "f32", "float32").
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "s16le" | "s16" | "int16" => Some(Self::S16),
+            "f32le" | "f32" | "float32" => Some(Self::F32),
+            _ => None,
+        }
+    }
+
+    fn sample_size(self) -> usize {
+        match self {
+            Self::S16 => 2,
+            Self::F32 => 4,
+        }
+    }
+}
+
+/// Resample interleaved `input` to mono at `output_rate`.
+pub fn resample_pcm(
+    input: &[u8],
+    input_rate: u32,
+    channels: u32,
+    input_format: PcmFormat,
+    output_rate: u32,
+    output_format: PcmFormat,
+) -> Result<Vec<u8>, String> {
+    if input_rate == 0 || output_rate == 0 {
+        return Err("Sample rates must be greater than 0".to_string());
+    }
+    if channels == 0 {
+        return Err("channels must be greater than 0".to_string());
+    }
+    let frame_size = input_format.sample_size() * channels as usize;
+    if !input.len().is_multiple_of(frame_size) {
+        return Err(format!(
+            "Input is {} bytes, not a whole number of {}-byte frames",
+            input.len(),
+            frame_size
+        ));
+    }
+
+    let samples: Vec<f32> = match input_format {
+        PcmFormat::S16 => input
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0)
+            .collect(),
+        PcmFormat::F32 => input
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect(),
+    };
+
+    let mut resampler = Resampler::with_output_rate(output_rate);
+    Ok(match output_format {
+        PcmFormat::S16 => resampler
+            .process(&samples, channels, input_rate)
+            .iter()
+            .flat_map(|s| s.to_le_bytes())
+            .collect(),
+        PcmFormat::F32 => resampler
+            .process_to_float(&samples, channels, input_rate)
+            .iter()
+            .flat_map(|s| s.to_le_bytes())
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_live_resampling_in_both_formats() {
+        let input: Vec<f32> = (0..9600)
+            .map(|i| 0.4 * (2.0 * std::f32::consts::PI * 440.0 * (i / 2) as f32 / 48000.0).sin())
+            .collect();
+        let bytes: Vec<u8> = input.iter().flat_map(|s| s.to_le_bytes()).collect();
+
+        let live = Resampler::new().process(&input, 2, 48000);
+        let converted = resample_pcm(&bytes, 48000, 2, PcmFormat::F32, 16000, PcmFormat::S16).unwrap();
+        assert_eq!(converted.len(), live.len() * 2);
+        assert!(converted.chunks_exact(2).map(|b| i16::from_le_bytes([b[0], b[1]])).eq(live.iter().copied()));
+
+        // Float output is the same signal before rounding
+        let float = resample_pcm(&bytes, 48000, 2, PcmFormat::F32, 16000, PcmFormat::F32).unwrap();
+        let float: Vec<f32> = float.chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect();
+        assert_eq!(float.len(), live.len());
+        assert!(float.iter().zip(&live).all(|(&f, &s)| (f * 32767.0 - s as f32).abs() <= 0.5));
+
+        // Int16 input
+        let s16: Vec<u8> = input.iter().flat_map(|&s| ((s * 32768.0) as i16).to_le_bytes()).collect();
+        let from_s16 = resample_pcm(&s16, 48000, 2, PcmFormat::S16, 16000, PcmFormat::S16).unwrap();
+        assert_eq!(from_s16.len(), converted.len());
+    }
+
+    #[test]
+    fn test_rejects_bad_input() {
+        assert_eq!(PcmFormat::parse("Float32"), Some(PcmFormat::F32));
+        assert_eq!(PcmFormat::parse("u8"), None);
+        assert!(resample_pcm(&[0; 6], 48000, 2, PcmFormat::S16, 16000, PcmFormat::S16).is_err());
+        assert!(resample_pcm(&[0; 8], 0, 2, PcmFormat::S16, 16000, PcmFormat::S16).is_err());
+        assert!(resample_pcm(&[], 48000, 2, PcmFormat::F32, 16000, PcmFormat::S16).unwrap().is_empty());
+    }
+}
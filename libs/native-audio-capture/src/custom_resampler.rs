@@ -0,0 +1,142 @@
+//! JS-supplied resampling and processing
+//!
+//! Lets a consumer replace the built-in FIR/decimator with their own DSP, or
+//! add a processing step after it, while keeping capture, permissions and
+//! delivery. The JS function runs on the main thread, so the audio thread
+//! blocks on its result for up to `CALL_TIMEOUT`; chunks that take longer are
+//! dropped by a resampler, and passed through unprocessed by a frame processor.
+
+use std::sync::mpsc;
+use std::time::Duration;
+
+use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use napi_derive::napi;
+
+use crate::logging::native_log;
+use crate::resampler::{FloatProcessor, SampleProcessor};
+
+/// Longest the audio thread waits for the JS resampler (one SCK buffer is ~10–20ms)
+const CALL_TIMEOUT: Duration = Duration::from_millis(50);
+
+/// Raw capture chunk handed to a custom resampler
+#[napi(object)]
+pub struct RawAudioChunk {
+    /// Interleaved float32 frames as delivered by ScreenCaptureKit
+    pub samples: Float32Array,
+    /// Channels per frame
+    pub channels: u32,
+    /// Input sample rate in Hz
+    pub sample_rate: u32,
+}
+
+/// `SampleProcessor` that forwards each chunk to a JS function.
+pub struct JsResampler {
+    func: ThreadsafeFunction<RawAudioChunk, Int16Array>,
+    timeout_reported: bool,
+}
+
+impl JsResampler {
+    pub fn new(func: ThreadsafeFunction<RawAudioChunk, Int16Array>) -> Self {
+        Self {
+            func,
+            timeout_reported: false,
+        }
+    }
+}
+
+impl SampleProcessor for JsResampler {
+    fn process(&mut self, input: &[f32], channels: u32, input_rate: u32) -> Vec<i16> {
+        let chunk = RawAudioChunk {
+            samples: Float32Array::new(input.to_vec()),
+            channels,
+            sample_rate: input_rate,
+        };
+        let (tx, rx) = mpsc::sync_channel(1);
+        let status = self.func.call_with_return_value(
+            Ok(chunk),
+            ThreadsafeFunctionCallMode::NonBlocking,
+            move |result: Result<Int16Array>, _env| {
+                let _ = tx.send(result.map(|samples| samples.to_vec()));
+                Ok(())
+            },
+        );
+        if status != Status::Ok {
+            return Vec::new();
+        }
+
+        match rx.recv_timeout(CALL_TIMEOUT) {
+            Ok(Ok(samples)) => samples,
+            Ok(Err(e)) => {
+                native_log!(Warn, "Custom resampler threw: {}", e.reason);
+                Vec::new()
+            }
+            Err(_) => {
+                if !self.timeout_reported {
+                    self.timeout_reported = true;
+                    native_log!(
+                        Warn,
+                        "Custom resampler took longer than {}ms; dropping chunks until it keeps up",
+                        CALL_TIMEOUT.as_millis()
+                    );
+                }
+                Vec::new()
+            }
+        }
+    }
+}
+
+/// `FloatProcessor` that forwards each chunk of resampled samples to a JS
+/// function and takes back what it returns.
+pub struct JsFrameProcessor {
+    func: ThreadsafeFunction<Float32Array, Float32Array>,
+    failure_reported: bool,
+}
+
+impl JsFrameProcessor {
+    pub fn new(func: ThreadsafeFunction<Float32Array, Float32Array>) -> Self {
+        Self {
+            func,
+            failure_reported: false,
+        }
+    }
+
+    /// Log the first failure only; the chunk goes on unprocessed either way.
+    fn report(&mut self, message: String) {
+        if !self.failure_reported {
+            self.failure_reported = true;
+            native_log!(Warn, "{}; passing chunks through unprocessed", message);
+        }
+    }
+}
+
+impl FloatProcessor for JsFrameProcessor {
+    fn process(&mut self, samples: &mut [f32]) {
+        if samples.is_empty() {
+            return;
+        }
+        let (tx, rx) = mpsc::sync_channel(1);
+        let status = self.func.call_with_return_value(
+            Ok(Float32Array::new(samples.to_vec())),
+            ThreadsafeFunctionCallMode::NonBlocking,
+            move |result: Result<Float32Array>, _env| {
+                let _ = tx.send(result.map(|processed| processed.to_vec()));
+                Ok(())
+            },
+        );
+        if status != Status::Ok {
+            return;
+        }
+
+        match rx.recv_timeout(CALL_TIMEOUT) {
+            Ok(Ok(processed)) if processed.len() == samples.len() => samples.copy_from_slice(&processed),
+            Ok(Ok(processed)) => self.report(format!(
+                "Frame processor returned {} samples for {}",
+                processed.len(),
+                samples.len()
+            )),
+            Ok(Err(e)) => self.report(format!("Frame processor threw: {}", e.reason)),
+            Err(_) => self.report(format!("Frame processor took longer than {}ms", CALL_TIMEOUT.as_millis())),
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! Audio device enumeration for the `device` capture backend
+//!
+//! Lists CoreAudio devices so a UI can pick a specific one to record, e.g. a
+//! BlackHole or Loopback device, or an aggregate built in Audio MIDI Setup,
+//! rather than the whole system mix.
+
+use std::os::raw::c_char;
+
+use napi_derive::napi;
+
+/// FFI struct for device info from ObjC
+#[repr(C)]
+struct CDeviceInfo {
+    device_id: u32,
+    uid: *const c_char,
+    name: *const c_char,
+    transport: u32,
+    input_channels: u32,
+    output_channels: u32,
+    sample_rate: u32,
+    is_default_input: i32,
+    is_default_output: i32,
+}
+
+extern "C" {
+    fn voxtape_list_audio_devices(out_count: *mut i32) -> *mut CDeviceInfo;
+    fn voxtape_free_audio_devices(devices: *mut CDeviceInfo, count: i32);
+}
+
+/// An audio device that can be captured with `backend: "device"`
+#[napi(object)]
+#[derive(Clone)]
+pub struct AudioDeviceInfo {
+    /// CoreAudio device id, for `CaptureOptions.deviceId`. Only valid until
+    /// the device is unplugged or the system restarts; use `uid` to remember it
+    pub id: u32,
+    /// Persistent identifier
+    pub uid: String,
+    /// Device name as shown in Sound settings
+    pub name: String,
+    /// Transport, as in `OutputDeviceInfo.transport` ("virtual" for most
+    /// loopback drivers, "aggregate" for aggregate devices)
+    pub transport: String,
+    pub input_channels: u32,
+    pub output_channels: u32,
+    /// Nominal sample rate in Hz (0 if unknown)
+    pub sample_rate: u32,
+    /// Whether this is the system default for its direction
+    pub is_default: bool,
+}
+
+/// Every device, with `is_default` taken from the given direction.
+fn list_devices(input: bool) -> Vec<AudioDeviceInfo> {
+    #[cfg(target_os = "macos")]
+    unsafe {
+        let mut count: i32 = 0;
+        let ptr = voxtape_list_audio_devices(&mut count);
+        if ptr.is_null() || count == 0 {
+            return Vec::new();
+        }
+        let string = |s: *const c_char| {
+            if s.is_null() {
+                String::new()
+            } else {
+                std::ffi::CStr::from_ptr(s).to_string_lossy().into_owned()
+            }
+        };
+        let devices = std::slice::from_raw_parts(ptr, count as usize)
+            .iter()
+            .map(|d| AudioDeviceInfo {
+                id: d.device_id,
+                uid: string(d.uid),
+                name: string(d.name),
+                transport: crate::output_device::transport_name(d.transport).to_string(),
+                input_channels: d.input_channels,
+                output_channels: d.output_channels,
+                sample_rate: d.sample_rate,
+                is_default: if input { d.is_default_input != 0 } else { d.is_default_output != 0 },
+            })
+            .collect();
+        voxtape_free_audio_devices(ptr, count);
+        devices
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = input;
+        Vec::new()
+    }
+}
+
+/// Devices with input channels. Capturing one records its input, which for
+/// loopback drivers and aggregate devices is whatever is routed into them.
+#[napi]
+pub fn list_input_devices() -> Vec<AudioDeviceInfo> {
+    list_devices(true)
+        .into_iter()
+        .filter(|d| d.input_channels > 0)
+        .collect()
+}
+
+/// Devices with output channels. Capturing an output-only device records
+/// what plays through it, through a process tap on that device (macOS 14.2+).
+#[napi]
+pub fn list_output_devices() -> Vec<AudioDeviceInfo> {
+    list_devices(false)
+        .into_iter()
+        .filter(|d| d.output_channels > 0)
+        .collect()
+}
+
+/// Look up a device by id (None if it doesn't exist), with `is_default`
+/// for the direction the `device` backend records it in.
+pub(crate) fn find_device(id: u32) -> Option<AudioDeviceInfo> {
+    let device = list_devices(true).into_iter().find(|d| d.id == id)?;
+    if device.input_channels > 0 {
+        return Some(device);
+    }
+    list_devices(false).into_iter().find(|d| d.id == id)
+}
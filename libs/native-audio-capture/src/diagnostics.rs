@@ -0,0 +1,208 @@
+//! Support diagnostics: a short history of warnings and errors, and a
+//! minimal JSON writer for `diagnostics_json` (the crate has no serde).
+
+use std::collections::VecDeque;
+
+/// Entries kept in the event log
+const MAX_EVENTS: usize = 50;
+
+/// One logged warning or error
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoggedEvent {
+    /// Wall-clock time (ms since the Unix epoch)
+    pub at_ms: f64,
+    pub message: String,
+}
+
+/// The most recent warnings and errors, oldest first
+#[derive(Default)]
+pub struct EventLog {
+    events: VecDeque<LoggedEvent>,
+}
+
+impl EventLog {
+    pub fn push(&mut self, at_ms: f64, message: impl Into<String>) {
+        if self.events.len() == MAX_EVENTS {
+            self.events.pop_front();
+        }
+        self.events.push_back(LoggedEvent {
+            at_ms,
+            message: message.into(),
+        });
+    }
+
+    pub fn events(&self) -> impl Iterator<Item = &LoggedEvent> {
+        self.events.iter()
+    }
+}
+
+/// A JSON value, built up by hand
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    /// Fields keep their insertion order
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn object() -> Self {
+        Json::Object(Vec::new())
+    }
+
+    /// Add a field to an object (ignored for other values).
+    pub fn field(mut self, key: &str, value: impl Into<Json>) -> Self {
+        if let Json::Object(fields) = &mut self {
+            fields.push((key.to_string(), value.into()));
+        }
+        self
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        self.write(&mut out);
+        out
+    }
+
+    fn write(&self, out: &mut String) {
+        match self {
+            Json::Null => out.push_str("null"),
+            Json::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+            // JSON has no NaN or infinity
+            Json::Number(n) if !n.is_finite() => out.push_str("null"),
+            Json::Number(n) => out.push_str(&n.to_string()),
+            Json::String(s) => write_string(s, out),
+            Json::Array(items) => {
+                out.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    item.write(out);
+                }
+                out.push(']');
+            }
+            Json::Object(fields) => {
+                out.push('{');
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    write_string(key, out);
+                    out.push(':');
+                    value.write(out);
+                }
+                out.push('}');
+            }
+        }
+    }
+}
+
+fn write_string(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+impl From<bool> for Json {
+    fn from(b: bool) -> Self {
+        Json::Bool(b)
+    }
+}
+
+impl From<f64> for Json {
+    fn from(n: f64) -> Self {
+        Json::Number(n)
+    }
+}
+
+impl From<u32> for Json {
+    fn from(n: u32) -> Self {
+        Json::Number(n as f64)
+    }
+}
+
+impl From<i64> for Json {
+    fn from(n: i64) -> Self {
+        Json::Number(n as f64)
+    }
+}
+
+impl From<&str> for Json {
+    fn from(s: &str) -> Self {
+        Json::String(s.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(s: String) -> Self {
+        Json::String(s)
+    }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Json::Null, Into::into)
+    }
+}
+
+impl<T: Into<Json>> From<Vec<T>> for Json {
+    fn from(items: Vec<T>) -> Self {
+        Json::Array(items.into_iter().map(Into::into).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_renders_nested_values() {
+        let json = Json::object()
+            .field("name", "tap")
+            .field("rate", 16000u32)
+            .field("latency", Some(1.5))
+            .field("missing", None::<bool>)
+            .field("tags", vec!["a", "b"])
+            .field("inner", Json::object().field("ok", true));
+        assert_eq!(
+            json.render(),
+            r#"{"name":"tap","rate":16000,"latency":1.5,"missing":null,"tags":["a","b"],"inner":{"ok":true}}"#
+        );
+    }
+
+    #[test]
+    fn test_escapes_strings_and_non_finite_numbers() {
+        let json = Json::object()
+            .field("message", "say \"hi\"\n\\ \u{1}")
+            .field("nan", f64::NAN);
+        assert_eq!(
+            json.render(),
+            r#"{"message":"say \"hi\"\n\\ \u0001","nan":null}"#
+        );
+    }
+
+    #[test]
+    fn test_event_log_keeps_most_recent() {
+        let mut log = EventLog::default();
+        for i in 0..MAX_EVENTS + 5 {
+            log.push(i as f64, format!("event {}", i));
+        }
+        let events: Vec<&LoggedEvent> = log.events().collect();
+        assert_eq!(events.len(), MAX_EVENTS);
+        assert_eq!(events[0].message, "event 5");
+        assert_eq!(events[MAX_EVENTS - 1].at_ms, (MAX_EVENTS + 4) as f64);
+    }
+}
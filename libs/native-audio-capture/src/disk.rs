@@ -0,0 +1,85 @@
+//! Free-space guard for file capture
+//!
+//! A recording that runs the disk full fails mid-write and leaves a WAV
+//! whose header was never patched. Free space on the recording's volume is
+//! polled while capturing: below the warning threshold the consumer hears
+//! about it once (again only after space recovers), and below the stop
+//! reserve the session is ended while finalizing the files still fits.
+
+/// Warning threshold when `lowDiskSpaceMb` isn't set
+pub const DEFAULT_LOW_DISK_SPACE_MB: u32 = 500;
+/// Free space left when capture is stopped (or refused); at most the
+/// warning threshold
+const STOP_RESERVE_BYTES: u64 = 100 * 1024 * 1024;
+
+#[derive(Debug, PartialEq)]
+pub enum DiskAction {
+    Continue,
+    /// Crossed below the warning threshold
+    Warn,
+    /// Too little left to keep writing; stop now
+    Stop,
+}
+
+pub struct DiskGuard {
+    warn_bytes: u64,
+    stop_bytes: u64,
+    warned: bool,
+}
+
+impl DiskGuard {
+    pub fn new(low_disk_space_mb: u32) -> Self {
+        let warn_bytes = low_disk_space_mb as u64 * 1024 * 1024;
+        Self {
+            warn_bytes,
+            stop_bytes: STOP_RESERVE_BYTES.min(warn_bytes),
+            warned: false,
+        }
+    }
+
+    /// Whether `available` bytes are too few to start writing at all.
+    pub fn refuses_start(&self, available: u64) -> bool {
+        available < self.stop_bytes
+    }
+
+    /// What to do with `available` bytes free.
+    pub fn check(&mut self, available: u64) -> DiskAction {
+        if available < self.stop_bytes {
+            return DiskAction::Stop;
+        }
+        if available >= self.warn_bytes {
+            self.warned = false;
+            return DiskAction::Continue;
+        }
+        if std::mem::replace(&mut self.warned, true) {
+            DiskAction::Continue
+        } else {
+            DiskAction::Warn
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: u64 = 1024 * 1024;
+
+    #[test]
+    fn test_warns_once_then_stops_at_the_reserve() {
+        let mut guard = DiskGuard::new(500);
+        assert_eq!(guard.check(2000 * MB), DiskAction::Continue);
+        assert_eq!(guard.check(400 * MB), DiskAction::Warn);
+        assert_eq!(guard.check(300 * MB), DiskAction::Continue);
+        // Space freed up, then ran low again: warn again
+        assert_eq!(guard.check(600 * MB), DiskAction::Continue);
+        assert_eq!(guard.check(450 * MB), DiskAction::Warn);
+        assert_eq!(guard.check(99 * MB), DiskAction::Stop);
+        assert!(guard.refuses_start(99 * MB) && !guard.refuses_start(100 * MB));
+
+        // A threshold below the reserve stops right at it
+        let mut guard = DiskGuard::new(50);
+        assert_eq!(guard.check(60 * MB), DiskAction::Continue);
+        assert_eq!(guard.check(49 * MB), DiskAction::Stop);
+    }
+}
@@ -0,0 +1,118 @@
+//! Encoded file sink: 16kHz mono Int16 → AAC (.m4a) or Opus (.caf)
+//!
+//! Wraps the AudioToolbox ExtAudioFile encoder exposed by the ObjC bridge.
+//! Fed from the capture callback with the same resampled samples that are
+//! delivered to JS, so the file and the PCM stream stay sample-aligned.
+
+use std::ffi::{c_void, CString};
+use std::os::raw::c_char;
+use std::ptr;
+
+use crate::RecordingMetadata;
+
+extern "C" {
+    fn voxtape_encoder_open(
+        path: *const c_char,
+        format: i32,
+        title: *const c_char,
+        artist: *const c_char,
+        comment: *const c_char,
+    ) -> *mut c_void;
+    fn voxtape_encoder_write(handle: *mut c_void, samples: *const i16, count: u32) -> i32;
+    fn voxtape_encoder_close(handle: *mut c_void);
+    fn voxtape_encoder_supported(format: i32) -> i32;
+}
+
+/// Codec used for the encoded sink.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EncodedFormat {
+    /// AAC-LC in an MPEG-4 (.m4a) container
+    Aac,
+    /// Opus in a Core Audio (.caf) container
+    Opus,
+}
+
+impl EncodedFormat {
+    /// Parse the `encodedFormat` option; `None` defaults to AAC.
+    pub fn parse(value: Option<&str>) -> Option<Self> {
+        match value.map(str::to_ascii_lowercase).as_deref() {
+            None | Some("aac") => Some(Self::Aac),
+            Some("opus") => Some(Self::Opus),
+            Some(_) => None,
+        }
+    }
+
+    /// Whether this system can encode the format.
+    pub fn is_supported(self) -> bool {
+        unsafe { voxtape_encoder_supported(self.ffi_code()) != 0 }
+    }
+
+    fn ffi_code(self) -> i32 {
+        match self {
+            Self::Aac => 0,
+            Self::Opus => 1,
+        }
+    }
+}
+
+/// An open encoded output file. Finalized when dropped.
+pub struct Encoder {
+    handle: *mut c_void,
+    /// Set once a write has failed; the file isn't written to after that
+    failed: bool,
+}
+
+unsafe impl Send for Encoder {}
+
+impl Encoder {
+    /// Create (or truncate) `path` and prepare it for 16kHz mono input,
+    /// tagged with `metadata`.
+    pub fn open(
+        path: &str,
+        format: EncodedFormat,
+        metadata: Option<&RecordingMetadata>,
+    ) -> Result<Self, String> {
+        let c_path = CString::new(path).map_err(|_| "Encoded path contains a NUL byte".to_string())?;
+        let tag = |value: Option<&String>| {
+            value
+                .map(|v| CString::new(v.as_str()))
+                .transpose()
+                .map_err(|_| "Recording metadata contains a NUL byte".to_string())
+        };
+        let title = tag(metadata.and_then(|m| m.title.as_ref()))?;
+        let artist = tag(metadata.and_then(|m| m.artist.as_ref()))?;
+        let comment = tag(metadata.and_then(|m| m.comment.as_ref()))?;
+        let tag_ptr = |value: &Option<CString>| value.as_ref().map_or(ptr::null(), |v| v.as_ptr());
+        let handle = unsafe {
+            voxtape_encoder_open(
+                c_path.as_ptr(),
+                format.ffi_code(),
+                tag_ptr(&title),
+                tag_ptr(&artist),
+                tag_ptr(&comment),
+            )
+        };
+        if handle.is_null() {
+            return Err(format!("Failed to open encoded output at {}", path));
+        }
+        Ok(Self { handle, failed: false })
+    }
+
+    /// Encode a chunk of 16kHz mono Int16 samples. Returns the OSStatus (0 on
+    /// success). After a failure the encoder gives up: later writes are
+    /// skipped and return 0, so the failure is reported once.
+    pub fn write(&mut self, samples: &[i16]) -> i32 {
+        if samples.is_empty() || self.failed {
+            return 0;
+        }
+        let status = unsafe { voxtape_encoder_write(self.handle, samples.as_ptr(), samples.len() as u32) };
+        self.failed = status != 0;
+        status
+    }
+}
+
+impl Drop for Encoder {
+    fn drop(&mut self) {
+        unsafe { voxtape_encoder_close(self.handle) };
+    }
+}
@@ -0,0 +1,122 @@
+//! Spectral flatness of the 16kHz output
+//!
+//! Flatness is the geometric over the arithmetic mean of the power
+//! spectrum: near 1 for noise-like audio (ambience, unvoiced speech), near 0
+//! for tonal audio (music, sustained tones). The spectrum is averaged over
+//! the 256-sample (16ms) Hann-windowed frames completed between readings,
+//! so a reading doesn't depend on how the audio was chunked.
+
+/// FFT size; 62.5Hz bins at 16kHz
+const FRAME_LEN: usize = 256;
+/// Mean bin power below this (about -100dBFS) counts as silence
+const SILENCE_POWER: f64 = 1e-10;
+
+pub struct SpectralFlatness {
+    window: Vec<f64>,
+    /// Twiddle factors e^(-2πik/N) for the first half of the circle
+    twiddles: Vec<(f64, f64)>,
+    /// Samples of the frame being filled
+    frame: Vec<f64>,
+    /// Power per bin (DC and Nyquist excluded) summed over finished frames
+    power: Vec<f64>,
+    frames: usize,
+}
+
+impl SpectralFlatness {
+    pub fn new() -> Self {
+        let angle = |k: usize| 2.0 * std::f64::consts::PI * k as f64 / FRAME_LEN as f64;
+        Self {
+            window: (0..FRAME_LEN).map(|i| 0.5 - 0.5 * angle(i).cos()).collect(),
+            twiddles: (0..FRAME_LEN / 2).map(|k| (angle(k).cos(), -angle(k).sin())).collect(),
+            frame: Vec::with_capacity(FRAME_LEN),
+            power: vec![0.0; FRAME_LEN / 2 - 1],
+            frames: 0,
+        }
+    }
+
+    /// Add one sample (-1..1), analyzing the frame once it is full.
+    pub fn push(&mut self, value: f64) {
+        self.frame.push(value);
+        if self.frame.len() < FRAME_LEN {
+            return;
+        }
+        let spectrum = self.transform();
+        self.frame.clear();
+        for (sum, (re, im)) in self.power.iter_mut().zip(&spectrum[1..FRAME_LEN / 2]) {
+            *sum += re * re + im * im;
+        }
+        self.frames += 1;
+    }
+
+    /// Flatness (0..1) of the frames finished since the previous call, or
+    /// None if none finished or they were silent.
+    pub fn take(&mut self) -> Option<f64> {
+        if self.frames == 0 {
+            return None;
+        }
+        let bins = self.power.len() as f64;
+        let mean = self.power.iter().sum::<f64>() / bins;
+        let log_mean = self.power.iter().map(|&p| p.max(1e-30).ln()).sum::<f64>() / bins;
+        self.power.fill(0.0);
+        let frames = std::mem::take(&mut self.frames) as f64;
+        if mean / frames < SILENCE_POWER {
+            return None;
+        }
+        Some((log_mean.exp() / mean).min(1.0))
+    }
+
+    /// Radix-2 FFT of the windowed frame.
+    fn transform(&self) -> Vec<(f64, f64)> {
+        let bits = FRAME_LEN.trailing_zeros();
+        let mut data = vec![(0.0, 0.0); FRAME_LEN];
+        for (i, (&x, &w)) in self.frame.iter().zip(&self.window).enumerate() {
+            data[i.reverse_bits() >> (usize::BITS - bits)] = (x * w, 0.0);
+        }
+        let mut size = 2;
+        while size <= FRAME_LEN {
+            let stride = FRAME_LEN / size;
+            for start in (0..FRAME_LEN).step_by(size) {
+                for k in 0..size / 2 {
+                    let (wr, wi) = self.twiddles[k * stride];
+                    let (br, bi) = data[start + k + size / 2];
+                    let t = (br * wr - bi * wi, br * wi + bi * wr);
+                    let a = data[start + k];
+                    data[start + k] = (a.0 + t.0, a.1 + t.1);
+                    data[start + k + size / 2] = (a.0 - t.0, a.1 - t.1);
+                }
+            }
+            size *= 2;
+        }
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flatness_of(samples: impl Iterator<Item = f64>) -> Option<f64> {
+        let mut analyzer = SpectralFlatness::new();
+        samples.for_each(|x| analyzer.push(x));
+        analyzer.take()
+    }
+
+    #[test]
+    fn test_noise_is_flat_and_tones_are_not() {
+        let mut state = 0x2545_f491u32;
+        let noise = (0..16_000).map(|_| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            ((state >> 8) as f64 / (1u32 << 23) as f64 - 1.0) * 0.3
+        });
+        let noise = flatness_of(noise).unwrap();
+        assert!(noise > 0.8, "noise flatness {}", noise);
+
+        let tone = (0..16_000).map(|i| 0.5 * (2.0 * std::f64::consts::PI * 440.0 * i as f64 / 16000.0).sin());
+        let tone = flatness_of(tone).unwrap();
+        assert!(tone < 0.05, "tone flatness {}", tone);
+
+        assert_eq!(flatness_of(std::iter::repeat_n(0.0, 16_000)), None);
+        // No frame finished yet
+        assert_eq!(flatness_of(std::iter::repeat_n(0.3, FRAME_LEN - 1)), None);
+    }
+}
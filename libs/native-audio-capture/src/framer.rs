@@ -0,0 +1,66 @@
+//! Fixed-size delivery for `framesPerCallback`
+//!
+//! Block-based consumers (FFTs, ML models) want exactly N samples per call.
+//! Output is regrouped into blocks of that length; whatever doesn't fill a
+//! block waits for the next chunk, and is flushed as a short final block
+//! when capture stops.
+
+pub struct Framer {
+    frame_len: usize,
+    pending: Vec<i16>,
+}
+
+impl Framer {
+    pub fn new(frame_len: usize) -> Self {
+        Self {
+            frame_len: frame_len.max(1),
+            pending: Vec::with_capacity(frame_len),
+        }
+    }
+
+    /// Add samples, returning every block that is now complete.
+    pub fn push(&mut self, samples: &[i16]) -> Vec<Vec<i16>> {
+        let mut frames = Vec::new();
+        let mut rest = samples;
+        while !rest.is_empty() {
+            let take = (self.frame_len - self.pending.len()).min(rest.len());
+            self.pending.extend_from_slice(&rest[..take]);
+            rest = &rest[take..];
+            if self.pending.len() == self.frame_len {
+                frames.push(std::mem::replace(&mut self.pending, Vec::with_capacity(self.frame_len)));
+            }
+        }
+        frames
+    }
+
+    /// Take the partial block left over, if any.
+    pub fn flush(&mut self) -> Option<Vec<i16>> {
+        (!self.pending.is_empty()).then(|| std::mem::take(&mut self.pending))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_non_final_frames_have_exact_length() {
+        let mut framer = Framer::new(512);
+        let mut frames = Vec::new();
+        let mut next = 0i16;
+        // Irregular chunk sizes, as SCK delivers them at different rates
+        for len in [160, 1024, 7, 480, 2000, 1, 333] {
+            let chunk: Vec<i16> = (0..len).map(|_| { next = next.wrapping_add(1); next }).collect();
+            frames.extend(framer.push(&chunk));
+        }
+        let last = framer.flush().unwrap();
+
+        assert!(frames.iter().all(|f| f.len() == 512));
+        assert_eq!(frames.len() * 512 + last.len(), 4005);
+        assert!(last.len() < 512);
+        // Order and content are preserved across the regrouping
+        let joined: Vec<i16> = frames.into_iter().flatten().chain(last).collect();
+        assert!(joined.iter().enumerate().all(|(i, &s)| s == i as i16 + 1));
+        assert_eq!(framer.flush(), None);
+    }
+}
@@ -0,0 +1,106 @@
+//! G.711 companding (µ-law / A-law) for telephony outputs
+//!
+//! Standard segment-based encoders as in ITU-T G.711, operating on 16-bit
+//! linear PCM and producing one byte per sample.
+
+/// Sample encoding for an output stream
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SampleEncoding {
+    /// Int16 little-endian PCM
+    #[default]
+    Linear,
+    /// 8-bit µ-law (North America / Japan)
+    Mulaw,
+    /// 8-bit A-law (Europe / international)
+    Alaw,
+}
+
+impl SampleEncoding {
+    /// Parse the `encoding` option; `None` defaults to linear.
+    pub fn parse(value: Option<&str>) -> Option<Self> {
+        match value.map(str::to_ascii_lowercase).as_deref() {
+            None | Some("linear") | Some("pcm") => Some(Self::Linear),
+            Some("mulaw") | Some("ulaw") => Some(Self::Mulaw),
+            Some("alaw") => Some(Self::Alaw),
+            Some(_) => None,
+        }
+    }
+
+    /// Encode samples to bytes.
+    pub fn encode(self, samples: &[i16]) -> Vec<u8> {
+        match self {
+            Self::Linear => samples.iter().flat_map(|s| s.to_le_bytes()).collect(),
+            Self::Mulaw => samples.iter().map(|&s| linear_to_ulaw(s)).collect(),
+            Self::Alaw => samples.iter().map(|&s| linear_to_alaw(s)).collect(),
+        }
+    }
+}
+
+const ULAW_BIAS: i32 = 0x84;
+const ULAW_CLIP: i32 = 32635;
+/// Upper bound of each µ-law segment (biased magnitude)
+const ULAW_SEG_END: [i32; 8] = [0xFF, 0x1FF, 0x3FF, 0x7FF, 0xFFF, 0x1FFF, 0x3FFF, 0x7FFF];
+/// Upper bound of each A-law segment (13-bit magnitude)
+const ALAW_SEG_END: [i32; 8] = [0x1F, 0x3F, 0x7F, 0xFF, 0x1FF, 0x3FF, 0x7FF, 0xFFF];
+
+fn segment(value: i32, ends: &[i32; 8]) -> Option<i32> {
+    ends.iter().position(|&end| value <= end).map(|i| i as i32)
+}
+
+/// 16-bit linear → µ-law.
+pub fn linear_to_ulaw(sample: i16) -> u8 {
+    let (magnitude, mask) = if sample < 0 {
+        (-(sample as i32), 0x7F)
+    } else {
+        (sample as i32, 0xFF)
+    };
+    let biased = magnitude.min(ULAW_CLIP) + ULAW_BIAS;
+    match segment(biased, &ULAW_SEG_END) {
+        Some(seg) => (((seg << 4) | ((biased >> (seg + 3)) & 0x0F)) ^ mask) as u8,
+        None => (0x7F ^ mask) as u8,
+    }
+}
+
+/// 16-bit linear → A-law.
+pub fn linear_to_alaw(sample: i16) -> u8 {
+    let value = sample as i32 >> 3;
+    let (magnitude, mask) = if value >= 0 { (value, 0xD5) } else { (-value - 1, 0x55) };
+    match segment(magnitude, &ALAW_SEG_END) {
+        Some(seg) => {
+            let shift = if seg < 2 { 1 } else { seg };
+            (((seg << 4) | ((magnitude >> shift) & 0x0F)) ^ mask) as u8
+        }
+        None => (0x7F ^ mask) as u8,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ulaw_reference_values() {
+        assert_eq!(linear_to_ulaw(0), 0xFF);
+        assert_eq!(linear_to_ulaw(-1), 0x7F);
+        assert_eq!(linear_to_ulaw(32767), 0x80);
+        assert_eq!(linear_to_ulaw(-32768), 0x00);
+        assert_eq!(linear_to_ulaw(1000), 0xCE);
+    }
+
+    #[test]
+    fn test_alaw_reference_values() {
+        assert_eq!(linear_to_alaw(0), 0xD5);
+        assert_eq!(linear_to_alaw(-8), 0x55);
+        assert_eq!(linear_to_alaw(32767), 0xAA);
+        assert_eq!(linear_to_alaw(-32768), 0x2A);
+        assert_eq!(linear_to_alaw(1000), 0xFA);
+    }
+
+    #[test]
+    fn test_encode_sizes() {
+        let samples = [0i16, 1000, -1000];
+        assert_eq!(SampleEncoding::Linear.encode(&samples).len(), 6);
+        assert_eq!(SampleEncoding::Mulaw.encode(&samples), vec![0xFF, 0xCE, 0x4E]);
+        assert_eq!(SampleEncoding::Alaw.encode(&samples).len(), 3);
+    }
+}
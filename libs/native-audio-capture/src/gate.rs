@@ -0,0 +1,138 @@
+//! Silence gate with attack/release hysteresis
+//!
+//! Energy is measured per 10ms frame of 16kHz output. The gate opens only
+//! after the level stays above threshold for the attack time, and closes only
+//! after it stays below for the hangover time, so short dips between words
+//! don't chop off their quiet tails.
+
+/// Samples per analysis frame (10ms at 16kHz)
+const FRAME_LEN: usize = 160;
+
+pub struct SilenceGate {
+    /// Linear RMS threshold on the Int16 scale
+    threshold: f64,
+    attack_ms: f64,
+    hangover_ms: f64,
+    open: bool,
+    /// Time the level has been above threshold while closed
+    above_ms: f64,
+    /// Time the level has been below threshold while open
+    below_ms: f64,
+}
+
+impl SilenceGate {
+    /// - `threshold_db`: level in dBFS below which audio counts as silence
+    /// - `attack_ms`: sustained time above threshold required to open
+    /// - `hangover_ms`: sustained time below threshold required to close
+    pub fn new(threshold_db: f64, attack_ms: u32, hangover_ms: u32) -> Self {
+        Self {
+            threshold: 32768.0 * 10f64.powf(threshold_db / 20.0),
+            attack_ms: attack_ms as f64,
+            hangover_ms: hangover_ms as f64,
+            open: false,
+            above_ms: 0.0,
+            below_ms: 0.0,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Run a chunk through the gate, returning only the frames that pass.
+    pub fn process(&mut self, samples: &[i16]) -> Vec<i16> {
+        let mut output = Vec::with_capacity(samples.len());
+        for frame in samples.chunks(FRAME_LEN) {
+            if self.update(frame) {
+                output.extend_from_slice(frame);
+            }
+        }
+        output
+    }
+
+    /// Advance the state machine by one frame; returns whether it passes.
+    fn update(&mut self, frame: &[i16]) -> bool {
+        let frame_ms = frame.len() as f64 / 16.0;
+        let sum: f64 = frame.iter().map(|&s| (s as f64) * (s as f64)).sum();
+        let loud = (sum / frame.len() as f64).sqrt() >= self.threshold;
+
+        if self.open {
+            if loud {
+                self.below_ms = 0.0;
+            } else {
+                self.below_ms += frame_ms;
+                if self.below_ms >= self.hangover_ms {
+                    self.open = false;
+                    self.below_ms = 0.0;
+                    return false;
+                }
+            }
+        } else if loud {
+            self.above_ms += frame_ms;
+            if self.above_ms >= self.attack_ms {
+                self.open = true;
+                self.above_ms = 0.0;
+            }
+        } else {
+            self.above_ms = 0.0;
+        }
+        self.open
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Speech-like envelope: `burst_ms` of a loud tone, `gap_ms` of near silence, repeated.
+    fn bursts(burst_ms: usize, gap_ms: usize, repeats: usize) -> Vec<i16> {
+        let mut out = Vec::new();
+        for _ in 0..repeats {
+            for n in 0..burst_ms * 16 {
+                let t = n as f64 / 16000.0;
+                out.push((8000.0 * (2.0 * std::f64::consts::PI * 220.0 * t).sin()) as i16);
+            }
+            out.extend(std::iter::repeat_n(3i16, gap_ms * 16));
+        }
+        out
+    }
+
+    /// Feed 10ms frames and count open/close transitions.
+    fn transitions(gate: &mut SilenceGate, input: &[i16]) -> usize {
+        let mut was_open = gate.is_open();
+        let mut count = 0;
+        for frame in input.chunks(FRAME_LEN) {
+            gate.process(frame);
+            if gate.is_open() != was_open {
+                count += 1;
+                was_open = gate.is_open();
+            }
+        }
+        count
+    }
+
+    #[test]
+    fn test_gate_does_not_chatter_between_words() {
+        let mut gate = SilenceGate::new(-40.0, 20, 300);
+        // Gaps shorter than the hangover keep the gate open after the first onset
+        let input = bursts(200, 150, 10);
+        assert_eq!(transitions(&mut gate, &input), 1);
+        assert!(gate.is_open());
+    }
+
+    #[test]
+    fn test_gate_closes_after_hangover() {
+        let mut gate = SilenceGate::new(-40.0, 20, 300);
+        let input = bursts(200, 1000, 3);
+        // Opens and closes once per burst
+        assert_eq!(transitions(&mut gate, &input), 6);
+        assert!(!gate.is_open());
+    }
+
+    #[test]
+    fn test_gate_ignores_short_clicks() {
+        let mut gate = SilenceGate::new(-40.0, 30, 300);
+        let passed = gate.process(&bursts(10, 200, 5));
+        assert!(passed.is_empty());
+    }
+}
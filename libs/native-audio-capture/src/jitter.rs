@@ -0,0 +1,130 @@
+//! Timing statistics for the capture callback
+//!
+//! SCK normally calls back every ~10ms. Wide spread in those intervals means
+//! the callback thread is being descheduled, which is what `realtimePriority`
+//! and paced delivery exist to absorb. Running mean and variance (Welford),
+//! so the cost per callback is constant. `BusyMeter` adds up the time spent
+//! inside the callback, as a measure of what the crate costs in CPU.
+
+use std::time::{Duration, Instant};
+
+#[derive(Default)]
+pub struct IntervalStats {
+    last: Option<Instant>,
+    /// Intervals measured so far
+    count: u64,
+    /// Running mean interval, in ms
+    mean: f64,
+    /// Sum of squared deviations from the mean
+    m2: f64,
+    /// Longest interval seen, in ms
+    max: f64,
+}
+
+impl IntervalStats {
+    /// Note a callback arriving at `now`.
+    pub fn record(&mut self, now: Instant) {
+        if let Some(last) = self.last.replace(now) {
+            let interval = now.saturating_duration_since(last).as_secs_f64() * 1000.0;
+            self.count += 1;
+            let delta = interval - self.mean;
+            self.mean += delta / self.count as f64;
+            self.m2 += delta * (interval - self.mean);
+            self.max = self.max.max(interval);
+        }
+    }
+
+    /// Mean time between callbacks, once two have arrived.
+    pub fn interval_ms(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.mean)
+    }
+
+    /// Standard deviation of the time between callbacks.
+    pub fn jitter_ms(&self) -> Option<f64> {
+        (self.count > 0).then(|| (self.m2 / self.count as f64).sqrt())
+    }
+
+    /// Longest gap between two callbacks.
+    pub fn max_interval_ms(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.max)
+    }
+}
+
+/// Time spent processing audio, relative to the time audio has been flowing.
+#[derive(Default)]
+pub struct BusyMeter {
+    first: Option<Instant>,
+    busy: Duration,
+}
+
+impl BusyMeter {
+    /// Note a callback that started at `started` and has just finished.
+    pub fn add(&mut self, started: Instant, finished: Instant) {
+        self.first.get_or_insert(started);
+        self.busy += finished.saturating_duration_since(started);
+    }
+
+    /// Ms of processing per second of wall time since the first callback,
+    /// summed over every callback, which approximates the CPU the pipeline
+    /// costs (1000 = one core saturated). Wall time inside a callback also
+    /// counts time its thread spent preempted, so this errs high.
+    pub fn ms_per_sec(&self, now: Instant) -> Option<f64> {
+        let elapsed = now.saturating_duration_since(self.first?).as_secs_f64();
+        (elapsed > 0.0).then(|| self.busy.as_secs_f64() * 1000.0 / elapsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn feed(intervals_ms: &[u64]) -> IntervalStats {
+        let mut stats = IntervalStats::default();
+        let mut now = Instant::now();
+        stats.record(now);
+        for &ms in intervals_ms {
+            now += Duration::from_millis(ms);
+            stats.record(now);
+        }
+        stats
+    }
+
+    #[test]
+    fn test_steady_callbacks_have_no_jitter() {
+        let stats = feed(&[10; 50]);
+        assert!((stats.interval_ms().unwrap() - 10.0).abs() < 1e-9);
+        assert!(stats.jitter_ms().unwrap() < 1e-9);
+        assert_eq!(stats.max_interval_ms(), Some(10.0));
+    }
+
+    #[test]
+    fn test_stall_shows_up_as_jitter_and_max() {
+        let stats = feed(&[10, 10, 10, 50, 10, 10, 10, 10]);
+        assert!((stats.interval_ms().unwrap() - 15.0).abs() < 1e-9);
+        // Deviations: seven of -5ms and one of +35ms
+        let expected = ((7.0 * 25.0 + 35.0 * 35.0) / 8.0f64).sqrt();
+        assert!((stats.jitter_ms().unwrap() - expected).abs() < 1e-9);
+        assert_eq!(stats.max_interval_ms(), Some(50.0));
+    }
+
+    #[test]
+    fn test_no_stats_before_two_callbacks() {
+        assert_eq!(feed(&[]).interval_ms(), None);
+        assert_eq!(feed(&[]).jitter_ms(), None);
+    }
+
+    #[test]
+    fn test_busy_meter_reports_ms_per_second() {
+        let mut meter = BusyMeter::default();
+        let start = Instant::now();
+        assert_eq!(meter.ms_per_sec(start), None);
+        // 100 callbacks, 10ms apart, each busy for 0.5ms
+        for i in 0..100 {
+            let entered = start + Duration::from_millis(10 * i);
+            meter.add(entered, entered + Duration::from_micros(500));
+        }
+        let rate = meter.ms_per_sec(start + Duration::from_secs(1)).unwrap();
+        assert!((rate - 50.0).abs() < 1e-9, "{} ms/s", rate);
+    }
+}
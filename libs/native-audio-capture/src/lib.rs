@@ -1,39 +1,270 @@
+mod auto;
+mod backlog;
+mod base64;
+mod claim;
+mod clock;
+mod coalesce;
+mod convert;
+mod custom_resampler;
+mod devices;
+mod diagnostics;
+mod disk;
+mod encoder;
+mod flatness;
+mod framer;
+mod g711;
+mod gate;
+mod jitter;
+mod logging;
+mod loudness;
+mod meter;
+mod mix;
+mod mute;
+mod output_device;
+mod outputs;
+mod pacer;
+#[cfg(feature = "permission-stub")]
+mod permission_stub;
+mod pool;
+mod recent;
 mod resampler;
+mod rolling;
+mod segments;
+mod socket;
+mod tone;
+mod tracks;
+mod vad;
+mod wav;
 
-use std::ffi::{c_void, CStr};
+use std::ffi::{c_void, CStr, CString};
 use std::os::raw::c_char;
-use std::sync::{Arc, Mutex, OnceLock};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 
 use napi::bindgen_prelude::*;
 use napi::threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode};
 use napi_derive::napi;
 
-use resampler::Resampler;
+#[cfg(all(feature = "permission-stub", not(debug_assertions)))]
+compile_error!("The permission-stub feature scripts permission checks and is for debug builds only");
+
+pub use auto::{auto_capture, stop_auto_capture, AutoCaptureEvent, AutoCaptureOptions};
+pub use custom_resampler::RawAudioChunk;
+pub use logging::LogLevel;
+pub use devices::{list_input_devices, list_output_devices, AudioDeviceInfo};
+pub use output_device::{output_device_info, OutputDeviceInfo};
+#[cfg(feature = "permission-stub")]
+pub use permission_stub::{set_permission_stub, PermissionStub};
+pub use tone::emit_test_tone;
+pub use tracks::{start_app_tracks, stop_app_tracks, TrackChunk};
+use backlog::{base_samples, Backlog};
+use claim::claim_start;
+use clock::ClockTracker;
+use coalesce::Coalescer;
+use convert::PcmFormat;
+use custom_resampler::{JsFrameProcessor, JsResampler};
+use diagnostics::{EventLog, Json};
+use disk::{DiskAction, DiskGuard, DEFAULT_LOW_DISK_SPACE_MB};
+use encoder::{EncodedFormat, Encoder};
+use framer::Framer;
+use g711::SampleEncoding;
+use gate::SilenceGate;
+use jitter::{BusyMeter, IntervalStats};
+use logging::native_log;
+use loudness::LoudnessMeter;
+use meter::LevelMeter;
+use mix::{Ducker, MicMix};
+use mute::{GainRamp, SoftMute};
+use outputs::OutputPipeline;
+use pacer::Pacer;
+use pool::PooledBuffer;
+use recent::RecentAudio;
+use resampler::{ChannelMap, ExpanderSettings, MixdownMode, Resampler, ResamplerState, SampleProcessor};
+use rolling::RollingBuffer;
+use segments::SegmentTracker;
+use socket::SocketSink;
+use vad::{Vad, VadSpan};
+use wav::{segment_path, PendingFiles, SegmentedWavWriter, WavSink, WavWriter};
 
 // ── Global capture state ────────────────────────────────────────────────────
 
 /// Tracks which capture backend is active.
+#[derive(Clone, Copy, PartialEq)]
 enum CaptureBackend {
     /// ScreenCaptureKit SCStream (primary, works on macOS 26+)
     Sck,
+    /// CoreAudio global process tap feeding a private aggregate device (macOS 14.2+)
+    CoreAudioTap,
+    /// One chosen CoreAudio device: its input, or a tap on its output
+    Device,
+}
+
+impl CaptureBackend {
+    /// Parse the `backend` option; `None` means the default (SCK).
+    fn parse(name: Option<&str>) -> Option<Self> {
+        match name {
+            None | Some("sck") => Some(Self::Sck),
+            Some("tap") => Some(Self::CoreAudioTap),
+            Some("device") => Some(Self::Device),
+            Some(_) => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Sck => "sck",
+            Self::CoreAudioTap => "tap",
+            Self::Device => "device",
+        }
+    }
 }
 
 struct CaptureState {
     backend: CaptureBackend,
+    /// Options in effect, defaults resolved (for `get_capture_config`)
+    options: CaptureOptions,
+    /// Identifies this session to every window sharing the module
+    session_id: u32,
+    /// Wall-clock start time
+    started_at: std::time::SystemTime,
+    /// Device being recorded, for the "device" backend
+    device: Option<AudioDeviceInfo>,
+    /// Free-space watcher, while recording to a file
+    disk_watcher: Option<JoinHandle<()>>,
 }
 
+static NEXT_SESSION_ID: AtomicU32 = AtomicU32::new(1);
+
+/// Set while a start is in progress; see `claim`
+static STARTING: AtomicBool = AtomicBool::new(false);
+
+/// Held while the stream is restarted after an output change, and by a stop
+/// before it stops the stream, so the two never overlap. Kept apart from the
+/// state lock: a restart can take many seconds, and everything else that
+/// reads the state would wait that long too.
+static RESTART_LOCK: Mutex<()> = Mutex::new(());
+
 static CAPTURE_STATE: OnceLock<Mutex<Option<CaptureState>>> = OnceLock::new();
 
 fn state_mutex() -> &'static Mutex<Option<CaptureState>> {
     CAPTURE_STATE.get_or_init(|| Mutex::new(None))
 }
 
+/// Id of the running capture session, if any.
+fn active_session_id() -> Option<u32> {
+    state_mutex().lock().ok().and_then(|s| s.as_ref().map(|s| s.session_id))
+}
+
 /// Shared context passed to the SCK audio callback via user_data pointer.
 struct CallbackContext {
-    callback: ThreadsafeFunction<Buffer>,
-    resampler: Mutex<Resampler>,
+    /// Id the session gets once started, for events raised on the audio thread
+    session_id: u32,
+    /// JS callback; None for native-only captures (e.g. `record_to_wav`)
+    callback: Option<Arc<ThreadsafeFunction<PooledBuffer>>>,
+    /// Built-in `Resampler` unless a custom one was supplied
+    resampler: Mutex<Box<dyn SampleProcessor>>,
+    /// `primeTail`, until the first buffer tells the input rate to prime at
+    prime_tail: Mutex<Option<Vec<f32>>>,
+    /// Optional encoded file sink fed with the same resampled samples
+    encoder: Option<Mutex<Encoder>>,
+    /// Optional WAV file sink fed with the same resampled samples
+    wav: Option<Mutex<WavSink>>,
+    /// Optional in-memory Opus ring holding the last N ms of audio
+    rolling: Option<Mutex<RollingBuffer>>,
+    /// Last `recentRetentionMs` of output as Int16, for `snapshot_recent`
+    recent: Option<Mutex<RecentAudio>>,
+    /// Optional framed stream over a Unix or TCP socket
+    socket: Option<Mutex<SocketSink>>,
+    /// Optional silence gate applied to JS delivery (file sinks stay continuous)
+    gate: Option<Mutex<SilenceGate>>,
+    /// Optional paced delivery; when set, chunks go through its timer thread
+    pacer: Option<Pacer>,
+    /// Regroups JS chunks into blocks of exactly `framesPerCallback` samples
+    framer: Option<Mutex<Framer>>,
+    /// Batches JS chunks up to `coalesceMs` when neither pacing nor framing
+    coalescer: Option<Mutex<Coalescer>>,
+    /// Set once the JS callback has been released; shared with the pacer
+    consumer_gone: Arc<AtomicBool>,
+    /// Host-clock timestamps of delivered audio
+    clock: Mutex<ClockTracker>,
+    /// Spacing of audio callback invocations
+    callback_timing: Mutex<IntervalStats>,
+    /// Time spent inside the audio callbacks
+    busy: Mutex<BusyMeter>,
+    /// Total output samples clamped to the Int16 range this session
+    clipped_samples: AtomicU64,
+    /// When the last clipping warning was sent to JS (rate-limits the warnings)
+    last_clip_warning: Mutex<Option<Instant>>,
+    /// Set once an unexpected channel count has been reported
+    channel_warning_sent: AtomicBool,
+    /// Set once a buffer shorter than its frame count has been reported
+    buffer_warning_sent: AtomicBool,
+    /// Set once a buffer with a 0Hz sample rate has been reported
+    rate_warning_sent: AtomicBool,
+    /// The bridge's SCK drop count when the session started
+    sck_drop_baseline: u64,
+    /// Sample rate (high 32 bits) and channel count of the latest SCK
+    /// buffer; 0 before the first one
+    input_format: AtomicU64,
+    /// While set, resampled audio is dropped instead of delivered
+    paused: AtomicBool,
+    /// Silences output (with a fade) while `set_muted(true)`, without
+    /// interrupting delivery
+    mute: Mutex<SoftMute>,
+    /// When the current pause began
+    paused_at: Mutex<Option<Instant>>,
+    /// Delivered segments between pauses
+    segments: Mutex<SegmentTracker>,
+    /// Microphone leg when `captureMicrophone` is set
+    mic: Option<Mutex<MicMix>>,
+    /// Set (and `first_audio_cv` notified) when the first SCK buffer arrives
+    first_audio: Mutex<bool>,
+    first_audio_cv: Condvar,
+    /// Output samples still to discard while the pipeline settles
+    warmup_remaining: AtomicU64,
+    /// Extra output configurations registered with `add_capture_output`
+    extra_outputs: Mutex<Vec<ExtraOutput>>,
+    /// How multichannel input is folded for mono outputs
+    mixdown_mode: MixdownMode,
+    /// Next sequence number for JS chunks, when `sequenceNumbers` is set.
+    /// Shared with the pacer, which delivers on its own thread.
+    sequence: Option<Arc<AtomicU32>>,
+    /// Deliver JS chunks as base64 strings
+    base64: bool,
+    /// Cap on audio queued for the JS thread
+    backlog: Arc<Backlog>,
+    /// Voice activity detector attached with `start_vad`
+    vad: Mutex<Option<VadSink>>,
+    /// Level meter attached with `start_level_meter`
+    meter: Mutex<Option<MeterSink>>,
+    /// Receiver of the resampler's pre-processing samples, set with `set_raw_tap`
+    raw_tap: Mutex<Option<ThreadsafeFunction<Float32Array>>>,
+    /// Loudness meter attached with `start_loudness_meter`
+    loudness: Mutex<Option<LoudnessSink>>,
+    /// Whole-session recording for `start_memory_capture`
+    memory: Option<Mutex<MemoryRecording>>,
+}
+
+/// Take the next sequence number from `counter`, if sequencing is enabled.
+fn next_seq(counter: &Option<Arc<AtomicU32>>) -> Option<u32> {
+    counter.as_ref().map(|c| c.fetch_add(1, Ordering::Relaxed))
+}
+
+/// An additional output stream with its own resampling and callback
+struct ExtraOutput {
+    id: u32,
+    pipeline: OutputPipeline,
+    encoding: SampleEncoding,
+    callback: ThreadsafeFunction<PooledBuffer>,
 }
 
+/// Fraction of clipped samples in a chunk above which JS gets a warning
+const CLIP_WARNING_RATIO: f64 = 0.01;
+/// Minimum time between two clipping warnings
+const CLIP_WARNING_INTERVAL: Duration = Duration::from_secs(10);
+
 unsafe impl Send for CallbackContext {}
 unsafe impl Sync for CallbackContext {}
 
@@ -43,6 +274,23 @@ fn context_mutex() -> &'static Mutex<Option<Arc<CallbackContext>>> {
     CALLBACK_CONTEXT.get_or_init(|| Mutex::new(None))
 }
 
+/// Recent warnings and errors, kept across sessions for `diagnostics_json`
+static EVENT_LOG: OnceLock<Mutex<EventLog>> = OnceLock::new();
+
+/// Log a warning or error to stderr and to the diagnostics event log.
+fn log_event(message: &str) {
+    native_log!(Warn, "{}", message);
+    if let Ok(mut log) = EVENT_LOG.get_or_init(|| Mutex::new(EventLog::default())).lock() {
+        log.push(unix_ms(std::time::SystemTime::now()), message);
+    }
+}
+
+fn unix_ms(time: std::time::SystemTime) -> f64 {
+    time.duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs_f64() * 1000.0)
+        .unwrap_or(0.0)
+}
+
 // ── SCK Audio Callback ─────────────────────────────────────────────────────
 
 /// C callback invoked by the ObjC SCStream delegate.
@@ -50,240 +298,3741 @@ fn context_mutex() -> &'static Mutex<Option<Arc<CallbackContext>>> {
 /// and sends to JS via ThreadsafeFunction.
 unsafe extern "C" fn sck_audio_callback(
     data: *const f32,
+    data_len: u32,
     frame_count: u32,
     channels: u32,
+    channel_labels: *const u32,
     sample_rate: u32,
+    host_time: u64,
     user_data: *mut c_void,
 ) {
     if data.is_null() || user_data.is_null() || frame_count == 0 {
         return;
     }
 
-    let ctx = &*(user_data as *const CallbackContext);
+    let ctx = &*(user_data as *const CallbackContext);
+
+    let entered = Instant::now();
+    let _busy = BusyGuard::new(ctx, entered);
+    if let Ok(mut timing) = ctx.callback_timing.lock() {
+        timing.record(entered);
+    }
+    // An uninitialized format; the resampler would turn it into nothing
+    if sample_rate == 0 {
+        report_zero_sample_rate(ctx, frame_count);
+        return;
+    }
+    let format = ((sample_rate as u64) << 32) | channels as u64;
+    let previous_format = ctx.input_format.swap(format, Ordering::Relaxed);
+    if previous_format != 0 && previous_format != format {
+        emit_capture_event(
+            "formatChange",
+            Some(ctx.session_id),
+            Some(format!(
+                "Input changed from {}Hz {}ch to {}Hz {}ch",
+                previous_format >> 32,
+                previous_format as u32,
+                sample_rate,
+                channels
+            )),
+        );
+    }
+
+    if let Ok(mut first) = ctx.first_audio.lock() {
+        if !*first {
+            *first = true;
+            ctx.first_audio_cv.notify_all();
+        }
+    }
+
+    if !resampler::is_expected_channel_count(channels) {
+        report_channel_count(ctx, channels);
+        if channels == 0 {
+            return;
+        }
+    }
+
+    let Some(total_samples) = checked_sample_count(frame_count, channels, data_len) else {
+        report_short_buffer(ctx, frame_count, channels, data_len);
+        return;
+    };
+    let float_slice = std::slice::from_raw_parts(data, total_samples);
+
+    // Resample to 16kHz mono Int16
+    let (int16_samples, clipped, phase_inverted, raw) = {
+        let mut resampler = match ctx.resampler.lock() {
+            Ok(r) => r,
+            Err(_) => return,
+        };
+        if let Some(tail) = ctx.prime_tail.lock().ok().and_then(|mut tail| tail.take()) {
+            resampler.prime(&tail, sample_rate);
+        }
+        resampler.set_channel_layout(channel_layout(channel_labels, channels));
+        let samples = resampler.process(float_slice, channels, sample_rate);
+        (
+            samples,
+            resampler.take_clipped(),
+            resampler.take_phase_warning(),
+            resampler.take_raw_tap(),
+        )
+    };
+    native_log!(
+        Debug,
+        "Buffer: {} frames, {}ch {}Hz -> {} samples",
+        frame_count,
+        channels,
+        sample_rate,
+        int16_samples.len()
+    );
+    native_log!(Trace, "Buffer resampled in {}us", entered.elapsed().as_micros());
+    if phase_inverted {
+        report_phase_inversion(ctx);
+    }
+
+    let mut int16_samples = int16_samples;
+    if let Some(mic) = &ctx.mic {
+        if let Ok(mut mic) = mic.lock() {
+            mic.mix_into(&mut int16_samples);
+        }
+    }
+    // Extra outputs and the raw tap are muted alike
+    let mute = ctx
+        .mute
+        .lock()
+        .map_or(GainRamp::UNITY, |mut mute| mute.apply(&mut int16_samples));
+
+    if clipped > 0 {
+        report_clipping(ctx, clipped, int16_samples.len());
+    }
+
+    if let Ok(mut clock) = ctx.clock.lock() {
+        clock.record(host_time, int16_samples.len());
+    }
+
+    // Warmup chunks are withheld exactly like paused ones
+    let warming_up = ctx
+        .warmup_remaining
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| {
+            (n > 0).then(|| n.saturating_sub(int16_samples.len() as u64))
+        })
+        .is_ok();
+    let paused = ctx.paused.load(Ordering::Acquire) || warming_up;
+    if !paused {
+        deliver_extra_outputs(ctx, float_slice, channels, sample_rate, mute);
+    }
+    if host_time != 0 {
+        if let Ok(mut segments) = ctx.segments.lock() {
+            segments.record(clock::host_time_to_ns(host_time), int16_samples.len(), paused);
+        }
+    }
+
+    if int16_samples.is_empty() || paused {
+        return;
+    }
+
+    if let Some(mut raw) = raw {
+        mute.apply_f32(&mut raw, 1);
+        if let Ok(tap) = ctx.raw_tap.lock() {
+            if let Some(tap) = tap.as_ref() {
+                tap.call(Ok(Float32Array::new(raw)), ThreadsafeFunctionCallMode::NonBlocking);
+            }
+        }
+    }
+
+    // Encode on the capture thread, before handing the PCM off to JS
+    if let Some(encoder) = &ctx.encoder {
+        if let Ok(mut encoder) = encoder.lock() {
+            let status = encoder.write(&int16_samples);
+            if status != 0 {
+                report_encoder_failure(ctx, status);
+            }
+        }
+    }
+
+    if let Some(wav) = &ctx.wav {
+        if let Ok(mut wav) = wav.lock() {
+            if let Err(e) = wav.write(&int16_samples) {
+                native_log!(Error, "WAV write failed: {}", e);
+            }
+        }
+    }
+
+    if let Some(rolling) = &ctx.rolling {
+        if let Ok(mut rolling) = rolling.lock() {
+            rolling.push(&int16_samples);
+        }
+    }
+
+    if let Some(recent) = &ctx.recent {
+        if let Ok(mut recent) = recent.lock() {
+            recent.push(&int16_samples);
+        }
+    }
+
+    if let Some(socket) = &ctx.socket {
+        if let Ok(mut socket) = socket.lock() {
+            let host_time_ns = if host_time != 0 { clock::host_time_to_ns(host_time) } else { 0 };
+            socket.push(host_time_ns, &int16_samples);
+        }
+    }
+
+    if let Ok(mut vad) = ctx.vad.lock() {
+        if let Some(vad) = vad.as_mut() {
+            for span in vad.detector.push(&int16_samples) {
+                vad.emit(span);
+            }
+        }
+    }
+
+    if let Ok(mut meter) = ctx.meter.lock() {
+        if let Some(meter) = meter.as_mut() {
+            for reading in meter.meter.push(&int16_samples) {
+                meter.callback.call(
+                    Ok(LevelEvent {
+                        rms_db: reading.rms_db,
+                        peak_db: reading.peak_db,
+                        spectral_flatness: reading.flatness,
+                    }),
+                    ThreadsafeFunctionCallMode::NonBlocking,
+                );
+            }
+        }
+    }
+
+    if let Ok(mut loudness) = ctx.loudness.lock() {
+        if let Some(loudness) = loudness.as_mut() {
+            for reading in loudness.meter.push(&int16_samples) {
+                loudness.callback.call(
+                    Ok(LoudnessEvent {
+                        momentary: reading.momentary,
+                        short_term: reading.short_term,
+                        integrated: reading.integrated,
+                    }),
+                    ThreadsafeFunctionCallMode::NonBlocking,
+                );
+            }
+        }
+    }
+
+    let int16_samples = match &ctx.gate {
+        Some(gate) => match gate.lock() {
+            Ok(mut gate) => gate.process(&int16_samples),
+            Err(_) => return,
+        },
+        None => int16_samples,
+    };
+    if int16_samples.is_empty() {
+        return;
+    }
+
+    if let Some(memory) = &ctx.memory {
+        if let Some(message) = memory.lock().ok().and_then(|mut m| m.push(&int16_samples)) {
+            log_event(&message);
+        }
+    }
+
+    let Some(callback) = &ctx.callback else {
+        return;
+    };
+
+    if let Some(pacer) = &ctx.pacer {
+        let dropped = pacer.push(&int16_samples);
+        if dropped > 0 {
+            report_overflow(&ctx.backlog, callback, dropped);
+        }
+        return;
+    }
+
+    if let Some(framer) = &ctx.framer {
+        let frames = match framer.lock() {
+            Ok(mut framer) => framer.push(&int16_samples),
+            Err(_) => return,
+        };
+        for frame in frames {
+            deliver_chunk(ctx, callback, &frame);
+        }
+        return;
+    }
+
+    if let Some(coalescer) = &ctx.coalescer {
+        let batch = match coalescer.lock() {
+            Ok(mut coalescer) => coalescer.push(&int16_samples),
+            Err(_) => return,
+        };
+        if let Some(batch) = batch {
+            deliver_chunk(ctx, callback, &batch);
+        }
+        return;
+    }
+
+    deliver_chunk(ctx, callback, &int16_samples);
+}
+
+/// Non-blocking call to JS with one chunk. The sequence number is taken
+/// even for a dropped chunk, so the gap shows downstream.
+fn deliver_chunk(ctx: &CallbackContext, callback: &ThreadsafeFunction<PooledBuffer>, samples: &[i16]) {
+    if ctx.consumer_gone.load(Ordering::Acquire) {
+        return;
+    }
+    let seq = next_seq(&ctx.sequence);
+    let Some(token) = ctx.backlog.reserve(samples.len()) else {
+        report_overflow(&ctx.backlog, callback, samples.len());
+        return;
+    };
+    let status = callback.call(
+        Ok(PooledBuffer::from_samples(samples)
+            .with_seq(seq)
+            .with_base64(ctx.base64)
+            .with_token(token)),
+        ThreadsafeFunctionCallMode::NonBlocking,
+    );
+    check_consumer(&ctx.consumer_gone, status);
+}
+
+/// Called by the bridge on its own queue when the default output changes.
+unsafe extern "C" fn default_output_changed(_user_data: *mut c_void) {
+    std::thread::spawn(retap_capture);
+}
+
+/// Restart the running stream against the new output route. The callback
+/// context (resampler, sinks, session id) is untouched; only the stream
+/// underneath is replaced. Holds `RESTART_LOCK` throughout, so a concurrent
+/// stop waits for the restart rather than racing it, but not the state
+/// lock, which the restart could otherwise hold for up to the bridge's
+/// start timeout.
+fn retap_capture() {
+    let (backend, session_id) = {
+        let Ok(state) = state_mutex().lock() else {
+            return;
+        };
+        let Some(capture) = state.as_ref() else {
+            return;
+        };
+        if capture.options.retap_on_device_change != Some(true) {
+            return;
+        }
+        (capture.backend, capture.session_id)
+    };
+
+    let Ok(_restart) = RESTART_LOCK.lock() else {
+        return;
+    };
+    // The session may have stopped, or been replaced, while we waited
+    if active_session_id() != Some(session_id) {
+        return;
+    }
+    let status = match backend {
+        CaptureBackend::Sck => unsafe { voxtape_sck_restart_capture() },
+        CaptureBackend::CoreAudioTap => unsafe { voxtape_tap_restart_capture() },
+        CaptureBackend::Device => return,
+    };
+
+    let route = output_device_info().map_or_else(|| "none".to_string(), |d| d.name);
+    if status == 0 {
+        native_log!(Info, "Output switched to \"{}\"; capture re-established", route);
+        emit_capture_event(
+            "reTapped",
+            Some(session_id),
+            Some(format!("Output switched to \"{}\"; capture re-established", route)),
+        );
+    } else {
+        emit_capture_event(
+            "reTapFailed",
+            Some(session_id),
+            Some(format!(
+                "Output switched to \"{}\", but restarting capture failed with code {}; \
+                 stop and start capture again",
+                route, status
+            )),
+        );
+    }
+}
+
+/// A call failing with `Closing` means JS released or aborted the callback:
+/// nothing can be delivered any more. The first time, end the session from
+/// another thread (the capture thread can't stop its own stream) and emit
+/// a "consumerGone" lifecycle event, rather than capturing into the void.
+fn check_consumer(consumer_gone: &AtomicBool, status: Status) {
+    if status != Status::Closing || consumer_gone.swap(true, Ordering::AcqRel) {
+        return;
+    }
+    let Some(session_id) = active_session_id() else {
+        return;
+    };
+    std::thread::spawn(move || {
+        // A new session may have started in the meantime; leave it alone
+        if active_session_id() != Some(session_id) {
+            return;
+        }
+        let _ = end_capture_session();
+        emit_capture_event(
+            "consumerGone",
+            Some(session_id),
+            Some("The audio callback was released by JS; capture stopped".to_string()),
+        );
+    });
+}
+
+/// Count audio dropped for lack of buffer room and, at most every 10s, warn
+/// JS through the callback's error argument.
+fn report_overflow(backlog: &Backlog, callback: &ThreadsafeFunction<PooledBuffer>, dropped: usize) {
+    if let Some(message) = backlog.record_drop(dropped) {
+        log_event(&message);
+        callback.call(
+            Err(Error::new(Status::GenericFailure, message)),
+            ThreadsafeFunctionCallMode::NonBlocking,
+        );
+    }
+}
+
+/// Accumulate clipped samples and, if a chunk is clipping heavily, send a
+/// (rate-limited) warning to JS through the callback's error argument.
+fn report_clipping(ctx: &CallbackContext, clipped: u64, chunk_len: usize) {
+    let total = ctx.clipped_samples.fetch_add(clipped, Ordering::Relaxed) + clipped;
+
+    if chunk_len == 0 || (clipped as f64 / chunk_len as f64) < CLIP_WARNING_RATIO {
+        return;
+    }
+    let Ok(mut last) = ctx.last_clip_warning.lock() else {
+        return;
+    };
+    if last.is_some_and(|t| t.elapsed() < CLIP_WARNING_INTERVAL) {
+        return;
+    }
+    *last = Some(Instant::now());
+
+    let message = format!(
+        "System audio is clipping ({} of {} samples in the last chunk, {} total). \
+         Lower the system output volume to avoid distortion.",
+        clipped, chunk_len, total
+    );
+    log_event(&message);
+    if let Some(callback) = &ctx.callback {
+        callback.call(
+            Err(Error::new(Status::GenericFailure, message)),
+            ThreadsafeFunctionCallMode::NonBlocking,
+        );
+    }
+}
+
+/// Warn (once per session) that SCK delivered a channel count the resampler
+/// doesn't fold properly. Audio keeps flowing, averaged across all channels
+/// (or weighted by the channel labels, when the bridge has them).
+fn report_channel_count(ctx: &CallbackContext, channels: u32) {
+    if ctx.channel_warning_sent.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    let message = if channels == 0 {
+        "SCK delivered audio with 0 channels; dropping these buffers".to_string()
+    } else {
+        format!(
+            "SCK delivered {}-channel audio (expected 1, 2, 6 or 8); averaging all channels to mono",
+            channels
+        )
+    };
+    log_event(&message);
+    if let Some(callback) = &ctx.callback {
+        callback.call(
+            Err(Error::new(Status::GenericFailure, message)),
+            ThreadsafeFunctionCallMode::NonBlocking,
+        );
+    }
+}
+
+/// Adds the time until it's dropped to the context's `BusyMeter`, so every
+/// early return out of a callback is counted.
+struct BusyGuard<'a> {
+    ctx: &'a CallbackContext,
+    entered: Instant,
+}
+
+impl<'a> BusyGuard<'a> {
+    fn new(ctx: &'a CallbackContext, entered: Instant) -> Self {
+        Self { ctx, entered }
+    }
+}
+
+impl Drop for BusyGuard<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut busy) = self.ctx.busy.lock() {
+            busy.add(self.entered, Instant::now());
+        }
+    }
+}
+
+/// Number of floats `frame_count` frames of `channels` span, or `None` if
+/// that's more than the `data_len` the bridge actually provided. Slicing
+/// past `data_len` would read out of bounds.
+fn checked_sample_count(frame_count: u32, channels: u32, data_len: u32) -> Option<usize> {
+    let total = frame_count as usize * channels as usize;
+    (total <= data_len as usize).then_some(total)
+}
+
+/// Report (once per session) a buffer claiming more frames than it holds.
+/// Such buffers are dropped.
+fn report_short_buffer(ctx: &CallbackContext, frame_count: u32, channels: u32, data_len: u32) {
+    if ctx.buffer_warning_sent.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    let message = format!(
+        "SCK buffer claims {} frames x {} channels but holds only {} samples; dropping it",
+        frame_count, channels, data_len
+    );
+    log_event(&message);
+    if let Some(callback) = &ctx.callback {
+        callback.call(
+            Err(Error::new(Status::GenericFailure, message)),
+            ThreadsafeFunctionCallMode::NonBlocking,
+        );
+    }
+}
+
+/// Report (once per session) a buffer with a 0Hz sample rate. Such buffers
+/// are dropped.
+fn report_zero_sample_rate(ctx: &CallbackContext, frame_count: u32) {
+    if ctx.rate_warning_sent.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    let message = format!(
+        "SCK delivered {} frames with a 0Hz sample rate (uninitialized format); dropping these buffers",
+        frame_count
+    );
+    log_event(&message);
+    if let Some(callback) = &ctx.callback {
+        callback.call(
+            Err(Error::new(Status::GenericFailure, message)),
+            ThreadsafeFunctionCallMode::NonBlocking,
+        );
+    }
+}
+
+/// Borrow the bridge's per-channel labels, if it knew the layout.
+unsafe fn channel_layout<'a>(labels: *const u32, channels: u32) -> Option<&'a [u32]> {
+    (!labels.is_null()).then(|| std::slice::from_raw_parts(labels, channels as usize))
+}
+
+/// Warn (once per session) that phase protection kicked in.
+fn report_phase_inversion(ctx: &CallbackContext) {
+    let message = "Stereo channels are phase-inverted and would cancel in the mixdown; \
+                   using the left channel only";
+    log_event(message);
+    if let Some(callback) = &ctx.callback {
+        callback.call(
+            Err(Error::new(Status::GenericFailure, message)),
+            ThreadsafeFunctionCallMode::NonBlocking,
+        );
+    }
+}
+
+/// Tell JS, once, that the encoded file stopped being written.
+fn report_encoder_failure(ctx: &CallbackContext, status: i32) {
+    let message = format!(
+        "Writing the encoded file failed with status {}; the rest of the capture won't be encoded",
+        status
+    );
+    log_event(&message);
+    if let Some(callback) = &ctx.callback {
+        callback.call(
+            Err(Error::new(Status::GenericFailure, message)),
+            ThreadsafeFunctionCallMode::NonBlocking,
+        );
+    }
+}
+
+/// C callback for SCK microphone buffers: resample and queue for the mix.
+unsafe extern "C" fn sck_mic_callback(
+    data: *const f32,
+    data_len: u32,
+    frame_count: u32,
+    channels: u32,
+    _channel_labels: *const u32,
+    sample_rate: u32,
+    _host_time: u64,
+    user_data: *mut c_void,
+) {
+    if data.is_null() || user_data.is_null() || frame_count == 0 || channels == 0 {
+        return;
+    }
+    let ctx = &*(user_data as *const CallbackContext);
+    let _busy = BusyGuard::new(ctx, Instant::now());
+    let Some(mic) = &ctx.mic else {
+        return;
+    };
+    let Some(total_samples) = checked_sample_count(frame_count, channels, data_len) else {
+        return;
+    };
+    let input = std::slice::from_raw_parts(data, total_samples);
+    if let Ok(mut mic) = mic.lock() {
+        let samples = mic.resampler.process(input, channels, sample_rate);
+        mic.push(samples);
+    }
+}
+
+/// Run the raw input through every registered extra output, muted as the
+/// main output was.
+fn deliver_extra_outputs(ctx: &CallbackContext, input: &[f32], channels: u32, sample_rate: u32, mute: GainRamp) {
+    let Ok(mut outputs) = ctx.extra_outputs.lock() else {
+        return;
+    };
+    for output in outputs.iter_mut() {
+        let mut samples = output.pipeline.process(input, channels, sample_rate);
+        mute.apply_i16(&mut samples, output.pipeline.channels() as usize);
+        ctx.clipped_samples
+            .fetch_add(output.pipeline.take_clipped(), Ordering::Relaxed);
+        if samples.is_empty() {
+            continue;
+        }
+        // Shares the session's backlog cap, counted by duration
+        let length = base_samples(samples.len(), output.pipeline.sample_rate(), output.pipeline.channels());
+        let Some(token) = ctx.backlog.reserve(length) else {
+            report_overflow(&ctx.backlog, &output.callback, length);
+            continue;
+        };
+        output.callback.call(
+            Ok(PooledBuffer::from(output.encoding.encode(&samples)).with_token(token)),
+            ThreadsafeFunctionCallMode::NonBlocking,
+        );
+    }
+}
+
+/// Convert Int16 samples to a little-endian byte Buffer for JS.
+fn samples_to_buffer(samples: &[i16]) -> Buffer {
+    let byte_len = samples.len() * 2;
+    let byte_slice =
+        unsafe { std::slice::from_raw_parts(samples.as_ptr() as *const u8, byte_len) };
+    Buffer::from(byte_slice)
+}
+
+// ── FFI declarations for ObjC bridge ────────────────────────────────────────
+
+type SckAudioCallback = unsafe extern "C" fn(
+    data: *const f32,
+    data_len: u32,
+    frame_count: u32,
+    channels: u32,
+    channel_labels: *const u32,
+    sample_rate: u32,
+    host_time: u64,
+    user_data: *mut c_void,
+);
+
+/// Stream settings handed to the ObjC bridge before each start
+#[repr(C)]
+struct SckStreamConfig {
+    realtime_priority: i32,
+    capture_microphone: i32,
+    mic_callback: Option<SckAudioCallback>,
+    mono: i32,
+    exclude_self: i32,
+}
+
+extern "C" {
+    fn voxtape_sck_start_capture(
+        callback: SckAudioCallback,
+        user_data: *mut c_void,
+    ) -> i32;
+
+    fn voxtape_sck_start_capture_with_spec(
+        display_id: u32,
+        bundle_ids: *const *const c_char,
+        bundle_count: i32,
+        include: i32,
+        callback: SckAudioCallback,
+        user_data: *mut c_void,
+    ) -> i32;
+
+    fn voxtape_sck_start_capture_with_filter(
+        filter: *mut c_void,
+        callback: SckAudioCallback,
+        user_data: *mut c_void,
+    ) -> i32;
+
+    fn voxtape_sck_stop_capture();
+
+    fn voxtape_tap_start_capture(callback: SckAudioCallback, user_data: *mut c_void) -> i32;
+    fn voxtape_tap_stop_capture();
+    fn voxtape_tap_restart_capture() -> i32;
+    fn voxtape_sck_restart_capture() -> i32;
+    fn voxtape_watch_default_output(callback: unsafe extern "C" fn(*mut c_void), user_data: *mut c_void) -> i32;
+    fn voxtape_unwatch_default_output();
+    fn voxtape_device_start_capture(device_id: u32, callback: SckAudioCallback, user_data: *mut c_void) -> i32;
+    fn voxtape_device_stop_capture();
+
+    fn voxtape_sck_configure(config: *const SckStreamConfig);
+    fn voxtape_available_disk_bytes(path: *const c_char) -> i64;
+    fn voxtape_sck_dropped_frames() -> u64;
+    fn voxtape_set_log_level(level: i32);
+
+    fn voxtape_has_screen_capture_access() -> i32;
+    fn voxtape_request_screen_capture_access() -> i32;
+    fn voxtape_request_sck_permission() -> i32;
+    fn voxtape_request_screen_capture_access_ex() -> i32;
+    fn voxtape_open_screen_recording_settings() -> i32;
+}
+
+// ── Exported API ────────────────────────────────────────────────────────────
+
+/// Tags embedded in recorded files: a LIST/INFO chunk in WAV output, the
+/// file's info dictionary in encoded output
+#[napi(object)]
+#[derive(Clone, Default)]
+pub struct RecordingMetadata {
+    /// e.g. the meeting title
+    pub title: Option<String>,
+    /// e.g. the participant or organizer
+    pub artist: Option<String>,
+    pub comment: Option<String>,
+}
+
+/// Manual L/R corrections for capture drivers that deliver the channels
+/// swapped or with one of them inverted
+#[napi(object)]
+#[derive(Clone, Default)]
+pub struct ChannelMapOptions {
+    /// Exchange L and R (applied before the inversions)
+    #[napi(js_name = "swapLR")]
+    pub swap_lr: Option<bool>,
+    /// Flip the polarity of L
+    pub invert_l: Option<bool>,
+    /// Flip the polarity of R
+    pub invert_r: Option<bool>,
+}
+
+/// Optional settings for `start_capture`.
+#[napi(object)]
+#[derive(Clone, Default)]
+pub struct CaptureOptions {
+    /// Also encode the resampled stream to this file, alongside the PCM callback
+    pub encoded_path: Option<String>,
+    /// Codec for `encoded_path`: "aac" (.m4a, default) or "opus" (.caf)
+    pub encoded_format: Option<String>,
+    /// Deliver fixed-size chunks every N ms from a timer thread instead of as SCK bursts arrive
+    pub paced_interval_ms: Option<u32>,
+    /// Maximum audio buffered for paced delivery before the oldest is dropped (default 1000ms)
+    pub paced_buffer_ms: Option<u32>,
+    /// Deliver exactly this many samples per callback, for block-based
+    /// consumers (FFT, ML inference), holding the remainder for the next
+    /// one. The final chunk when capture stops may be shorter. Not combinable
+    /// with `pacedIntervalMs`
+    pub frames_per_callback: Option<u32>,
+    /// Hold chunks until at least this much audio has built up and deliver
+    /// them as one callback, trading up to that much latency for fewer JS
+    /// wakeups, which matters on battery during long recordings (default
+    /// 20ms, at most 1000; 0 delivers every chunk as it arrives). Chunks are
+    /// never split, so sizes vary. Ignored with `framesPerCallback` or
+    /// `pacedIntervalMs`, which set their own sizes
+    pub coalesce_ms: Option<u32>,
+    /// Also write the resampled stream to this WAV file (16kHz mono 16-bit)
+    pub wav_path: Option<String>,
+    /// Split `wavPath` into numbered files of this many ms each
+    /// (`meeting-001.wav`, `meeting-002.wav`, ...) instead of one growing file
+    pub wav_segment_duration_ms: Option<u32>,
+    /// With `wavPath` or `encodedPath`: once free space on the recording's
+    /// volume drops below this many MB, emit a "lowDiskSpace" capture
+    /// event; below 100MB (or this, if lower) stop the capture, finalizing
+    /// the files, before the disk fills. Starting with less than that fails
+    /// with an error starting with `LowDiskSpace:`. Checked every 5s
+    /// (default 500; 0 turns the guard off)
+    pub low_disk_space_mb: Option<u32>,
+    /// Keep the last N ms of audio as Opus in memory, for `save_rolling_buffer`
+    pub rolling_retention_ms: Option<u32>,
+    /// Keep the last N ms of 16kHz Int16 in memory (32KB per second, at
+    /// most 600000ms), for `snapshot_recent` to clip from while streaming
+    pub recent_retention_ms: Option<u32>,
+    /// Also stream the resampled audio to a socket, "unix:/path/to.sock" or
+    /// "tcp:host:port", as framed chunks (32-byte header with sequence
+    /// number and host time, then PCM; see the README). Frames are dropped,
+    /// and the next one flagged, if the reader falls behind
+    pub socket_sink: Option<String>,
+    /// Suppress delivery of audio quieter than this level (dBFS, e.g. -50)
+    pub silence_threshold_db: Option<f64>,
+    /// Time the level must stay above the threshold to open the gate (default 20ms)
+    pub gate_attack_ms: Option<u32>,
+    /// Time the level must stay below the threshold to close the gate (default 400ms)
+    pub gate_hangover_ms: Option<u32>,
+    /// Stereo→mono fold: "average" (default), "peak" or its alias "louder"
+    /// (the louder channel per frame, for speakers hard-panned L/R) or "rms"
+    /// (equal-power)
+    pub mixdown_mode: Option<String>,
+    /// Run the audio callback on a real-time (time-constraint) thread to avoid
+    /// dropouts under load. The scheduler reserves CPU for it every 10ms, so
+    /// keep per-chunk work (encoders, gates) light or other threads may starve.
+    pub realtime_priority: Option<bool>,
+    /// Enable a downward expander on the 16kHz output: audio below this level
+    /// (dBFS) is attenuated by `expanderRatio` instead of hard-gated
+    pub expander_threshold_db: Option<f64>,
+    /// dB of output drop per dB below threshold (default 2.0, must be >= 1)
+    pub expander_ratio: Option<f64>,
+    /// Envelope attack time (default 5ms)
+    pub expander_attack_ms: Option<u32>,
+    /// Envelope release time (default 100ms)
+    pub expander_release_ms: Option<u32>,
+    /// Normalize the 16kHz output toward this peak level (dBFS, -60 to 0),
+    /// with DC removed. Slow-tracking, for a consistent recording level:
+    /// the gain drops instantly on a peak that would overshoot and rises
+    /// over seconds, so unlike the expander it leaves the dynamics within
+    /// a phrase alone. Applied after the expander; gain is capped at +30dB
+    pub normalize_to_target: Option<f64>,
+    /// Mix the default microphone into the 16kHz stream (macOS 15+;
+    /// see `capabilities().microphoneMix`). Extra outputs stay system-only
+    pub capture_microphone: Option<bool>,
+    /// Fade the microphone in over this long at the start of the session
+    /// instead of starting at full gain (default 0)
+    pub mic_ramp_ms: Option<u32>,
+    /// Duck the system audio by this many dB while the microphone carries
+    /// speech, for narrating over content (0 to 60; needs `captureMicrophone`).
+    /// A sidechain compressor keyed by the mic's level, held through short
+    /// pauses between words
+    pub duck_system_db: Option<f64>,
+    /// Mic level in dBFS that triggers ducking (default -40)
+    pub duck_threshold_db: Option<f64>,
+    /// Time to duck once speech starts (default 50ms)
+    pub duck_attack_ms: Option<u32>,
+    /// Time to recover once speech ends (default 500ms)
+    pub duck_release_ms: Option<u32>,
+    /// Run the pipeline for this long after start without delivering anything,
+    /// so the filter state and SCStream have settled (default 0). Warmup
+    /// audio is discarded from every output, including the rolling buffer,
+    /// which starts retaining only once warmup ends
+    pub warmup_ms: Option<u32>,
+    /// Resample in Q15 fixed point instead of float (within ±1 LSB of the
+    /// float output). Saves CPU with many streams; ignored while the expander,
+    /// normalization or dither is enabled
+    pub fixed_point: Option<bool>,
+    /// Keep the expander and normalization in float end to end, rounding to
+    /// Int16 only once as the last step (default true). With false, each
+    /// stage's output is rounded before the next, so the rounding error
+    /// compounds; only useful to compare with or bit-match an Int16 pipeline
+    pub process_in_float: Option<bool>,
+    /// Detect stereo whose channels are inverted copies of each other (which
+    /// would sum to silence) and pick up the left channel alone while it
+    /// lasts. Warns once when it engages (default false)
+    pub phase_protection: Option<bool>,
+    /// Capture backend: "sck" (ScreenCaptureKit, default) or "tap" (CoreAudio
+    /// process tap, macOS 14.2+). The tap needs no Screen Recording access
+    /// (macOS asks for audio capture permission instead) and has less
+    /// overhead, but doesn't support content filters or `captureMicrophone`.
+    /// "device" records one CoreAudio device chosen with `deviceId`
+    pub backend: Option<String>,
+    /// Device to record with `backend: "device"`: an `id` from
+    /// `listInputDevices()` (a loopback driver such as BlackHole, or an
+    /// aggregate device, whose input is recorded) or `listOutputDevices()`
+    /// (an output-only device, recorded through a process tap on it)
+    pub device_id: Option<u32>,
+    /// Deliver `{ seq, samples }` objects instead of bare Buffers, where
+    /// `seq` counts delivered chunks from 0 for each session, so gaps or
+    /// reordering downstream are detectable (default false)
+    pub sequence_numbers: Option<bool>,
+    /// Decimate with a plain block average instead of the anti-alias filter,
+    /// for live monitoring. Cuts the resampler's group delay (see
+    /// `captureStatus().processingLatencyMs`) at the cost of audible aliasing
+    /// from content above 8kHz; keep it off for transcription (default false)
+    pub low_latency: Option<bool>,
+    /// Tags to embed in the `wavPath` and `encodedPath` files
+    pub metadata: Option<RecordingMetadata>,
+    /// Swap or invert L/R before mixdown, to work around a driver that
+    /// delivers them that way (an inverted channel cancels the mix to
+    /// near-silence). Applies to the main 16kHz stream
+    pub channel_map: Option<ChannelMapOptions>,
+    /// Deliver each chunk's little-endian Int16 bytes as a base64 string
+    /// instead of a Buffer, ready for a JSON or HTTP transport (default
+    /// false). Combines with `sequenceNumbers` (`samples` is the string)
+    pub base64: Option<bool>,
+    /// Most audio held in native memory by each internal buffer (default
+    /// 30000ms): the paced queue (also capped by `pacedBufferMs`) and the
+    /// chunks queued for a busy JS thread, for the main callback and
+    /// `addCaptureOutput` outputs together. When the paced queue is full its
+    /// oldest audio is dropped; chunks already queued for JS can't be
+    /// recalled, so new chunks are dropped instead until JS catches up.
+    /// Either way the callback gets a warning (at most every 10s). Also caps
+    /// the length of a `start_memory_capture` recording. The rolling buffer
+    /// is bounded by `rollingRetentionMs` instead
+    pub max_buffered_ms: Option<u32>,
+    /// Ask ScreenCaptureKit for mono audio, so the resampler gets one
+    /// channel and skips the stereo mixdown (and its phase issues) entirely.
+    /// `sck` backend only; `capture_status().monoInputHonored` reports
+    /// whether SCK delivered mono (default false)
+    pub request_mono_input: Option<bool>,
+    /// Retries after a transient start failure, e.g. right after Screen
+    /// Recording was granted (default 2, 0 disables). Permanent failures
+    /// such as missing permission are never retried
+    pub start_retry_attempts: Option<u32>,
+    /// Wait before the first retry, doubling for each one after (default
+    /// 250ms). `start_capture` blocks while it waits
+    pub start_retry_delay_ms: Option<u32>,
+    /// Keep this app's own audio out of the capture, including its helper
+    /// processes (bundle IDs under this app's, e.g. Electron's
+    /// "<id>.helper"), which is where TTS and notification sounds usually
+    /// play from. The current process alone is always excluded. Not
+    /// applied to a prebuilt `ContentFilter.handle` (default false)
+    pub exclude_self: Option<bool>,
+    /// Re-establish capture when the default output device changes (e.g.
+    /// headphones unplugged), keeping the session id and the pipeline's
+    /// state; each re-tap is reported as a "reTapped" lifecycle event.
+    /// Not for `backend: "device"`, which is pinned to its device (default false)
+    pub retap_on_device_change: Option<bool>,
+    /// Add ±1 LSB triangular dither before rounding to Int16, trading
+    /// quantization distortion on quiet audio for benign noise (default false)
+    pub dither: Option<bool>,
+    /// Seed the dither so the same input gives byte-identical output, for
+    /// tests and reproducible offline processing (default: random per session)
+    pub dither_seed: Option<i64>,
+    /// Decimate with this FIR instead of the built-in anti-alias filter.
+    /// Must sum to 1 (unity DC gain), at most 1024 taps; applies to 48kHz
+    /// and 32kHz input. See `filterCoefficients()` for the default
+    pub filter_coefficients: Option<Vec<f64>>,
+    /// The previous session's final input samples (mono float, -1..1, at
+    /// the input rate, at most 48000), run through the resampler before the
+    /// first buffer so a restarted capture continues without a seam. The
+    /// built-in resampler only needs the last few hundred; ignored by a
+    /// custom resampler
+    pub prime_tail: Option<Vec<f64>>,
+}
+
+/// `options` with the defaults `start_capture` applies filled in.
+fn effective_options(options: &CaptureOptions, backend: CaptureBackend) -> CaptureOptions {
+    let mut effective = options.clone();
+    effective.backend = Some(backend.name().to_string());
+    let mixdown_mode = options.mixdown_mode.as_deref().unwrap_or("average");
+    effective.mixdown_mode = Some(mixdown_mode.to_ascii_lowercase());
+    if options.encoded_path.is_some() {
+        effective.encoded_format.get_or_insert_with(|| "aac".to_string());
+    }
+    if options.wav_path.is_some() || options.encoded_path.is_some() {
+        effective.low_disk_space_mb.get_or_insert(DEFAULT_LOW_DISK_SPACE_MB);
+    }
+    if options.paced_interval_ms.is_some() {
+        effective.paced_buffer_ms.get_or_insert(1000);
+    }
+    if options.paced_interval_ms.is_some() || options.frames_per_callback.is_some() {
+        effective.coalesce_ms = None;
+    } else {
+        effective.coalesce_ms.get_or_insert(DEFAULT_COALESCE_MS);
+    }
+    if options.silence_threshold_db.is_some() {
+        effective.gate_attack_ms.get_or_insert(20);
+        effective.gate_hangover_ms.get_or_insert(400);
+    }
+    if options.expander_threshold_db.is_some() {
+        effective.expander_ratio.get_or_insert(2.0);
+        effective.expander_attack_ms.get_or_insert(5);
+        effective.expander_release_ms.get_or_insert(100);
+    }
+    let capture_microphone = *effective.capture_microphone.get_or_insert(false);
+    if capture_microphone {
+        effective.mic_ramp_ms.get_or_insert(0);
+    }
+    if options.duck_system_db.is_some() {
+        effective.duck_threshold_db.get_or_insert(-40.0);
+        effective.duck_attack_ms.get_or_insert(50);
+        effective.duck_release_ms.get_or_insert(500);
+    }
+    effective.warmup_ms.get_or_insert(0);
+    effective.process_in_float.get_or_insert(true);
+    effective.max_buffered_ms.get_or_insert(DEFAULT_MAX_BUFFERED_MS);
+    effective.start_retry_attempts.get_or_insert(DEFAULT_START_RETRY_ATTEMPTS);
+    effective.start_retry_delay_ms.get_or_insert(DEFAULT_START_RETRY_DELAY_MS);
+    for flag in [
+        &mut effective.realtime_priority,
+        &mut effective.fixed_point,
+        &mut effective.phase_protection,
+        &mut effective.sequence_numbers,
+        &mut effective.low_latency,
+        &mut effective.base64,
+        &mut effective.request_mono_input,
+        &mut effective.exclude_self,
+        &mut effective.dither,
+    ] {
+        flag.get_or_insert(false);
+    }
+    effective
+}
+
+/// Default `maxBufferedMs`
+const DEFAULT_MAX_BUFFERED_MS: u32 = 30_000;
+/// Default `coalesceMs`: batches SCK's smallest buffers without adding
+/// noticeable latency to its typical ~20ms ones
+const DEFAULT_COALESCE_MS: u32 = 20;
+
+/// Running macOS version as (major, minor), from `sw_vers`.
+fn macos_version() -> Option<(u32, u32)> {
+    #[cfg(target_os = "macos")]
+    {
+        use std::process::Command;
+        let out = Command::new("sw_vers")
+            .arg("-productVersion")
+            .output()
+            .ok()?;
+
+        let version = String::from_utf8_lossy(&out.stdout).trim().to_string();
+        let parts: Vec<u32> = version
+            .split('.')
+            .filter_map(|p| p.parse().ok())
+            .collect();
+        match parts.as_slice() {
+            [major, minor, ..] => Some((*major, *minor)),
+            [major] => Some((*major, 0)),
+            [] => None,
+        }
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        None
+    }
+}
+
+/// Check if system audio capture is supported on this platform.
+/// Requires macOS 14.2+ (Sonoma).
+#[napi]
+pub fn is_supported() -> bool {
+    macos_version().is_some_and(|v| v >= (14, 2))
+}
+
+extern "C" {
+    fn voxtape_api_capabilities() -> u32;
+}
+
+/// Bits returned by `voxtape_api_capabilities`
+const API_SCK_AUDIO: u32 = 1 << 0;
+const API_PROCESS_TAP: u32 = 1 << 1;
+const API_SCK_MICROPHONE: u32 = 1 << 2;
+
+/// Feature availability on the running system
+#[napi(object)]
+pub struct Capabilities {
+    /// macOS version as "major.minor" (empty if unknown)
+    pub os_version: String,
+    /// Whole-system audio capture (`start_capture`; macOS 14.2+)
+    pub system_audio: bool,
+    /// Per-application audio capture via process taps (CATapDescription)
+    pub per_app_audio: bool,
+    /// ScreenCaptureKit can mix the microphone into the stream (macOS 15+)
+    pub microphone_mix: bool,
+}
+
+/// Report which capture features this system supports, based on the OS
+/// version and on which ScreenCaptureKit / CoreAudio APIs are present.
+#[napi]
+pub fn capabilities() -> Capabilities {
+    let version = macos_version();
+    #[cfg(target_os = "macos")]
+    let api = unsafe { voxtape_api_capabilities() };
+    #[cfg(not(target_os = "macos"))]
+    let api = 0;
+
+    let system_audio = is_supported() && api & API_SCK_AUDIO != 0;
+    Capabilities {
+        os_version: version
+            .map(|(major, minor)| format!("{}.{}", major, minor))
+            .unwrap_or_default(),
+        system_audio,
+        per_app_audio: system_audio && api & API_PROCESS_TAP != 0,
+        microphone_mix: system_audio && api & API_SCK_MICROPHONE != 0,
+    }
+}
+
+/// How many captures with `backend` ("sck" by default, "tap" or "device")
+/// can run at once on this system; 0 where the backend isn't available.
+/// `start_capture` runs one session at a time whatever the backend, so this
+/// is at most 1; starting another while one runs or is starting fails with
+/// an error starting with `AlreadyCapturing:`. Per-app tracks
+/// (`start_app_tracks`) are separate streams, limited to 8 at a time; going
+/// over that fails with an error starting with `TooManyCaptures:`.
+#[napi]
+pub fn max_concurrent_captures(backend: Option<String>) -> Result<u32> {
+    let backend = CaptureBackend::parse(backend.as_deref())
+        .ok_or_else(|| Error::from_reason(format!("Unsupported capture backend: {}", backend.unwrap_or_default())))?;
+    let capabilities = capabilities();
+    let available = match backend {
+        CaptureBackend::Sck => capabilities.system_audio,
+        CaptureBackend::CoreAudioTap => capabilities.per_app_audio,
+        CaptureBackend::Device => cfg!(target_os = "macos"),
+    };
+    Ok(available as u32)
+}
+
+/// Identifies the loaded native binary
+#[napi(object)]
+pub struct BuildInfo {
+    /// Crate version
+    pub version: String,
+    /// macOS SDK version the bridge was compiled against, if known
+    pub sdk_version: Option<String>,
+    /// Capture backends compiled in ("sck", "tap" and "device" on macOS)
+    pub backends: Vec<String>,
+    /// Whether this is a debug build
+    pub debug: bool,
+}
+
+/// Report which build of the native module is loaded.
+#[napi]
+pub fn build_info() -> BuildInfo {
+    // Other platforms build a stub with no backends
+    let mut backends = Vec::new();
+    if cfg!(target_os = "macos") {
+        backends.push(CaptureBackend::Sck.name().to_string());
+        backends.push(CaptureBackend::CoreAudioTap.name().to_string());
+        backends.push(CaptureBackend::Device.name().to_string());
+    }
+    BuildInfo {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        sdk_version: option_env!("VOXTAPE_SDK_VERSION").map(str::to_string),
+        backends,
+        debug: cfg!(debug_assertions),
+    }
+}
+
+/// Check if the app has Screen Capture (Screen Recording) access.
+#[napi]
+pub fn has_screen_capture_access() -> bool {
+    #[cfg(feature = "permission-stub")]
+    if let Some(granted) = permission_stub::screen_capture_access() {
+        return granted;
+    }
+    #[cfg(target_os = "macos")]
+    unsafe {
+        voxtape_has_screen_capture_access() != 0
+    }
+    #[cfg(not(target_os = "macos"))]
+    false
+}
+
+/// Check whether `start_capture` can run right now without macOS showing a
+/// permission prompt: the OS supports system audio capture and Screen
+/// Recording access is already granted. Uses only the preflight check, which
+/// never shows UI, so it's safe for a silent capability probe at launch
+/// (e.g. to decide whether to show a rationale screen before requesting).
+#[napi]
+pub fn can_capture_without_prompt() -> bool {
+    capabilities().system_audio && has_screen_capture_access()
+}
+
+/// Request Screen Capture access (triggers macOS permission dialog).
+#[napi]
+pub fn request_screen_capture_access() -> bool {
+    #[cfg(feature = "permission-stub")]
+    if let Some(granted) = permission_stub::screen_capture_access() {
+        return granted;
+    }
+    #[cfg(target_os = "macos")]
+    unsafe {
+        voxtape_request_screen_capture_access() != 0
+    }
+    #[cfg(not(target_os = "macos"))]
+    false
+}
+
+/// Request Screen & System Audio Recording permission via ScreenCaptureKit.
+#[napi]
+pub fn request_audio_capture_permission() -> bool {
+    #[cfg(feature = "permission-stub")]
+    if let Some(granted) = permission_stub::screen_capture_access() {
+        return granted;
+    }
+    #[cfg(target_os = "macos")]
+    unsafe {
+        voxtape_request_sck_permission() != 0
+    }
+    #[cfg(not(target_os = "macos"))]
+    false
+}
+
+/// FFI struct for the permission context from ObjC
+#[repr(C)]
+struct CPermissionContext {
+    bundle_id: *const c_char,
+    bundle_path: *const c_char,
+    executable_path: *const c_char,
+    pid: i32,
+}
+
+extern "C" {
+    fn voxtape_get_permission_context() -> *mut CPermissionContext;
+    fn voxtape_free_permission_context(ctx: *mut CPermissionContext);
+    fn voxtape_process_session_kind() -> i32;
+}
+
+/// How the process is hosted, as far as capture is concerned
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ProcessSession {
+    /// Regular app with a Dock icon
+    App,
+    /// UI-less agent (LSUIElement), e.g. a menu bar app
+    Agent,
+    /// Background-only process (LSBackgroundOnly, or a bare helper binary)
+    Background,
+    /// No GUI login session (LaunchDaemon, ssh): no window server
+    NoGui,
+}
+
+impl ProcessSession {
+    #[cfg(target_os = "macos")]
+    fn current() -> Self {
+        match unsafe { voxtape_process_session_kind() } {
+            0 => ProcessSession::App,
+            1 => ProcessSession::Agent,
+            2 => ProcessSession::Background,
+            _ => ProcessSession::NoGui,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            ProcessSession::App => "app",
+            ProcessSession::Agent => "agent",
+            ProcessSession::Background => "background",
+            ProcessSession::NoGui => "nogui",
+        }
+    }
+
+    fn is_background(self) -> bool {
+        matches!(self, ProcessSession::Background | ProcessSession::NoGui)
+    }
+}
+
+/// The identity macOS evaluates Screen Recording permission against
+#[napi(object)]
+pub struct PermissionContext {
+    /// Bundle identifier of the running process (None for unbundled binaries)
+    pub bundle_id: Option<String>,
+    /// Path of the main bundle (the .app, or the binary's directory if unbundled)
+    pub bundle_path: String,
+    /// Path of the executable that is actually running
+    pub executable_path: String,
+    /// Process ID
+    pub pid: i32,
+    /// How the process is hosted: "app", "agent" (LSUIElement), "background"
+    /// (background-only) or "nogui" (no GUI login session, e.g. a LaunchDaemon)
+    pub session: String,
+}
+
+/// Report which bundle the permission APIs are evaluated against.
+/// Useful when a grant appears not to apply (e.g. it was given to a different
+/// helper or to the unpackaged Electron binary).
+#[napi]
+pub fn capture_permission_context() -> Option<PermissionContext> {
+    #[cfg(target_os = "macos")]
+    unsafe {
+        let ctx = voxtape_get_permission_context();
+        if ctx.is_null() {
+            return None;
+        }
+
+        let bundle_id = if (*ctx).bundle_id.is_null() {
+            None
+        } else {
+            Some(CStr::from_ptr((*ctx).bundle_id).to_string_lossy().into_owned())
+        };
+
+        let result = PermissionContext {
+            bundle_id,
+            bundle_path: CStr::from_ptr((*ctx).bundle_path).to_string_lossy().into_owned(),
+            executable_path: CStr::from_ptr((*ctx).executable_path)
+                .to_string_lossy()
+                .into_owned(),
+            pid: (*ctx).pid,
+            session: ProcessSession::current().name().to_string(),
+        };
+
+        voxtape_free_permission_context(ctx);
+        Some(result)
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        None
+    }
+}
+
+/// Outcome of `request_capture_permission`
+#[napi(string_enum = "lowercase")]
+#[derive(Clone, Copy)]
+pub enum PermissionRequestOutcome {
+    /// Access is granted
+    Granted,
+    /// The system dialog was shown; the user hasn't granted access (yet)
+    Prompted,
+    /// Access was denied earlier. macOS never re-prompts after a denial, so
+    /// the user has to enable it in System Settings (`open_screen_recording_settings`)
+    Denied,
+    /// Not running on macOS
+    Unsupported,
+}
+
+/// Request Screen Recording access and report what happened.
+/// Unlike `request_screen_capture_access`, this distinguishes a first-time
+/// prompt from an earlier denial, which macOS cannot be forced to re-prompt.
+#[napi]
+pub fn request_capture_permission() -> PermissionRequestOutcome {
+    #[cfg(feature = "permission-stub")]
+    if let Some(outcome) = permission_stub::request_outcome() {
+        return outcome;
+    }
+    #[cfg(target_os = "macos")]
+    unsafe {
+        match voxtape_request_screen_capture_access_ex() {
+            1 => PermissionRequestOutcome::Granted,
+            0 => PermissionRequestOutcome::Prompted,
+            _ => PermissionRequestOutcome::Denied,
+        }
+    }
+    #[cfg(not(target_os = "macos"))]
+    PermissionRequestOutcome::Unsupported
+}
+
+/// Open System Settings at Privacy & Security > Screen Recording.
+/// Returns false if the settings pane could not be opened.
+#[napi]
+pub fn open_screen_recording_settings() -> bool {
+    #[cfg(target_os = "macos")]
+    unsafe {
+        voxtape_open_screen_recording_settings() != 0
+    }
+    #[cfg(not(target_os = "macos"))]
+    false
+}
+
+/// Start capturing system audio via ScreenCaptureKit.
+/// The callback receives Buffer chunks of 16kHz mono Int16 PCM data; it is
+/// called with an error (and no chunk) for non-fatal warnings such as clipping.
+/// If `options.encodedPath` is set, the same samples are also encoded to that file.
+/// If `options.pacedIntervalMs` is set, chunks are re-timed to a steady cadence.
+#[napi]
+pub fn start_capture(
+    callback: ThreadsafeFunction<PooledBuffer>,
+    options: Option<CaptureOptions>,
+) -> Result<()> {
+    start_capture_with(Some(Arc::new(callback)), options, |user_data| unsafe {
+        voxtape_sck_start_capture(sck_audio_callback, user_data)
+    })
+}
+
+/// Start capturing system audio with a custom resampler in place of the
+/// built-in one. `resampler` receives each raw chunk and must synchronously
+/// return 16kHz mono Int16 samples; it runs on the main thread and chunks it
+/// doesn't answer within 50ms are dropped. `mixdownMode`, the expander
+/// options, `normalizeToTarget` and dither are ignored.
+#[napi]
+pub fn start_capture_with_resampler(
+    callback: ThreadsafeFunction<PooledBuffer>,
+    resampler: ThreadsafeFunction<RawAudioChunk, Int16Array>,
+    options: Option<CaptureOptions>,
+) -> Result<()> {
+    start_capture_with_processor(
+        Some(Arc::new(callback)),
+        options,
+        Some(Box::new(JsResampler::new(resampler))),
+        false,
+        |user_data| unsafe { voxtape_sck_start_capture(sck_audio_callback, user_data) },
+    )
+}
+
+/// Longest `start_capture_async` waits for the first buffer after the stream starts
+const FIRST_AUDIO_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Result of `start_capture_async`
+#[napi(object)]
+pub struct CaptureStarted {
+    /// Id of the new session (as in `capture_status().sessionId`)
+    pub session_id: u32,
+    /// Whether audio was flowing before the promise resolved. False means the
+    /// stream started but nothing arrived within 3s (e.g. no audio device).
+    pub audio_flowing: bool,
+}
+
+pub struct StartCaptureTask {
+    callback: Option<Arc<ThreadsafeFunction<PooledBuffer>>>,
+    options: Option<CaptureOptions>,
+}
+
+impl Task for StartCaptureTask {
+    type Output = CaptureStarted;
+    type JsValue = CaptureStarted;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        start_capture_with(self.callback.take(), self.options.take(), |user_data| unsafe {
+            voxtape_sck_start_capture(sck_audio_callback, user_data)
+        })?;
+        let ctx = active_context()?;
+        let session_id = active_session_id().unwrap_or(0);
+
+        // Wait in short slices so a stop_capture in the meantime ends the wait
+        let deadline = Instant::now() + FIRST_AUDIO_TIMEOUT;
+        let mut first = ctx
+            .first_audio
+            .lock()
+            .map_err(|e| Error::from_reason(format!("Failed to acquire context lock: {}", e)))?;
+        while !*first && Instant::now() < deadline {
+            first = ctx
+                .first_audio_cv
+                .wait_timeout(first, Duration::from_millis(50))
+                .map_err(|e| Error::from_reason(format!("Failed to acquire context lock: {}", e)))?
+                .0;
+            let still_running = context_mutex()
+                .lock()
+                .map(|c| c.as_ref().is_some_and(|c| Arc::ptr_eq(c, &ctx)))
+                .unwrap_or(false);
+            if !still_running {
+                return Err(Error::from_reason("Capture was stopped before audio arrived"));
+            }
+        }
+
+        Ok(CaptureStarted {
+            session_id,
+            audio_flowing: *first,
+        })
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+/// `start_capture` without blocking the calling thread. Resolves once the
+/// stream is running and the first buffer has arrived (or after 3s with
+/// `audioFlowing: false`); rejects with the same errors as `start_capture`,
+/// or if the capture is stopped before audio arrives.
+#[napi]
+pub fn start_capture_async(
+    callback: ThreadsafeFunction<PooledBuffer>,
+    options: Option<CaptureOptions>,
+) -> AsyncTask<StartCaptureTask> {
+    AsyncTask::new(StartCaptureTask {
+        callback: Some(Arc::new(callback)),
+        options,
+    })
+}
+
+/// Content selection for `start_capture_with_filter`: either an existing
+/// `SCContentFilter` handle, or the parameters to build one.
+#[napi(object)]
+pub struct ContentFilter {
+    /// Address of an `SCContentFilter *` created by another native module.
+    /// Borrowed: the stream retains the filter itself, so the caller keeps
+    /// ownership and only has to keep it alive until the start call returns.
+    pub handle: Option<BigInt>,
+    /// CGDirectDisplayID to attach to (default: main display)
+    pub display_id: Option<u32>,
+    /// Only capture audio from these apps
+    pub included_bundle_ids: Option<Vec<String>>,
+    /// Capture all audio except these apps
+    pub excluded_bundle_ids: Option<Vec<String>>,
+}
+
+/// Start capturing system audio against a specific ScreenCaptureKit filter,
+/// bypassing the default main-display filter. Lets a process that already
+/// captures video reuse the same filter instead of running competing streams.
+/// Chunks and options behave exactly as in `start_capture`.
+#[napi]
+pub fn start_capture_with_filter(
+    callback: ThreadsafeFunction<PooledBuffer>,
+    filter: ContentFilter,
+    options: Option<CaptureOptions>,
+) -> Result<()> {
+    if let Some(problem) = filter_problem(&filter, options.as_ref()) {
+        return Err(Error::from_reason(problem));
+    }
+
+    if let Some(handle) = &filter.handle {
+        let (_, address, _) = handle.get_u64();
+        return start_capture_with(Some(Arc::new(callback)), options, |user_data| unsafe {
+            voxtape_sck_start_capture_with_filter(address as *mut c_void, sck_audio_callback, user_data)
+        });
+    }
+
+    let (bundle_ids, include) = match (filter.included_bundle_ids, filter.excluded_bundle_ids) {
+        (Some(ids), _) => (ids, true),
+        (None, Some(ids)) => (ids, false),
+        (None, None) => (Vec::new(), false),
+    };
+    let bundle_ids = bundle_ids
+        .into_iter()
+        .map(|id| CString::new(id).map_err(|_| Error::from_reason("Bundle ID contains a NUL byte")))
+        .collect::<Result<Vec<_>>>()?;
+    let bundle_ptrs: Vec<*const c_char> = bundle_ids.iter().map(|id| id.as_ptr()).collect();
+
+    start_capture_with(Some(Arc::new(callback)), options, |user_data| unsafe {
+        voxtape_sck_start_capture_with_spec(
+            filter.display_id.unwrap_or(0),
+            bundle_ptrs.as_ptr(),
+            bundle_ptrs.len() as i32,
+            include as i32,
+            sck_audio_callback,
+            user_data,
+        )
+    })
+}
+
+/// Why `start_capture_with_filter` would reject `filter` with `options`
+/// whatever the system, if it would.
+fn filter_problem(filter: &ContentFilter, options: Option<&CaptureOptions>) -> Option<String> {
+    let backend = options.and_then(|o| o.backend.as_deref());
+    if CaptureBackend::parse(backend).is_some_and(|b| b != CaptureBackend::Sck) {
+        return Some("Content filters need the \"sck\" backend".to_string());
+    }
+    let has_spec = filter.display_id.is_some()
+        || filter.included_bundle_ids.is_some()
+        || filter.excluded_bundle_ids.is_some();
+    if let Some(handle) = &filter.handle {
+        if has_spec {
+            return Some("Pass either a filter handle or filter parameters, not both".to_string());
+        }
+        let (signed, address, lossless) = handle.get_u64();
+        if signed || !lossless || address == 0 {
+            return Some("Invalid SCContentFilter handle".to_string());
+        }
+    }
+    if filter.included_bundle_ids.is_some() && filter.excluded_bundle_ids.is_some() {
+        return Some("includedBundleIds and excludedBundleIds are mutually exclusive".to_string());
+    }
+    None
+}
+
+/// Error returned when capture is attempted without Screen Recording access.
+/// The reason starts with "PermissionDenied:" so callers can match on it.
+fn permission_denied_error() -> Error {
+    Error::from_reason(
+        "PermissionDenied: Screen Recording permission has not been granted. \
+         Call openScreenRecordingSettings() and enable it under \
+         Privacy & Security > Screen Recording, then restart the app.",
+    )
+}
+
+/// Error returned when ScreenCaptureKit can't run in this process's session.
+/// The reason starts with "BackgroundSessionUnsupported:" so callers can
+/// match on it. See the package README for running from a helper.
+fn background_session_unsupported_error(session: ProcessSession) -> Error {
+    let detail = match session {
+        ProcessSession::NoGui => {
+            "this process has no GUI login session (LaunchDaemon or ssh), so it can't reach the \
+             window server. Run it as a LaunchAgent in the user's Aqua session \
+             (LimitLoadToSessionType Aqua), or use the \"tap\" backend"
+        }
+        _ => {
+            "macOS refused to start capture in this background-only process. Grant Screen \
+             Recording to the helper's own signed bundle from a foreground run first (the \
+             prompt can't be shown from the background), and prefer LSUIElement over \
+             LSBackgroundOnly"
+        }
+    };
+    Error::from_reason(format!("BackgroundSessionUnsupported: ScreenCaptureKit capture failed: {}.", detail))
+}
+
+/// Default `startRetryAttempts` and `startRetryDelayMs`
+const DEFAULT_START_RETRY_ATTEMPTS: u32 = 2;
+const DEFAULT_START_RETRY_DELAY_MS: u32 = 250;
+
+/// Bridge statuses worth retrying: the system often hasn't settled right
+/// after Screen Recording is granted, and shareable content lookup or the
+/// stream start fail once, then succeed. Everything else (framework
+/// missing, capture already active, no displays, bad filter, 30s timeout)
+/// is permanent.
+fn is_transient_start_failure(backend: CaptureBackend, status: i32) -> bool {
+    match backend {
+        // -2: shareable content lookup failed, -5: SCStream start failed.
+        // Not SCK_NO_SHAREABLE_CONTENT: no displays means a headless or
+        // background session, which waiting doesn't fix
+        CaptureBackend::Sck => matches!(status, -2 | -5),
+        // -13: the aggregate device's IOProc didn't start
+        CaptureBackend::CoreAudioTap => status == -13,
+        // -13: the device's (or its tap's) IOProc didn't start
+        CaptureBackend::Device => status == -13,
+    }
+}
+
+/// Bridge status when ScreenCaptureKit isn't present at runtime
+const SCK_UNAVAILABLE: i32 = -9;
+
+/// Bridge status when SCShareableContent lists no displays to capture
+const SCK_NO_SHAREABLE_CONTENT: i32 = -3;
+
+/// Error returned when ScreenCaptureKit has nothing to capture from.
+/// The reason starts with "NoShareableContent:" so callers can match on it.
+fn no_shareable_content_error() -> Error {
+    Error::from_reason(
+        "NoShareableContent: ScreenCaptureKit reported no displays to capture. \
+         This usually means Screen Recording permission is missing or stale, \
+         or the session has no display attached (headless login, CI runner or VM).",
+    )
+}
+
+/// Error returned when ScreenCaptureKit (weak-linked) is missing at runtime or
+/// lacks audio capture, e.g. on macOS releases before 13.
+/// The reason starts with "FrameworkUnavailable:" so callers can match on it.
+fn framework_unavailable_error() -> Error {
+    Error::from_reason(format!(
+        "FrameworkUnavailable: ScreenCaptureKit audio capture is not available on this system \
+         (macOS {}); system audio capture needs macOS 14.2 or later.",
+        macos_version()
+            .map(|(major, minor)| format!("{}.{}", major, minor))
+            .unwrap_or_else(|| "unknown".to_string())
+    ))
+}
+
+/// Error returned when the "tap" backend is requested on a system without
+/// CoreAudio process taps. Uses the same "FrameworkUnavailable:" prefix.
+fn tap_unavailable_error() -> Error {
+    Error::from_reason(format!(
+        "FrameworkUnavailable: CoreAudio process taps are not available on this system \
+         (macOS {}); the \"tap\" backend needs macOS 14.2 or later.",
+        macos_version()
+            .map(|(major, minor)| format!("{}.{}", major, minor))
+            .unwrap_or_else(|| "unknown".to_string())
+    ))
+}
+
+/// Bridge status when the "device" backend's device doesn't exist
+const DEVICE_NOT_FOUND: i32 = -20;
+
+/// Error returned when `deviceId` doesn't name a current audio device (ids
+/// change when devices are unplugged). The reason starts with
+/// "DeviceNotFound:" so callers can match on it.
+fn device_not_found_error(device_id: u32) -> Error {
+    Error::from_reason(format!(
+        "DeviceNotFound: No audio device with id {}; list devices again with \
+         listInputDevices() or listOutputDevices().",
+        device_id
+    ))
+}
+
+/// Error returned when a capture would exceed the app track limit. The
+/// reason starts with "TooManyCaptures:" so callers can match on it.
+pub(crate) fn too_many_captures_error(detail: &str) -> Error {
+    Error::from_reason(format!("TooManyCaptures: {}", detail))
+}
+
+/// Error returned when a session is started while another runs or is
+/// starting. The reason starts with "AlreadyCapturing:" so callers can
+/// match on it.
+fn already_capturing_error(detail: &str) -> Error {
+    Error::from_reason(format!("AlreadyCapturing: {}", detail))
+}
+
+/// Error returned when the recording's volume is too full to start writing
+/// to it. The reason starts with "LowDiskSpace:" so callers can match on it.
+fn low_disk_space_error(available: u64) -> Error {
+    Error::from_reason(format!(
+        "LowDiskSpace: Only {}MB free on the recording volume; free up space or record elsewhere.",
+        available / (1024 * 1024)
+    ))
+}
+
+/// Free bytes on the volume(s) the file sinks write to (the least, if
+/// several), or None if there are none or it can't be determined.
+fn available_disk_bytes(paths: &[String]) -> Option<u64> {
+    #[cfg(target_os = "macos")]
+    {
+        paths
+            .iter()
+            .filter_map(|path| {
+                let dir = std::path::Path::new(path)
+                    .parent()
+                    .filter(|dir| !dir.as_os_str().is_empty())
+                    .unwrap_or(std::path::Path::new("."));
+                let dir = CString::new(dir.to_string_lossy().as_bytes()).ok()?;
+                let available = unsafe { voxtape_available_disk_bytes(dir.as_ptr()) };
+                u64::try_from(available).ok()
+            })
+            .min()
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = paths;
+        None
+    }
+}
+
+/// How often free space is checked while recording to a file
+const DISK_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Poll free space for the file sinks of `session_id` until it ends, warning
+/// and finally stopping the session as `guard` decides. Unpark the thread
+/// to have it notice the end without waiting out the interval.
+fn watch_disk_space(session_id: u32, paths: Vec<String>, mut guard: DiskGuard) -> JoinHandle<()> {
+    std::thread::spawn(move || {
+        while active_session_id() == Some(session_id) {
+            let Some(available) = available_disk_bytes(&paths) else {
+                return;
+            };
+            let free_mb = available / (1024 * 1024);
+            match guard.check(available) {
+                DiskAction::Continue => {}
+                DiskAction::Warn => emit_capture_event(
+                    "lowDiskSpace",
+                    Some(session_id),
+                    Some(format!("Only {}MB free on the recording volume", free_mb)),
+                ),
+                DiskAction::Stop => {
+                    let _ = end_capture_session();
+                    emit_capture_event(
+                        "lowDiskSpaceStopped",
+                        Some(session_id),
+                        Some(format!(
+                            "Only {}MB free on the recording volume; capture stopped and files finalized",
+                            free_mb
+                        )),
+                    );
+                    return;
+                }
+            }
+            std::thread::park_timeout(DISK_CHECK_INTERVAL);
+        }
+    })
+}
+
+/// Options that `start_capture` rejects whatever the system, as (option
+/// name, reason) pairs, in the order it checks them.
+fn option_problems(options: &CaptureOptions) -> Vec<(&'static str, String)> {
+    let mut problems = Vec::new();
+    let mut problem = |option: &'static str, reason: String| problems.push((option, reason));
+    if options.paced_interval_ms == Some(0) {
+        problem("pacedIntervalMs", "pacedIntervalMs must be greater than 0".to_string());
+    }
+    if options.frames_per_callback == Some(0) {
+        problem("framesPerCallback", "framesPerCallback must be greater than 0".to_string());
+    }
+    if options.frames_per_callback.is_some() && options.paced_interval_ms.is_some() {
+        problem(
+            "framesPerCallback",
+            "framesPerCallback can't be combined with pacedIntervalMs".to_string(),
+        );
+    }
+    if options.coalesce_ms.is_some_and(|ms| ms > 1000) {
+        problem("coalesceMs", "coalesceMs must be at most 1000".to_string());
+    }
+    if options.max_buffered_ms.is_some_and(|ms| ms < 100) {
+        problem("maxBufferedMs", "maxBufferedMs must be at least 100".to_string());
+    }
+    if options.prime_tail.as_ref().is_some_and(|tail| tail.len() > 48_000) {
+        problem("primeTail", "primeTail must be at most 48000 samples".to_string());
+    }
+    if options.recent_retention_ms.is_some_and(|ms| !(1..=600_000).contains(&ms)) {
+        problem("recentRetentionMs", "recentRetentionMs must be between 1 and 600000".to_string());
+    }
+    if options.rolling_retention_ms == Some(0) {
+        problem("rollingRetentionMs", "rollingRetentionMs must be greater than 0".to_string());
+    }
+    if MixdownMode::parse(options.mixdown_mode.as_deref()).is_none() {
+        problem(
+            "mixdownMode",
+            format!("Unsupported mixdown mode: {}", options.mixdown_mode.as_deref().unwrap_or_default()),
+        );
+    }
+    if options.expander_ratio.is_some_and(|r| r < 1.0) {
+        problem("expanderRatio", "expanderRatio must be at least 1".to_string());
+    }
+    if options.normalize_to_target.is_some_and(|db| !(-60.0..=0.0).contains(&db)) {
+        problem("normalizeToTarget", "normalizeToTarget must be between -60 and 0 dBFS".to_string());
+    }
+    if let Some(depth_db) = options.duck_system_db {
+        if !(0.0..=60.0).contains(&depth_db) {
+            problem("duckSystemDb", "duckSystemDb must be between 0 and 60".to_string());
+        }
+        if !options.capture_microphone.unwrap_or(false) {
+            problem("duckSystemDb", "duckSystemDb needs captureMicrophone".to_string());
+        }
+    }
+    if let Some(coefficients) = &options.filter_coefficients {
+        let coefficients: Vec<f32> = coefficients.iter().map(|&c| c as f32).collect();
+        if let Err(reason) = Resampler::with_coefficients(&coefficients) {
+            problem("filterCoefficients", reason);
+        }
+    }
+    if CaptureBackend::parse(options.backend.as_deref()).is_none() {
+        problem(
+            "backend",
+            format!("Unsupported capture backend: {}", options.backend.as_deref().unwrap_or_default()),
+        );
+    }
+    if options.wav_segment_duration_ms == Some(0) {
+        problem("wavSegmentDurationMs", "wavSegmentDurationMs must be positive".to_string());
+    }
+    if options.encoded_path.is_some() && EncodedFormat::parse(options.encoded_format.as_deref()).is_none() {
+        problem(
+            "encodedFormat",
+            format!("Unsupported encoded format: {}", options.encoded_format.as_deref().unwrap_or_default()),
+        );
+    }
+    problems
+}
+
+/// Shared start path: builds the callback context, then runs `start` with the
+/// user_data pointer the SCK callback expects. `start` returns the bridge's
+/// status code (0 on success), and is called again after a transient failure
+/// (see `startRetryAttempts`).
+fn start_capture_with<F>(
+    callback: Option<Arc<ThreadsafeFunction<PooledBuffer>>>,
+    options: Option<CaptureOptions>,
+    start: F,
+) -> Result<()>
+where
+    F: FnMut(*mut c_void) -> i32,
+{
+    start_capture_with_processor(callback, options, None, false, start)
+}
+
+/// `start_capture_with`, optionally replacing the built-in resampler.
+fn start_capture_with_processor<F>(
+    callback: Option<Arc<ThreadsafeFunction<PooledBuffer>>>,
+    options: Option<CaptureOptions>,
+    processor: Option<Box<dyn SampleProcessor>>,
+    memory: bool,
+    mut start: F,
+) -> Result<()>
+where
+    F: FnMut(*mut c_void) -> i32,
+{
+    let options = options.unwrap_or_default();
+    if let Some((_, problem)) = option_problems(&options).into_iter().next() {
+        return Err(Error::from_reason(problem));
+    }
+    let mixdown_mode = MixdownMode::parse(options.mixdown_mode.as_deref()).ok_or_else(|| {
+        Error::from_reason(format!(
+            "Unsupported mixdown mode: {}",
+            options.mixdown_mode.as_deref().unwrap_or_default()
+        ))
+    })?;
+    let expander = options.expander_threshold_db.map(|threshold_db| ExpanderSettings {
+        threshold_db,
+        ratio: options.expander_ratio.unwrap_or(2.0),
+        attack_ms: options.expander_attack_ms.unwrap_or(5) as f64,
+        release_ms: options.expander_release_ms.unwrap_or(100) as f64,
+    });
+    let custom_filter = options
+        .filter_coefficients
+        .as_ref()
+        .map(|coefficients| {
+            let coefficients: Vec<f32> = coefficients.iter().map(|&c| c as f32).collect();
+            Resampler::with_coefficients(&coefficients)
+        })
+        .transpose()
+        .map_err(Error::from_reason)?;
+    let backend = CaptureBackend::parse(options.backend.as_deref()).ok_or_else(|| {
+        Error::from_reason(format!(
+            "Unsupported capture backend: {}",
+            options.backend.as_deref().unwrap_or_default()
+        ))
+    })?;
+
+    // Check that nothing is capturing or starting, and claim the start,
+    // under one lock: of concurrent starts exactly one gets past here. Held
+    // until the state is stored or the start fails.
+    let _claim = {
+        let state = state_mutex().lock().map_err(|e| {
+            Error::from_reason(format!("Failed to acquire state lock: {}", e))
+        })?;
+        claim_start(&state, &STARTING, |state| state.session_id)
+            .map_err(|reason| already_capturing_error(&reason))?
+    };
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        return Err(Error::from_reason("System audio capture is only supported on macOS 14.2+"));
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let api = unsafe { voxtape_api_capabilities() };
+        let capture_microphone = options.capture_microphone.unwrap_or(false);
+        let device = match backend {
+            CaptureBackend::Sck => {
+                // Fail here rather than with an opaque SCK status code
+                if api & API_SCK_AUDIO == 0 {
+                    return Err(framework_unavailable_error());
+                }
+                let session = ProcessSession::current();
+                if session == ProcessSession::NoGui {
+                    return Err(background_session_unsupported_error(session));
+                }
+                if !has_screen_capture_access() {
+                    return Err(permission_denied_error());
+                }
+                if capture_microphone && api & API_SCK_MICROPHONE == 0 {
+                    return Err(Error::from_reason(
+                        "captureMicrophone needs macOS 15 or later (see capabilities().microphoneMix)",
+                    ));
+                }
+                None
+            }
+            CaptureBackend::CoreAudioTap => {
+                if api & API_PROCESS_TAP == 0 {
+                    return Err(tap_unavailable_error());
+                }
+                if capture_microphone {
+                    return Err(Error::from_reason("captureMicrophone needs the \"sck\" backend"));
+                }
+                None
+            }
+            CaptureBackend::Device => {
+                let device_id = options
+                    .device_id
+                    .ok_or_else(|| Error::from_reason("The \"device\" backend needs deviceId"))?;
+                let device = devices::find_device(device_id).ok_or_else(|| device_not_found_error(device_id))?;
+                if device.input_channels == 0 && api & API_PROCESS_TAP == 0 {
+                    return Err(tap_unavailable_error());
+                }
+                if capture_microphone {
+                    return Err(Error::from_reason("captureMicrophone needs the \"sck\" backend"));
+                }
+                Some(device)
+            }
+        };
+
+        let recording_paths: Vec<String> = options.wav_path.iter().chain(&options.encoded_path).cloned().collect();
+        let disk_guard = Some(options.low_disk_space_mb.unwrap_or(DEFAULT_LOW_DISK_SPACE_MB))
+            .filter(|&mb| mb > 0 && !recording_paths.is_empty())
+            .map(DiskGuard::new);
+        if let Some(guard) = &disk_guard {
+            if let Some(available) = available_disk_bytes(&recording_paths) {
+                if guard.refuses_start(available) {
+                    let error = low_disk_space_error(available);
+                    log_event(&error.reason);
+                    return Err(error);
+                }
+            }
+        }
+
+        // Created before the stream starts, since the callback writes to
+        // them; removed again if the start fails
+        let mut pending = PendingFiles::new();
+
+        let encoder = match &options.encoded_path {
+            Some(path) => {
+                let format = EncodedFormat::parse(options.encoded_format.as_deref()).ok_or_else(|| {
+                    Error::from_reason(format!(
+                        "Unsupported encoded format: {}",
+                        options.encoded_format.as_deref().unwrap_or_default()
+                    ))
+                })?;
+                let encoder = Encoder::open(path, format, options.metadata.as_ref()).map_err(Error::from_reason)?;
+                pending.add(path.clone());
+                Some(Mutex::new(encoder))
+            }
+            None => None,
+        };
+
+        let wav = match &options.wav_path {
+            Some(path) => {
+                let sink = match options.wav_segment_duration_ms {
+                    Some(0) => return Err(Error::from_reason("wavSegmentDurationMs must be positive")),
+                    Some(ms) => SegmentedWavWriter::create(path, ms as u64 * 16, options.metadata.as_ref())
+                        .map(WavSink::Segmented),
+                    None => WavWriter::create(path, options.metadata.as_ref()).map(WavSink::Single),
+                };
+                let sink = sink.map_err(|e| {
+                    Error::from_reason(format!("Failed to create WAV file at {}: {}", path, e))
+                })?;
+                pending.add(match options.wav_segment_duration_ms {
+                    Some(_) => segment_path(path, 1),
+                    None => path.clone(),
+                });
+                Some(Mutex::new(sink))
+            }
+            None => None,
+        };
+
+        let rolling = match options.rolling_retention_ms {
+            Some(retention_ms) => Some(Mutex::new(
+                RollingBuffer::new(retention_ms).map_err(Error::from_reason)?,
+            )),
+            None => None,
+        };
+
+        let socket = match &options.socket_sink {
+            Some(target) => Some(Mutex::new(SocketSink::connect(target).map_err(Error::from_reason)?)),
+            None => None,
+        };
+
+        let gate = options.silence_threshold_db.map(|threshold_db| {
+            Mutex::new(SilenceGate::new(
+                threshold_db,
+                options.gate_attack_ms.unwrap_or(20),
+                options.gate_hangover_ms.unwrap_or(400),
+            ))
+        });
+
+        let sequence = options
+            .sequence_numbers
+            .unwrap_or(false)
+            .then(|| Arc::new(AtomicU32::new(0)));
+        let base64 = options.base64.unwrap_or(false);
+        let max_buffered_ms = options.max_buffered_ms.unwrap_or(DEFAULT_MAX_BUFFERED_MS);
+        let backlog = Backlog::new(max_buffered_ms);
+        let memory = memory.then(|| Mutex::new(MemoryRecording::new(max_buffered_ms)));
+        let consumer_gone = Arc::new(AtomicBool::new(false));
+
+        let pacer = match (options.paced_interval_ms, &callback) {
+            (Some(interval_ms), Some(callback)) => {
+                let sink = Arc::clone(callback);
+                let sequence = sequence.clone();
+                let backlog = Arc::clone(&backlog);
+                let consumer_gone = Arc::clone(&consumer_gone);
+                Some(Pacer::spawn(
+                    interval_ms,
+                    options.paced_buffer_ms.unwrap_or(1000).min(max_buffered_ms),
+                    move |chunk| {
+                        let seq = next_seq(&sequence);
+                        let Some(token) = backlog.reserve(chunk.len()) else {
+                            report_overflow(&backlog, &sink, chunk.len());
+                            return;
+                        };
+                        let chunk = PooledBuffer::from_samples(&chunk)
+                            .with_seq(seq)
+                            .with_base64(base64)
+                            .with_token(token);
+                        let status = sink.call(Ok(chunk), ThreadsafeFunctionCallMode::NonBlocking);
+                        check_consumer(&consumer_gone, status);
+                    },
+                ))
+            }
+            _ => None,
+        };
+
+        let resampler = processor.unwrap_or_else(|| {
+            let mut resampler = custom_filter.unwrap_or_else(Resampler::new);
+            resampler.set_mixdown_mode(mixdown_mode);
+            resampler.set_fixed_point(options.fixed_point.unwrap_or(false));
+            resampler.set_process_in_float(options.process_in_float.unwrap_or(true));
+            resampler.set_phase_protection(options.phase_protection.unwrap_or(false));
+            resampler.set_low_latency(options.low_latency.unwrap_or(false));
+            if let Some(map) = &options.channel_map {
+                resampler.set_channel_map(ChannelMap {
+                    swap_lr: map.swap_lr.unwrap_or(false),
+                    invert_l: map.invert_l.unwrap_or(false),
+                    invert_r: map.invert_r.unwrap_or(false),
+                });
+            }
+            if let Some(settings) = expander {
+                resampler.set_expander(settings);
+            }
+            if let Some(target_db) = options.normalize_to_target {
+                resampler.set_normalize_target(target_db);
+            }
+            if let Some(seed) = options.dither_seed {
+                resampler.set_dither_seed(seed as u64);
+            }
+            resampler.set_dither(options.dither.unwrap_or(false));
+            Box::new(resampler)
+        });
+
+        // Create the callback context
+        let session_id = NEXT_SESSION_ID.fetch_add(1, Ordering::Relaxed);
+        let ctx = Arc::new(CallbackContext {
+            session_id,
+            callback,
+            resampler: Mutex::new(resampler),
+            prime_tail: Mutex::new(
+                options.prime_tail.as_ref().map(|tail| tail.iter().map(|&x| x as f32).collect()),
+            ),
+            encoder,
+            wav,
+            rolling,
+            recent: options.recent_retention_ms.map(|ms| Mutex::new(RecentAudio::new(ms))),
+            socket,
+            gate,
+            pacer,
+            consumer_gone,
+            framer: options.frames_per_callback.map(|len| Mutex::new(Framer::new(len as usize))),
+            coalescer: options
+                .coalesce_ms
+                .or(Some(DEFAULT_COALESCE_MS))
+                .filter(|&ms| ms > 0 && options.paced_interval_ms.is_none() && options.frames_per_callback.is_none())
+                .map(|ms| Mutex::new(Coalescer::new(ms as usize * 16))),
+            clock: Mutex::new(ClockTracker::default()),
+            callback_timing: Mutex::new(IntervalStats::default()),
+            busy: Mutex::new(BusyMeter::default()),
+            clipped_samples: AtomicU64::new(0),
+            last_clip_warning: Mutex::new(None),
+            channel_warning_sent: AtomicBool::new(false),
+            buffer_warning_sent: AtomicBool::new(false),
+            rate_warning_sent: AtomicBool::new(false),
+            sck_drop_baseline: unsafe { voxtape_sck_dropped_frames() },
+            input_format: AtomicU64::new(0),
+            paused: AtomicBool::new(false),
+            mute: Mutex::new(SoftMute::default()),
+            paused_at: Mutex::new(None),
+            segments: Mutex::new(SegmentTracker::default()),
+            mic: capture_microphone.then(|| {
+                let ducker = options.duck_system_db.map(|depth_db| {
+                    Ducker::new(
+                        depth_db,
+                        options.duck_threshold_db.unwrap_or(-40.0),
+                        options.duck_attack_ms.unwrap_or(50) as f64,
+                        options.duck_release_ms.unwrap_or(500) as f64,
+                    )
+                });
+                Mutex::new(MicMix::new(options.mic_ramp_ms.unwrap_or(0), ducker))
+            }),
+            first_audio: Mutex::new(false),
+            first_audio_cv: Condvar::new(),
+            warmup_remaining: AtomicU64::new(options.warmup_ms.unwrap_or(0) as u64 * 16),
+            extra_outputs: Mutex::new(Vec::new()),
+            mixdown_mode,
+            sequence,
+            base64,
+            backlog,
+            vad: Mutex::new(None),
+            meter: Mutex::new(None),
+            raw_tap: Mutex::new(None),
+            loudness: Mutex::new(None),
+            memory,
+        });
+
+        // Store context globally so it stays alive
+        {
+            let mut ctx_guard = context_mutex().lock().map_err(|e| {
+                Error::from_reason(format!("Failed to acquire context lock: {}", e))
+            })?;
+            *ctx_guard = Some(Arc::clone(&ctx));
+        }
+
+        let user_data = Arc::as_ptr(&ctx) as *mut c_void;
+
+        native_log!(Info, "Starting {} capture...", backend.name());
+
+        let config = SckStreamConfig {
+            realtime_priority: options.realtime_priority.unwrap_or(false) as i32,
+            capture_microphone: capture_microphone as i32,
+            mic_callback: capture_microphone.then_some(sck_mic_callback as SckAudioCallback),
+            mono: options.request_mono_input.unwrap_or(false) as i32,
+            exclude_self: options.exclude_self.unwrap_or(false) as i32,
+        };
+        unsafe { voxtape_sck_configure(&config) };
+
+        let retry_attempts = options.start_retry_attempts.unwrap_or(DEFAULT_START_RETRY_ATTEMPTS);
+        let mut retry_delay = Duration::from_millis(
+            options.start_retry_delay_ms.unwrap_or(DEFAULT_START_RETRY_DELAY_MS) as u64,
+        );
+        let mut attempt = 0;
+        let result = loop {
+            let result = match backend {
+                CaptureBackend::Sck => start(user_data),
+                CaptureBackend::CoreAudioTap => unsafe { voxtape_tap_start_capture(sck_audio_callback, user_data) },
+                CaptureBackend::Device => unsafe {
+                    voxtape_device_start_capture(options.device_id.unwrap_or(0), sck_audio_callback, user_data)
+                },
+            };
+            if result == 0 || attempt == retry_attempts || !is_transient_start_failure(backend, result) {
+                break result;
+            }
+            attempt += 1;
+            native_log!(
+                Warn,
+                "{} start failed with code {}; retrying in {}ms ({}/{})",
+                backend.name(),
+                result,
+                retry_delay.as_millis(),
+                attempt,
+                retry_attempts
+            );
+            std::thread::sleep(retry_delay);
+            retry_delay *= 2;
+        };
+
+        if result != 0 {
+            // Cleanup context on failure
+            if let Ok(mut ctx_guard) = context_mutex().lock() {
+                *ctx_guard = None;
+            }
+            let error = if result == SCK_UNAVAILABLE {
+                match backend {
+                    CaptureBackend::Sck => framework_unavailable_error(),
+                    CaptureBackend::CoreAudioTap | CaptureBackend::Device => tap_unavailable_error(),
+                }
+            } else if backend == CaptureBackend::Sck && ProcessSession::current().is_background() {
+                background_session_unsupported_error(ProcessSession::current())
+            } else if backend == CaptureBackend::Sck && result == SCK_NO_SHAREABLE_CONTENT {
+                no_shareable_content_error()
+            } else if backend == CaptureBackend::Device && result == DEVICE_NOT_FOUND {
+                device_not_found_error(options.device_id.unwrap_or(0))
+            } else {
+                Error::from_reason(format!("{} start capture failed with code {}", backend.name(), result))
+            };
+            log_event(&error.reason);
+            return Err(error);
+        }
+
+        // Store state
+        {
+            let mut state = state_mutex().lock().map_err(|e| {
+                Error::from_reason(format!("Failed to acquire state lock: {}", e))
+            })?;
+            *state = Some(CaptureState {
+                backend,
+                options: effective_options(&options, backend),
+                session_id,
+                started_at: std::time::SystemTime::now(),
+                device,
+                // Started under the state lock, so its first check sees this session
+                disk_watcher: disk_guard.map(|guard| watch_disk_space(session_id, recording_paths, guard)),
+            });
+        }
+        pending.keep();
+
+        native_log!(Info, "{} capture active — 48kHz stereo → 16kHz mono Int16", backend.name());
+
+        if options.retap_on_device_change == Some(true) && backend != CaptureBackend::Device {
+            let status = unsafe { voxtape_watch_default_output(default_output_changed, std::ptr::null_mut()) };
+            if status != 0 {
+                log_event(&format!("Could not watch for output device changes (status {})", status));
+            }
+        }
+
+        if let Some(message) = output_device::uncapturable_output_warning() {
+            log_event(&message);
+            if let Some(callback) = &ctx.callback {
+                callback.call(
+                    Err(Error::new(Status::GenericFailure, message)),
+                    ThreadsafeFunctionCallMode::NonBlocking,
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A contiguous run of delivered audio between pauses
+#[napi(object)]
+pub struct CaptureSegment {
+    /// Start on the session timeline (ms since the first SCK buffer)
+    pub start_ms: f64,
+    /// End on the session timeline (ms since the first SCK buffer)
+    pub end_ms: f64,
+    /// Index of the segment's first sample in the delivered 16kHz stream
+    pub sample_offset: i64,
+    /// Number of 16kHz samples in the segment
+    pub sample_count: i64,
+}
+
+/// Returned by `resume_capture`: where the next audio lands relative to the pause
+#[napi(object)]
+pub struct ResumeInfo {
+    /// How long the capture was paused, in milliseconds
+    pub pause_duration_ms: f64,
+    /// Index in the delivered 16kHz stream of the first sample after resuming
+    pub sample_offset: i64,
+}
+
+fn active_context() -> Result<Arc<CallbackContext>> {
+    context_mutex()
+        .lock()
+        .map_err(|e| Error::from_reason(format!("Failed to acquire context lock: {}", e)))?
+        .as_ref()
+        .map(Arc::clone)
+        .ok_or_else(|| Error::from_reason("Not capturing system audio"))
+}
+
+/// Pause delivery. The stream keeps running (no permission or startup cost on
+/// resume); audio arriving while paused is dropped from every output.
+#[napi]
+pub fn pause_capture() -> Result<()> {
+    let ctx = active_context()?;
+    if !ctx.paused.swap(true, Ordering::AcqRel) {
+        if let Ok(mut paused_at) = ctx.paused_at.lock() {
+            *paused_at = Some(Instant::now());
+        }
+    }
+    Ok(())
+}
+
+/// Mute or unmute the active capture. Unlike `pause_capture`, a muted
+/// capture keeps delivering audio, timestamps and sequence numbers, only
+/// silent (to the callback, extra outputs, the raw tap and every file and
+/// stream alike), so recordings keep a muted span of the right length
+/// instead of a gap. Gain fades over 10ms on each change to avoid clicks.
+#[napi]
+pub fn set_muted(muted: bool) -> Result<()> {
+    let ctx = active_context()?;
+    ctx.mute
+        .lock()
+        .map_err(|e| Error::from_reason(format!("Failed to acquire mute lock: {}", e)))?
+        .set_muted(muted);
+    Ok(())
+}
+
+/// Resume delivery after `pause_capture`.
+#[napi]
+pub fn resume_capture() -> Result<ResumeInfo> {
+    let ctx = active_context()?;
+    let pause_duration_ms = ctx
+        .paused_at
+        .lock()
+        .ok()
+        .and_then(|mut p| p.take())
+        .map(|t| t.elapsed().as_secs_f64() * 1000.0)
+        .unwrap_or(0.0);
+    let sample_offset = ctx.segments.lock().map(|s| s.samples_out()).unwrap_or(0);
+    ctx.paused.store(false, Ordering::Release);
+    Ok(ResumeInfo {
+        pause_duration_ms,
+        sample_offset: sample_offset as i64,
+    })
+}
+
+// ── Extra Outputs ───────────────────────────────────────────────────────────
+
+/// Format of an additional output stream
+#[napi(object)]
+pub struct OutputConfig {
+    /// Output sample rate in Hz: a divisor or integer multiple of the capture
+    /// rate (e.g. 16000 or 48000 for a 48kHz stream). 8000 uses a
+    /// telephony-grade filter, flat to 3.4kHz
+    pub sample_rate: u32,
+    /// 1 (mono mixdown, default) or 2 (stereo)
+    pub channels: Option<u32>,
+    /// "linear" (Int16 PCM, default), "mulaw" or "alaw" (8-bit G.711)
+    pub encoding: Option<String>,
+}
+
+static NEXT_OUTPUT_ID: AtomicU32 = AtomicU32::new(1);
+
+/// Check `config`, returning its channel count and encoding.
+fn parse_output_config(config: &OutputConfig) -> Result<(u32, SampleEncoding)> {
+    let channels = config.channels.unwrap_or(1);
+    if channels != 1 && channels != 2 {
+        return Err(Error::from_reason(format!(
+            "Unsupported output channel count: {}",
+            channels
+        )));
+    }
+    if config.sample_rate == 0 {
+        return Err(Error::from_reason("sampleRate must be greater than 0"));
+    }
+    let encoding = SampleEncoding::parse(config.encoding.as_deref()).ok_or_else(|| {
+        Error::from_reason(format!(
+            "Unsupported output encoding: {}",
+            config.encoding.as_deref().unwrap_or_default()
+        ))
+    })?;
+    Ok((channels, encoding))
+}
+
+/// Add an output to the running capture, e.g. 48kHz stereo for archival next
+/// to the main 16kHz mono stream. The raw input is resampled separately for
+/// each output; `callback` receives interleaved samples in the requested
+/// format. Outputs end with the capture. Returns an id for
+/// `remove_capture_output`.
+#[napi]
+pub fn add_capture_output(callback: ThreadsafeFunction<PooledBuffer>, config: OutputConfig) -> Result<u32> {
+    let (channels, encoding) = parse_output_config(&config)?;
+
+    let ctx = active_context()?;
+    let id = NEXT_OUTPUT_ID.fetch_add(1, Ordering::Relaxed);
+    ctx.extra_outputs
+        .lock()
+        .map_err(|e| Error::from_reason(format!("Failed to acquire outputs lock: {}", e)))?
+        .push(ExtraOutput {
+            id,
+            pipeline: OutputPipeline::new(config.sample_rate, channels, ctx.mixdown_mode),
+            encoding,
+            callback,
+        });
+    Ok(id)
+}
+
+/// Remove an output added with `add_capture_output`.
+/// Returns false if no such output is registered.
+#[napi]
+pub fn remove_capture_output(id: u32) -> Result<bool> {
+    let ctx = active_context()?;
+    let mut outputs = ctx
+        .extra_outputs
+        .lock()
+        .map_err(|e| Error::from_reason(format!("Failed to acquire outputs lock: {}", e)))?;
+    let before = outputs.len();
+    outputs.retain(|o| o.id != id);
+    Ok(outputs.len() != before)
+}
+
+// ── Config Validation ───────────────────────────────────────────────────────
+
+/// Outcome of one check in a `ValidationReport`
+#[napi(string_enum = "lowercase")]
+#[derive(PartialEq)]
+pub enum ValidationStatus {
+    Pass,
+    /// Capture would start, but maybe not as intended
+    Warn,
+    /// Capture would fail to start
+    Fail,
+}
+
+/// One check made by `validate_capture_config`
+#[napi(object)]
+pub struct ValidationCheck {
+    /// The option checked ("wavPath", "outputs[0]", ...), or "session",
+    /// "platform", "permission" or "filter" for checks of the system
+    pub option: String,
+    pub status: ValidationStatus,
+    /// What's wrong, for a warning or failure. Failures carry the error
+    /// starting the capture would throw, prefix included
+    pub message: Option<String>,
+}
+
+/// Result of `validate_capture_config`
+#[napi(object)]
+pub struct ValidationReport {
+    /// No check failed
+    pub ok: bool,
+    pub checks: Vec<ValidationCheck>,
+}
+
+/// Check whether a capture with `options` (and, for
+/// `start_capture_with_filter`, `filter`; for `add_capture_output`,
+/// `outputs`) could start on this machine, without starting anything,
+/// prompting or creating files: option values, a capture already running,
+/// OS and backend support, Screen Recording access, the device, recording
+/// paths and free space, the encoder, and included apps. Options with
+/// nothing to check beyond their values are only listed if they fail.
+#[napi]
+pub fn validate_capture_config(
+    options: Option<CaptureOptions>,
+    filter: Option<ContentFilter>,
+    outputs: Option<Vec<OutputConfig>>,
+) -> ValidationReport {
+    let options = options.unwrap_or_default();
+    let mut checks = Vec::new();
+    let mut check = |option: &str, problem: std::result::Result<(), (ValidationStatus, String)>| {
+        let (status, message) = match problem {
+            Ok(()) => (ValidationStatus::Pass, None),
+            Err((status, message)) => (status, Some(message)),
+        };
+        checks.push(ValidationCheck {
+            option: option.to_string(),
+            status,
+            message,
+        });
+    };
+    let fail = |error: Error| Err((ValidationStatus::Fail, error.reason.clone()));
+
+    let running = state_mutex().lock().ok().and_then(|state| state.as_ref().map(|s| s.session_id));
+    check(
+        "session",
+        match running {
+            Some(session_id) => fail(already_capturing_error(&format!(
+                "Already capturing system audio (session {}); only one capture runs at a time",
+                session_id
+            ))),
+            None => Ok(()),
+        },
+    );
+
+    for (option, problem) in option_problems(&options) {
+        check(option, Err((ValidationStatus::Fail, problem)));
+    }
+
+    let backend = CaptureBackend::parse(options.backend.as_deref());
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = backend;
+        check(
+            "platform",
+            fail(Error::from_reason("System audio capture is only supported on macOS 14.2+")),
+        );
+    }
+
+    #[cfg(target_os = "macos")]
+    if let Some(backend) = backend {
+        let api = unsafe { voxtape_api_capabilities() };
+        let capture_microphone = options.capture_microphone.unwrap_or(false);
+        match backend {
+            CaptureBackend::Sck => {
+                let session = ProcessSession::current();
+                check(
+                    "platform",
+                    if api & API_SCK_AUDIO == 0 {
+                        fail(framework_unavailable_error())
+                    } else if session == ProcessSession::NoGui {
+                        fail(background_session_unsupported_error(session))
+                    } else {
+                        Ok(())
+                    },
+                );
+                check(
+                    "permission",
+                    if has_screen_capture_access() { Ok(()) } else { fail(permission_denied_error()) },
+                );
+                if capture_microphone && api & API_SCK_MICROPHONE == 0 {
+                    check(
+                        "captureMicrophone",
+                        fail(Error::from_reason(
+                            "captureMicrophone needs macOS 15 or later (see capabilities().microphoneMix)",
+                        )),
+                    );
+                }
+            }
+            CaptureBackend::CoreAudioTap | CaptureBackend::Device => {
+                if backend == CaptureBackend::Device {
+                    check(
+                        "deviceId",
+                        match options.device_id.map(|id| (id, devices::find_device(id))) {
+                            None => fail(Error::from_reason("The \"device\" backend needs deviceId")),
+                            Some((id, None)) => fail(device_not_found_error(id)),
+                            Some((_, Some(device))) if device.input_channels == 0 && api & API_PROCESS_TAP == 0 => {
+                                fail(tap_unavailable_error())
+                            }
+                            Some(_) => Ok(()),
+                        },
+                    );
+                } else {
+                    check(
+                        "platform",
+                        if api & API_PROCESS_TAP == 0 { fail(tap_unavailable_error()) } else { Ok(()) },
+                    );
+                    check(
+                        "permission",
+                        Err((
+                            ValidationStatus::Warn,
+                            "Audio capture permission can't be checked in advance; macOS asks when capture starts"
+                                .to_string(),
+                        )),
+                    );
+                }
+                if capture_microphone {
+                    check(
+                        "captureMicrophone",
+                        fail(Error::from_reason("captureMicrophone needs the \"sck\" backend")),
+                    );
+                }
+            }
+        }
+    }
+
+    let segmented = options.wav_segment_duration_ms.is_some();
+    for (option, path, overwritten) in [
+        ("wavPath", &options.wav_path, !segmented),
+        ("encodedPath", &options.encoded_path, true),
+    ] {
+        let Some(path) = path else { continue };
+        let file = std::path::Path::new(path);
+        let dir = file.parent().filter(|dir| !dir.as_os_str().is_empty());
+        check(
+            option,
+            if dir.is_some_and(|dir| !dir.is_dir()) {
+                fail(Error::from_reason(format!("Directory for {} doesn't exist", path)))
+            } else if overwritten && file.exists() {
+                Err((ValidationStatus::Warn, format!("{} exists and will be overwritten", path)))
+            } else {
+                Ok(())
+            },
+        );
+    }
+
+    let recording_paths: Vec<String> = options.wav_path.iter().chain(&options.encoded_path).cloned().collect();
+    let low_disk_space_mb = options.low_disk_space_mb.unwrap_or(DEFAULT_LOW_DISK_SPACE_MB);
+    if low_disk_space_mb > 0 {
+        if let Some(available) = available_disk_bytes(&recording_paths) {
+            let mut guard = DiskGuard::new(low_disk_space_mb);
+            check(
+                "lowDiskSpaceMb",
+                if guard.refuses_start(available) {
+                    fail(low_disk_space_error(available))
+                } else if guard.check(available) == DiskAction::Warn {
+                    Err((
+                        ValidationStatus::Warn,
+                        format!(
+                            "Only {}MB free on the recording volume; a \"lowDiskSpace\" event will follow the start",
+                            available / (1024 * 1024)
+                        ),
+                    ))
+                } else {
+                    Ok(())
+                },
+            );
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    if options.encoded_path.is_some() {
+        if let Some(format) = EncodedFormat::parse(options.encoded_format.as_deref()) {
+            check(
+                "encodedFormat",
+                if format.is_supported() {
+                    Ok(())
+                } else {
+                    fail(Error::from_reason(format!(
+                        "No {} encoder on this system",
+                        options.encoded_format.as_deref().unwrap_or("aac")
+                    )))
+                },
+            );
+        }
+    }
+
+    if let Some(filter) = &filter {
+        check(
+            "filter",
+            match filter_problem(filter, Some(&options)) {
+                Some(problem) => Err((ValidationStatus::Fail, problem)),
+                None => {
+                    let missing: Vec<&str> = filter
+                        .included_bundle_ids
+                        .iter()
+                        .flatten()
+                        .filter(|id| !is_app_running(id))
+                        .map(String::as_str)
+                        .collect();
+                    if missing.is_empty() {
+                        Ok(())
+                    } else {
+                        Err((
+                            ValidationStatus::Warn,
+                            format!(
+                                "Not running: {}. Included apps are only captured if running when capture starts",
+                                missing.join(", ")
+                            ),
+                        ))
+                    }
+                }
+            },
+        );
+    }
+
+    for (index, output) in outputs.iter().flatten().enumerate() {
+        check(&format!("outputs[{}]", index), parse_output_config(output).map(|_| ()).or_else(fail));
+    }
+
+    ValidationReport {
+        ok: checks.iter().all(|c| c.status != ValidationStatus::Fail),
+        checks,
+    }
+}
+
+/// Whether an app with `bundle_id` is running (false off macOS).
+fn is_app_running(bundle_id: &str) -> bool {
+    #[cfg(target_os = "macos")]
+    {
+        CString::new(bundle_id).is_ok_and(|id| unsafe { voxtape_is_app_running(id.as_ptr()) } != 0)
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = bundle_id;
+        false
+    }
+}
+
+// ── Lifecycle Events ────────────────────────────────────────────────────────
+
+/// Something that happened to a capture session outside the caller's control
+#[napi(object)]
+pub struct CaptureEvent {
+    /// - "consumerGone": the audio callback was released or aborted by JS
+    ///   while capturing, so the session was stopped
+    /// - "reTapped": capture was re-established after the default output
+    ///   device changed (`retapOnDeviceChange`)
+    /// - "reTapFailed": that restart failed; the session delivers nothing
+    ///   more until restarted
+    /// - "formatChange": the input sample rate or channel count changed
+    ///   mid-session (output is still 16kHz mono)
+    /// - "lowDiskSpace": free space on the recording's volume dropped below
+    ///   `lowDiskSpaceMb`
+    /// - "lowDiskSpaceStopped": it got so low that the session was stopped,
+    ///   with its files finalized
+    pub kind: String,
+    /// Session the event concerns
+    pub session_id: Option<u32>,
+    /// Human-readable description
+    pub message: Option<String>,
+}
+
+static CAPTURE_EVENTS: OnceLock<Mutex<Option<ThreadsafeFunction<CaptureEvent>>>> = OnceLock::new();
+
+fn capture_events_mutex() -> &'static Mutex<Option<ThreadsafeFunction<CaptureEvent>>> {
+    CAPTURE_EVENTS.get_or_init(|| Mutex::new(None))
+}
+
+/// Log a lifecycle event and pass it to the listener, if one is set.
+fn emit_capture_event(kind: &str, session_id: Option<u32>, message: Option<String>) {
+    let text = match &message {
+        Some(message) => format!("{}: {}", kind, message),
+        None => kind.to_string(),
+    };
+    log_event(&text);
+    if let Some(listener) = capture_events_mutex().lock().ok().as_deref().and_then(Option::as_ref) {
+        listener.call(
+            Ok(CaptureEvent {
+                kind: kind.to_string(),
+                session_id,
+                message,
+            }),
+            ThreadsafeFunctionCallMode::NonBlocking,
+        );
+    }
+}
+
+/// Receive lifecycle events for every capture session, independent of the
+/// audio callback (which is exactly what a "consumerGone" event reports
+/// losing). Replaces any listener already set.
+#[napi]
+pub fn set_capture_event_listener(callback: ThreadsafeFunction<CaptureEvent>) -> Result<()> {
+    let mut listener = capture_events_mutex()
+        .lock()
+        .map_err(|e| Error::from_reason(format!("Failed to acquire event listener lock: {}", e)))?;
+    *listener = Some(callback);
+    Ok(())
+}
+
+/// Remove the lifecycle event listener. Returns whether one was set.
+#[napi]
+pub fn clear_capture_event_listener() -> Result<bool> {
+    let mut listener = capture_events_mutex()
+        .lock()
+        .map_err(|e| Error::from_reason(format!("Failed to acquire event listener lock: {}", e)))?;
+    Ok(listener.take().is_some())
+}
+
+// ── Voice Activity ──────────────────────────────────────────────────────────
+
+/// Voice activity detector settings for `start_vad`
+#[napi(object)]
+pub struct VadConfig {
+    /// Level (dBFS) below which audio is never speech (default -45)
+    pub threshold_db: Option<f64>,
+    /// Speech must last this long to start a speech segment (default 60ms)
+    pub min_speech_ms: Option<u32>,
+    /// Non-speech must last this long to end one (default 300ms), so pauses
+    /// between words stay inside the segment
+    pub hangover_ms: Option<u32>,
+}
+
+/// A closed run of speech or non-speech. Times are positions in the
+/// delivered 16kHz stream (paused audio isn't counted), in ms.
+#[napi(object)]
+pub struct VadEvent {
+    pub is_speech: bool,
+    pub start_ms: f64,
+    pub end_ms: f64,
+}
+
+struct VadSink {
+    detector: Vad,
+    callback: ThreadsafeFunction<VadEvent>,
+}
+
+impl VadSink {
+    fn emit(&self, span: VadSpan) {
+        let event = VadEvent {
+            is_speech: span.is_speech,
+            start_ms: span.start_sample as f64 / 16.0,
+            end_ms: span.end_sample as f64 / 16.0,
+        };
+        self.callback.call(Ok(event), ThreadsafeFunctionCallMode::NonBlocking);
+    }
+}
+
+/// Detach the session's VAD, reporting the segment still open.
+fn finish_vad(ctx: &CallbackContext) -> bool {
+    let Some(mut sink) = ctx.vad.lock().ok().and_then(|mut vad| vad.take()) else {
+        return false;
+    };
+    if let Some(span) = sink.detector.finish() {
+        sink.emit(span);
+    }
+    true
+}
+
+/// Run voice activity detection (level and zero-crossing rate per 20ms
+/// frame) on the running capture's 16kHz output, independent of the
+/// silence gate. `callback` receives the stream labeled as alternating
+/// speech and non-speech segments, each once it has ended; the last one is
+/// reported when VAD or the capture stops. Replaces any VAD already running.
+#[napi]
+pub fn start_vad(callback: ThreadsafeFunction<VadEvent>, config: Option<VadConfig>) -> Result<()> {
+    let threshold_db = config.as_ref().and_then(|c| c.threshold_db).unwrap_or(-45.0);
+    let min_speech_ms = config.as_ref().and_then(|c| c.min_speech_ms).unwrap_or(60);
+    let hangover_ms = config.as_ref().and_then(|c| c.hangover_ms).unwrap_or(300);
+
+    let ctx = active_context()?;
+    finish_vad(&ctx);
+    let mut vad = ctx
+        .vad
+        .lock()
+        .map_err(|e| Error::from_reason(format!("Failed to acquire VAD lock: {}", e)))?;
+    *vad = Some(VadSink {
+        detector: Vad::new(threshold_db, min_speech_ms, hangover_ms),
+        callback,
+    });
+    Ok(())
+}
+
+/// Stop voice activity detection, reporting the segment still open.
+/// Returns false if VAD wasn't running.
+#[napi]
+pub fn stop_vad() -> Result<bool> {
+    let ctx = active_context()?;
+    Ok(finish_vad(&ctx))
+}
+
+// ── Level Meter ─────────────────────────────────────────────────────────────
+
+/// Meter ballistics for `start_level_meter`
+#[napi(object)]
+pub struct LevelMeterConfig {
+    /// Time constant for rising levels (default 10ms)
+    pub attack_ms: Option<f64>,
+    /// Time constant for falling levels (default 300ms). A long release
+    /// (~1500ms) with a short attack behaves like a PPM; attack and release
+    /// both at 300ms like a VU meter
+    pub release_ms: Option<f64>,
+    /// Time between readings (default 50ms)
+    pub interval_ms: Option<u32>,
+    /// Also report `spectralFlatness` with each reading (default false; runs
+    /// a small FFT per 16ms of audio)
+    pub spectral_flatness: Option<bool>,
+}
+
+/// A smoothed level reading of the 16kHz output, in dBFS (-100 for silence)
+#[napi(object)]
+pub struct LevelEvent {
+    pub rms_db: f64,
+    pub peak_db: f64,
+    /// With `spectralFlatness` enabled: how noise-like the interval's audio
+    /// was, from near 0 (tonal, e.g. music) to near 1 (noise, ambience).
+    /// Speech sits in between and varies quickly. Absent while silent or
+    /// before 16ms of audio has been analyzed
+    pub spectral_flatness: Option<f64>,
+}
+
+struct MeterSink {
+    meter: LevelMeter,
+    callback: ThreadsafeFunction<LevelEvent>,
+}
+
+/// Meter the running capture's 16kHz output (before the silence gate),
+/// smoothed per sample with attack/release ballistics so readings don't
+/// jump with the chunk size. `callback` receives a reading every
+/// `intervalMs` of audio. Replaces any meter already running.
+#[napi]
+pub fn start_level_meter(callback: ThreadsafeFunction<LevelEvent>, config: Option<LevelMeterConfig>) -> Result<()> {
+    let attack_ms = config.as_ref().and_then(|c| c.attack_ms).unwrap_or(10.0);
+    let release_ms = config.as_ref().and_then(|c| c.release_ms).unwrap_or(300.0);
+    let interval_ms = config.as_ref().and_then(|c| c.interval_ms).unwrap_or(50);
+    if interval_ms == 0 {
+        return Err(Error::from_reason("intervalMs must be greater than 0"));
+    }
+    if attack_ms < 0.0 || release_ms < 0.0 {
+        return Err(Error::from_reason("attackMs and releaseMs must not be negative"));
+    }
+
+    let ctx = active_context()?;
+    let mut meter = ctx
+        .meter
+        .lock()
+        .map_err(|e| Error::from_reason(format!("Failed to acquire meter lock: {}", e)))?;
+    let mut level_meter = LevelMeter::new(attack_ms, release_ms, interval_ms);
+    level_meter.set_spectral_flatness(config.as_ref().and_then(|c| c.spectral_flatness).unwrap_or(false));
+    *meter = Some(MeterSink {
+        meter: level_meter,
+        callback,
+    });
+    Ok(())
+}
+
+/// Stop level metering. Returns false if no meter was running.
+#[napi]
+pub fn stop_level_meter() -> Result<bool> {
+    let ctx = active_context()?;
+    let mut meter = ctx
+        .meter
+        .lock()
+        .map_err(|e| Error::from_reason(format!("Failed to acquire meter lock: {}", e)))?;
+    Ok(meter.take().is_some())
+}
+
+// ── Loudness Meter ──────────────────────────────────────────────────────────
+
+/// Options for `start_loudness_meter`
+#[napi(object)]
+pub struct LoudnessMeterConfig {
+    /// Time between readings, a multiple of 100ms (default 100ms)
+    pub interval_ms: Option<u32>,
+}
+
+/// A BS.1770 loudness reading of the 16kHz output, in LUFS (-100 for
+/// silence)
+#[napi(object)]
+pub struct LoudnessEvent {
+    /// Over the last 400ms; absent for the first 400ms
+    pub momentary: Option<f64>,
+    /// Over the last 3s; absent for the first 3s
+    pub short_term: Option<f64>,
+    /// Since the meter started, gated: 400ms blocks below -70 LUFS, then
+    /// those more than 10 LU below the loudness of the rest, are left out,
+    /// so pauses and quiet passages don't pull it down. Absent until a
+    /// block passes the gates
+    pub integrated: Option<f64>,
+}
+
+struct LoudnessSink {
+    meter: LoudnessMeter,
+    callback: ThreadsafeFunction<LoudnessEvent>,
+}
+
+/// Measure the running capture's 16kHz output (before the silence gate)
+/// per ITU-R BS.1770: K-weighted momentary, short-term and gated integrated
+/// loudness, e.g. to normalize a recording to -16 LUFS afterwards.
+/// `callback` receives a reading every `intervalMs` of audio. Replaces any
+/// loudness meter already running, restarting the integrated measurement.
+#[napi]
+pub fn start_loudness_meter(
+    callback: ThreadsafeFunction<LoudnessEvent>,
+    config: Option<LoudnessMeterConfig>,
+) -> Result<()> {
+    let interval_ms = config.as_ref().and_then(|c| c.interval_ms).unwrap_or(100);
+    if interval_ms == 0 || !interval_ms.is_multiple_of(100) {
+        return Err(Error::from_reason("intervalMs must be a positive multiple of 100"));
+    }
+
+    let ctx = active_context()?;
+    let mut loudness = ctx
+        .loudness
+        .lock()
+        .map_err(|e| Error::from_reason(format!("Failed to acquire loudness lock: {}", e)))?;
+    *loudness = Some(LoudnessSink {
+        meter: LoudnessMeter::new((interval_ms / 100) as usize),
+        callback,
+    });
+    Ok(())
+}
+
+/// Stop loudness metering. Returns false if no loudness meter was running.
+#[napi]
+pub fn stop_loudness_meter() -> Result<bool> {
+    let ctx = active_context()?;
+    let mut loudness = ctx
+        .loudness
+        .lock()
+        .map_err(|e| Error::from_reason(format!("Failed to acquire loudness lock: {}", e)))?;
+    Ok(loudness.take().is_some())
+}
+
+/// Stop the stream and detach its callback context.
+fn end_capture_session() -> Result<Option<Arc<CallbackContext>>> {
+    // Let a restart in progress finish first
+    let _restart = RESTART_LOCK
+        .lock()
+        .map_err(|e| Error::from_reason(format!("Failed to acquire restart lock: {}", e)))?;
+    let capture = {
+        let mut state = state_mutex().lock().map_err(|e| {
+            Error::from_reason(format!("Failed to acquire state lock: {}", e))
+        })?;
+        state.take()
+    };
+
+    if let Some(capture) = capture {
+        #[cfg(target_os = "macos")]
+        unsafe {
+            voxtape_unwatch_default_output();
+            match capture.backend {
+                CaptureBackend::Sck => {
+                    voxtape_sck_stop_capture();
+                    native_log!(Info, "SCK capture stopped");
+                }
+                CaptureBackend::CoreAudioTap => {
+                    voxtape_tap_stop_capture();
+                    native_log!(Info, "Tap capture stopped");
+                }
+                CaptureBackend::Device => {
+                    voxtape_device_stop_capture();
+                    native_log!(Info, "Device capture stopped");
+                }
+            }
+        }
+        if let Some(watcher) = capture.disk_watcher {
+            // The watcher ends sessions itself when the disk fills, and
+            // can't join itself
+            watcher.thread().unpark();
+            if watcher.thread().id() != std::thread::current().id() {
+                let _ = watcher.join();
+            }
+        }
+    }
+
+    // Clear the callback context once the stream no longer calls into it.
+    // Dropping it also finalizes the encoded output, if any.
+    let ctx = context_mutex().lock().ok().and_then(|mut ctx| ctx.take());
+    if let Some(ctx) = &ctx {
+        finish_vad(ctx);
+        flush_delivery(ctx);
+    }
+    // The pacer may still hold a reference; patch the WAV header now anyway
+    if let Some(wav) = ctx.as_ref().and_then(|c| c.wav.as_ref()) {
+        if let Ok(mut wav) = wav.lock() {
+            if let Err(e) = wav.finalize() {
+                native_log!(Error, "Failed to finalize WAV file: {}", e);
+            }
+        }
+    }
+    Ok(ctx)
+}
+
+/// Deliver the short last block left in the framer or coalescer, if any.
+fn flush_delivery(ctx: &CallbackContext) {
+    let Some(callback) = &ctx.callback else {
+        return;
+    };
+    let rest = match (&ctx.framer, &ctx.coalescer) {
+        (Some(framer), _) => framer.lock().ok().and_then(|mut f| f.flush()),
+        (None, Some(coalescer)) => coalescer.lock().ok().and_then(|mut c| c.flush()),
+        (None, None) => None,
+    };
+    if let Some(rest) = rest {
+        deliver_chunk(ctx, callback, &rest);
+    }
+}
+
+fn capture_segments(ctx: &CallbackContext) -> Vec<CaptureSegment> {
+    let segments = ctx.segments.lock().map(|s| s.segments()).unwrap_or_default();
+    segments
+        .into_iter()
+        .map(|s| CaptureSegment {
+            start_ms: s.start_ns as f64 / 1e6,
+            end_ms: s.end_ns as f64 / 1e6,
+            sample_offset: s.sample_offset as i64,
+            sample_count: s.sample_count as i64,
+        })
+        .collect()
+}
 
-    let total_samples = (frame_count * channels) as usize;
-    let float_slice = std::slice::from_raw_parts(data, total_samples);
+/// Stop capturing system audio. Cleans up all resources.
+/// Returns the delivered segments (one per run between pauses).
+#[napi]
+pub fn stop_capture() -> Result<Vec<CaptureSegment>> {
+    Ok(end_capture_session()?
+        .map(|ctx| capture_segments(&ctx))
+        .unwrap_or_default())
+}
 
-    // Resample to 16kHz mono Int16
-    let int16_samples = {
-        let mut resampler = match ctx.resampler.lock() {
-            Ok(r) => r,
-            Err(_) => return,
+/// Longest `stop_capture_and_drain` waits for one callback queue to empty
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct StopCaptureTask;
+
+impl Task for StopCaptureTask {
+    type Output = Vec<CaptureSegment>;
+    type JsValue = Vec<CaptureSegment>;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        let Some(ctx) = end_capture_session()? else {
+            return Ok(Vec::new());
         };
-        resampler.process(float_slice, channels, sample_rate)
-    };
+        let segments = capture_segments(&ctx);
 
-    if int16_samples.is_empty() {
-        return;
+        let mut callbacks: Vec<Arc<ThreadsafeFunction<PooledBuffer>>> = ctx.callback.iter().cloned().collect();
+        if let Ok(mut outputs) = ctx.extra_outputs.lock() {
+            callbacks.extend(outputs.drain(..).map(|o| Arc::new(o.callback)));
+        }
+        // Last reference in the common case: stops the pacer, which flushes
+        // whatever it still holds into the callback queue
+        drop(ctx);
+
+        // Calls are delivered in order, so once this marker's call completes
+        // everything queued before it has reached JS
+        for callback in callbacks {
+            let (tx, rx) = std::sync::mpsc::sync_channel(1);
+            let status = callback.call_with_return_value(
+                Ok(PooledBuffer::default()),
+                ThreadsafeFunctionCallMode::Blocking,
+                move |_, _| {
+                    let _ = tx.send(());
+                    Ok(())
+                },
+            );
+            if status == Status::Ok && rx.recv_timeout(DRAIN_TIMEOUT).is_err() {
+                native_log!(Warn, "Timed out waiting for audio callbacks to drain");
+            }
+        }
+        Ok(segments)
     }
 
-    // Convert Int16 slice to bytes for the Buffer
-    let byte_len = int16_samples.len() * 2;
-    let byte_slice =
-        std::slice::from_raw_parts(int16_samples.as_ptr() as *const u8, byte_len);
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
+    }
+}
 
-    let buffer = Buffer::from(byte_slice);
+/// Stop capturing and resolve once every chunk has been delivered to JS,
+/// including whatever paced delivery still held. Each callback receives one
+/// final empty Buffer marking the end of the stream. Resolves with the same
+/// segments as `stop_capture`.
+#[napi]
+pub fn stop_capture_and_drain() -> AsyncTask<StopCaptureTask> {
+    AsyncTask::new(StopCaptureTask)
+}
 
-    // Non-blocking call to JS
-    ctx.callback.call(Ok(buffer), ThreadsafeFunctionCallMode::NonBlocking);
+/// Result of a `record_to_wav` call
+#[napi(object)]
+pub struct RecordingResult {
+    /// Number of 16kHz samples written to the file
+    pub samples: i64,
+    /// Duration of the recorded audio in milliseconds
+    pub duration_ms: f64,
 }
 
-// ── FFI declarations for ObjC bridge ────────────────────────────────────────
+pub struct RecordToWavTask {
+    path: String,
+    duration_ms: u32,
+    metadata: Option<RecordingMetadata>,
+}
 
-type SckAudioCallback = unsafe extern "C" fn(
-    data: *const f32,
-    frame_count: u32,
-    channels: u32,
-    sample_rate: u32,
-    user_data: *mut c_void,
-);
+impl Task for RecordToWavTask {
+    type Output = RecordingResult;
+    type JsValue = RecordingResult;
 
-extern "C" {
-    fn voxtape_sck_start_capture(
-        callback: SckAudioCallback,
-        user_data: *mut c_void,
-    ) -> i32;
+    fn compute(&mut self) -> Result<Self::Output> {
+        let options = CaptureOptions {
+            wav_path: Some(self.path.clone()),
+            metadata: self.metadata.take(),
+            ..Default::default()
+        };
+        start_capture_with(None, Some(options), |user_data| unsafe {
+            voxtape_sck_start_capture(sck_audio_callback, user_data)
+        })?;
+        let session_id = active_session_id();
 
-    fn voxtape_sck_stop_capture();
+        std::thread::sleep(Duration::from_millis(self.duration_ms as u64));
 
-    fn voxtape_has_screen_capture_access() -> i32;
-    fn voxtape_request_screen_capture_access() -> i32;
-    fn voxtape_request_sck_permission() -> i32;
-}
+        // The caller may have stopped this session, and maybe started
+        // another, in the meantime; leave that one alone
+        if session_id.is_none() || active_session_id() != session_id {
+            return Err(Error::from_reason(format!(
+                "The recording to {} was stopped before {}ms had been recorded",
+                self.path, self.duration_ms
+            )));
+        }
+        // Hold on to the context so the WAV writer outlives stop_capture
+        // long enough to read its final sample count.
+        let ctx = context_mutex().lock().ok().and_then(|c| c.as_ref().map(Arc::clone));
+        stop_capture()?;
 
-// ── Exported API ────────────────────────────────────────────────────────────
+        let samples = ctx
+            .as_ref()
+            .and_then(|c| c.wav.as_ref())
+            .and_then(|w| w.lock().ok().map(|w| w.samples_written()))
+            .unwrap_or(0);
 
-/// Check if system audio capture is supported on this platform.
-/// Requires macOS 14.2+ (Sonoma).
-#[napi]
-pub fn is_supported() -> bool {
-    #[cfg(target_os = "macos")]
-    {
-        use std::process::Command;
-        let output = Command::new("sw_vers")
-            .arg("-productVersion")
-            .output();
-
-        match output {
-            Ok(out) => {
-                let version = String::from_utf8_lossy(&out.stdout).trim().to_string();
-                let parts: Vec<u32> = version
-                    .split('.')
-                    .filter_map(|p| p.parse().ok())
-                    .collect();
-                // macOS 14.2+
-                if parts.len() >= 2 {
-                    parts[0] > 14 || (parts[0] == 14 && parts[1] >= 2)
-                } else if parts.len() == 1 {
-                    parts[0] > 14
-                } else {
-                    false
-                }
-            }
-            Err(_) => false,
-        }
+        Ok(RecordingResult {
+            samples: samples as i64,
+            duration_ms: samples as f64 / 16.0,
+        })
     }
 
-    #[cfg(not(target_os = "macos"))]
-    {
-        false
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
     }
 }
 
-/// Check if the app has Screen Capture (Screen Recording) access.
+/// Record system audio for `duration_ms` to a 16kHz mono WAV file at `path`.
+/// Resolves once the file is finalized; rejects if permission is missing, a
+/// capture is already running, or the capture is stopped before the time is
+/// up. `metadata` is embedded in the file.
 #[napi]
-pub fn has_screen_capture_access() -> bool {
-    #[cfg(target_os = "macos")]
-    unsafe {
-        voxtape_has_screen_capture_access() != 0
-    }
-    #[cfg(not(target_os = "macos"))]
-    false
+pub fn record_to_wav(
+    path: String,
+    duration_ms: u32,
+    metadata: Option<RecordingMetadata>,
+) -> AsyncTask<RecordToWavTask> {
+    AsyncTask::new(RecordToWavTask {
+        path,
+        duration_ms,
+        metadata,
+    })
 }
 
-/// Request Screen Capture access (triggers macOS permission dialog).
+// ── Shutdown ────────────────────────────────────────────────────────────────
+
+/// Release everything the module holds for the life of the process, for
+/// embedders that unload or reload it (hot reload, test runners): stops
+/// auto-capture, per-app tracks and any running capture (finalizing their
+/// files and joining the pacer, socket and free-space threads), removes the
+/// output device observer and the capture event listener, frees pooled
+/// buffers, clears the diagnostics event log and restores the default log
+/// level. Callbacks are released, so Node can exit or unload the addon.
+/// Safe to call with nothing running or more than once; capture can be
+/// started again afterwards.
 #[napi]
-pub fn request_screen_capture_access() -> bool {
+pub fn shutdown() -> Result<()> {
+    // First: the watcher thread would otherwise start a new session
+    stop_auto_capture()?;
+    stop_app_tracks()?;
+    end_capture_session()?;
     #[cfg(target_os = "macos")]
     unsafe {
-        voxtape_request_screen_capture_access() != 0
+        voxtape_unwatch_default_output();
     }
-    #[cfg(not(target_os = "macos"))]
-    false
+    clear_capture_event_listener()?;
+    #[cfg(feature = "permission-stub")]
+    set_permission_stub(None);
+    pool::release_pooled();
+    if let Some(log) = EVENT_LOG.get() {
+        if let Ok(mut log) = log.lock() {
+            *log = EventLog::default();
+        }
+    }
+    set_log_level(LogLevel::Info);
+    native_log!(Info, "Native module shut down");
+    Ok(())
 }
 
-/// Request Screen & System Audio Recording permission via ScreenCaptureKit.
+// ── Rolling Capture ─────────────────────────────────────────────────────────
+
+/// Start an always-on capture that keeps only the last `retention_ms` of
+/// audio, Opus-compressed in native memory. No JS callback is involved;
+/// use `save_rolling_buffer` to dump the window and `stop_capture` to end it.
 #[napi]
-pub fn request_audio_capture_permission() -> bool {
-    #[cfg(target_os = "macos")]
-    unsafe {
-        voxtape_request_sck_permission() != 0
-    }
-    #[cfg(not(target_os = "macos"))]
-    false
+pub fn start_rolling_capture(retention_ms: u32) -> Result<()> {
+    let options = CaptureOptions {
+        rolling_retention_ms: Some(retention_ms),
+        ..Default::default()
+    };
+    start_capture_with(None, Some(options), |user_data| unsafe {
+        voxtape_sck_start_capture(sck_audio_callback, user_data)
+    })
 }
 
-/// Start capturing system audio via ScreenCaptureKit.
-/// The callback receives Buffer chunks of 16kHz mono Int16 PCM data.
+/// Write the currently retained rolling window to an Opus .caf file.
+/// Capture keeps running. Returns the duration saved in milliseconds.
 #[napi]
-pub fn start_capture(
-    callback: ThreadsafeFunction<Buffer>,
-) -> Result<()> {
-    // Check if already capturing
-    {
-        let state = state_mutex().lock().map_err(|e| {
-            Error::from_reason(format!("Failed to acquire state lock: {}", e))
-        })?;
-        if state.is_some() {
-            return Err(Error::from_reason("Already capturing system audio"));
+pub fn save_rolling_buffer(path: String) -> Result<f64> {
+    let ctx = active_context()?;
+    let rolling = ctx
+        .rolling
+        .as_ref()
+        .ok_or_else(|| Error::from_reason("Rolling retention is not enabled for this capture"))?;
+    let rolling = rolling
+        .lock()
+        .map_err(|e| Error::from_reason(format!("Failed to acquire rolling buffer lock: {}", e)))?;
+    rolling.save(&path).map_err(Error::from_reason)
+}
+
+/// Copy up to the last `ms` of audio (16kHz mono Int16) from the ring kept
+/// with `recentRetentionMs`, e.g. to clip the last 30 seconds on a user
+/// action. Delivery carries on uninterrupted; the copy is shorter if less
+/// has been captured or retained.
+#[napi]
+pub fn snapshot_recent(ms: u32) -> Result<Buffer> {
+    let ctx = active_context()?;
+    let recent = ctx
+        .recent
+        .as_ref()
+        .ok_or_else(|| Error::from_reason("recentRetentionMs is not enabled for this capture"))?;
+    let recent = recent
+        .lock()
+        .map_err(|e| Error::from_reason(format!("Failed to acquire recent audio lock: {}", e)))?;
+    Ok(samples_to_buffer(&recent.snapshot(ms)))
+}
+
+// ── Memory Capture ──────────────────────────────────────────────────────────
+
+/// The session's delivered audio as little-endian Int16 bytes, up to a cap
+struct MemoryRecording {
+    data: Vec<u8>,
+    max_ms: u32,
+    /// Set once the cap has been reported
+    full: bool,
+}
+
+impl MemoryRecording {
+    fn new(max_ms: u32) -> Self {
+        Self {
+            data: Vec::new(),
+            max_ms,
+            full: false,
         }
     }
 
-    #[cfg(not(target_os = "macos"))]
-    {
-        return Err(Error::from_reason("System audio capture is only supported on macOS 14.2+"));
+    /// Append what fits. Returns a warning the first time audio is dropped.
+    fn push(&mut self, samples: &[i16]) -> Option<String> {
+        let room = (self.max_ms as usize * 16).saturating_sub(self.data.len() / 2);
+        let kept = samples.len().min(room);
+        for sample in &samples[..kept] {
+            self.data.extend_from_slice(&sample.to_le_bytes());
+        }
+        if kept == samples.len() || std::mem::replace(&mut self.full, true) {
+            return None;
+        }
+        Some(format!(
+            "Memory capture reached maxBufferedMs ({}ms); dropping further audio",
+            self.max_ms
+        ))
     }
+}
 
-    #[cfg(target_os = "macos")]
-    unsafe {
-        // Create the callback context
-        let ctx = Arc::new(CallbackContext {
-            callback,
-            resampler: Mutex::new(Resampler::new()),
-        });
+/// Start a capture that accumulates the 16kHz mono Int16 output in native
+/// memory instead of streaming it; `stop_memory_capture` returns all of it.
+/// Recording stops growing at `maxBufferedMs` (default 30000ms), keeping the
+/// beginning; later audio is dropped with a warning logged to stderr.
+/// Other options apply as for `start_capture`.
+#[napi]
+pub fn start_memory_capture(options: Option<CaptureOptions>) -> Result<()> {
+    start_capture_with_processor(None, options, None, true, |user_data| unsafe {
+        voxtape_sck_start_capture(sck_audio_callback, user_data)
+    })
+}
 
-        // Store context globally so it stays alive
-        {
-            let mut ctx_guard = context_mutex().lock().map_err(|e| {
-                Error::from_reason(format!("Failed to acquire context lock: {}", e))
-            })?;
-            *ctx_guard = Some(Arc::clone(&ctx));
-        }
+/// Stop a capture started with `start_memory_capture` and return the whole
+/// recording as one Buffer of 16kHz mono Int16 (little-endian).
+#[napi]
+pub fn stop_memory_capture() -> Result<Buffer> {
+    let ctx = active_context()?;
+    let Some(memory) = &ctx.memory else {
+        return Err(Error::from_reason("The running capture was not started with startMemoryCapture"));
+    };
+    stop_capture()?;
+    let data = memory
+        .lock()
+        .map(|mut m| std::mem::take(&mut m.data))
+        .map_err(|e| Error::from_reason(format!("Failed to acquire recording lock: {}", e)))?;
+    Ok(data.into())
+}
 
-        let user_data = Arc::as_ptr(&ctx) as *mut c_void;
+/// Snapshot of the capture session's health
+#[napi(object)]
+pub struct CaptureStatus {
+    /// Whether a capture session is active
+    pub is_capturing: bool,
+    /// Id of the active session, shared by every window in this process
+    pub session_id: Option<u32>,
+    /// When the active session started (ms since the Unix epoch)
+    pub started_at_ms: Option<f64>,
+    /// Output samples clamped to the Int16 range since capture started
+    pub clipped_sample_count: i64,
+    /// Whether the silence gate is passing audio (None if the gate is disabled)
+    pub gate_open: Option<bool>,
+    /// Whether the default output device is known to be capturable
+    /// (None if unknown); see `output_device_info`
+    pub output_device_capturable: Option<bool>,
+    /// Backend of the active session ("sck", "tap" or "device")
+    pub backend: Option<String>,
+    /// Device being recorded, with `backend: "device"`
+    pub device: Option<AudioDeviceInfo>,
+    /// Group delay the resampler adds, in ms (None before the first buffer
+    /// or with a custom resampler)
+    pub processing_latency_ms: Option<f64>,
+    /// Mean time between audio callbacks this session (about 10ms normally)
+    pub callback_interval_ms: Option<f64>,
+    /// Standard deviation of the time between audio callbacks. Persistently
+    /// high values point at thread scheduling; try `realtimePriority`
+    pub callback_jitter_ms: Option<f64>,
+    /// Longest gap between two audio callbacks this session
+    pub callback_max_interval_ms: Option<f64>,
+    /// Ms per second spent processing audio in the capture callbacks
+    /// (resampling, file sinks, extra outputs), i.e. roughly the module's
+    /// CPU cost: 10 means 1% of one core
+    pub processing_cpu_ms_per_sec: Option<f64>,
+    /// Whether SCK delivered mono after `requestMonoInput` (None if it
+    /// wasn't requested or before the first buffer)
+    pub mono_input_honored: Option<bool>,
+    /// Input frames (at the input rate) ScreenCaptureKit dropped before
+    /// handing audio to the module this session, going by gaps in its
+    /// buffer timestamps: the OS falling behind, as opposed to delivery to
+    /// JS falling behind (the overflow warnings). None unless capturing
+    /// with the "sck" backend
+    pub sck_reported_drops: Option<i64>,
+    /// Whether `set_muted(true)` is in effect (None if not capturing)
+    pub muted: Option<bool>,
+}
 
-        eprintln!("[native-audio] Starting SCK capture...");
+/// Get the current capture status.
+#[napi]
+pub fn capture_status() -> CaptureStatus {
+    let (session, session_info) = state_mutex()
+        .lock()
+        .ok()
+        .and_then(|s| {
+            s.as_ref().map(|s| {
+                (
+                    (s.session_id, unix_ms(s.started_at)),
+                    (
+                        s.backend.name().to_string(),
+                        (s.options.request_mono_input == Some(true), s.device.clone()),
+                    ),
+                )
+            })
+        })
+        .unzip();
+    let (backend, session_extras) = session_info.unzip();
+    let (mono_requested, device) = session_extras.unzip();
+    let (session_id, started_at_ms) = session.unzip();
+    let is_capturing = session_id.is_some();
+    let ctx = context_mutex().lock().ok().and_then(|c| c.as_ref().map(Arc::clone));
 
-        let result = voxtape_sck_start_capture(sck_audio_callback, user_data);
+    #[cfg(target_os = "macos")]
+    let sck_reported_drops = ctx
+        .as_ref()
+        .filter(|_| backend.as_deref() == Some(CaptureBackend::Sck.name()))
+        .map(|c| unsafe { voxtape_sck_dropped_frames() }.saturating_sub(c.sck_drop_baseline) as i64);
+    #[cfg(not(target_os = "macos"))]
+    let sck_reported_drops = None;
 
-        if result != 0 {
-            // Cleanup context on failure
-            if let Ok(mut ctx_guard) = context_mutex().lock() {
-                *ctx_guard = None;
-            }
-            return Err(Error::from_reason(format!(
-                "SCK start capture failed with code {}",
-                result
-            )));
-        }
+    let timing = ctx.as_ref().and_then(|c| {
+        c.callback_timing
+            .lock()
+            .ok()
+            .map(|t| (t.interval_ms(), t.jitter_ms(), t.max_interval_ms()))
+    });
 
-        // Store state
-        {
-            let mut state = state_mutex().lock().map_err(|e| {
-                Error::from_reason(format!("Failed to acquire state lock: {}", e))
-            })?;
-            *state = Some(CaptureState {
-                backend: CaptureBackend::Sck,
-            });
-        }
+    CaptureStatus {
+        is_capturing,
+        session_id,
+        started_at_ms,
+        clipped_sample_count: ctx
+            .as_ref()
+            .map(|c| c.clipped_samples.load(Ordering::Relaxed) as i64)
+            .unwrap_or(0),
+        gate_open: ctx
+            .as_ref()
+            .and_then(|c| c.gate.as_ref())
+            .and_then(|g| g.lock().ok().map(|g| g.is_open())),
+        output_device_capturable: output_device_info().and_then(|d| d.capturable),
+        backend,
+        device: device.flatten(),
+        processing_latency_ms: ctx
+            .as_ref()
+            .and_then(|c| c.resampler.lock().ok().and_then(|r| r.latency_ms())),
+        callback_interval_ms: timing.and_then(|t| t.0),
+        callback_jitter_ms: timing.and_then(|t| t.1),
+        processing_cpu_ms_per_sec: ctx
+            .as_ref()
+            .and_then(|c| c.busy.lock().ok()?.ms_per_sec(Instant::now())),
+        callback_max_interval_ms: timing.and_then(|t| t.2),
+        mono_input_honored: ctx.as_ref().filter(|_| mono_requested == Some(true)).and_then(|c| {
+            let packed = c.input_format.load(Ordering::Relaxed);
+            (packed != 0).then_some(packed as u32 == 1)
+        }),
+        sck_reported_drops,
+        muted: ctx.as_ref().and_then(|c| c.mute.lock().ok().map(|m| m.is_muted())),
+    }
+}
 
-        eprintln!("[native-audio] SCK capture active — 48kHz stereo → 16kHz mono Int16");
-        Ok(())
+/// The active session's options as `start_capture` applied them, with
+/// every default it filled in spelled out, so a reloaded UI can rehydrate
+/// its controls. Returns null when not capturing.
+#[napi]
+pub fn get_capture_config() -> Option<CaptureOptions> {
+    let state = state_mutex().lock().ok()?;
+    state.as_ref().map(|s| s.options.clone())
+}
+
+/// The anti-alias FIR the active capture decimates with, or the built-in
+/// one when not capturing, oldest-sample tap first.
+#[napi]
+pub fn filter_coefficients() -> Vec<f64> {
+    let custom = state_mutex()
+        .lock()
+        .ok()
+        .and_then(|state| state.as_ref().and_then(|s| s.options.filter_coefficients.clone()));
+    custom.unwrap_or_else(|| Resampler::new().coefficients().into_iter().map(f64::from).collect())
+}
+
+/// Convert a recorded PCM buffer to mono at `output_rate` with the same
+/// resampler live capture uses (built-in filter, average mixdown), e.g. to
+/// batch-convert 48kHz recordings consistently with live transcription.
+/// `input` holds interleaved `channels`-channel samples; formats are
+/// "s16le" (Int16) or "f32le" (Float32, -1..1). Each call starts from a
+/// fresh filter state, so convert a recording in one call rather than in
+/// pieces. Runs synchronously; convert very long recordings from a worker.
+#[napi]
+pub fn resample_pcm(
+    input: Buffer,
+    input_rate: u32,
+    channels: u32,
+    input_format: String,
+    output_rate: u32,
+    output_format: String,
+) -> Result<Buffer> {
+    let parse = |name: &str| {
+        PcmFormat::parse(name)
+            .ok_or_else(|| Error::from_reason(format!("Unsupported PCM format: {} (use \"s16le\" or \"f32le\")", name)))
+    };
+    let input_format = parse(&input_format)?;
+    let output_format = parse(&output_format)?;
+    convert::resample_pcm(&input, input_rate, channels, input_format, output_rate, output_format)
+        .map(Buffer::from)
+        .map_err(Error::from_reason)
+}
+
+/// Run `processor` on every chunk of the active capture's resampled audio,
+/// after the expander and normalization and just before the conversion to
+/// Int16, e.g. for a custom filter or a watermark. It receives 16kHz mono
+/// float samples (-1..1) and must synchronously return as many processed
+/// samples. Each chunk is a round trip to the main thread that the audio
+/// thread waits on, adding its latency to every chunk; a busy event loop
+/// stalls capture for up to 50ms per chunk, after which the chunk goes on
+/// unprocessed. Keep it cheap. Replaces any processor already set; not
+/// available with a custom resampler.
+#[napi]
+pub fn set_frame_processor(processor: ThreadsafeFunction<Float32Array, Float32Array>) -> Result<()> {
+    let ctx = active_context()?;
+    let mut resampler = ctx
+        .resampler
+        .lock()
+        .map_err(|e| Error::from_reason(format!("Failed to acquire resampler lock: {}", e)))?;
+    if !resampler.set_float_processor(Some(Box::new(JsFrameProcessor::new(processor)))) {
+        return Err(Error::from_reason("A custom resampler can't run a frame processor"));
     }
+    Ok(())
 }
 
-/// Stop capturing system audio. Cleans up all resources.
+/// Remove the frame processor. Returns false if not capturing or none was set.
 #[napi]
-pub fn stop_capture() -> Result<()> {
-    let capture = {
-        let mut state = state_mutex().lock().map_err(|e| {
-            Error::from_reason(format!("Failed to acquire state lock: {}", e))
-        })?;
-        state.take()
+pub fn clear_frame_processor() -> Result<bool> {
+    let Ok(ctx) = active_context() else {
+        return Ok(false);
     };
+    let mut resampler = ctx
+        .resampler
+        .lock()
+        .map_err(|e| Error::from_reason(format!("Failed to acquire resampler lock: {}", e)))?;
+    Ok(resampler.set_float_processor(None))
+}
 
-    // Clear the callback context
-    {
-        if let Ok(mut ctx) = context_mutex().lock() {
-            *ctx = None;
-        }
+/// Also send `callback` the active capture's audio as it left the
+/// resampler, before the expander, normalization, frame processor, dither,
+/// microphone mix, mute and silence gate: 16kHz mono float samples (-1..1),
+/// one chunk per capture buffer, to A/B against the processed delivery
+/// when tuning those options. Chunks line up with the processed stream
+/// before gating (same sample counts, not sent while paused or warming
+/// up), but don't share its chunking. Opt-in, as it costs a second
+/// callback per buffer and disables `fixedPoint`. Replaces any tap already
+/// set; not available with a custom resampler.
+#[napi]
+pub fn set_raw_tap(callback: ThreadsafeFunction<Float32Array>) -> Result<()> {
+    let ctx = active_context()?;
+    let mut resampler = ctx
+        .resampler
+        .lock()
+        .map_err(|e| Error::from_reason(format!("Failed to acquire resampler lock: {}", e)))?;
+    if !resampler.set_raw_tap(true) {
+        return Err(Error::from_reason("A custom resampler has no raw tap"));
     }
+    let mut tap = ctx
+        .raw_tap
+        .lock()
+        .map_err(|e| Error::from_reason(format!("Failed to acquire raw tap lock: {}", e)))?;
+    *tap = Some(callback);
+    Ok(())
+}
 
-    let Some(capture) = capture else {
-        return Ok(()); // Not capturing, nothing to do
+/// Remove the raw tap. Returns false if not capturing or none was set.
+#[napi]
+pub fn clear_raw_tap() -> Result<bool> {
+    let Ok(ctx) = active_context() else {
+        return Ok(false);
     };
+    if let Ok(mut resampler) = ctx.resampler.lock() {
+        resampler.set_raw_tap(false);
+    }
+    let mut tap = ctx
+        .raw_tap
+        .lock()
+        .map_err(|e| Error::from_reason(format!("Failed to acquire raw tap lock: {}", e)))?;
+    Ok(tap.take().is_some())
+}
+
+/// Snapshot the active capture's resampler state (filter histories, phase,
+/// expander, normalization and dither state) as an opaque Buffer, to
+/// persist or to hand the stream to another capture with
+/// `import_resampler_state`. Fails with a custom resampler.
+#[napi]
+pub fn export_resampler_state() -> Result<Buffer> {
+    let ctx = active_context()?;
+    let resampler = ctx
+        .resampler
+        .lock()
+        .map_err(|e| Error::from_reason(format!("Failed to acquire resampler lock: {}", e)))?;
+    let state = resampler
+        .export_state()
+        .ok_or_else(|| Error::from_reason("A custom resampler has no state to export"))?;
+    Ok(state.to_bytes().into())
+}
+
+/// Restore a state from `export_resampler_state` into the active capture,
+/// which continues as if it had processed the exporting capture's audio.
+/// The capture must resample the same way: same `filterCoefficients`,
+/// expander, `normalizeToTarget` and dither settings. Audio processed
+/// before the import used fresh state, so call it right after starting.
+#[napi]
+pub fn import_resampler_state(state: Buffer) -> Result<()> {
+    let state = ResamplerState::from_bytes(&state).map_err(Error::from_reason)?;
+    let ctx = active_context()?;
+    let mut resampler = ctx
+        .resampler
+        .lock()
+        .map_err(|e| Error::from_reason(format!("Failed to acquire resampler lock: {}", e)))?;
+    resampler.import_state(&state).map_err(Error::from_reason)
+}
 
+// ── Diagnostics ─────────────────────────────────────────────────────────────
+
+/// How much the native module prints to stderr (default "info"): "error"
+/// and "warn" quiet lifecycle messages, "debug" adds the format and size of
+/// every buffer and "trace" its processing time too. Those two print from
+/// the audio thread, so leave them for debugging sessions. Warnings still
+/// reach JS and `diagnostics_json` whatever the level.
+#[napi]
+pub fn set_log_level(level: LogLevel) {
     #[cfg(target_os = "macos")]
     unsafe {
-        match capture.backend {
-            CaptureBackend::Sck => {
-                voxtape_sck_stop_capture();
-                eprintln!("[native-audio] SCK capture stopped");
-            }
-        }
+        voxtape_set_log_level(level.rank() as i32);
     }
+    logging::set_level(&level);
+}
 
-    Ok(())
+/// Everything support needs in one JSON string: OS and build, capabilities,
+/// permissions, output device, the active session's status, config and
+/// formats, running meeting apps, and the most recent warnings and errors
+/// (kept across sessions). Never throws; anything that can't be determined
+/// is null.
+#[napi]
+pub fn diagnostics_json() -> String {
+    let build = build_info();
+    let caps = capabilities();
+    let status = capture_status();
+    let config = get_capture_config();
+    let ctx = context_mutex().lock().ok().and_then(|c| c.as_ref().map(Arc::clone));
+
+    let permission_context = capture_permission_context().map(|c| {
+        Json::object()
+            .field("bundleId", c.bundle_id)
+            .field("bundlePath", c.bundle_path)
+            .field("executablePath", c.executable_path)
+            .field("pid", c.pid as i64)
+            .field("session", c.session)
+    });
+    let output_device = output_device_info().map(|d| {
+        Json::object()
+            .field("name", d.name)
+            .field("transport", d.transport)
+            .field("capturable", d.capturable)
+    });
+    let input = ctx.as_ref().and_then(|c| {
+        let packed = c.input_format.load(Ordering::Relaxed);
+        (packed != 0).then(|| {
+            Json::object()
+                .field("sampleRate", (packed >> 32) as u32)
+                .field("channels", packed as u32)
+        })
+    });
+    let output = config.as_ref().map(|o| {
+        Json::object()
+            .field("sampleRate", 16000u32)
+            .field("channels", 1u32)
+            .field("sampleFormat", "s16le")
+            .field("delivery", if o.base64 == Some(true) { "base64" } else { "buffer" })
+            .field("mixdownMode", o.mixdown_mode.clone())
+            .field("encodedFormat", o.encoded_format.clone())
+            .field("wav", o.wav_path.is_some())
+    });
+    let events: Vec<Json> = EVENT_LOG
+        .get()
+        .and_then(|log| log.lock().ok())
+        .map(|log| {
+            log.events()
+                .map(|e| Json::object().field("atMs", e.at_ms).field("message", e.message.as_str()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Json::object()
+        .field(
+            "build",
+            Json::object()
+                .field("version", build.version)
+                .field("sdkVersion", build.sdk_version)
+                .field("backends", build.backends)
+                .field("debug", build.debug),
+        )
+        .field(
+            "capabilities",
+            Json::object()
+                .field("osVersion", caps.os_version)
+                .field("systemAudio", caps.system_audio)
+                .field("perAppAudio", caps.per_app_audio)
+                .field("microphoneMix", caps.microphone_mix),
+        )
+        .field(
+            "permissions",
+            Json::object()
+                .field("screenCapture", has_screen_capture_access())
+                .field("context", permission_context),
+        )
+        .field("outputDevice", output_device)
+        .field(
+            "capture",
+            Json::object()
+                .field("isCapturing", status.is_capturing)
+                .field("sessionId", status.session_id)
+                .field("startedAtMs", status.started_at_ms)
+                .field("backend", status.backend)
+                .field(
+                    "device",
+                    status.device.map(|d| {
+                        Json::object()
+                            .field("name", d.name)
+                            .field("uid", d.uid)
+                            .field("transport", d.transport)
+                    }),
+                )
+                .field("gateOpen", status.gate_open)
+                .field("clippedSampleCount", status.clipped_sample_count)
+                .field("processingLatencyMs", status.processing_latency_ms)
+                .field("callbackIntervalMs", status.callback_interval_ms)
+                .field("callbackJitterMs", status.callback_jitter_ms)
+                .field("callbackMaxIntervalMs", status.callback_max_interval_ms)
+                .field("processingCpuMsPerSec", status.processing_cpu_ms_per_sec)
+                .field("sckReportedDrops", status.sck_reported_drops)
+                .field("inputFormat", input)
+                .field("outputFormat", output),
+        )
+        .field(
+            "meetingApps",
+            get_running_meeting_apps()
+                .into_iter()
+                .map(|app| app.bundle_id)
+                .collect::<Vec<String>>(),
+        )
+        .field("recentEvents", events)
+        .render()
+}
+
+// ── Clock Alignment ─────────────────────────────────────────────────────────
+
+/// Host-clock reference for aligning captured audio with other recorders
+#[napi(object)]
+pub struct ClockInfo {
+    /// mach_timebase_info numerator (ticks * numer / denom = nanoseconds)
+    pub timebase_numer: u32,
+    /// mach_timebase_info denominator
+    pub timebase_denom: u32,
+    /// Host time (mach ticks) of the first delivered sample
+    pub anchor_host_time: i64,
+    /// Host time (mach ticks) of the most recent SCK buffer
+    pub latest_host_time: i64,
+    /// Index in the 16kHz output stream of the sample stamped `latest_host_time`
+    pub latest_sample_index: i64,
+}
+
+/// Get the host-clock reference for the active capture.
+/// Returns null when not capturing or before the first buffer arrives.
+/// Sample positions are exact up to the resampler's filter delay (~7 input samples).
+#[napi]
+pub fn capture_clock_info() -> Option<ClockInfo> {
+    let ctx = context_mutex().lock().ok()?.as_ref().map(Arc::clone)?;
+    let clock = ctx.clock.lock().ok()?;
+    let anchor_host_time = clock.anchor_host_time()?;
+    let (latest_host_time, latest_sample_index) = clock.latest();
+    let (timebase_numer, timebase_denom) = clock::host_timebase();
+
+    Some(ClockInfo {
+        timebase_numer,
+        timebase_denom,
+        anchor_host_time: anchor_host_time as i64,
+        latest_host_time: latest_host_time as i64,
+        latest_sample_index: latest_sample_index as i64,
+    })
 }
 
 // ── Meeting App Detection ───────────────────────────────────────────────────
@@ -293,6 +4042,8 @@ pub fn stop_capture() -> Result<()> {
 struct CMeetingAppInfo {
     bundle_id: *const c_char,
     name: *const c_char,
+    name_utf16: *const u16,
+    name_utf16_len: i32,
     pid: i32,
     is_active: i32,
 }
@@ -300,10 +4051,14 @@ struct CMeetingAppInfo {
 extern "C" {
     fn voxtape_get_running_meeting_apps(out_count: *mut i32) -> *mut CMeetingAppInfo;
     fn voxtape_free_meeting_apps(apps: *mut CMeetingAppInfo, count: i32);
+    fn voxtape_is_app_producing_audio(bundle_id: *const c_char) -> i32;
+    fn voxtape_is_app_running(bundle_id: *const c_char) -> i32;
+    fn voxtape_list_capturable_audio_apps(out_count: *mut i32) -> *mut CMeetingAppInfo;
 }
 
 /// Information about a detected meeting application
 #[napi(object)]
+#[derive(Clone)]
 pub struct MeetingAppInfo {
     /// Bundle identifier (e.g., "us.zoom.xos")
     pub bundle_id: String,
@@ -323,40 +4078,74 @@ pub fn get_running_meeting_apps() -> Vec<MeetingAppInfo> {
     unsafe {
         let mut count: i32 = 0;
         let apps_ptr = voxtape_get_running_meeting_apps(&mut count);
+        take_app_infos(apps_ptr, count)
+    }
 
-        if apps_ptr.is_null() || count == 0 {
-            return Vec::new();
-        }
+    #[cfg(not(target_os = "macos"))]
+    {
+        Vec::new()
+    }
+}
 
-        let mut result = Vec::with_capacity(count as usize);
+/// Convert and free an app list returned by the bridge.
+unsafe fn take_app_infos(apps_ptr: *mut CMeetingAppInfo, count: i32) -> Vec<MeetingAppInfo> {
+    if apps_ptr.is_null() || count == 0 {
+        return Vec::new();
+    }
 
-        for i in 0..count {
-            let app = apps_ptr.add(i as usize);
+    let mut result = Vec::with_capacity(count as usize);
 
-            let bundle_id = if (*app).bundle_id.is_null() {
-                String::new()
-            } else {
-                CStr::from_ptr((*app).bundle_id)
-                    .to_string_lossy()
-                    .into_owned()
-            };
+    for i in 0..count {
+        let app = apps_ptr.add(i as usize);
 
-            let name = if (*app).name.is_null() {
-                String::new()
-            } else {
-                CStr::from_ptr((*app).name).to_string_lossy().into_owned()
-            };
+        let utf8 = |ptr: *const c_char| (!ptr.is_null()).then(|| CStr::from_ptr(ptr));
+        let name_utf16 = ((*app).name_utf16_len > 0 && !(*app).name_utf16.is_null())
+            .then(|| std::slice::from_raw_parts((*app).name_utf16, (*app).name_utf16_len as usize));
 
-            result.push(MeetingAppInfo {
-                bundle_id,
-                name,
-                pid: (*app).pid,
-                is_active: (*app).is_active != 0,
-            });
-        }
+        let bundle_id = decode_app_string(None, utf8((*app).bundle_id));
+        let name = decode_app_string(name_utf16, utf8((*app).name));
 
-        voxtape_free_meeting_apps(apps_ptr, count);
-        result
+        result.push(MeetingAppInfo {
+            bundle_id,
+            name,
+            pid: (*app).pid,
+            is_active: (*app).is_active != 0,
+        });
+    }
+
+    voxtape_free_meeting_apps(apps_ptr, count);
+    result
+}
+
+/// Decode a string from the bridge without losing anything that can be kept:
+/// the UTF-16 copy if it's valid, else the UTF-8 one if valid, and only then
+/// a lossy conversion (with U+FFFD for whatever couldn't be decoded).
+fn decode_app_string(utf16: Option<&[u16]>, utf8: Option<&CStr>) -> String {
+    if let Some(Ok(decoded)) = utf16.map(String::from_utf16) {
+        return decoded;
+    }
+    if let Some(Ok(decoded)) = utf8.map(CStr::to_str) {
+        return decoded.to_string();
+    }
+    match (utf16, utf8) {
+        (Some(units), _) => String::from_utf16_lossy(units),
+        (None, Some(bytes)) => bytes.to_string_lossy().into_owned(),
+        (None, None) => String::new(),
+    }
+}
+
+/// List every app ScreenCaptureKit can capture that is playing audio right
+/// now (including through helper processes), for a "select source" UI
+/// feeding `ContentFilter.includedBundleIds`. Not limited to meeting apps;
+/// this app itself is left out. Returns an empty list without Screen
+/// Recording permission.
+#[napi]
+pub fn list_capturable_audio_apps() -> Vec<MeetingAppInfo> {
+    #[cfg(target_os = "macos")]
+    unsafe {
+        let mut count: i32 = 0;
+        let apps_ptr = voxtape_list_capturable_audio_apps(&mut count);
+        take_app_infos(apps_ptr, count)
     }
 
     #[cfg(not(target_os = "macos"))]
@@ -364,3 +4153,78 @@ pub fn get_running_meeting_apps() -> Vec<MeetingAppInfo> {
         Vec::new()
     }
 }
+
+/// Whether the app with `bundle_id` (or one of its helper processes, e.g.
+/// `com.google.Chrome.helper`) is playing audio right now. Finer than
+/// meeting-app presence or focus: a muted, idle call reports false.
+/// Returns null where the system can't tell (before macOS 14, or off macOS).
+#[napi]
+pub fn is_app_producing_audio(bundle_id: String) -> Option<bool> {
+    #[cfg(target_os = "macos")]
+    {
+        let bundle_id = CString::new(bundle_id).ok()?;
+        match unsafe { voxtape_is_app_producing_audio(bundle_id.as_ptr()) } {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = bundle_id;
+        None
+    }
+}
+
+#[cfg(all(test, target_os = "macos", feature = "permission-stub"))]
+mod tests {
+    use super::*;
+    use std::sync::Barrier;
+
+    #[test]
+    fn test_concurrent_start_capture_has_exactly_one_winner() {
+        const THREADS: usize = 16;
+        set_permission_stub(Some(PermissionStub {
+            screen_capture_access: true,
+            request_outcome: None,
+        }));
+        let streams_started = Arc::new(AtomicU32::new(0));
+        let barrier = Arc::new(Barrier::new(THREADS));
+
+        let handles: Vec<_> = (0..THREADS)
+            .map(|_| {
+                let (streams_started, barrier) = (Arc::clone(&streams_started), Arc::clone(&barrier));
+                std::thread::spawn(move || {
+                    barrier.wait();
+                    start_capture_with(None, None, |_| {
+                        streams_started.fetch_add(1, Ordering::Relaxed);
+                        // Starting the stream takes a while
+                        std::thread::sleep(Duration::from_millis(50));
+                        0
+                    })
+                    .map_err(|e| e.reason.clone())
+                })
+            })
+            .collect();
+        let results: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+        assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 1, "{:?}", results);
+        assert!(results
+            .iter()
+            .filter_map(|r| r.as_ref().err())
+            .all(|reason| reason.starts_with("AlreadyCapturing:")));
+        assert_eq!(streams_started.load(Ordering::Relaxed), 1);
+        // The winner's context is only held globally: no loser replaced or
+        // leaked one
+        let ctx = context_mutex().lock().unwrap().clone().unwrap();
+        assert_eq!(Arc::strong_count(&ctx), 2);
+        drop(ctx);
+
+        let ctx = end_capture_session().unwrap().unwrap();
+        assert_eq!(Arc::strong_count(&ctx), 1);
+        assert!(context_mutex().lock().unwrap().is_none());
+        assert!(!STARTING.load(Ordering::Acquire));
+        set_permission_stub(None);
+    }
+}
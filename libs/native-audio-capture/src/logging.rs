@@ -0,0 +1,79 @@
+//! Verbosity filter for the module's own stderr logging
+//!
+//! Everything the native layer prints goes through `native_log!` with a
+//! level, so consumers can quiet it (or open it up for per-buffer detail)
+//! with `set_log_level` without touching a global logger. The ObjC bridge keeps
+//! its own copy of the level for its NSLog output.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use napi_derive::napi;
+
+/// Verbosity of the native module's logging, least to most chatty
+#[napi(string_enum = "lowercase")]
+pub enum LogLevel {
+    /// Failures that lose audio or stop a capture
+    Error,
+    /// Recoverable problems (the warnings also sent to JS)
+    Warn,
+    /// Capture lifecycle: starts, stops, route changes (the default)
+    Info,
+    /// Format and size of every buffer
+    Debug,
+    /// Per-buffer timing on top of Debug
+    Trace,
+}
+
+impl LogLevel {
+    /// 1 (Error) to 5 (Trace), as the bridge takes it
+    pub fn rank(&self) -> u8 {
+        match self {
+            LogLevel::Error => 1,
+            LogLevel::Warn => 2,
+            LogLevel::Info => 3,
+            LogLevel::Debug => 4,
+            LogLevel::Trace => 5,
+        }
+    }
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(3);
+
+pub fn set_level(level: &LogLevel) {
+    LEVEL.store(level.rank(), Ordering::Relaxed);
+}
+
+/// Whether messages at `level` are printed; check before building costly ones.
+pub fn enabled(level: LogLevel) -> bool {
+    level.rank() <= LEVEL.load(Ordering::Relaxed)
+}
+
+/// `eprintln!` with the "[native-audio]" prefix, printed only if the
+/// `LogLevel` variant given first is enabled.
+macro_rules! native_log {
+    ($level:ident, $($arg:tt)*) => {
+        if $crate::logging::enabled($crate::logging::LogLevel::$level) {
+            eprintln!("[native-audio] {}", format_args!($($arg)*));
+        }
+    };
+}
+pub(crate) use native_log;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levels_above_the_setting_are_filtered() {
+        assert!(enabled(LogLevel::Info));
+        assert!(!enabled(LogLevel::Debug));
+
+        set_level(&LogLevel::Warn);
+        assert!(enabled(LogLevel::Error) && enabled(LogLevel::Warn));
+        assert!(!enabled(LogLevel::Info));
+
+        set_level(&LogLevel::Trace);
+        assert!(enabled(LogLevel::Trace));
+        set_level(&LogLevel::Info);
+    }
+}
@@ -0,0 +1,262 @@
+//! Loudness metering per ITU-R BS.1770 on the 16kHz output
+//!
+//! Audio is K-weighted (a high shelf modelling the head, then a high-pass
+//! below ~38Hz), and loudness is the mean square of the result in LUFS:
+//! -0.691 + 10·log10(mean square). The output is mono, so there is no
+//! channel weighting, and K-weighting above 8kHz is lost to the resampling,
+//! which reads slightly low on material with a lot of top end.
+//!
+//! Mean squares are kept per 100ms sub-block. Momentary loudness covers the
+//! last 400ms, short-term the last 3s. Integrated loudness is gated as in
+//! BS.1770-4: 400ms blocks overlapping by 75% (one per sub-block) are
+//! dropped below -70 LUFS (absolute gate), then again below 10 LU under the
+//! loudness of the blocks that remain (relative gate); what is left is
+//! averaged. Silence and pauses therefore don't drag the figure down, which
+//! is what makes it usable for normalizing to a target like -16 LUFS.
+//!
+//! Blocks that pass the absolute gate are binned by loudness in 0.1 LU
+//! steps, keeping a count and a sum of mean squares per bin, as libebur128
+//! does. Memory stays fixed however long the capture runs, and the relative
+//! gate is applied per bin: a reading costs the same after hours as after
+//! seconds, at the price of placing the gate to within 0.1 LU.
+
+use std::collections::VecDeque;
+
+/// Output sample rate the meter runs at
+const SAMPLE_RATE: f64 = 16000.0;
+/// Samples per 100ms sub-block
+const SUB_BLOCK: usize = 1600;
+/// Sub-blocks in the momentary (400ms) and short-term (3s) windows
+const MOMENTARY_BLOCKS: usize = 4;
+const SHORT_TERM_BLOCKS: usize = 30;
+/// Gates for integrated loudness
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const RELATIVE_GATE_LU: f64 = -10.0;
+/// Floor reported for silence, in LUFS
+const FLOOR_LUFS: f64 = -100.0;
+/// Width and number of the gated-block histogram bins, which cover
+/// -70..+30 LUFS; louder blocks (not possible from Int16) go in the last
+const BIN_LU: f64 = 0.1;
+const BINS: usize = 1000;
+
+/// One reading, in LUFS
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LoudnessReading {
+    /// Over the last 400ms; None until 400ms have been metered
+    pub momentary: Option<f64>,
+    /// Over the last 3s; None until 3s have been metered
+    pub short_term: Option<f64>,
+    /// Gated, since the meter started; None while no block has passed the gates
+    pub integrated: Option<f64>,
+}
+
+/// Second-order section in transposed direct form II
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    z: [f64; 2],
+}
+
+impl Biquad {
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+/// The two K-weighting stages, designed for `SAMPLE_RATE` from the analog
+/// prototypes behind the 48kHz coefficients in BS.1770.
+fn k_weighting() -> [Biquad; 2] {
+    let shelf = {
+        let (f0, gain_db, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+        let k = (std::f64::consts::PI * f0 / SAMPLE_RATE).tan();
+        let vh = 10f64.powf(gain_db / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+        Biquad {
+            b: [(vh + vb * k / q + k * k) / a0, 2.0 * (k * k - vh) / a0, (vh - vb * k / q + k * k) / a0],
+            a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+            z: [0.0; 2],
+        }
+    };
+    let high_pass = {
+        let (f0, q) = (38.13547087602444, 0.5003270373238773);
+        let k = (std::f64::consts::PI * f0 / SAMPLE_RATE).tan();
+        let a0 = 1.0 + k / q + k * k;
+        Biquad {
+            b: [1.0, -2.0, 1.0],
+            a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+            z: [0.0; 2],
+        }
+    };
+    [shelf, high_pass]
+}
+
+fn lufs(mean_square: f64) -> f64 {
+    if mean_square > 0.0 {
+        (-0.691 + 10.0 * mean_square.log10()).max(FLOOR_LUFS)
+    } else {
+        FLOOR_LUFS
+    }
+}
+
+fn mean(values: impl ExactSizeIterator<Item = f64>) -> f64 {
+    let count = values.len();
+    values.sum::<f64>() / count as f64
+}
+
+/// Histogram bin for a block at `loudness` LUFS, clamped to the range
+fn bin(loudness: f64) -> usize {
+    (((loudness - ABSOLUTE_GATE_LUFS) / BIN_LU).floor().max(0.0) as usize).min(BINS - 1)
+}
+
+/// Blocks that passed the absolute gate, binned by loudness
+#[derive(Clone, Copy, Default)]
+struct Bin {
+    count: u64,
+    /// Sum of the blocks' mean squares
+    sum: f64,
+}
+
+pub struct LoudnessMeter {
+    filters: [Biquad; 2],
+    /// Sub-blocks between readings
+    interval: usize,
+    /// Sum of squares of the sub-block in progress, and its length so far
+    sum: f64,
+    filled: usize,
+    /// Mean squares of the latest sub-blocks, newest last
+    sub_blocks: VecDeque<f64>,
+    /// The 400ms blocks that passed the absolute gate, per loudness bin
+    histogram: Vec<Bin>,
+    /// All of them together
+    gated: Bin,
+    /// Sub-blocks since the last reading
+    elapsed: usize,
+}
+
+impl LoudnessMeter {
+    /// `interval_blocks`: 100ms sub-blocks between readings
+    pub fn new(interval_blocks: usize) -> Self {
+        Self {
+            filters: k_weighting(),
+            interval: interval_blocks.max(1),
+            sum: 0.0,
+            filled: 0,
+            sub_blocks: VecDeque::with_capacity(SHORT_TERM_BLOCKS),
+            histogram: vec![Bin::default(); BINS],
+            gated: Bin::default(),
+            elapsed: 0,
+        }
+    }
+
+    /// Meter a chunk, returning the readings that fell due within it.
+    pub fn push(&mut self, samples: &[i16]) -> Vec<LoudnessReading> {
+        let mut readings = Vec::new();
+        for &sample in samples {
+            let mut value = sample as f64 / 32768.0;
+            for filter in &mut self.filters {
+                value = filter.process(value);
+            }
+            self.sum += value * value;
+            self.filled += 1;
+            if self.filled == SUB_BLOCK {
+                self.finish_sub_block();
+                self.elapsed += 1;
+                if self.elapsed == self.interval {
+                    self.elapsed = 0;
+                    readings.push(self.reading());
+                }
+            }
+        }
+        readings
+    }
+
+    fn finish_sub_block(&mut self) {
+        if self.sub_blocks.len() == SHORT_TERM_BLOCKS {
+            self.sub_blocks.pop_front();
+        }
+        self.sub_blocks.push_back(self.sum / SUB_BLOCK as f64);
+        self.sum = 0.0;
+        self.filled = 0;
+
+        if let Some(block) = self.window(MOMENTARY_BLOCKS) {
+            let loudness = lufs(block);
+            if loudness > ABSOLUTE_GATE_LUFS {
+                for entry in [&mut self.histogram[bin(loudness)], &mut self.gated] {
+                    entry.count += 1;
+                    entry.sum += block;
+                }
+            }
+        }
+    }
+
+    /// Mean square over the last `blocks` sub-blocks, once that many exist.
+    fn window(&self, blocks: usize) -> Option<f64> {
+        let len = self.sub_blocks.len();
+        (len >= blocks).then(|| mean(self.sub_blocks.range(len - blocks..).copied()))
+    }
+
+    fn integrated(&self) -> Option<f64> {
+        if self.gated.count == 0 {
+            return None;
+        }
+        let threshold = lufs(self.gated.sum / self.gated.count as f64) + RELATIVE_GATE_LU;
+        let (sum, count) = self.histogram[bin(threshold)..]
+            .iter()
+            .fold((0.0, 0), |(sum, count), bin| (sum + bin.sum, count + bin.count));
+        (count > 0).then(|| lufs(sum / count as f64))
+    }
+
+    fn reading(&self) -> LoudnessReading {
+        LoudnessReading {
+            momentary: self.window(MOMENTARY_BLOCKS).map(lufs),
+            short_term: self.window(SHORT_TERM_BLOCKS).map(lufs),
+            integrated: self.integrated(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(ms: usize, amplitude: f64) -> Vec<i16> {
+        (0..ms * 16)
+            .map(|i| (amplitude * 32767.0 * (2.0 * std::f64::consts::PI * 997.0 * i as f64 / 16000.0).sin()) as i16)
+            .collect()
+    }
+
+    #[test]
+    fn test_full_scale_sine_reads_minus_three_lufs() {
+        // The BS.1770 reference: a 997Hz sine at 0dBFS reads -3.01 LUFS
+        let mut meter = LoudnessMeter::new(1);
+        let readings = meter.push(&tone(3000, 1.0));
+        assert_eq!(readings.len(), 30);
+        assert_eq!(readings[2].momentary, None);
+        assert!(readings[28].short_term.is_none());
+
+        let last = readings.last().unwrap();
+        for value in [last.momentary, last.short_term, last.integrated] {
+            let value = value.unwrap();
+            assert!((value + 3.01).abs() < 0.1, "read {}", value);
+        }
+    }
+
+    #[test]
+    fn test_integrated_gates_out_silence_and_quiet_passages() {
+        let mut meter = LoudnessMeter::new(10);
+        meter.push(&tone(5000, 0.5));
+        // A pause and a passage 20dB down both fall below the gates
+        meter.push(&vec![0; 16 * 5000]);
+        let readings = meter.push(&tone(5000, 0.05));
+        let last = readings.last().unwrap();
+
+        let integrated = last.integrated.unwrap();
+        assert!((integrated + 9.03).abs() < 0.2, "integrated {}", integrated);
+        let momentary = last.momentary.unwrap();
+        assert!((momentary + 29.03).abs() < 0.2, "momentary {}", momentary);
+    }
+}
@@ -0,0 +1,146 @@
+//! Level metering with meter ballistics on the 16kHz output
+//!
+//! Levels are smoothed per sample rather than per chunk, so readings don't
+//! depend on how SCK happened to slice the audio. The RMS is integrated
+//! over a short fixed window first; then both it and the peak envelope rise
+//! with the attack time constant and fall with the release one. A short
+//! attack and long release behaves like a PPM, equal times of about 300ms
+//! like a VU meter.
+
+use crate::flatness::SpectralFlatness;
+
+/// Output sample rate the meter runs at
+const SAMPLE_RATE: f64 = 16000.0;
+/// Floor reported for silence, in dBFS
+const FLOOR_DB: f64 = -100.0;
+/// Time constant of the mean-square integration behind the RMS
+const RMS_WINDOW_MS: f64 = 20.0;
+
+/// One smoothed reading, in dBFS
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LevelReading {
+    pub rms_db: f64,
+    pub peak_db: f64,
+    /// Spectral flatness over the interval, if enabled (see `flatness`)
+    pub flatness: Option<f64>,
+}
+
+pub struct LevelMeter {
+    attack: f64,
+    release: f64,
+    /// Samples between readings
+    interval: usize,
+    /// Coefficient of the fixed mean-square integration
+    integration: f64,
+    /// Integrated mean square, and the RMS and peak after ballistics (0..1)
+    mean_square: f64,
+    rms: f64,
+    peak: f64,
+    /// Samples since the last reading
+    elapsed: usize,
+    flatness: Option<SpectralFlatness>,
+}
+
+impl LevelMeter {
+    /// - `attack_ms` / `release_ms`: time constants for rising / falling levels
+    /// - `interval_ms`: time between readings
+    pub fn new(attack_ms: f64, release_ms: f64, interval_ms: u32) -> Self {
+        let coeff = |ms: f64| 1.0 - (-1000.0 / (ms.max(0.01) * SAMPLE_RATE)).exp();
+        Self {
+            attack: coeff(attack_ms),
+            release: coeff(release_ms),
+            interval: ((interval_ms as f64 * SAMPLE_RATE / 1000.0) as usize).max(1),
+            integration: coeff(RMS_WINDOW_MS),
+            mean_square: 0.0,
+            rms: 0.0,
+            peak: 0.0,
+            elapsed: 0,
+            flatness: None,
+        }
+    }
+
+    /// Also estimate spectral flatness for each reading (off by default;
+    /// costs an FFT per 16ms of audio).
+    pub fn set_spectral_flatness(&mut self, enabled: bool) {
+        self.flatness = enabled.then(SpectralFlatness::new);
+    }
+
+    /// Meter a chunk, returning the readings that fell due within it.
+    pub fn push(&mut self, samples: &[i16]) -> Vec<LevelReading> {
+        let mut readings = Vec::new();
+        for &sample in samples {
+            let value = sample as f64 / 32768.0;
+            self.mean_square += self.integration * (value * value - self.mean_square);
+            self.rms = self.smooth(self.rms, self.mean_square.sqrt());
+            self.peak = self.smooth(self.peak, value.abs());
+            if let Some(flatness) = &mut self.flatness {
+                flatness.push(value);
+            }
+            self.elapsed += 1;
+            if self.elapsed == self.interval {
+                self.elapsed = 0;
+                readings.push(self.reading());
+            }
+        }
+        readings
+    }
+
+    fn smooth(&self, current: f64, target: f64) -> f64 {
+        let coeff = if target > current { self.attack } else { self.release };
+        current + coeff * (target - current)
+    }
+
+    fn reading(&mut self) -> LevelReading {
+        let db = |x: f64| if x > 0.0 { (20.0 * x.log10()).max(FLOOR_DB) } else { FLOOR_DB };
+        LevelReading {
+            rms_db: db(self.rms),
+            peak_db: db(self.peak),
+            flatness: self.flatness.as_mut().and_then(SpectralFlatness::take),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(ms: usize, amplitude: f64) -> Vec<i16> {
+        (0..ms * 16)
+            .map(|i| (amplitude * 32767.0 * (2.0 * std::f64::consts::PI * 1000.0 * i as f64 / 16000.0).sin()) as i16)
+            .collect()
+    }
+
+    #[test]
+    fn test_readings_are_independent_of_chunking() {
+        let input = tone(1000, 0.5);
+        let mut whole = LevelMeter::new(10.0, 300.0, 50);
+        let expected = whole.push(&input);
+        assert_eq!(expected.len(), 20);
+
+        let mut chunked = LevelMeter::new(10.0, 300.0, 50);
+        let mut readings = Vec::new();
+        for chunk in input.chunks(137) {
+            readings.extend(chunked.push(chunk));
+        }
+        assert_eq!(readings, expected);
+
+        // A steady sine settles at -9dBFS RMS; the 10ms attack slightly
+        // under-reads its -6dBFS peaks, as a PPM does
+        let last = expected.last().unwrap();
+        assert!((last.peak_db + 6.0).abs() < 1.0, "peak {}", last.peak_db);
+        assert!((last.rms_db + 9.0).abs() < 0.2, "rms {}", last.rms_db);
+    }
+
+    #[test]
+    fn test_fast_attack_slow_release() {
+        let mut meter = LevelMeter::new(5.0, 1500.0, 50);
+        let attack = meter.push(&tone(50, 0.5));
+        // Within a few attack time constants of the burst
+        assert!(attack[0].peak_db > -7.0, "attack reached only {}", attack[0].peak_db);
+
+        let release = meter.push(&vec![0; 16 * 500]);
+        // 500ms of silence with a 1.5s release: about 3dB of decay, not a drop to the floor
+        let after = release.last().unwrap().peak_db;
+        assert!(after < attack[0].peak_db - 1.0 && after > -12.0, "released to {}", after);
+    }
+}
@@ -0,0 +1,209 @@
+//! Microphone mix-in for captures with `captureMicrophone`
+//!
+//! SCK delivers microphone and system audio as separate buffers on their own
+//! schedule. Mic audio is resampled with its own `Resampler`, faded in by a
+//! `GainRamp`, and queued; each system-audio chunk then sums in whatever mic
+//! audio is waiting. With ducking, the mic also acts as a sidechain: while
+//! it carries speech, the system audio it's summed into is attenuated.
+
+use std::collections::VecDeque;
+
+use crate::resampler::Resampler;
+
+/// Most mic audio held waiting for system audio (1s at 16kHz)
+const MAX_PENDING: usize = 16_000;
+
+/// Linear fade-in from silence to unity gain over a fixed number of samples.
+pub struct GainRamp {
+    length: u64,
+    position: u64,
+}
+
+impl GainRamp {
+    pub fn new(ramp_ms: u32) -> Self {
+        Self {
+            length: ramp_ms as u64 * 16,
+            position: 0,
+        }
+    }
+
+    /// Apply the ramp in place; a no-op once it has completed.
+    pub fn apply(&mut self, samples: &mut [i16]) {
+        for sample in samples {
+            if self.position >= self.length {
+                return;
+            }
+            let gain = self.position as f32 / self.length as f32;
+            *sample = (*sample as f32 * gain).round() as i16;
+            self.position += 1;
+        }
+    }
+}
+
+/// Time constant of the mic envelope follower's decay
+const SIDECHAIN_RELEASE_MS: f64 = 50.0;
+/// How long the mic must stay below the threshold before the duck releases,
+/// so the system audio doesn't pump back up between words
+const SIDECHAIN_HOLD_MS: u64 = 250;
+
+/// Sidechain compressor keyed by the mic: attenuates system audio by a
+/// fixed depth while the mic's envelope is above a threshold.
+pub struct Ducker {
+    /// Linear envelope threshold on the Int16 scale
+    threshold: f64,
+    /// Gain applied while ducked (0..1)
+    depth: f64,
+    attack: f64,
+    release: f64,
+    envelope_decay: f64,
+    hold: u64,
+    envelope: f64,
+    /// Samples since the mic was last above the threshold
+    quiet: u64,
+    gain: f64,
+}
+
+impl Ducker {
+    /// - `depth_db`: attenuation of the system audio during speech
+    /// - `threshold_db`: mic level in dBFS that counts as speech
+    /// - `attack_ms` / `release_ms`: time constants for ducking / recovering
+    pub fn new(depth_db: f64, threshold_db: f64, attack_ms: f64, release_ms: f64) -> Self {
+        let coeff = |ms: f64| 1.0 - (-1000.0 / (ms.max(0.01) * 16000.0)).exp();
+        let hold = SIDECHAIN_HOLD_MS * 16;
+        Self {
+            threshold: 32768.0 * 10f64.powf(threshold_db / 20.0),
+            depth: 10f64.powf(-depth_db.abs() / 20.0),
+            attack: coeff(attack_ms),
+            release: coeff(release_ms),
+            envelope_decay: coeff(SIDECHAIN_RELEASE_MS),
+            hold,
+            envelope: 0.0,
+            quiet: hold,
+            gain: 1.0,
+        }
+    }
+
+    /// Advance by one mic sample, returning the gain for the matching system sample.
+    fn next(&mut self, mic: i16) -> f64 {
+        let level = (mic as f64).abs();
+        if level > self.envelope {
+            self.envelope = level;
+        } else {
+            self.envelope += self.envelope_decay * (level - self.envelope);
+        }
+        if self.envelope >= self.threshold {
+            self.quiet = 0;
+        } else {
+            self.quiet = self.quiet.saturating_add(1);
+        }
+        let (target, coeff) = if self.quiet < self.hold {
+            (self.depth, self.attack)
+        } else {
+            (1.0, self.release)
+        };
+        self.gain += coeff * (target - self.gain);
+        self.gain
+    }
+}
+
+/// Per-source state for the microphone leg of the mix.
+pub struct MicMix {
+    pub resampler: Resampler,
+    ramp: GainRamp,
+    pending: VecDeque<i16>,
+    ducker: Option<Ducker>,
+}
+
+impl MicMix {
+    pub fn new(ramp_ms: u32, ducker: Option<Ducker>) -> Self {
+        Self {
+            resampler: Resampler::new(),
+            ramp: GainRamp::new(ramp_ms),
+            pending: VecDeque::with_capacity(MAX_PENDING),
+            ducker,
+        }
+    }
+
+    /// Queue resampled mic audio, fading it in at the start of the session.
+    /// If system audio stalls, the oldest mic audio is dropped.
+    pub fn push(&mut self, mut samples: Vec<i16>) {
+        self.ramp.apply(&mut samples);
+        self.pending.extend(samples);
+        let excess = self.pending.len().saturating_sub(MAX_PENDING);
+        self.pending.drain(..excess);
+    }
+
+    /// Sum queued mic audio into a system-audio chunk, saturating at the Int16 rails.
+    /// With ducking, the system audio is attenuated first wherever the mic is
+    /// speaking; the ducker also sees silence for any part of the chunk the
+    /// mic hasn't caught up with, so it can release.
+    pub fn mix_into(&mut self, output: &mut [i16]) {
+        let n = output.len().min(self.pending.len());
+        let Some(ducker) = self.ducker.as_mut() else {
+            for (out, mic) in output.iter_mut().zip(self.pending.drain(..n)) {
+                *out = out.saturating_add(mic);
+            }
+            return;
+        };
+        let mut mic = self.pending.drain(..n);
+        for out in output.iter_mut() {
+            let mic = mic.next().unwrap_or(0);
+            let ducked = (*out as f64 * ducker.next(mic)).round() as i16;
+            *out = ducked.saturating_add(mic);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ramp_fades_in_then_holds() {
+        let mut ramp = GainRamp::new(1); // 16 samples
+        let mut samples = [1000i16; 32];
+        ramp.apply(&mut samples[..10]);
+        ramp.apply(&mut samples[10..]);
+        assert_eq!(samples[0], 0);
+        assert_eq!(samples[8], 500);
+        assert!(samples[..16].windows(2).all(|w| w[0] < w[1]));
+        assert!(samples[16..].iter().all(|&s| s == 1000));
+    }
+
+    #[test]
+    fn test_mix_sums_and_saturates() {
+        let mut mic = MicMix::new(0, None);
+        mic.push(vec![100, 30_000, -30_000]);
+        let mut system = vec![1, 10_000, -10_000, 7];
+        mic.mix_into(&mut system);
+        assert_eq!(system, vec![101, i16::MAX, i16::MIN, 7]);
+    }
+
+    #[test]
+    fn test_pending_mic_audio_is_bounded() {
+        let mut mic = MicMix::new(0, None);
+        mic.push(vec![1; MAX_PENDING]);
+        mic.push(vec![2; 10]);
+        let mut system = vec![0i16; MAX_PENDING];
+        mic.mix_into(&mut system);
+        assert_eq!(system[0], 1);
+        assert_eq!(system[MAX_PENDING - 1], 2);
+    }
+
+    #[test]
+    fn test_ducks_system_audio_while_mic_speaks() {
+        let mut mic = MicMix::new(0, Some(Ducker::new(12.0, -40.0, 10.0, 100.0)));
+        // 200ms of speech-level mic audio, then the mic goes quiet
+        mic.push(vec![3000; 3200]);
+        let mut system = vec![10_000i16; 16_000];
+        mic.mix_into(&mut system);
+
+        // Ducks smoothly over the attack, then settles at -12dB (about 0.25)
+        assert!(system[0] > 12_900);
+        let ducked = system[3000] - 3000;
+        assert!((2400..2600).contains(&ducked), "ducked to {}", ducked);
+        // Held past the end of speech, then released back to unity
+        assert!(system[3200 + 16 * 100] < 5000);
+        assert!(*system.last().unwrap() > 9_900);
+    }
+}
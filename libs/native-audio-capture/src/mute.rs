@@ -0,0 +1,143 @@
+//! Soft mute for `set_muted`
+//!
+//! Unlike pausing, a muted session keeps producing output of the right
+//! length, only silent, so recordings and timestamps carry on without a
+//! gap. Gain moves linearly between unity and silence over a short fade so
+//! muting mid-word doesn't click. The gain a chunk got is handed back as a
+//! `GainRamp`, so other renderings of the same audio (extra outputs at other
+//! rates, the raw tap) can be muted alike.
+
+/// Fade length in 16kHz samples (10ms)
+const FADE_SAMPLES: u32 = 160;
+
+#[derive(Default)]
+pub struct SoftMute {
+    muted: bool,
+    /// Steps of the fade toward silence done so far (0 = unity gain)
+    position: u32,
+}
+
+impl SoftMute {
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    /// Apply the current gain in place, advancing any fade in progress.
+    /// Returns the gain at the chunk's start and end.
+    pub fn apply(&mut self, samples: &mut [i16]) -> GainRamp {
+        let from = self.gain();
+        for sample in samples {
+            match (self.muted, self.position) {
+                (false, 0) => break,
+                (true, FADE_SAMPLES) => *sample = 0,
+                _ => {
+                    if self.muted {
+                        self.position += 1;
+                    } else {
+                        self.position -= 1;
+                    }
+                    *sample = (*sample as f32 * self.gain()).round() as i16;
+                }
+            }
+        }
+        GainRamp { from, to: self.gain() }
+    }
+
+    fn gain(&self) -> f32 {
+        1.0 - self.position as f32 / FADE_SAMPLES as f32
+    }
+}
+
+/// Gain applied over one chunk, moving linearly from `from` to `to`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GainRamp {
+    from: f32,
+    to: f32,
+}
+
+impl GainRamp {
+    pub const UNITY: GainRamp = GainRamp { from: 1.0, to: 1.0 };
+
+    /// Apply to interleaved Int16 frames covering the same span of time.
+    pub fn apply_i16(&self, samples: &mut [i16], channels: usize) {
+        self.apply(samples, channels, |sample, gain| (sample as f32 * gain).round() as i16);
+    }
+
+    /// Apply to interleaved float frames covering the same span of time.
+    pub fn apply_f32(&self, samples: &mut [f32], channels: usize) {
+        self.apply(samples, channels, |sample, gain| sample * gain);
+    }
+
+    fn apply<T: Copy>(&self, samples: &mut [T], channels: usize, scale: impl Fn(T, f32) -> T) {
+        if *self == Self::UNITY || channels == 0 {
+            return;
+        }
+        let frames = samples.len() / channels;
+        for (i, frame) in samples.chunks_exact_mut(channels).enumerate() {
+            let gain = self.from + (self.to - self.from) * (i + 1) as f32 / frames as f32;
+            for sample in frame {
+                *sample = scale(*sample, gain);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fades_to_silence_and_back() {
+        let mut mute = SoftMute::default();
+        let mut samples = vec![1000i16; 400];
+        mute.apply(&mut samples);
+        assert!(samples.iter().all(|&s| s == 1000));
+
+        mute.set_muted(true);
+        let mut samples = vec![1000i16; 400];
+        mute.apply(&mut samples[..100]);
+        mute.apply(&mut samples[100..]);
+        // Falls steadily over the fade, then stays silent
+        assert!(samples[..160].windows(2).all(|w| w[1] <= w[0]));
+        assert!(samples[0] > 990 && samples[80] > 0);
+        assert!(samples[159..].iter().all(|&s| s == 0));
+
+        mute.set_muted(false);
+        let mut samples = vec![1000i16; 400];
+        mute.apply(&mut samples);
+        assert!(samples[..160].windows(2).all(|w| w[1] >= w[0]));
+        assert!(samples[0] < 10 && samples[159..].iter().all(|&s| s == 1000));
+    }
+
+    #[test]
+    fn test_ramp_mutes_other_renderings_alike() {
+        let mut mute = SoftMute::default();
+        assert_eq!(mute.apply(&mut [1000i16; 160]), GainRamp::UNITY);
+
+        mute.set_muted(true);
+        // Half the fade in this chunk, the rest in the next
+        let ramp = mute.apply(&mut [1000i16; 80]);
+        // The same 5ms at 48kHz stereo
+        let mut stereo = vec![1000i16; 480];
+        ramp.apply_i16(&mut stereo, 2);
+        assert!(stereo.chunks(2).all(|f| f[0] == f[1]));
+        assert!(stereo[..2].iter().all(|&s| s > 990));
+        assert_eq!(stereo[478], 500);
+
+        let ramp = mute.apply(&mut [1000i16; 160]);
+        let mut raw = vec![0.5f32; 160];
+        ramp.apply_f32(&mut raw, 1);
+        assert!(raw.windows(2).all(|w| w[1] <= w[0]));
+        assert_eq!(raw[159], 0.0);
+
+        // Fully muted: everything silent
+        let ramp = mute.apply(&mut [1000i16; 160]);
+        let mut stereo = vec![1000i16; 960];
+        ramp.apply_i16(&mut stereo, 2);
+        assert!(stereo.iter().all(|&s| s == 0));
+    }
+}
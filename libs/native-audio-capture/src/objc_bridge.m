@@ -1,18 +1,45 @@
 #import <Foundation/Foundation.h>
+#import <AppKit/AppKit.h>
+#import <AudioToolbox/AudioToolbox.h>
 #import <CoreAudio/CoreAudio.h>
 #import <CoreGraphics/CoreGraphics.h>
 #import <CoreMedia/CoreMedia.h>
 #import <ScreenCaptureKit/ScreenCaptureKit.h>
 #import <objc/runtime.h>
 #import <objc/message.h>
+#import <dlfcn.h>
+#import <mach/mach.h>
+#import <mach/mach_time.h>
+#import <mach/thread_policy.h>
+#import <stdatomic.h>
+
+// ── Logging ─────────────────────────────────────────────────────────────────
+
+#define VOXTAPE_LOG_ERROR 1
+#define VOXTAPE_LOG_WARN 2
+#define VOXTAPE_LOG_INFO 3
+#define VOXTAPE_LOG_DEBUG 4
+
+/// Most verbose level printed, set from Rust by `set_log_level`
+static _Atomic int g_log_level = VOXTAPE_LOG_INFO;
+
+/// NSLog, if `level` is enabled
+#define VOXTAPE_LOG(level, ...) \
+    do { \
+        if ((level) <= atomic_load_explicit(&g_log_level, memory_order_relaxed)) NSLog(__VA_ARGS__); \
+    } while (0)
+
+void voxtape_set_log_level(int level) {
+    atomic_store_explicit(&g_log_level, level, memory_order_relaxed);
+}
 
-// ── CATapDescription (CoreAudio tap, kept for reference/fallback) ──────────
+// ── CATapDescription (CoreAudio tap) ───────────────────────────────────────
 
 void *voxtape_create_global_tap_description(void) {
     @try {
         Class cls = NSClassFromString(@"CATapDescription");
         if (!cls) {
-            NSLog(@"[native-audio] CATapDescription class not found");
+            VOXTAPE_LOG(VOXTAPE_LOG_WARN, @"[native-audio] CATapDescription class not found");
             return NULL;
         }
         id instance = ((id (*)(Class, SEL))objc_msgSend)(cls, sel_registerName("alloc"));
@@ -25,7 +52,7 @@ void *voxtape_create_global_tap_description(void) {
         return (__bridge_retained void *)tapDesc;
     }
     @catch (NSException *exception) {
-        NSLog(@"[native-audio] Exception creating tap description: %@", exception);
+        VOXTAPE_LOG(VOXTAPE_LOG_ERROR, @"[native-audio] Exception creating tap description: %@", exception);
         return NULL;
     }
 }
@@ -36,6 +63,18 @@ void voxtape_release_tap_description(void *desc) {
     }
 }
 
+// ── Runtime API detection ──────────────────────────────────────────────────
+
+/// Bitmask of capture APIs available at runtime:
+/// 1 = SCStream audio, 2 = CoreAudio process taps, 4 = SCStream microphone capture
+uint32_t voxtape_api_capabilities(void) {
+    uint32_t caps = 0;
+    if ([SCStreamConfiguration instancesRespondToSelector:@selector(setCapturesAudio:)]) caps |= 1;
+    if (NSClassFromString(@"CATapDescription")) caps |= 2;
+    if ([SCStreamConfiguration instancesRespondToSelector:NSSelectorFromString(@"setCaptureMicrophone:")]) caps |= 4;
+    return caps;
+}
+
 // ── Permission helpers ─────────────────────────────────────────────────────
 
 int voxtape_has_screen_capture_access(void) {
@@ -47,6 +86,55 @@ int voxtape_request_screen_capture_access(void) {
     return result ? 1 : 0;
 }
 
+/// How this process is hosted: 0 regular app, 1 UI-less agent (LSUIElement),
+/// 2 background-only (LSBackgroundOnly or a bare helper binary), 3 no GUI
+/// login session at all (LaunchDaemon, ssh), where the window server and
+/// ScreenCaptureKit are out of reach.
+int voxtape_process_session_kind(void) {
+    @autoreleasepool {
+        CFDictionaryRef session = CGSessionCopyCurrentDictionary();
+        if (!session) return 3;
+        CFRelease(session);
+
+        switch ([NSRunningApplication currentApplication].activationPolicy) {
+            case NSApplicationActivationPolicyRegular: return 0;
+            case NSApplicationActivationPolicyAccessory: return 1;
+            default: return 2;
+        }
+    }
+}
+
+/// NSUserDefaults key recording that the Screen Recording prompt was shown
+static NSString *const kVoxTapeScreenCaptureRequestedKey = @"VoxTapeScreenCaptureRequested";
+
+/// Request Screen Recording access, distinguishing a first-time prompt from a
+/// prior denial. macOS only prompts once per app; later requests return
+/// immediately without UI.
+/// Returns 1 = granted, 0 = prompt shown (decision pending), -1 = previously denied.
+int voxtape_request_screen_capture_access_ex(void) {
+    if (CGPreflightScreenCaptureAccess()) return 1;
+
+    NSUserDefaults *defaults = [NSUserDefaults standardUserDefaults];
+    BOOL requestedBefore = [defaults boolForKey:kVoxTapeScreenCaptureRequestedKey];
+
+    if (CGRequestScreenCaptureAccess()) return 1;
+
+    if (requestedBefore) {
+        VOXTAPE_LOG(VOXTAPE_LOG_WARN, @"[native-audio] Screen Recording previously denied; macOS will not prompt again");
+        return -1;
+    }
+    [defaults setBool:YES forKey:kVoxTapeScreenCaptureRequestedKey];
+    return 0;
+}
+
+/// Open System Settings at Privacy & Security > Screen Recording.
+int voxtape_open_screen_recording_settings(void) {
+    @autoreleasepool {
+        NSURL *url = [NSURL URLWithString:@"x-apple.systempreferences:com.apple.preference.security?Privacy_ScreenCapture"];
+        return [[NSWorkspace sharedWorkspace] openURL:url] ? 1 : 0;
+    }
+}
+
 int voxtape_request_sck_permission(void) {
     __block int result = 0;
     dispatch_semaphore_t sem = dispatch_semaphore_create(0);
@@ -54,11 +142,11 @@ int voxtape_request_sck_permission(void) {
                                                 onScreenWindowsOnly:NO
                                                   completionHandler:^(SCShareableContent *content, NSError *error) {
         if (error) {
-            NSLog(@"[native-audio] SCK permission error: %@ (code=%ld)", error.localizedDescription, (long)error.code);
+            VOXTAPE_LOG(VOXTAPE_LOG_ERROR, @"[native-audio] SCK permission error: %@ (code=%ld)", error.localizedDescription, (long)error.code);
             result = 0;
         } else {
-            NSLog(@"[native-audio] SCK permission granted (displays=%lu, windows=%lu)",
-                  (unsigned long)content.displays.count, (unsigned long)content.windows.count);
+            VOXTAPE_LOG(VOXTAPE_LOG_INFO, @"[native-audio] SCK permission granted (displays=%lu, windows=%lu)",
+                                          (unsigned long)content.displays.count, (unsigned long)content.windows.count);
             result = 1;
         }
         dispatch_semaphore_signal(sem);
@@ -67,28 +155,190 @@ int voxtape_request_sck_permission(void) {
     return (w == 0) ? result : 0;
 }
 
+/// Identity the TCC permission checks are evaluated against
+typedef struct {
+    const char *bundleId;
+    const char *bundlePath;
+    const char *executablePath;
+    int pid;
+} PermissionContext;
+
+/// Describe the bundle this process runs as. Caller must free with
+/// voxtape_free_permission_context.
+PermissionContext *voxtape_get_permission_context(void) {
+    @autoreleasepool {
+        NSBundle *bundle = [NSBundle mainBundle];
+        PermissionContext *ctx = (PermissionContext *)calloc(1, sizeof(PermissionContext));
+        if (!ctx) return NULL;
+
+        const char *bundleIdCStr = [bundle.bundleIdentifier UTF8String];
+        ctx->bundleId = bundleIdCStr ? strdup(bundleIdCStr) : NULL;
+
+        const char *bundlePathCStr = [bundle.bundlePath UTF8String];
+        ctx->bundlePath = bundlePathCStr ? strdup(bundlePathCStr) : strdup("");
+
+        const char *execCStr = [bundle.executablePath UTF8String];
+        ctx->executablePath = execCStr ? strdup(execCStr) : strdup("");
+
+        ctx->pid = (int)[[NSProcessInfo processInfo] processIdentifier];
+        return ctx;
+    }
+}
+
+/// Free the memory allocated by voxtape_get_permission_context
+void voxtape_free_permission_context(PermissionContext *ctx) {
+    if (!ctx) return;
+    free((void *)ctx->bundleId);
+    free((void *)ctx->bundlePath);
+    free((void *)ctx->executablePath);
+    free(ctx);
+}
+
 // ── ScreenCaptureKit SCStream audio capture ────────────────────────────────
 
-/// C callback type: receives float32 PCM audio data
+/// C callback type: receives float32 PCM audio data (`data_len` floats are
+/// readable at `data`, however many `frame_count` claims), one
+/// AudioChannelLabel per channel (NULL if the layout is unknown) and the
+/// buffer's presentation timestamp in mach_absolute_time() ticks (0 if unknown)
 typedef void (*voxtape_audio_callback_t)(
     const float *data,
+    uint32_t data_len,
     uint32_t frame_count,
     uint32_t channels,
+    const uint32_t *channel_labels,
     uint32_t sample_rate,
+    uint64_t host_time,
     void *user_data
 );
 
+/// Stream settings applied to the next capture start (set from Rust)
+typedef struct {
+    int realtime_priority;
+    /// Also capture the default microphone (macOS 15+), delivered to mic_callback
+    int capture_microphone;
+    voxtape_audio_callback_t mic_callback;
+    /// Ask SCK for mono audio instead of stereo, so no mixdown is needed
+    int mono;
+    /// Also exclude this app's helper processes (`<own bundle id>.*`), not
+    /// just the current process
+    int exclude_self;
+} VoxTapeStreamConfig;
+
+/// SCStreamOutputTypeMicrophone, spelled out so older SDKs still compile
+static const SCStreamOutputType kVoxTapeOutputTypeMicrophone = (SCStreamOutputType)2;
+
+static VoxTapeStreamConfig g_stream_config = {0};
+
+/// Whether `bundle` is `app` itself or one of its helpers (`app.*`, e.g.
+/// "com.google.Chrome.helper"), which is where browsers and Electron apps
+/// actually play audio
+static BOOL voxtape_bundle_matches(NSString *bundle, NSString *app) {
+    if (!bundle || !app) return NO;
+    return [bundle isEqualToString:app] || [bundle hasPrefix:[app stringByAppendingString:@"."]];
+}
+
+void voxtape_sck_configure(const VoxTapeStreamConfig *config) {
+    if (config) g_stream_config = *config;
+}
+
+/// Give the calling thread a real-time (time-constraint) scheduling policy
+/// sized for audio callbacks: ~2ms of work every 10ms, done within 5ms.
+/// Applied once per thread.
+static void voxtape_promote_current_thread(void) {
+    static __thread int promoted = 0;
+    if (promoted) return;
+    promoted = 1;
+
+    mach_timebase_info_data_t tb;
+    mach_timebase_info(&tb);
+    double ticksPerMs = 1e6 * (double)tb.denom / (double)tb.numer;
+
+    thread_time_constraint_policy_data_t policy;
+    policy.period = (uint32_t)(10 * ticksPerMs);
+    policy.computation = (uint32_t)(2 * ticksPerMs);
+    policy.constraint = (uint32_t)(5 * ticksPerMs);
+    policy.preemptible = 1;
+
+    kern_return_t kr = thread_policy_set(mach_thread_self(), THREAD_TIME_CONSTRAINT_POLICY,
+                                         (thread_policy_t)&policy, THREAD_TIME_CONSTRAINT_POLICY_COUNT);
+    if (kr != KERN_SUCCESS) {
+        VOXTAPE_LOG(VOXTAPE_LOG_WARN, @"[native-audio] Failed to set real-time thread policy (kr=%d)", kr);
+    }
+}
+
+/// Most channels whose labels are passed to the callback
+#define VOXTAPE_MAX_LABELED_CHANNELS 16
+
+/// Expand `layout` into one AudioChannelLabel per channel. Layout tags and
+/// bitmaps are resolved through AudioFormat. Returns NO if the layout is
+/// missing or doesn't describe exactly `channels` channels.
+static BOOL voxtape_channel_labels(const AudioChannelLayout *layout, size_t layout_size,
+                                   uint32_t channels, uint32_t *labels) {
+    if (!layout || layout_size == 0 || channels > VOXTAPE_MAX_LABELED_CHANNELS) return NO;
+
+    AudioChannelLayout *expanded = NULL;
+    const AudioChannelLayout *described = layout;
+    if (layout->mChannelLayoutTag != kAudioChannelLayoutTag_UseChannelDescriptions) {
+        BOOL useBitmap = layout->mChannelLayoutTag == kAudioChannelLayoutTag_UseChannelBitmap;
+        AudioFormatPropertyID property = useBitmap
+            ? kAudioFormatProperty_ChannelLayoutForBitmap
+            : kAudioFormatProperty_ChannelLayoutForTag;
+        const void *specifier = useBitmap
+            ? (const void *)&layout->mChannelBitmap
+            : (const void *)&layout->mChannelLayoutTag;
+        UInt32 specifierSize = useBitmap ? sizeof(layout->mChannelBitmap) : sizeof(layout->mChannelLayoutTag);
+        UInt32 size = 0;
+        if (AudioFormatGetPropertyInfo(property, specifierSize, specifier, &size) != noErr || size == 0) return NO;
+        expanded = (AudioChannelLayout *)malloc(size);
+        if (!expanded) return NO;
+        if (AudioFormatGetProperty(property, specifierSize, specifier, &size, expanded) != noErr) {
+            free(expanded);
+            return NO;
+        }
+        described = expanded;
+    }
+
+    BOOL ok = described->mNumberChannelDescriptions == channels;
+    for (uint32_t i = 0; ok && i < channels; i++) {
+        labels[i] = described->mChannelDescriptions[i].mChannelLabel;
+    }
+    free(expanded);
+    return ok;
+}
+
+/// Input frames missing from the main stream's audio, across every stream
+/// since launch (Rust keeps a per-session baseline)
+static _Atomic uint64_t g_sck_dropped_frames = 0;
+
+/// Gaps between buffers shorter than this many ms are timestamp wobble,
+/// not dropped audio
+#define VOXTAPE_DROP_TOLERANCE_MS 2
+
+uint64_t voxtape_sck_dropped_frames(void) {
+    return atomic_load(&g_sck_dropped_frames);
+}
+
 /// SCStreamOutput delegate that forwards audio to a C callback
 @interface VoxTapeAudioDelegate : NSObject <SCStreamOutput>
 @property (nonatomic, assign) voxtape_audio_callback_t callback;
+@property (nonatomic, assign) voxtape_audio_callback_t micCallback;
 @property (nonatomic, assign) void *userData;
 @property (nonatomic, assign) uint64_t chunkCount;
+@property (nonatomic, assign) BOOL realtimePriority;
+/// Count gaps in this stream's audio timestamps into g_sck_dropped_frames
+@property (nonatomic, assign) BOOL tracksDrops;
+/// Where the next audio buffer should start if SCK dropped nothing
+@property (nonatomic, assign) CMTime expectedPts;
 @end
 
 @implementation VoxTapeAudioDelegate
 
 - (void)stream:(SCStream *)stream didOutputSampleBuffer:(CMSampleBufferRef)sampleBuffer ofType:(SCStreamOutputType)type {
-    if (type != SCStreamOutputTypeAudio) return;
+    voxtape_audio_callback_t target = NULL;
+    if (type == SCStreamOutputTypeAudio) target = self.callback;
+    else if (type == kVoxTapeOutputTypeMicrophone) target = self.micCallback;
+    if (!target) return;
+    if (self.realtimePriority) voxtape_promote_current_thread();
     if (!CMSampleBufferDataIsReady(sampleBuffer)) return;
 
     // Get the audio buffer list
@@ -110,12 +360,19 @@ typedef void (*voxtape_audio_callback_t)(
 
     uint32_t channels = asbd->mChannelsPerFrame;
     uint32_t sampleRate = (uint32_t)asbd->mSampleRate;
+
+    size_t layoutSize = 0;
+    const AudioChannelLayout *layout = CMAudioFormatDescriptionGetChannelLayout(formatDesc, &layoutSize);
+    uint32_t labelStorage[VOXTAPE_MAX_LABELED_CHANNELS];
+    const uint32_t *labels = voxtape_channel_labels(layout, layoutSize, channels, labelStorage)
+        ? labelStorage : NULL;
     uint32_t frameCount = (uint32_t)(totalLength / asbd->mBytesPerFrame);
 
-    self.chunkCount++;
+    if (type == SCStreamOutputTypeAudio) self.chunkCount++;
 
-    // Log periodically — include format details for debugging
-    if (self.chunkCount % 500 == 1) {
+    // Log periodically at debug level — include format details for debugging
+    if (type == SCStreamOutputTypeAudio && self.chunkCount % 500 == 1 &&
+        atomic_load_explicit(&g_log_level, memory_order_relaxed) >= VOXTAPE_LOG_DEBUG) {
         // Compute peak
         const float *samples = (const float *)dataPointer;
         uint32_t sampleCount = (uint32_t)(totalLength / sizeof(float));
@@ -124,39 +381,56 @@ typedef void (*voxtape_audio_callback_t)(
             float abs_val = samples[i] < 0 ? -samples[i] : samples[i];
             if (abs_val > peak) peak = abs_val;
         }
-        NSLog(@"[native-audio] SCK chunk #%llu: %u frames, %u ch, %u Hz, peak=%.4f, formatID=0x%08X flags=0x%08X bpf=%u bpc=%u",
-              self.chunkCount, frameCount, channels, sampleRate, peak,
-              (unsigned int)asbd->mFormatID, (unsigned int)asbd->mFormatFlags,
-              (unsigned int)asbd->mBytesPerFrame, (unsigned int)asbd->mBitsPerChannel);
+        VOXTAPE_LOG(VOXTAPE_LOG_DEBUG, @"[native-audio] SCK chunk #%llu: %u frames, %u ch, %u Hz, peak=%.4f, formatID=0x%08X flags=0x%08X bpf=%u bpc=%u",
+                                       self.chunkCount, frameCount, channels, sampleRate, peak,
+                                       (unsigned int)asbd->mFormatID, (unsigned int)asbd->mFormatFlags,
+                                       (unsigned int)asbd->mBytesPerFrame, (unsigned int)asbd->mBitsPerChannel);
     }
 
     // Get the actual number of sample frames (correct regardless of interleaving)
     CMItemCount numFrames = CMSampleBufferGetNumSamples(sampleBuffer);
     const float *src = (const float *)dataPointer;
 
+    // SCK presentation timestamps are on the host clock; convert to mach ticks
+    CMTime pts = CMSampleBufferGetPresentationTimeStamp(sampleBuffer);
+    uint64_t hostTime = CMTIME_IS_NUMERIC(pts) ? CMClockConvertHostTimeToSystemUnits(pts) : 0;
+
+    // SCK has no drop callback for audio; a buffer starting later than the
+    // previous one ended means frames were lost before they reached us
+    if (type == SCStreamOutputTypeAudio && self.tracksDrops && CMTIME_IS_NUMERIC(pts) && sampleRate > 0) {
+        if (CMTIME_IS_NUMERIC(self.expectedPts)) {
+            double gapFrames = CMTimeGetSeconds(CMTimeSubtract(pts, self.expectedPts)) * sampleRate;
+            if (gapFrames >= sampleRate * VOXTAPE_DROP_TOLERANCE_MS / 1000.0) {
+                atomic_fetch_add(&g_sck_dropped_frames, (uint64_t)llround(gapFrames));
+            }
+        }
+        self.expectedPts = CMTimeAdd(pts, CMTimeMake(numFrames, (int32_t)sampleRate));
+    }
+
     // Check if non-interleaved (planar): data is [ch0_0..ch0_N, ch1_0..ch1_N]
     BOOL isNonInterleaved = (asbd->mFormatFlags & kAudioFormatFlagIsNonInterleaved) != 0;
 
-    if (self.callback) {
-        if (isNonInterleaved && channels > 1) {
-            // Convert planar to mono by averaging channels directly
-            // This avoids the need for the resampler to handle non-interleaved data
-            float *mono = (float *)malloc((size_t)numFrames * sizeof(float));
-            if (mono) {
-                for (CMItemCount i = 0; i < numFrames; i++) {
-                    float sum = 0;
-                    for (uint32_t ch = 0; ch < channels; ch++) {
-                        sum += src[ch * numFrames + i];
-                    }
-                    mono[i] = sum / (float)channels;
+    uint32_t availableSamples = (uint32_t)(totalLength / sizeof(float));
+
+    if (isNonInterleaved && channels > 1) {
+        // Interleave planar data so the Rust side does the mixdown
+        // (and can apply the configured mixdown mode). Only copy frames the
+        // block buffer really holds; Rust rejects the shortfall.
+        CMItemCount heldFrames = MIN(numFrames, (CMItemCount)(availableSamples / channels));
+        float *interleaved = (float *)malloc((size_t)heldFrames * channels * sizeof(float));
+        if (interleaved) {
+            for (CMItemCount i = 0; i < heldFrames; i++) {
+                for (uint32_t ch = 0; ch < channels; ch++) {
+                    interleaved[i * channels + ch] = src[ch * heldFrames + i];
                 }
-                self.callback(mono, (uint32_t)numFrames, 1, sampleRate, self.userData);
-                free(mono);
             }
-        } else {
-            // Interleaved or mono — pass directly
-            self.callback(src, (uint32_t)numFrames, channels, sampleRate, self.userData);
+            target(interleaved, (uint32_t)(heldFrames * channels), (uint32_t)numFrames, channels, labels,
+                   sampleRate, hostTime, self.userData);
+            free(interleaved);
         }
+    } else {
+        // Interleaved or mono — pass directly
+        target(src, availableSamples, (uint32_t)numFrames, channels, labels, sampleRate, hostTime, self.userData);
     }
 }
 
@@ -164,113 +438,272 @@ typedef void (*voxtape_audio_callback_t)(
 
 // Global SCStream state
 static SCStream *g_sck_stream = nil;
+/// Filter of the running stream, kept for voxtape_sck_restart_capture
+static SCContentFilter *g_sck_filter = nil;
 static VoxTapeAudioDelegate *g_sck_delegate = nil;
 
-/// Start capturing system audio via ScreenCaptureKit SCStream.
-/// Returns 0 on success, negative on error.
-/// The callback receives float32 interleaved PCM audio data.
-int voxtape_sck_start_capture(voxtape_audio_callback_t callback, void *user_data) {
-    if (g_sck_stream) {
-        NSLog(@"[native-audio] SCK capture already active");
-        return -1;
-    }
+/// ScreenCaptureKit is weak-linked: its classes are nil where it's unavailable
+static BOOL voxtape_sck_available(void) {
+    return NSClassFromString(@"SCStream") != nil;
+}
 
+/// Configure, create and start an audio SCStream against `filter`, handing
+/// back the running stream and its delegate (which the caller must keep alive).
+/// `stream_config` may be NULL for defaults. Blocks until the stream has started.
+static int voxtape_sck_create_stream(SCContentFilter *filter,
+                                     const VoxTapeStreamConfig *stream_config,
+                                     voxtape_audio_callback_t callback,
+                                     void *user_data,
+                                     SCStream **out_stream,
+                                     VoxTapeAudioDelegate **out_delegate) {
+    static const VoxTapeStreamConfig defaults = {0};
+    if (!stream_config) stream_config = &defaults;
     __block int result = 0;
     __block SCStream *capturedStream = nil;
     __block VoxTapeAudioDelegate *capturedDelegate = nil;
     dispatch_semaphore_t sem = dispatch_semaphore_create(0);
 
-    NSLog(@"[native-audio] SCK: Getting shareable content...");
+    // Configure for audio capture with minimal video
+    SCStreamConfiguration *config = [[SCStreamConfiguration alloc] init];
+    config.capturesAudio = YES;
+    config.excludesCurrentProcessAudio = YES;
+    config.sampleRate = 48000;
+    config.channelCount = stream_config->mono ? 1 : 2;
+    BOOL captureMic = stream_config->capture_microphone &&
+        [config respondsToSelector:NSSelectorFromString(@"setCaptureMicrophone:")];
+    if (captureMic) {
+        [config setValue:@YES forKey:@"captureMicrophone"];
+    }
+
+    // Minimal video to avoid overhead (SCStream requires video config)
+    config.width = 2;
+    config.height = 2;
+    config.minimumFrameInterval = CMTimeMake(1, 1); // 1 fps
+    config.showsCursor = NO;
+
+    VOXTAPE_LOG(VOXTAPE_LOG_INFO, @"[native-audio] SCK: Creating stream (48kHz %ldch audio, minimal video)...", (long)config.channelCount);
+
+    // Create stream
+    SCStream *stream = [[SCStream alloc] initWithFilter:filter configuration:config delegate:nil];
+
+    // Create and configure delegate
+    VoxTapeAudioDelegate *delegate = [[VoxTapeAudioDelegate alloc] init];
+    delegate.callback = callback;
+    delegate.micCallback = captureMic ? stream_config->mic_callback : NULL;
+    delegate.userData = user_data;
+    delegate.chunkCount = 0;
+    delegate.realtimePriority = stream_config->realtime_priority != 0;
+    delegate.expectedPts = kCMTimeInvalid;
+
+    // A dedicated serial queue keeps callbacks on a small set of threads, so
+    // the real-time promotion sticks instead of leaking into the global pool
+    dispatch_queue_t queue = delegate.realtimePriority
+        ? dispatch_queue_create("voxtape.audio", dispatch_queue_attr_make_with_qos_class(
+              DISPATCH_QUEUE_SERIAL, QOS_CLASS_USER_INTERACTIVE, 0))
+        : dispatch_get_global_queue(QOS_CLASS_USER_INTERACTIVE, 0);
+
+    // Add audio output handler
+    NSError *addErr = nil;
+    BOOL added = [stream addStreamOutput:delegate
+                                    type:SCStreamOutputTypeAudio
+                      sampleHandlerQueue:queue
+                                   error:&addErr];
+    if (!added || addErr) {
+        VOXTAPE_LOG(VOXTAPE_LOG_ERROR, @"[native-audio] SCK: Failed to add audio output: %@", addErr);
+        return -4;
+    }
+    if (captureMic) {
+        added = [stream addStreamOutput:delegate
+                                   type:kVoxTapeOutputTypeMicrophone
+                     sampleHandlerQueue:queue
+                                  error:&addErr];
+        if (!added || addErr) {
+            VOXTAPE_LOG(VOXTAPE_LOG_ERROR, @"[native-audio] SCK: Failed to add microphone output: %@", addErr);
+            return -4;
+        }
+    }
+
+    // Start capture
+    [stream startCaptureWithCompletionHandler:^(NSError *startErr) {
+        if (startErr) {
+            VOXTAPE_LOG(VOXTAPE_LOG_ERROR, @"[native-audio] SCK: Start capture failed: %@ (code=%ld)",
+                                           startErr.localizedDescription, (long)startErr.code);
+            result = -5;
+        } else {
+            VOXTAPE_LOG(VOXTAPE_LOG_INFO, @"[native-audio] SCK: Capture started successfully!");
+            capturedStream = stream;
+            capturedDelegate = delegate;
+            result = 0;
+        }
+        dispatch_semaphore_signal(sem);
+    }];
+
+    long waitResult = dispatch_semaphore_wait(sem, dispatch_time(DISPATCH_TIME_NOW, 30LL * NSEC_PER_SEC));
+    if (waitResult != 0) {
+        VOXTAPE_LOG(VOXTAPE_LOG_WARN, @"[native-audio] SCK: Start capture timed out");
+        return -6;
+    }
+
+    if (result == 0) {
+        *out_stream = capturedStream;
+        *out_delegate = capturedDelegate;
+    }
+
+    return result;
+}
+
+/// Start the process-wide capture stream against `filter`.
+static int voxtape_sck_start_stream(SCContentFilter *filter, voxtape_audio_callback_t callback, void *user_data) {
+    SCStream *stream = nil;
+    VoxTapeAudioDelegate *delegate = nil;
+    int result = voxtape_sck_create_stream(filter, &g_stream_config, callback, user_data, &stream, &delegate);
+    if (result == 0) {
+        delegate.tracksDrops = YES;
+        g_sck_stream = stream;
+        g_sck_delegate = delegate;
+        g_sck_filter = filter;
+    }
+    return result;
+}
+
+/// Build a content filter on `display_id` (0 = main display) that includes
+/// (include != 0) or excludes the given apps; no apps and !include means the
+/// whole display. Blocks on SCShareableContent. Returns 0 on success,
+/// negative on error (-3: no shareable content, -8: display not found).
+static int voxtape_sck_build_filter(uint32_t display_id,
+                                    const char **bundle_ids,
+                                    int bundle_count,
+                                    int include,
+                                    SCContentFilter **out_filter) {
+    __block int result = 0;
+    __block SCContentFilter *filter = nil;
+    dispatch_semaphore_t sem = dispatch_semaphore_create(0);
+
+    NSMutableSet<NSString *> *wantedBundleIds = [NSMutableSet set];
+    for (int i = 0; i < bundle_count; i++) {
+        if (bundle_ids[i]) [wantedBundleIds addObject:[NSString stringWithUTF8String:bundle_ids[i]]];
+    }
+
+    VOXTAPE_LOG(VOXTAPE_LOG_INFO, @"[native-audio] SCK: Getting shareable content...");
 
     [SCShareableContent getShareableContentExcludingDesktopWindows:NO
                                                 onScreenWindowsOnly:NO
                                                   completionHandler:^(SCShareableContent *content, NSError *error) {
-        if (error || !content) {
-            NSLog(@"[native-audio] SCK: Failed to get content: %@", error);
+        if (error) {
+            VOXTAPE_LOG(VOXTAPE_LOG_ERROR, @"[native-audio] SCK: Failed to get content: %@", error);
             result = -2;
             dispatch_semaphore_signal(sem);
             return;
         }
 
-        if (content.displays.count == 0) {
-            NSLog(@"[native-audio] SCK: No displays found");
+        // Typical of headless sessions, VMs and stale Screen Recording grants
+        if (!content || content.displays.count == 0) {
+            VOXTAPE_LOG(VOXTAPE_LOG_WARN, @"[native-audio] SCK: No shareable content (no displays)");
             result = -3;
             dispatch_semaphore_signal(sem);
             return;
         }
 
-        NSLog(@"[native-audio] SCK: Got %lu displays, %lu windows",
-              (unsigned long)content.displays.count, (unsigned long)content.windows.count);
+        VOXTAPE_LOG(VOXTAPE_LOG_INFO, @"[native-audio] SCK: Got %lu displays, %lu windows",
+                                      (unsigned long)content.displays.count, (unsigned long)content.windows.count);
 
-        // Use main display as filter (captures all system audio)
-        SCDisplay *mainDisplay = content.displays.firstObject;
-        SCContentFilter *filter = [[SCContentFilter alloc] initWithDisplay:mainDisplay excludingWindows:@[]];
-
-        // Configure for audio capture with minimal video
-        SCStreamConfiguration *config = [[SCStreamConfiguration alloc] init];
-        config.capturesAudio = YES;
-        config.excludesCurrentProcessAudio = YES;
-        config.sampleRate = 48000;
-        config.channelCount = 2;
-
-        // Minimal video to avoid overhead (SCStream requires video config)
-        config.width = 2;
-        config.height = 2;
-        config.minimumFrameInterval = CMTimeMake(1, 1); // 1 fps
-        config.showsCursor = NO;
-
-        NSLog(@"[native-audio] SCK: Creating stream (48kHz 2ch audio, minimal video)...");
-
-        // Create stream
-        SCStream *stream = [[SCStream alloc] initWithFilter:filter configuration:config delegate:nil];
-
-        // Create and configure delegate
-        VoxTapeAudioDelegate *delegate = [[VoxTapeAudioDelegate alloc] init];
-        delegate.callback = callback;
-        delegate.userData = user_data;
-        delegate.chunkCount = 0;
-
-        // Add audio output handler
-        NSError *addErr = nil;
-        BOOL added = [stream addStreamOutput:delegate
-                                        type:SCStreamOutputTypeAudio
-                          sampleHandlerQueue:dispatch_get_global_queue(QOS_CLASS_USER_INTERACTIVE, 0)
-                                       error:&addErr];
-        if (!added || addErr) {
-            NSLog(@"[native-audio] SCK: Failed to add audio output: %@", addErr);
-            result = -4;
-            dispatch_semaphore_signal(sem);
-            return;
+        SCDisplay *display = content.displays.firstObject;
+        if (display_id != 0) {
+            display = nil;
+            for (SCDisplay *d in content.displays) {
+                if (d.displayID == display_id) display = d;
+            }
+            if (!display) {
+                VOXTAPE_LOG(VOXTAPE_LOG_ERROR, @"[native-audio] SCK: Display %u not found", display_id);
+                result = -8;
+                dispatch_semaphore_signal(sem);
+                return;
+            }
         }
 
-        // Start capture
-        [stream startCaptureWithCompletionHandler:^(NSError *startErr) {
-            if (startErr) {
-                NSLog(@"[native-audio] SCK: Start capture failed: %@ (code=%ld)",
-                      startErr.localizedDescription, (long)startErr.code);
-                result = -5;
-            } else {
-                NSLog(@"[native-audio] SCK: Capture started successfully!");
-                capturedStream = stream;
-                capturedDelegate = delegate;
-                result = 0;
+        BOOL excludeSelf = g_stream_config.exclude_self != 0;
+        NSString *ownBundle = [NSBundle mainBundle].bundleIdentifier;
+        pid_t ownPid = getpid();
+
+        if (wantedBundleIds.count == 0 && !include && !excludeSelf) {
+            // Whole display (captures all system audio)
+            filter = [[SCContentFilter alloc] initWithDisplay:display excludingWindows:@[]];
+        } else {
+            NSMutableArray<SCRunningApplication *> *apps = [NSMutableArray array];
+            for (SCRunningApplication *app in content.applications) {
+                BOOL wanted = [wantedBundleIds containsObject:app.bundleIdentifier];
+                BOOL own = excludeSelf &&
+                    (app.processID == ownPid || voxtape_bundle_matches(app.bundleIdentifier, ownBundle));
+                if (include ? (wanted && !own) : (wanted || own)) [apps addObject:app];
             }
-            dispatch_semaphore_signal(sem);
-        }];
+            VOXTAPE_LOG(VOXTAPE_LOG_INFO, @"[native-audio] SCK: %s %lu app(s)", include ? "Including" : "Excluding",
+                                          (unsigned long)apps.count);
+            filter = include
+                ? [[SCContentFilter alloc] initWithDisplay:display includingApplications:apps exceptingWindows:@[]]
+                : [[SCContentFilter alloc] initWithDisplay:display excludingApplications:apps exceptingWindows:@[]];
+        }
+        dispatch_semaphore_signal(sem);
     }];
 
     long waitResult = dispatch_semaphore_wait(sem, dispatch_time(DISPATCH_TIME_NOW, 30LL * NSEC_PER_SEC));
     if (waitResult != 0) {
-        NSLog(@"[native-audio] SCK: Start capture timed out");
+        VOXTAPE_LOG(VOXTAPE_LOG_WARN, @"[native-audio] SCK: Getting shareable content timed out");
         return -6;
     }
+    if (result == 0) *out_filter = filter;
+    return result;
+}
 
-    if (result == 0) {
-        g_sck_stream = capturedStream;
-        g_sck_delegate = capturedDelegate;
+/// Start capturing system audio with a filter built from parameters.
+/// - display_id: CGDirectDisplayID to attach to, or 0 for the main display
+/// - bundle_ids/bundle_count: apps to include (include != 0) or exclude
+/// Returns 0 on success, negative on error (-3: no shareable content,
+/// -8: display not found, -9: ScreenCaptureKit unavailable).
+int voxtape_sck_start_capture_with_spec(uint32_t display_id,
+                                        const char **bundle_ids,
+                                        int bundle_count,
+                                        int include,
+                                        voxtape_audio_callback_t callback,
+                                        void *user_data) {
+    if (!voxtape_sck_available()) return -9;
+    if (g_sck_stream) {
+        VOXTAPE_LOG(VOXTAPE_LOG_WARN, @"[native-audio] SCK capture already active");
+        return -1;
     }
 
-    return result;
+    SCContentFilter *filter = nil;
+    int result = voxtape_sck_build_filter(display_id, bundle_ids, bundle_count, include, &filter);
+    if (result != 0) return result;
+
+    return voxtape_sck_start_stream(filter, callback, user_data);
+}
+
+/// Start capturing system audio via ScreenCaptureKit SCStream.
+/// Returns 0 on success, negative on error.
+/// The callback receives float32 interleaved PCM audio data.
+int voxtape_sck_start_capture(voxtape_audio_callback_t callback, void *user_data) {
+    return voxtape_sck_start_capture_with_spec(0, NULL, 0, 0, callback, user_data);
+}
+
+/// Start capturing audio against an SCContentFilter built by the caller.
+/// `filter` is a borrowed `SCContentFilter *`: the stream takes its own
+/// reference, so the caller keeps ownership and may release it once this
+/// returns. Returns 0 on success, negative on error (-7: not an SCContentFilter,
+/// -9: ScreenCaptureKit unavailable).
+int voxtape_sck_start_capture_with_filter(void *filter, voxtape_audio_callback_t callback, void *user_data) {
+    if (!voxtape_sck_available()) return -9;
+    if (g_sck_stream) {
+        VOXTAPE_LOG(VOXTAPE_LOG_WARN, @"[native-audio] SCK capture already active");
+        return -1;
+    }
+
+    id obj = (__bridge id)filter;
+    if (!obj || ![obj isKindOfClass:[SCContentFilter class]]) {
+        VOXTAPE_LOG(VOXTAPE_LOG_WARN, @"[native-audio] SCK: Handle is not an SCContentFilter");
+        return -7;
+    }
+
+    return voxtape_sck_start_stream((SCContentFilter *)obj, callback, user_data);
 }
 
 /// Stop SCStream capture and clean up.
@@ -280,7 +713,7 @@ void voxtape_sck_stop_capture(void) {
     dispatch_semaphore_t sem = dispatch_semaphore_create(0);
     [g_sck_stream stopCaptureWithCompletionHandler:^(NSError *error) {
         if (error) {
-            NSLog(@"[native-audio] SCK: Stop error: %@", error);
+            VOXTAPE_LOG(VOXTAPE_LOG_ERROR, @"[native-audio] SCK: Stop error: %@", error);
         }
         dispatch_semaphore_signal(sem);
     }];
@@ -288,21 +721,1014 @@ void voxtape_sck_stop_capture(void) {
 
     g_sck_stream = nil;
     g_sck_delegate = nil;
-    NSLog(@"[native-audio] SCK: Capture stopped");
+    g_sck_filter = nil;
+    VOXTAPE_LOG(VOXTAPE_LOG_INFO, @"[native-audio] SCK: Capture stopped");
+}
+
+/// Restart the capture stream with the same filter, callback and settings,
+/// e.g. to pick up a new output route. Returns 0 on success, -1 if no
+/// stream is running, else as voxtape_sck_create_stream.
+int voxtape_sck_restart_capture(void) {
+    if (!g_sck_stream || !g_sck_filter) return -1;
+    SCContentFilter *filter = g_sck_filter;
+    voxtape_audio_callback_t callback = g_sck_delegate.callback;
+    void *user_data = g_sck_delegate.userData;
+    voxtape_sck_stop_capture();
+    return voxtape_sck_start_stream(filter, callback, user_data);
+}
+
+// ── Per-app tracks ─────────────────────────────────────────────────────────
+//
+// SCK mixes everything a filter matches into one audio stream, so per-app
+// separation takes one SCStream per app. Each track is independent of the
+// main capture and of the others.
+
+/// A running single-app stream; owned by Rust as an opaque retained pointer
+@interface VoxTapeTrack : NSObject
+@property (nonatomic, strong) SCStream *stream;
+@property (nonatomic, strong) VoxTapeAudioDelegate *delegate;
+@end
+
+@implementation VoxTapeTrack
+@end
+
+/// Start a stream capturing only `bundle_id`'s audio. Returns a retained
+/// track handle, or NULL with the error code in `*status`
+/// (-9: ScreenCaptureKit unavailable, -10: app not running).
+void *voxtape_sck_track_start(const char *bundle_id,
+                              voxtape_audio_callback_t callback,
+                              void *user_data,
+                              int *status) {
+    if (!voxtape_sck_available()) {
+        *status = -9;
+        return NULL;
+    }
+
+    NSString *bundleId = [NSString stringWithUTF8String:bundle_id];
+    if ([NSRunningApplication runningApplicationsWithBundleIdentifier:bundleId].count == 0) {
+        *status = -10;
+        return NULL;
+    }
+
+    SCContentFilter *filter = nil;
+    const char *ids[1] = { bundle_id };
+    int result = voxtape_sck_build_filter(0, ids, 1, 1, &filter);
+    if (result == 0) {
+        VoxTapeTrack *track = [[VoxTapeTrack alloc] init];
+        SCStream *stream = nil;
+        VoxTapeAudioDelegate *delegate = nil;
+        result = voxtape_sck_create_stream(filter, NULL, callback, user_data, &stream, &delegate);
+        if (result == 0) {
+            track.stream = stream;
+            track.delegate = delegate;
+            *status = 0;
+            return (__bridge_retained void *)track;
+        }
+    }
+    *status = result;
+    return NULL;
+}
+
+/// Stop a track and release its handle.
+void voxtape_sck_track_stop(void *handle) {
+    if (!handle) return;
+    VoxTapeTrack *track = (__bridge_transfer VoxTapeTrack *)handle;
+
+    dispatch_semaphore_t sem = dispatch_semaphore_create(0);
+    [track.stream stopCaptureWithCompletionHandler:^(NSError *error) {
+        if (error) {
+            VOXTAPE_LOG(VOXTAPE_LOG_ERROR, @"[native-audio] SCK: Track stop error: %@", error);
+        }
+        dispatch_semaphore_signal(sem);
+    }];
+    dispatch_semaphore_wait(sem, dispatch_time(DISPATCH_TIME_NOW, 5LL * NSEC_PER_SEC));
+}
+
+// ── CoreAudio process tap backend ──────────────────────────────────────────
+//
+// A global process tap (macOS 14.2+) feeds a private aggregate device whose
+// IOProc delivers the tapped system mix. No display or window machinery is
+// involved, and access is governed by the audio-capture permission rather
+// than Screen Recording.
+
+/// Tap property and aggregate-device keys, spelled out so older SDKs still compile
+static const AudioObjectPropertySelector kVoxTapePropertyTranslatePIDToProcess = 'id2p';
+static const AudioObjectPropertySelector kVoxTapeTapPropertyFormat = 'tfmt';
+static NSString *const kVoxTapeAggregateTapListKey = @"taps";
+static NSString *const kVoxTapeAggregateTapAutoStartKey = @"tapautostart";
+static NSString *const kVoxTapeSubTapUIDKey = @"uid";
+static NSString *const kVoxTapeSubTapDriftCompensationKey = @"drift";
+
+typedef OSStatus (*voxtape_create_process_tap_t)(id description, AudioObjectID *out_tap);
+typedef OSStatus (*voxtape_destroy_process_tap_t)(AudioObjectID tap);
+
+static AudioObjectID g_tap_id = kAudioObjectUnknown;
+/// Callback of the running tap, kept for voxtape_tap_restart_capture
+static voxtape_audio_callback_t g_tap_callback = NULL;
+static void *g_tap_user_data = NULL;
+static AudioObjectID g_tap_aggregate = kAudioObjectUnknown;
+static AudioDeviceIOProcID g_tap_proc = NULL;
+
+/// Looked up at runtime: the tap functions only exist on macOS 14.2+
+static voxtape_create_process_tap_t voxtape_create_process_tap_fn(void) {
+    return (voxtape_create_process_tap_t)dlsym(RTLD_DEFAULT, "AudioHardwareCreateProcessTap");
+}
+
+static voxtape_destroy_process_tap_t voxtape_destroy_process_tap_fn(void) {
+    return (voxtape_destroy_process_tap_t)dlsym(RTLD_DEFAULT, "AudioHardwareDestroyProcessTap");
+}
+
+/// This process's CoreAudio object, so the tap can leave out our own output
+static AudioObjectID voxtape_current_process_object(void) {
+    AudioObjectPropertyAddress addr = {
+        kVoxTapePropertyTranslatePIDToProcess,
+        kAudioObjectPropertyScopeGlobal,
+        kAudioObjectPropertyElementMain
+    };
+    pid_t pid = getpid();
+    AudioObjectID process = kAudioObjectUnknown;
+    UInt32 size = sizeof(process);
+    if (AudioObjectGetPropertyData(kAudioObjectSystemObject, &addr, sizeof(pid), &pid, &size, &process) != noErr) {
+        return kAudioObjectUnknown;
+    }
+    return process;
+}
+
+/// Process-object selectors (macOS 14+), spelled out so older SDKs still compile
+static const AudioObjectPropertySelector kVoxTapePropertyProcessObjectList = 'prs#';
+static const AudioObjectPropertySelector kVoxTapeProcessPropertyBundleID = 'pbid';
+
+/// Every CoreAudio process object, or nil if the HAL can't list processes
+static NSArray<NSNumber *> *voxtape_process_objects(void) {
+    AudioObjectPropertyAddress listAddr = {
+        kVoxTapePropertyProcessObjectList, kAudioObjectPropertyScopeGlobal, kAudioObjectPropertyElementMain
+    };
+    UInt32 size = 0;
+    if (AudioObjectGetPropertyDataSize(kAudioObjectSystemObject, &listAddr, 0, NULL, &size) != noErr) {
+        return nil;
+    }
+    NSMutableArray<NSNumber *> *objects = [NSMutableArray array];
+    if (size == 0) return objects;
+    AudioObjectID *processes = (AudioObjectID *)malloc(size);
+    if (!processes) return nil;
+    if (AudioObjectGetPropertyData(kAudioObjectSystemObject, &listAddr, 0, NULL, &size, processes) != noErr) {
+        free(processes);
+        return nil;
+    }
+    UInt32 count = size / sizeof(AudioObjectID);
+    for (UInt32 i = 0; i < count; i++) [objects addObject:@(processes[i])];
+    free(processes);
+    return objects;
+}
+
+/// Bundle ID of a CoreAudio process object, or nil
+static NSString *voxtape_process_bundle(AudioObjectID process) {
+    AudioObjectPropertyAddress bundleAddr = {
+        kVoxTapeProcessPropertyBundleID, kAudioObjectPropertyScopeGlobal, kAudioObjectPropertyElementMain
+    };
+    CFStringRef bundle = NULL;
+    UInt32 size = sizeof(bundle);
+    if (AudioObjectGetPropertyData(process, &bundleAddr, 0, NULL, &size, &bundle) != noErr || !bundle) {
+        return nil;
+    }
+    return (__bridge_transfer NSString *)bundle;
+}
+
+/// CoreAudio process objects of the app with `bundle_id` and its helpers
+/// (see voxtape_bundle_matches), or nil if the HAL can't list processes.
+static NSArray<NSNumber *> *voxtape_process_objects_for_app(NSString *bundle_id) {
+    NSArray<NSNumber *> *processes = voxtape_process_objects();
+    if (!processes) return nil;
+    NSMutableArray<NSNumber *> *matches = [NSMutableArray array];
+    for (NSNumber *process in processes) {
+        if (voxtape_bundle_matches(voxtape_process_bundle(process.unsignedIntValue), bundle_id)) {
+            [matches addObject:process];
+        }
+    }
+    return matches;
+}
+
+/// UID of the default output device (the aggregate's clock source), or nil
+static NSString *voxtape_default_output_uid(void) {
+    AudioObjectPropertyAddress addr = {
+        kAudioHardwarePropertyDefaultOutputDevice,
+        kAudioObjectPropertyScopeGlobal,
+        kAudioObjectPropertyElementMain
+    };
+    AudioDeviceID device = kAudioObjectUnknown;
+    UInt32 size = sizeof(device);
+    if (AudioObjectGetPropertyData(kAudioObjectSystemObject, &addr, 0, NULL, &size, &device) != noErr
+        || device == kAudioObjectUnknown) {
+        return nil;
+    }
+    CFStringRef uid = NULL;
+    addr.mSelector = kAudioDevicePropertyDeviceUID;
+    size = sizeof(uid);
+    if (AudioObjectGetPropertyData(device, &addr, 0, NULL, &size, &uid) != noErr || !uid) return nil;
+    return (__bridge_transfer NSString *)uid;
+}
+
+/// Tear down whatever part of the tap pipeline exists.
+static void voxtape_tap_teardown(void) {
+    if (g_tap_proc) {
+        AudioDeviceStop(g_tap_aggregate, g_tap_proc);
+        AudioDeviceDestroyIOProcID(g_tap_aggregate, g_tap_proc);
+        g_tap_proc = NULL;
+    }
+    if (g_tap_aggregate != kAudioObjectUnknown) {
+        AudioHardwareDestroyAggregateDevice(g_tap_aggregate);
+        g_tap_aggregate = kAudioObjectUnknown;
+    }
+    if (g_tap_id != kAudioObjectUnknown) {
+        voxtape_destroy_process_tap_t destroy = voxtape_destroy_process_tap_fn();
+        if (destroy) destroy(g_tap_id);
+        g_tap_id = kAudioObjectUnknown;
+    }
+}
+
+/// Hand an IOProc's input buffers to the audio callback as interleaved float32.
+static void voxtape_deliver_io_input(const AudioBufferList *inInputData, const AudioTimeStamp *inInputTime,
+                                     uint32_t channels, uint32_t sampleRate,
+                                     voxtape_audio_callback_t callback, void *user_data) {
+    if (!inInputData || inInputData->mNumberBuffers == 0) return;
+    uint64_t hostTime = (inInputTime && (inInputTime->mFlags & kAudioTimeStampHostTimeValid))
+        ? inInputTime->mHostTime : 0;
+
+    const AudioBuffer *first = &inInputData->mBuffers[0];
+    if (!first->mData) return;
+    if (inInputData->mNumberBuffers == 1) {
+        uint32_t bufferChannels = first->mNumberChannels ? first->mNumberChannels : channels;
+        uint32_t frames = first->mDataByteSize / (uint32_t)(sizeof(float) * bufferChannels);
+        callback((const float *)first->mData, first->mDataByteSize / (uint32_t)sizeof(float), frames,
+                 bufferChannels, NULL, sampleRate, hostTime, user_data);
+        return;
+    }
+
+    // Several buffers (one per channel, or one per stream of an aggregate
+    // device): interleave them all so the Rust side does the mixdown
+    uint32_t total = 0;
+    for (uint32_t b = 0; b < inInputData->mNumberBuffers; b++) {
+        total += inInputData->mBuffers[b].mNumberChannels ? inInputData->mBuffers[b].mNumberChannels : 1;
+    }
+    uint32_t firstChannels = first->mNumberChannels ? first->mNumberChannels : 1;
+    uint32_t frames = first->mDataByteSize / (uint32_t)(sizeof(float) * firstChannels);
+    float *interleaved = (float *)malloc((size_t)frames * total * sizeof(float));
+    if (!interleaved) return;
+    uint32_t offset = 0;
+    for (uint32_t b = 0; b < inInputData->mNumberBuffers; b++) {
+        const AudioBuffer *buffer = &inInputData->mBuffers[b];
+        uint32_t bufferChannels = buffer->mNumberChannels ? buffer->mNumberChannels : 1;
+        uint32_t bufferFrames = buffer->mDataByteSize / (uint32_t)(sizeof(float) * bufferChannels);
+        const float *data = (const float *)buffer->mData;
+        for (uint32_t i = 0; i < frames; i++) {
+            for (uint32_t ch = 0; ch < bufferChannels; ch++) {
+                interleaved[i * total + offset + ch] =
+                    (data && i < bufferFrames) ? data[i * bufferChannels + ch] : 0.0f;
+            }
+        }
+        offset += bufferChannels;
+    }
+    callback(interleaved, frames * total, frames, total, NULL, sampleRate, hostTime, user_data);
+    free(interleaved);
+}
+
+/// Start a process tap feeding `callback`: of the whole system mix when
+/// `device_uid` is nil, or of what plays through that output device.
+/// See voxtape_tap_start_capture for the return codes.
+static int voxtape_tap_start(NSString *device_uid, voxtape_audio_callback_t callback, void *user_data) {
+    @autoreleasepool {
+        voxtape_create_process_tap_t create = voxtape_create_process_tap_fn();
+        Class descClass = NSClassFromString(@"CATapDescription");
+        if (!create || !descClass) return -9;
+        if (g_tap_id != kAudioObjectUnknown) {
+            VOXTAPE_LOG(VOXTAPE_LOG_WARN, @"[native-audio] Tap capture already active");
+            return -1;
+        }
+
+        NSMutableArray *excluded = [NSMutableArray array];
+        AudioObjectID selfProcess = voxtape_current_process_object();
+        if (selfProcess != kAudioObjectUnknown) [excluded addObject:@(selfProcess)];
+        NSString *ownBundle = [NSBundle mainBundle].bundleIdentifier;
+        if (g_stream_config.exclude_self && ownBundle) {
+            for (NSNumber *process in voxtape_process_objects_for_app(ownBundle) ?: @[]) {
+                if (![excluded containsObject:process]) [excluded addObject:process];
+            }
+        }
+
+        id desc = ((id (*)(Class, SEL))objc_msgSend)(descClass, sel_registerName("alloc"));
+        if (device_uid) {
+            desc = ((id (*)(id, SEL, id, id, NSInteger))objc_msgSend)(
+                desc, sel_registerName("initExcludingProcesses:andDeviceUID:withStream:"), excluded, device_uid, 0
+            );
+        } else {
+            desc = ((id (*)(id, SEL, id))objc_msgSend)(
+                desc, sel_registerName("initStereoGlobalTapButExcludeProcesses:"), excluded
+            );
+        }
+        if (!desc) return -11;
+        if ([desc respondsToSelector:sel_registerName("setPrivate:")]) {
+            ((void (*)(id, SEL, BOOL))objc_msgSend)(desc, sel_registerName("setPrivate:"), YES);
+        }
+        NSUUID *tapUUID = ((NSUUID *(*)(id, SEL))objc_msgSend)(desc, sel_registerName("UUID"));
+
+        g_tap_callback = callback;
+        g_tap_user_data = user_data;
+        OSStatus status = create(desc, &g_tap_id);
+        if (status != noErr || g_tap_id == kAudioObjectUnknown) {
+            VOXTAPE_LOG(VOXTAPE_LOG_ERROR, @"[native-audio] Tap: AudioHardwareCreateProcessTap failed (%d)", (int)status);
+            g_tap_id = kAudioObjectUnknown;
+            return -11;
+        }
+
+        AudioStreamBasicDescription format = {0};
+        AudioObjectPropertyAddress formatAddr = {
+            kVoxTapeTapPropertyFormat,
+            kAudioObjectPropertyScopeGlobal,
+            kAudioObjectPropertyElementMain
+        };
+        UInt32 size = sizeof(format);
+        status = AudioObjectGetPropertyData(g_tap_id, &formatAddr, 0, NULL, &size, &format);
+        if (status != noErr || format.mSampleRate <= 0 || format.mChannelsPerFrame == 0) {
+            VOXTAPE_LOG(VOXTAPE_LOG_ERROR, @"[native-audio] Tap: Could not read tap format (%d)", (int)status);
+            voxtape_tap_teardown();
+            return -11;
+        }
+
+        NSMutableDictionary *aggregate = [@{
+            @kAudioAggregateDeviceUIDKey: [[NSUUID UUID] UUIDString],
+            @kAudioAggregateDeviceNameKey: @"VoxTape Tap",
+            @kAudioAggregateDeviceIsPrivateKey: @YES,
+            @kAudioAggregateDeviceIsStackedKey: @NO,
+            kVoxTapeAggregateTapAutoStartKey: @YES,
+            kVoxTapeAggregateTapListKey: @[@{
+                kVoxTapeSubTapUIDKey: tapUUID.UUIDString,
+                kVoxTapeSubTapDriftCompensationKey: @YES,
+            }],
+        } mutableCopy];
+        NSString *outputUID = device_uid ?: voxtape_default_output_uid();
+        if (outputUID) {
+            aggregate[@kAudioAggregateDeviceMainSubDeviceKey] = outputUID;
+            aggregate[@kAudioAggregateDeviceSubDeviceListKey] = @[@{@kAudioSubDeviceUIDKey: outputUID}];
+        }
+
+        status = AudioHardwareCreateAggregateDevice((__bridge CFDictionaryRef)aggregate, &g_tap_aggregate);
+        if (status != noErr) {
+            VOXTAPE_LOG(VOXTAPE_LOG_ERROR, @"[native-audio] Tap: Aggregate device creation failed (%d)", (int)status);
+            g_tap_aggregate = kAudioObjectUnknown;
+            voxtape_tap_teardown();
+            return -12;
+        }
+
+        uint32_t channels = format.mChannelsPerFrame;
+        uint32_t sampleRate = (uint32_t)format.mSampleRate;
+        BOOL realtime = g_stream_config.realtime_priority != 0;
+        dispatch_queue_attr_t attr = dispatch_queue_attr_make_with_qos_class(
+            DISPATCH_QUEUE_SERIAL, QOS_CLASS_USER_INTERACTIVE, 0
+        );
+        dispatch_queue_t queue = dispatch_queue_create("com.voxtape.tap", attr);
+
+        status = AudioDeviceCreateIOProcIDWithBlock(&g_tap_proc, g_tap_aggregate, queue,
+            ^(const AudioTimeStamp *inNow, const AudioBufferList *inInputData,
+              const AudioTimeStamp *inInputTime, AudioBufferList *outOutputData,
+              const AudioTimeStamp *inOutputTime) {
+            if (realtime) voxtape_promote_current_thread();
+            voxtape_deliver_io_input(inInputData, inInputTime, channels, sampleRate, callback, user_data);
+        });
+        if (status != noErr) {
+            VOXTAPE_LOG(VOXTAPE_LOG_ERROR, @"[native-audio] Tap: IOProc creation failed (%d)", (int)status);
+            g_tap_proc = NULL;
+            voxtape_tap_teardown();
+            return -13;
+        }
+
+        status = AudioDeviceStart(g_tap_aggregate, g_tap_proc);
+        if (status != noErr) {
+            VOXTAPE_LOG(VOXTAPE_LOG_ERROR, @"[native-audio] Tap: Device start failed (%d)", (int)status);
+            voxtape_tap_teardown();
+            return -13;
+        }
+
+        VOXTAPE_LOG(VOXTAPE_LOG_INFO, @"[native-audio] Tap: Capture started (%u ch, %u Hz)", channels, sampleRate);
+        return 0;
+    }
+}
+
+/// Start capturing the system mix (minus this process) through a CoreAudio
+/// process tap. Honors the realtime setting from voxtape_sck_configure.
+/// Returns 0 on success, negative on error (-9: process taps unavailable,
+/// -11: tap creation failed, -12: aggregate device creation failed,
+/// -13: IOProc failed to start).
+int voxtape_tap_start_capture(voxtape_audio_callback_t callback, void *user_data) {
+    return voxtape_tap_start(nil, callback, user_data);
+}
+
+/// Stop tap capture and destroy the tap and its aggregate device.
+void voxtape_tap_stop_capture(void) {
+    if (g_tap_id == kAudioObjectUnknown) return;
+    voxtape_tap_teardown();
+    VOXTAPE_LOG(VOXTAPE_LOG_INFO, @"[native-audio] Tap: Capture stopped");
+}
+
+// ── Device capture (CoreAudio HAL) ─────────────────────────────────────────
+//
+// Records one chosen device instead of the system mix. Devices with input
+// streams (loopback drivers such as BlackHole or Loopback, aggregate devices)
+// are read directly through an IOProc, since their input mirrors what's routed
+// into them. Output-only devices are captured with a process tap on that
+// device, which needs macOS 14.2+.
+
+static AudioObjectID g_device_id = kAudioObjectUnknown;
+static AudioDeviceIOProcID g_device_proc = NULL;
+
+/// Channels across all of a device's streams in `scope` (input or output)
+static UInt32 voxtape_device_channel_count(AudioObjectID device, AudioObjectPropertyScope scope) {
+    AudioObjectPropertyAddress addr = {
+        kAudioDevicePropertyStreamConfiguration, scope, kAudioObjectPropertyElementMain
+    };
+    UInt32 size = 0;
+    if (AudioObjectGetPropertyDataSize(device, &addr, 0, NULL, &size) != noErr || size == 0) return 0;
+    AudioBufferList *buffers = (AudioBufferList *)malloc(size);
+    if (!buffers) return 0;
+    UInt32 channels = 0;
+    if (AudioObjectGetPropertyData(device, &addr, 0, NULL, &size, buffers) == noErr) {
+        for (UInt32 i = 0; i < buffers->mNumberBuffers; i++) channels += buffers->mBuffers[i].mNumberChannels;
+    }
+    free(buffers);
+    return channels;
+}
+
+/// A global string property of a device (name, UID), or nil
+static NSString *voxtape_device_string(AudioObjectID device, AudioObjectPropertySelector selector) {
+    AudioObjectPropertyAddress addr = {selector, kAudioObjectPropertyScopeGlobal, kAudioObjectPropertyElementMain};
+    CFStringRef value = NULL;
+    UInt32 size = sizeof(value);
+    if (AudioObjectGetPropertyData(device, &addr, 0, NULL, &size, &value) != noErr || !value) return nil;
+    return (__bridge_transfer NSString *)value;
+}
+
+/// A global UInt32 property of an object, or 0 if it can't be read
+static UInt32 voxtape_object_uint32(AudioObjectID object, AudioObjectPropertySelector selector) {
+    AudioObjectPropertyAddress addr = {selector, kAudioObjectPropertyScopeGlobal, kAudioObjectPropertyElementMain};
+    UInt32 value = 0;
+    UInt32 size = sizeof(value);
+    if (AudioObjectGetPropertyData(object, &addr, 0, NULL, &size, &value) != noErr) return 0;
+    return value;
+}
+
+/// One audio device, as listed by voxtape_list_audio_devices
+typedef struct {
+    uint32_t deviceId;
+    const char *uid;
+    const char *name;
+    uint32_t transport;
+    uint32_t inputChannels;
+    uint32_t outputChannels;
+    uint32_t sampleRate;
+    int isDefaultInput;
+    int isDefaultOutput;
+} VoxTapeDeviceInfo;
+
+/// Every audio device the HAL knows about. Caller frees with
+/// voxtape_free_audio_devices. Returns NULL (count 0) if there are none.
+VoxTapeDeviceInfo *voxtape_list_audio_devices(int *outCount) {
+    @autoreleasepool {
+        *outCount = 0;
+        AudioObjectPropertyAddress addr = {
+            kAudioHardwarePropertyDevices, kAudioObjectPropertyScopeGlobal, kAudioObjectPropertyElementMain
+        };
+        UInt32 size = 0;
+        if (AudioObjectGetPropertyDataSize(kAudioObjectSystemObject, &addr, 0, NULL, &size) != noErr || size == 0) {
+            return NULL;
+        }
+        AudioDeviceID *devices = (AudioDeviceID *)malloc(size);
+        if (!devices) return NULL;
+        if (AudioObjectGetPropertyData(kAudioObjectSystemObject, &addr, 0, NULL, &size, devices) != noErr) {
+            free(devices);
+            return NULL;
+        }
+        int count = (int)(size / sizeof(AudioDeviceID));
+        AudioDeviceID defaultInput = voxtape_object_uint32(kAudioObjectSystemObject,
+                                                           kAudioHardwarePropertyDefaultInputDevice);
+        AudioDeviceID defaultOutput = voxtape_object_uint32(kAudioObjectSystemObject,
+                                                            kAudioHardwarePropertyDefaultOutputDevice);
+
+        VoxTapeDeviceInfo *result = (VoxTapeDeviceInfo *)calloc(count, sizeof(VoxTapeDeviceInfo));
+        if (!result) {
+            free(devices);
+            return NULL;
+        }
+        for (int i = 0; i < count; i++) {
+            AudioDeviceID device = devices[i];
+            NSString *uid = voxtape_device_string(device, kAudioDevicePropertyDeviceUID);
+            NSString *name = voxtape_device_string(device, kAudioObjectPropertyName);
+            result[i].deviceId = device;
+            result[i].uid = strdup(uid.UTF8String ?: "");
+            result[i].name = strdup(name.UTF8String ?: "");
+            result[i].transport = voxtape_object_uint32(device, kAudioDevicePropertyTransportType);
+            result[i].inputChannels = voxtape_device_channel_count(device, kAudioObjectPropertyScopeInput);
+            result[i].outputChannels = voxtape_device_channel_count(device, kAudioObjectPropertyScopeOutput);
+
+            Float64 rate = 0;
+            AudioObjectPropertyAddress rateAddr = {
+                kAudioDevicePropertyNominalSampleRate, kAudioObjectPropertyScopeGlobal, kAudioObjectPropertyElementMain
+            };
+            UInt32 rateSize = sizeof(rate);
+            if (AudioObjectGetPropertyData(device, &rateAddr, 0, NULL, &rateSize, &rate) == noErr) {
+                result[i].sampleRate = (uint32_t)rate;
+            }
+            result[i].isDefaultInput = device == defaultInput;
+            result[i].isDefaultOutput = device == defaultOutput;
+        }
+        free(devices);
+        *outCount = count;
+        return result;
+    }
+}
+
+/// Free the memory allocated by voxtape_list_audio_devices
+void voxtape_free_audio_devices(VoxTapeDeviceInfo *devices, int count) {
+    if (!devices) return;
+    for (int i = 0; i < count; i++) {
+        free((void *)devices[i].uid);
+        free((void *)devices[i].name);
+    }
+    free(devices);
+}
+
+/// Tear down the direct device IOProc, if any.
+static void voxtape_device_teardown(void) {
+    if (g_device_proc) {
+        AudioDeviceStop(g_device_id, g_device_proc);
+        AudioDeviceDestroyIOProcID(g_device_id, g_device_proc);
+        g_device_proc = NULL;
+    }
+    g_device_id = kAudioObjectUnknown;
+}
+
+/// Start capturing the device with `device_id` (an AudioObjectID from
+/// voxtape_list_audio_devices): its input streams if it has any, else what
+/// plays through it, via a process tap. Honors the realtime setting from
+/// voxtape_sck_configure. Returns 0 on success, negative on error
+/// (-1: capture already active, -20: no such device, -21: the device has no
+/// streams, -9/-11/-12/-13 as for voxtape_tap_start_capture).
+int voxtape_device_start_capture(uint32_t device_id, voxtape_audio_callback_t callback, void *user_data) {
+    @autoreleasepool {
+        if (g_device_id != kAudioObjectUnknown || g_tap_id != kAudioObjectUnknown) {
+            VOXTAPE_LOG(VOXTAPE_LOG_WARN, @"[native-audio] Device capture already active");
+            return -1;
+        }
+        NSString *uid = voxtape_device_string(device_id, kAudioDevicePropertyDeviceUID);
+        if (!uid || !voxtape_object_uint32(device_id, kAudioDevicePropertyDeviceIsAlive)) return -20;
+
+        UInt32 inputChannels = voxtape_device_channel_count(device_id, kAudioObjectPropertyScopeInput);
+        if (inputChannels == 0) {
+            if (voxtape_device_channel_count(device_id, kAudioObjectPropertyScopeOutput) == 0) return -21;
+            g_device_id = device_id;
+            int status = voxtape_tap_start(uid, callback, user_data);
+            if (status != 0) g_device_id = kAudioObjectUnknown;
+            return status;
+        }
+
+        Float64 rate = 0;
+        AudioObjectPropertyAddress rateAddr = {
+            kAudioDevicePropertyNominalSampleRate, kAudioObjectPropertyScopeGlobal, kAudioObjectPropertyElementMain
+        };
+        UInt32 size = sizeof(rate);
+        if (AudioObjectGetPropertyData(device_id, &rateAddr, 0, NULL, &size, &rate) != noErr || rate <= 0) {
+            return -20;
+        }
+
+        uint32_t channels = inputChannels;
+        uint32_t sampleRate = (uint32_t)rate;
+        BOOL realtime = g_stream_config.realtime_priority != 0;
+        dispatch_queue_attr_t attr = dispatch_queue_attr_make_with_qos_class(
+            DISPATCH_QUEUE_SERIAL, QOS_CLASS_USER_INTERACTIVE, 0
+        );
+        dispatch_queue_t queue = dispatch_queue_create("com.voxtape.device", attr);
+
+        g_device_id = device_id;
+        OSStatus status = AudioDeviceCreateIOProcIDWithBlock(&g_device_proc, device_id, queue,
+            ^(const AudioTimeStamp *inNow, const AudioBufferList *inInputData,
+              const AudioTimeStamp *inInputTime, AudioBufferList *outOutputData,
+              const AudioTimeStamp *inOutputTime) {
+            if (realtime) voxtape_promote_current_thread();
+            voxtape_deliver_io_input(inInputData, inInputTime, channels, sampleRate, callback, user_data);
+        });
+        if (status != noErr) {
+            VOXTAPE_LOG(VOXTAPE_LOG_ERROR, @"[native-audio] Device: IOProc creation failed (%d)", (int)status);
+            g_device_proc = NULL;
+            voxtape_device_teardown();
+            return -13;
+        }
+
+        status = AudioDeviceStart(device_id, g_device_proc);
+        if (status != noErr) {
+            VOXTAPE_LOG(VOXTAPE_LOG_ERROR, @"[native-audio] Device: Device start failed (%d)", (int)status);
+            voxtape_device_teardown();
+            return -13;
+        }
+
+        VOXTAPE_LOG(VOXTAPE_LOG_INFO, @"[native-audio] Device: Capture of %@ started (%u ch, %u Hz)", uid, channels, sampleRate);
+        return 0;
+    }
+}
+
+/// Stop device capture, whichever way it was started.
+void voxtape_device_stop_capture(void) {
+    if (g_device_id == kAudioObjectUnknown) return;
+    if (g_device_proc) {
+        voxtape_device_teardown();
+    } else {
+        voxtape_tap_teardown();
+        g_device_id = kAudioObjectUnknown;
+    }
+    VOXTAPE_LOG(VOXTAPE_LOG_INFO, @"[native-audio] Device: Capture stopped");
+}
+
+// ── Default output device changes ──────────────────────────────────────────
+
+typedef void (*voxtape_output_change_callback_t)(void *user_data);
+
+static AudioObjectPropertyListenerBlock g_output_listener = nil;
+static dispatch_queue_t g_output_listener_queue = nil;
+
+static const AudioObjectPropertyAddress kVoxTapeDefaultOutputAddress = {
+    kAudioHardwarePropertyDefaultOutputDevice, kAudioObjectPropertyScopeGlobal, kAudioObjectPropertyElementMain
+};
+
+/// Stop calling back on default output device changes.
+void voxtape_unwatch_default_output(void) {
+    if (!g_output_listener) return;
+    AudioObjectRemovePropertyListenerBlock(kAudioObjectSystemObject, &kVoxTapeDefaultOutputAddress,
+                                           g_output_listener_queue, g_output_listener);
+    g_output_listener = nil;
+    g_output_listener_queue = nil;
+}
+
+/// Call `callback` on a private serial queue whenever the default output
+/// device changes, replacing any previous watch. Returns 0 on success or
+/// the CoreAudio status.
+int voxtape_watch_default_output(voxtape_output_change_callback_t callback, void *user_data) {
+    voxtape_unwatch_default_output();
+    dispatch_queue_t queue = dispatch_queue_create("com.voxtape.route", DISPATCH_QUEUE_SERIAL);
+    AudioObjectPropertyListenerBlock listener = ^(UInt32 count, const AudioObjectPropertyAddress *addresses) {
+        callback(user_data);
+    };
+    OSStatus status = AudioObjectAddPropertyListenerBlock(kAudioObjectSystemObject, &kVoxTapeDefaultOutputAddress,
+                                                          queue, listener);
+    if (status != noErr) return (int)status;
+    g_output_listener = listener;
+    g_output_listener_queue = queue;
+    return 0;
+}
+
+/// Rebuild the global tap and its aggregate device, which is clocked by the
+/// output device that was the default when it was created. Returns 0 on
+/// success, -1 if no global tap is running, else as voxtape_tap_start_capture.
+int voxtape_tap_restart_capture(void) {
+    if (g_tap_id == kAudioObjectUnknown || g_device_id != kAudioObjectUnknown) return -1;
+    voxtape_audio_callback_t callback = g_tap_callback;
+    void *user_data = g_tap_user_data;
+    voxtape_tap_teardown();
+    return voxtape_tap_start(nil, callback, user_data);
+}
+
+/// Host clock timebase: ticks * numer / denom = nanoseconds
+void voxtape_host_timebase(uint32_t *numer, uint32_t *denom) {
+    mach_timebase_info_data_t info;
+    mach_timebase_info(&info);
+    *numer = info.numer;
+    *denom = info.denom;
+}
+
+// ── Encoded file sink (ExtAudioFile) ───────────────────────────────────────
+
+/// Store the non-NULL tags in the file's info dictionary (a CAF info chunk,
+/// or iTunes-style atoms in .m4a). Best effort: a container that rejects
+/// the dictionary still records audio.
+static void voxtape_encoder_set_info(ExtAudioFileRef file, const char *title, const char *artist,
+                                     const char *comment) {
+    NSMutableDictionary *info = [NSMutableDictionary dictionary];
+    if (title) info[@kAFInfoDictionary_Title] = [NSString stringWithUTF8String:title];
+    if (artist) info[@kAFInfoDictionary_Artist] = [NSString stringWithUTF8String:artist];
+    if (comment) info[@kAFInfoDictionary_Comments] = [NSString stringWithUTF8String:comment];
+    if (info.count == 0) return;
+
+    AudioFileID audioFile = NULL;
+    UInt32 size = sizeof(audioFile);
+    OSStatus status = ExtAudioFileGetProperty(file, kExtAudioFileProperty_AudioFile, &size, &audioFile);
+    if (status != noErr || !audioFile) return;
+
+    CFDictionaryRef dict = (__bridge CFDictionaryRef)info;
+    status = AudioFileSetProperty(audioFile, kAudioFilePropertyInfoDictionary, sizeof(dict), &dict);
+    if (status != noErr) {
+        VOXTAPE_LOG(VOXTAPE_LOG_ERROR, @"[native-audio] Encoder: Container rejected metadata (status=%d)", (int)status);
+    }
+}
+
+/// Open an encoded output file fed with 16kHz mono Int16 PCM.
+/// format: 0 = AAC in .m4a, 1 = Opus in .caf.
+/// Returns an opaque ExtAudioFileRef, or NULL on error.
+/// title/artist/comment: tags for the file's info dictionary, or NULL.
+void *voxtape_encoder_open(const char *path, int format, const char *title, const char *artist,
+                           const char *comment) {
+    @autoreleasepool {
+        if (!path) return NULL;
+        NSURL *url = [NSURL fileURLWithPath:[NSString stringWithUTF8String:path]];
+
+        AudioStreamBasicDescription dst = {0};
+        dst.mSampleRate = 16000;
+        dst.mChannelsPerFrame = 1;
+        AudioFileTypeID fileType;
+        if (format == 1) {
+            dst.mFormatID = kAudioFormatOpus;
+            dst.mFramesPerPacket = 320; // 20ms at 16kHz
+            fileType = kAudioFileCAFType;
+        } else {
+            dst.mFormatID = kAudioFormatMPEG4AAC;
+            fileType = kAudioFileM4AType;
+        }
+
+        ExtAudioFileRef file = NULL;
+        OSStatus status = ExtAudioFileCreateWithURL((__bridge CFURLRef)url, fileType, &dst, NULL,
+                                                    kAudioFileFlags_EraseFile, &file);
+        if (status != noErr || !file) {
+            VOXTAPE_LOG(VOXTAPE_LOG_ERROR, @"[native-audio] Encoder: Failed to create %s (status=%d)", path, (int)status);
+            return NULL;
+        }
+
+        // Client side: what we hand to ExtAudioFileWrite
+        AudioStreamBasicDescription client = {0};
+        client.mSampleRate = 16000;
+        client.mFormatID = kAudioFormatLinearPCM;
+        client.mFormatFlags = kAudioFormatFlagIsSignedInteger | kAudioFormatFlagIsPacked;
+        client.mBytesPerPacket = 2;
+        client.mFramesPerPacket = 1;
+        client.mBytesPerFrame = 2;
+        client.mChannelsPerFrame = 1;
+        client.mBitsPerChannel = 16;
+
+        status = ExtAudioFileSetProperty(file, kExtAudioFileProperty_ClientDataFormat, sizeof(client), &client);
+        if (status != noErr) {
+            VOXTAPE_LOG(VOXTAPE_LOG_ERROR, @"[native-audio] Encoder: Failed to set client format (status=%d)", (int)status);
+            ExtAudioFileDispose(file);
+            return NULL;
+        }
+
+        voxtape_encoder_set_info(file, title, artist, comment);
+
+        VOXTAPE_LOG(VOXTAPE_LOG_INFO, @"[native-audio] Encoder: Writing %s to %s", format == 1 ? "Opus" : "AAC", path);
+        return file;
+    }
+}
+
+/// Whether the system has an encoder for `format` (0: AAC, 1: Opus), without
+/// creating a file. Returns 1 if so, 0 if not.
+int voxtape_encoder_supported(int format) {
+    AudioFormatID formatID = format == 1 ? kAudioFormatOpus : kAudioFormatMPEG4AAC;
+    UInt32 size = 0;
+    OSStatus status = AudioFormatGetPropertyInfo(kAudioFormatProperty_Encoders, sizeof(formatID), &formatID, &size);
+    return status == noErr && size > 0 ? 1 : 0;
+}
+
+/// Encode `count` mono Int16 samples. Returns the OSStatus (0 on success).
+int voxtape_encoder_write(void *handle, const int16_t *samples, uint32_t count) {
+    if (!handle || !samples || count == 0) return 0;
+    AudioBufferList abl;
+    abl.mNumberBuffers = 1;
+    abl.mBuffers[0].mNumberChannels = 1;
+    abl.mBuffers[0].mDataByteSize = count * sizeof(int16_t);
+    abl.mBuffers[0].mData = (void *)samples;
+    return (int)ExtAudioFileWrite((ExtAudioFileRef)handle, count, &abl);
+}
+
+/// Flush pending packets and close the file.
+void voxtape_encoder_close(void *handle) {
+    if (!handle) return;
+    ExtAudioFileDispose((ExtAudioFileRef)handle);
+    VOXTAPE_LOG(VOXTAPE_LOG_INFO, @"[native-audio] Encoder: Closed");
+}
+
+// ── Free disk space ────────────────────────────────────────────────────────
+
+/// Bytes available on the volume holding `path` (a directory), counting
+/// purgeable space the system would free for a user-initiated write, as
+/// Finder reports it. Falls back to the plain free count; -1 if unknown.
+int64_t voxtape_available_disk_bytes(const char *path) {
+    @autoreleasepool {
+        if (!path) return -1;
+        NSURL *url = [NSURL fileURLWithPath:[NSString stringWithUTF8String:path] isDirectory:YES];
+        NSNumber *capacity = nil;
+        if ([url getResourceValue:&capacity forKey:NSURLVolumeAvailableCapacityForImportantUsageKey error:nil] &&
+            capacity) {
+            return capacity.longLongValue;
+        }
+        if ([url getResourceValue:&capacity forKey:NSURLVolumeAvailableCapacityKey error:nil] && capacity) {
+            return capacity.longLongValue;
+        }
+        return -1;
+    }
+}
+
+// ── In-memory Opus packet encoder (AudioConverter) ─────────────────────────
+
+/// PCM frames per Opus packet (20ms at 16kHz)
+#define VOXTAPE_OPUS_FRAMES_PER_PACKET 320
+
+typedef struct {
+    const int16_t *pcm;
+    uint32_t frames;
+    AudioStreamPacketDescription packetDesc;
+} VoxTapeOpusInput;
+
+static AudioStreamBasicDescription voxtape_pcm16_mono_format(void) {
+    AudioStreamBasicDescription fmt = {0};
+    fmt.mSampleRate = 16000;
+    fmt.mFormatID = kAudioFormatLinearPCM;
+    fmt.mFormatFlags = kAudioFormatFlagIsSignedInteger | kAudioFormatFlagIsPacked;
+    fmt.mBytesPerPacket = 2;
+    fmt.mFramesPerPacket = 1;
+    fmt.mBytesPerFrame = 2;
+    fmt.mChannelsPerFrame = 1;
+    fmt.mBitsPerChannel = 16;
+    return fmt;
+}
+
+static AudioStreamBasicDescription voxtape_opus_format(void) {
+    AudioStreamBasicDescription fmt = {0};
+    fmt.mSampleRate = 16000;
+    fmt.mFormatID = kAudioFormatOpus;
+    fmt.mChannelsPerFrame = 1;
+    fmt.mFramesPerPacket = VOXTAPE_OPUS_FRAMES_PER_PACKET;
+    return fmt;
+}
+
+static OSStatus voxtape_opus_input_proc(AudioConverterRef converter,
+                                        UInt32 *ioNumberDataPackets,
+                                        AudioBufferList *ioData,
+                                        AudioStreamPacketDescription **outDataPacketDescription,
+                                        void *inUserData) {
+    VoxTapeOpusInput *input = (VoxTapeOpusInput *)inUserData;
+    if (input->frames == 0) {
+        // No more input for this call; the converter keeps its state
+        *ioNumberDataPackets = 0;
+        return 1;
+    }
+    ioData->mBuffers[0].mData = (void *)input->pcm;
+    ioData->mBuffers[0].mDataByteSize = input->frames * sizeof(int16_t);
+    ioData->mBuffers[0].mNumberChannels = 1;
+    *ioNumberDataPackets = input->frames;
+    input->frames = 0;
+    return noErr;
+}
+
+/// Create an encoder for 16kHz mono Int16 → Opus packets. Returns NULL on error.
+void *voxtape_opus_encoder_new(void) {
+    AudioStreamBasicDescription src = voxtape_pcm16_mono_format();
+    AudioStreamBasicDescription dst = voxtape_opus_format();
+    AudioConverterRef converter = NULL;
+    OSStatus status = AudioConverterNew(&src, &dst, &converter);
+    if (status != noErr || !converter) {
+        VOXTAPE_LOG(VOXTAPE_LOG_ERROR, @"[native-audio] Opus: Failed to create converter (status=%d)", (int)status);
+        return NULL;
+    }
+    return converter;
+}
+
+/// Encode exactly VOXTAPE_OPUS_FRAMES_PER_PACKET samples into at most one packet.
+/// `*out_len` is 0 if the encoder is still priming. Returns the OSStatus.
+int voxtape_opus_encode_packet(void *encoder, const int16_t *pcm, uint8_t *out,
+                               uint32_t out_capacity, uint32_t *out_len) {
+    *out_len = 0;
+    if (!encoder) return -1;
+
+    VoxTapeOpusInput input = { pcm, VOXTAPE_OPUS_FRAMES_PER_PACKET, {0} };
+    AudioBufferList outList;
+    outList.mNumberBuffers = 1;
+    outList.mBuffers[0].mNumberChannels = 1;
+    outList.mBuffers[0].mDataByteSize = out_capacity;
+    outList.mBuffers[0].mData = out;
+
+    UInt32 packets = 1;
+    AudioStreamPacketDescription desc = {0};
+    OSStatus status = AudioConverterFillComplexBuffer((AudioConverterRef)encoder, voxtape_opus_input_proc,
+                                                      &input, &packets, &outList, &desc);
+    // Status 1 is our own "input exhausted" marker, not a failure
+    if (status != noErr && status != 1) return (int)status;
+    if (packets > 0) *out_len = outList.mBuffers[0].mDataByteSize;
+    return 0;
+}
+
+void voxtape_opus_encoder_free(void *encoder) {
+    if (encoder) AudioConverterDispose((AudioConverterRef)encoder);
+}
+
+/// Write Opus packets to a .caf file. `packet_sizes[i]` is the byte length of
+/// packet i in `data`. Returns 0 on success, an OSStatus otherwise.
+int voxtape_opus_write_caf(const char *path, void *encoder, const uint8_t *data,
+                           const uint32_t *packet_sizes, uint32_t packet_count) {
+    @autoreleasepool {
+        NSURL *url = [NSURL fileURLWithPath:[NSString stringWithUTF8String:path]];
+        AudioStreamBasicDescription fmt = voxtape_opus_format();
+        AudioFileID file = NULL;
+        OSStatus status = AudioFileCreateWithURL((__bridge CFURLRef)url, kAudioFileCAFType, &fmt,
+                                                 kAudioFileFlags_EraseFile, &file);
+        if (status != noErr) return (int)status;
+
+        // Decoder configuration lives in the converter's magic cookie
+        UInt32 cookieSize = 0;
+        if (encoder && AudioConverterGetPropertyInfo((AudioConverterRef)encoder,
+                kAudioConverterCompressionMagicCookie, &cookieSize, NULL) == noErr && cookieSize > 0) {
+            void *cookie = malloc(cookieSize);
+            if (cookie && AudioConverterGetProperty((AudioConverterRef)encoder,
+                    kAudioConverterCompressionMagicCookie, &cookieSize, cookie) == noErr) {
+                AudioFileSetProperty(file, kAudioFilePropertyMagicCookieData, cookieSize, cookie);
+            }
+            free(cookie);
+        }
+
+        AudioStreamPacketDescription *descs =
+            (AudioStreamPacketDescription *)calloc(packet_count ? packet_count : 1, sizeof(AudioStreamPacketDescription));
+        UInt32 totalBytes = 0;
+        for (uint32_t i = 0; i < packet_count; i++) {
+            descs[i].mStartOffset = totalBytes;
+            descs[i].mDataByteSize = packet_sizes[i];
+            descs[i].mVariableFramesInPacket = 0;
+            totalBytes += packet_sizes[i];
+        }
+
+        UInt32 ioPackets = packet_count;
+        if (packet_count > 0) {
+            status = AudioFileWritePackets(file, false, totalBytes, descs, 0, &ioPackets, data);
+        }
+        free(descs);
+        AudioFileClose(file);
+        return (int)status;
+    }
+}
+
+// ── Default output device (CoreAudio HAL) ──────────────────────────────────
+
+/// Copy the default output device's name (UTF-8) into `name` and return its
+/// transport type (kAudioDeviceTransportType*), or 0 if there is no device.
+/// An unreadable or unknown transport is returned as 1.
+uint32_t voxtape_default_output_device(char *name, uint32_t name_capacity) {
+    AudioObjectPropertyAddress addr = {
+        kAudioHardwarePropertyDefaultOutputDevice,
+        kAudioObjectPropertyScopeGlobal,
+        kAudioObjectPropertyElementMain
+    };
+    AudioDeviceID device = kAudioObjectUnknown;
+    UInt32 size = sizeof(device);
+    if (AudioObjectGetPropertyData(kAudioObjectSystemObject, &addr, 0, NULL, &size, &device) != noErr
+        || device == kAudioObjectUnknown) {
+        return 0;
+    }
+
+    if (name && name_capacity > 0) {
+        name[0] = '\0';
+        CFStringRef cfName = NULL;
+        addr.mSelector = kAudioObjectPropertyName;
+        size = sizeof(cfName);
+        if (AudioObjectGetPropertyData(device, &addr, 0, NULL, &size, &cfName) == noErr && cfName) {
+            CFStringGetCString(cfName, name, name_capacity, kCFStringEncodingUTF8);
+            CFRelease(cfName);
+        }
+    }
+
+    UInt32 transport = 0;
+    addr.mSelector = kAudioDevicePropertyTransportType;
+    size = sizeof(transport);
+    if (AudioObjectGetPropertyData(device, &addr, 0, NULL, &size, &transport) != noErr) {
+        return 1;
+    }
+    return transport ? transport : 1;
 }
 
 // ── Meeting App Detection (NSWorkspace) ─────────────────────────────────────
 
-#import <AppKit/AppKit.h>
 
 /// Structure to hold info about a running meeting app
 typedef struct {
     const char *bundleId;
     const char *name;
+    /// The name again as UTF-16, NSString's own encoding, so it survives
+    /// even where UTF8String can't convert it (NULL if unavailable)
+    const unichar *nameUtf16;
+    int nameUtf16Length;
     int pid;
     int isActive;
 } MeetingAppInfo;
 
+/// Copy `string` as UTF-16 into `info`; freed with voxtape_free_meeting_apps
+static void voxtape_set_name_utf16(MeetingAppInfo *info, NSString *string) {
+    NSUInteger length = string.length;
+    unichar *chars = (unichar *)malloc(sizeof(unichar) * (length ?: 1));
+    if (chars && length) [string getCharacters:chars range:NSMakeRange(0, length)];
+    info->nameUtf16 = chars;
+    info->nameUtf16Length = chars ? (int)length : 0;
+}
+
 /// Known meeting app bundle IDs
 static NSArray<NSString *> *getMeetingBundleIds(void) {
     return @[
@@ -369,6 +1795,7 @@ MeetingAppInfo *voxtape_get_running_meeting_apps(int *outCount) {
             // Copy localized name
             const char *nameCStr = [app.localizedName UTF8String];
             result[i].name = nameCStr ? strdup(nameCStr) : strdup("");
+            voxtape_set_name_utf16(&result[i], app.localizedName);
 
             result[i].pid = (int)app.processIdentifier;
             result[i].isActive = (app == activeApp) ? 1 : 0;
@@ -384,6 +1811,106 @@ void voxtape_free_meeting_apps(MeetingAppInfo *apps, int count) {
     for (int i = 0; i < count; i++) {
         free((void *)apps[i].bundleId);
         free((void *)apps[i].name);
+        free((void *)apps[i].nameUtf16);
     }
     free(apps);
 }
+
+// ── Per-app audio activity (CoreAudio process objects) ─────────────────────
+
+/// kAudioProcessPropertyIsRunningOutput, spelled out so older SDKs still compile
+static const AudioObjectPropertySelector kVoxTapeProcessPropertyIsRunningOutput = 'piro';
+
+/// Whether a CoreAudio process object is running audio output
+static BOOL voxtape_process_running_output(AudioObjectID process) {
+    AudioObjectPropertyAddress runningAddr = {
+        kVoxTapeProcessPropertyIsRunningOutput, kAudioObjectPropertyScopeGlobal, kAudioObjectPropertyElementMain
+    };
+    UInt32 running = 0;
+    UInt32 size = sizeof(running);
+    return AudioObjectGetPropertyData(process, &runningAddr, 0, NULL, &size, &running) == noErr && running;
+}
+
+/// Whether any process with bundle ID `bundle_id`, or one of its helpers
+/// (`bundle_id.*`, where browsers and Electron apps actually play audio), is
+/// running audio output right now.
+/// Returns 1 if so, 0 if not, -1 if the HAL can't tell (before macOS 14).
+int voxtape_is_app_producing_audio(const char *bundle_id) {
+    @autoreleasepool {
+        if (!bundle_id) return -1;
+        NSArray<NSNumber *> *processes =
+            voxtape_process_objects_for_app([NSString stringWithUTF8String:bundle_id]);
+        if (!processes) return -1;
+
+        for (NSNumber *process in processes) {
+            if (voxtape_process_running_output(process.unsignedIntValue)) return 1;
+        }
+        return 0;
+    }
+}
+
+/// Whether an app with bundle ID `bundle_id` is running. Returns 1 if so, 0 if not.
+int voxtape_is_app_running(const char *bundle_id) {
+    @autoreleasepool {
+        if (!bundle_id) return 0;
+        NSString *bundle = [NSString stringWithUTF8String:bundle_id];
+        return [NSRunningApplication runningApplicationsWithBundleIdentifier:bundle].count > 0 ? 1 : 0;
+    }
+}
+
+/// Apps ScreenCaptureKit can capture that are playing audio right now
+/// (counting their helper processes), as MeetingAppInfo with isActive set
+/// for the frontmost app. Caller frees with voxtape_free_meeting_apps.
+/// Returns NULL (count 0) without Screen Recording permission, or if the
+/// HAL or SCK can't tell.
+MeetingAppInfo *voxtape_list_capturable_audio_apps(int *outCount) {
+    @autoreleasepool {
+        *outCount = 0;
+        if (!voxtape_sck_available() || !CGPreflightScreenCaptureAccess()) return NULL;
+
+        NSMutableArray<NSString *> *playing = [NSMutableArray array];
+        for (NSNumber *process in voxtape_process_objects() ?: @[]) {
+            NSString *bundle = voxtape_process_bundle(process.unsignedIntValue);
+            if (bundle && voxtape_process_running_output(process.unsignedIntValue)) [playing addObject:bundle];
+        }
+        if (playing.count == 0) return NULL;
+
+        __block NSArray<SCRunningApplication *> *apps = nil;
+        dispatch_semaphore_t sem = dispatch_semaphore_create(0);
+        [SCShareableContent getShareableContentExcludingDesktopWindows:NO
+                                                    onScreenWindowsOnly:NO
+                                                      completionHandler:^(SCShareableContent *content, NSError *error) {
+            if (!error) apps = content.applications;
+            dispatch_semaphore_signal(sem);
+        }];
+        if (dispatch_semaphore_wait(sem, dispatch_time(DISPATCH_TIME_NOW, 5LL * NSEC_PER_SEC)) != 0 || !apps) {
+            return NULL;
+        }
+
+        pid_t ownPid = getpid();
+        NSMutableArray<SCRunningApplication *> *audible = [NSMutableArray array];
+        for (SCRunningApplication *app in apps) {
+            if (app.processID == ownPid || app.bundleIdentifier.length == 0) continue;
+            for (NSString *bundle in playing) {
+                if (voxtape_bundle_matches(bundle, app.bundleIdentifier)) {
+                    [audible addObject:app];
+                    break;
+                }
+            }
+        }
+        if (audible.count == 0) return NULL;
+
+        pid_t frontmost = [NSWorkspace sharedWorkspace].frontmostApplication.processIdentifier;
+        MeetingAppInfo *result = (MeetingAppInfo *)malloc(sizeof(MeetingAppInfo) * audible.count);
+        for (NSUInteger i = 0; i < audible.count; i++) {
+            SCRunningApplication *app = audible[i];
+            result[i].bundleId = strdup(app.bundleIdentifier.UTF8String ?: "");
+            result[i].name = strdup(app.applicationName.UTF8String ?: "");
+            voxtape_set_name_utf16(&result[i], app.applicationName);
+            result[i].pid = app.processID;
+            result[i].isActive = app.processID == frontmost ? 1 : 0;
+        }
+        *outCount = (int)audible.count;
+        return result;
+    }
+}
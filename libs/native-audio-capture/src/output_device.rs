@@ -0,0 +1,124 @@
+//! Default output device diagnostics
+//!
+//! ScreenCaptureKit taps audio on its way to the output device. Some routes
+//! (Bluetooth headsets, AirPlay) hand audio off before that point, so capture
+//! runs but records silence. This reports the current route and whether it's
+//! known to be capturable.
+
+use std::os::raw::c_char;
+
+use napi_derive::napi;
+
+extern "C" {
+    fn voxtape_default_output_device(name: *mut c_char, name_capacity: u32) -> u32;
+}
+
+/// CoreAudio transport types (kAudioDeviceTransportType*), as FourCCs
+const fn fourcc(code: &[u8; 4]) -> u32 {
+    u32::from_be_bytes(*code)
+}
+
+const TRANSPORT_BUILT_IN: u32 = fourcc(b"bltn");
+const TRANSPORT_USB: u32 = fourcc(b"usb ");
+const TRANSPORT_HDMI: u32 = fourcc(b"hdmi");
+const TRANSPORT_DISPLAY_PORT: u32 = fourcc(b"dprt");
+const TRANSPORT_THUNDERBOLT: u32 = fourcc(b"thun");
+const TRANSPORT_PCI: u32 = fourcc(b"pci ");
+const TRANSPORT_FIREWIRE: u32 = fourcc(b"1394");
+const TRANSPORT_BLUETOOTH: u32 = fourcc(b"blue");
+const TRANSPORT_BLUETOOTH_LE: u32 = fourcc(b"blea");
+const TRANSPORT_AIRPLAY: u32 = fourcc(b"airp");
+const TRANSPORT_VIRTUAL: u32 = fourcc(b"virt");
+const TRANSPORT_AGGREGATE: u32 = fourcc(b"grup");
+
+/// The current default output device
+#[napi(object)]
+pub struct OutputDeviceInfo {
+    /// Device name as shown in Sound settings
+    pub name: String,
+    /// "builtin", "usb", "hdmi", "displayport", "thunderbolt", "pci",
+    /// "firewire", "bluetooth", "airplay", "virtual", "aggregate" or "unknown"
+    pub transport: String,
+    /// Whether system audio capture is known to work on this route
+    /// (None if not known either way)
+    pub capturable: Option<bool>,
+}
+
+pub(crate) fn transport_name(transport: u32) -> &'static str {
+    match transport {
+        TRANSPORT_BUILT_IN => "builtin",
+        TRANSPORT_USB => "usb",
+        TRANSPORT_HDMI => "hdmi",
+        TRANSPORT_DISPLAY_PORT => "displayport",
+        TRANSPORT_THUNDERBOLT => "thunderbolt",
+        TRANSPORT_PCI => "pci",
+        TRANSPORT_FIREWIRE => "firewire",
+        TRANSPORT_BLUETOOTH | TRANSPORT_BLUETOOTH_LE => "bluetooth",
+        TRANSPORT_AIRPLAY => "airplay",
+        TRANSPORT_VIRTUAL => "virtual",
+        TRANSPORT_AGGREGATE => "aggregate",
+        _ => "unknown",
+    }
+}
+
+/// Wired routes are tapped reliably; Bluetooth and AirPlay can hand audio
+/// off before the tap. Virtual and aggregate devices depend on their driver.
+fn is_capturable(transport: u32) -> Option<bool> {
+    match transport {
+        TRANSPORT_BUILT_IN | TRANSPORT_USB | TRANSPORT_HDMI | TRANSPORT_DISPLAY_PORT
+        | TRANSPORT_THUNDERBOLT | TRANSPORT_PCI | TRANSPORT_FIREWIRE => Some(true),
+        TRANSPORT_BLUETOOTH | TRANSPORT_BLUETOOTH_LE | TRANSPORT_AIRPLAY => Some(false),
+        _ => None,
+    }
+}
+
+/// Describe the current default output device (None if it can't be read).
+#[napi]
+pub fn output_device_info() -> Option<OutputDeviceInfo> {
+    #[cfg(target_os = "macos")]
+    {
+        let mut name = [0 as c_char; 256];
+        let transport = unsafe { voxtape_default_output_device(name.as_mut_ptr(), name.len() as u32) };
+        if transport == 0 {
+            return None;
+        }
+        let name = unsafe { std::ffi::CStr::from_ptr(name.as_ptr()) }
+            .to_string_lossy()
+            .into_owned();
+        Some(OutputDeviceInfo {
+            name,
+            transport: transport_name(transport).to_string(),
+            capturable: is_capturable(transport),
+        })
+    }
+    #[cfg(not(target_os = "macos"))]
+    None
+}
+
+/// Warning text for a route known to record silence, if the current one is.
+pub(crate) fn uncapturable_output_warning() -> Option<String> {
+    let device = output_device_info()?;
+    (device.capturable == Some(false)).then(|| {
+        format!(
+            "Output device \"{}\" ({}) may not be capturable; capture can record silence. \
+             Switch system output to built-in speakers or a wired device.",
+            device.name, device.transport
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transport_classification() {
+        assert_eq!(transport_name(fourcc(b"bltn")), "builtin");
+        assert_eq!(is_capturable(fourcc(b"bltn")), Some(true));
+        assert_eq!(transport_name(fourcc(b"blea")), "bluetooth");
+        assert_eq!(is_capturable(fourcc(b"blue")), Some(false));
+        assert_eq!(is_capturable(fourcc(b"airp")), Some(false));
+        assert_eq!(is_capturable(fourcc(b"virt")), None);
+        assert_eq!(transport_name(0x1234_5678), "unknown");
+    }
+}
@@ -0,0 +1,101 @@
+//! Additional output streams fed from the same capture
+//!
+//! Each output runs the raw float input through its own resamplers, so one
+//! SCStream can serve e.g. 16kHz mono for transcription and 48kHz stereo for
+//! archival at the same time.
+
+use crate::resampler::{MixdownMode, Resampler};
+
+/// Resampling chain for one output configuration.
+pub struct OutputPipeline {
+    sample_rate: u32,
+    channels: u32,
+    /// One resampler per output channel; mono output folds all input channels
+    resamplers: Vec<Resampler>,
+}
+
+impl OutputPipeline {
+    /// `channels` must be 1 (mono mixdown) or 2 (stereo).
+    pub fn new(sample_rate: u32, channels: u32, mixdown_mode: MixdownMode) -> Self {
+        let resamplers = (0..channels)
+            .map(|_| {
+                let mut resampler = Resampler::with_output_rate(sample_rate);
+                resampler.set_mixdown_mode(mixdown_mode);
+                resampler
+            })
+            .collect();
+        Self {
+            sample_rate,
+            channels,
+            resamplers,
+        }
+    }
+
+    /// Output sample rate
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Output channel count
+    pub fn channels(&self) -> u32 {
+        self.channels
+    }
+
+    /// Convert interleaved float input to interleaved Int16 output.
+    pub fn process(&mut self, input: &[f32], channels: u32, input_rate: u32) -> Vec<i16> {
+        if self.channels == 1 {
+            return self.resamplers[0].process(input, channels, input_rate);
+        }
+        if channels == 0 {
+            return Vec::new();
+        }
+
+        // Resample each channel separately (mono input feeds both sides)
+        let frames = input.len() / channels as usize;
+        let per_channel: Vec<Vec<i16>> = self
+            .resamplers
+            .iter_mut()
+            .enumerate()
+            .map(|(out_ch, resampler)| {
+                let in_ch = out_ch.min(channels as usize - 1);
+                let mono: Vec<f32> = (0..frames)
+                    .map(|f| input[f * channels as usize + in_ch])
+                    .collect();
+                resampler.process(&mono, 1, input_rate)
+            })
+            .collect();
+
+        let len = per_channel.iter().map(Vec::len).min().unwrap_or(0);
+        let mut output = Vec::with_capacity(len * per_channel.len());
+        for i in 0..len {
+            output.extend(per_channel.iter().map(|ch| ch[i]));
+        }
+        output
+    }
+
+    /// Number of clipped output samples since the previous call.
+    pub fn take_clipped(&mut self) -> u64 {
+        self.resamplers.iter_mut().map(Resampler::take_clipped).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stereo_output_keeps_channels_apart() {
+        let mut pipeline = OutputPipeline::new(48000, 2, MixdownMode::Average);
+        let input: Vec<f32> = (0..480).flat_map(|_| [0.5f32, -0.25]).collect();
+        let output = pipeline.process(&input, 2, 48000);
+        assert_eq!(output.len(), 960);
+        assert!(output.chunks(2).all(|f| f[0] == 16384 && f[1] == -8192));
+    }
+
+    #[test]
+    fn test_mono_output_matches_default_resampler() {
+        let input: Vec<f32> = (0..4800).map(|n| (n as f32 * 0.01).sin() * 0.3).collect();
+        let mut pipeline = OutputPipeline::new(16000, 1, MixdownMode::Average);
+        assert_eq!(pipeline.process(&input, 1, 48000), Resampler::new().process(&input, 1, 48000));
+    }
+}
@@ -0,0 +1,172 @@
+//! Paced delivery: smooth bursty SCK output into fixed-size chunks on a steady clock
+//!
+//! The capture callback pushes resampled 16kHz samples into a bounded queue;
+//! a timer thread pops one chunk per interval and hands it to the sink.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Output samples per millisecond (16kHz)
+const SAMPLES_PER_MS: usize = 16;
+
+/// Bounded FIFO of 16kHz samples. Drops the oldest audio when full.
+struct PacedQueue {
+    samples: VecDeque<i16>,
+    capacity: usize,
+}
+
+impl PacedQueue {
+    fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Append samples, discarding the oldest ones beyond capacity.
+    /// Returns the number of samples dropped.
+    fn push(&mut self, input: &[i16]) -> usize {
+        self.samples.extend(input);
+        let overflow = self.samples.len().saturating_sub(self.capacity);
+        self.samples.drain(..overflow);
+        overflow
+    }
+
+    /// Pop exactly `len` samples, or nothing if not enough are buffered.
+    fn pop_chunk(&mut self, len: usize) -> Option<Vec<i16>> {
+        if self.samples.len() < len {
+            return None;
+        }
+        Some(self.samples.drain(..len).collect())
+    }
+
+    /// Pop whatever is left.
+    fn drain_all(&mut self) -> Vec<i16> {
+        self.samples.drain(..).collect()
+    }
+}
+
+/// Timer thread that releases buffered audio at a constant cadence.
+pub struct Pacer {
+    queue: Arc<Mutex<PacedQueue>>,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Pacer {
+    /// Start the timer thread.
+    ///
+    /// - `interval_ms`: delivery cadence; each chunk holds `interval_ms` of audio
+    /// - `max_buffer_ms`: queue depth before the oldest audio is dropped
+    /// - `deliver`: called from the timer thread with each chunk, and once more
+    ///   with any remainder when the pacer stops
+    pub fn spawn<F>(interval_ms: u32, max_buffer_ms: u32, deliver: F) -> Self
+    where
+        F: Fn(Vec<i16>) + Send + 'static,
+    {
+        let chunk_len = interval_ms as usize * SAMPLES_PER_MS;
+        let capacity = (max_buffer_ms as usize * SAMPLES_PER_MS).max(chunk_len);
+        let queue = Arc::new(Mutex::new(PacedQueue::new(capacity)));
+        let running = Arc::new(AtomicBool::new(true));
+
+        let thread_queue = Arc::clone(&queue);
+        let thread_running = Arc::clone(&running);
+        let interval = Duration::from_millis(interval_ms as u64);
+
+        let thread = std::thread::spawn(move || {
+            // Schedule against absolute deadlines so the cadence doesn't drift
+            let mut next_tick = Instant::now() + interval;
+            while thread_running.load(Ordering::Acquire) {
+                let now = Instant::now();
+                if next_tick > now {
+                    std::thread::sleep(next_tick - now);
+                }
+                next_tick += interval;
+
+                let chunk = match thread_queue.lock() {
+                    Ok(mut q) => q.pop_chunk(chunk_len),
+                    Err(_) => break,
+                };
+                if let Some(chunk) = chunk {
+                    deliver(chunk);
+                }
+            }
+
+            // Flush the tail so stopping never loses audio
+            let rest = match thread_queue.lock() {
+                Ok(mut q) => q.drain_all(),
+                Err(_) => Vec::new(),
+            };
+            if !rest.is_empty() {
+                deliver(rest);
+            }
+        });
+
+        Self {
+            queue,
+            running,
+            thread: Some(thread),
+        }
+    }
+
+    /// Queue resampled samples for paced delivery. Returns the number of
+    /// (oldest) samples dropped to make room.
+    pub fn push(&self, samples: &[i16]) -> usize {
+        match self.queue.lock() {
+            Ok(mut q) => q.push(samples),
+            Err(_) => 0,
+        }
+    }
+
+    /// Stop the timer thread after flushing the remaining audio.
+    pub fn stop(&mut self) {
+        self.running.store(false, Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for Pacer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue_drops_oldest_when_full() {
+        let mut q = PacedQueue::new(4);
+        assert_eq!(q.push(&[1, 2, 3]), 0);
+        assert_eq!(q.push(&[4, 5, 6]), 2);
+        assert_eq!(q.drain_all(), vec![3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_queue_only_pops_full_chunks() {
+        let mut q = PacedQueue::new(16);
+        q.push(&[1, 2, 3]);
+        assert_eq!(q.pop_chunk(4), None);
+        q.push(&[4, 5]);
+        assert_eq!(q.pop_chunk(4), Some(vec![1, 2, 3, 4]));
+        assert_eq!(q.drain_all(), vec![5]);
+    }
+
+    #[test]
+    fn test_pacer_flushes_on_stop() {
+        let delivered = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&delivered);
+        let mut pacer = Pacer::spawn(10, 1000, move |chunk| {
+            sink.lock().unwrap().extend(chunk);
+        });
+        pacer.push(&[7; 500]);
+        pacer.stop();
+        assert_eq!(delivered.lock().unwrap().len(), 500);
+    }
+}
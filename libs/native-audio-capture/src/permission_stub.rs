@@ -0,0 +1,81 @@
+//! Scripted permission answers for CI (`permission-stub` feature)
+//!
+//! CI machines can't click the Screen Recording dialog, and their real
+//! permission state is whatever the runner image has. With the feature
+//! built in, `set_permission_stub` makes `has_screen_capture_access` and
+//! the request functions return scripted values instead of asking macOS,
+//! so the `start_capture` precheck and its error mapping can be exercised.
+//! The feature refuses to compile without debug assertions, so a release
+//! build can't ship it.
+
+use std::sync::Mutex;
+
+use napi_derive::napi;
+
+use crate::PermissionRequestOutcome;
+
+/// Values the permission functions return while stubbed
+#[napi(object)]
+#[derive(Clone)]
+pub struct PermissionStub {
+    /// Answer for `has_screen_capture_access` and the start precheck, and
+    /// the result of `request_screen_capture_access` /
+    /// `request_audio_capture_permission`
+    pub screen_capture_access: bool,
+    /// Outcome of `request_capture_permission` (default: "granted" if
+    /// `screenCaptureAccess`, else "denied")
+    pub request_outcome: Option<PermissionRequestOutcome>,
+}
+
+static STUB: Mutex<Option<PermissionStub>> = Mutex::new(None);
+
+/// Script the permission functions, or restore the real checks with null.
+/// Only present in builds with the `permission-stub` feature
+/// (`npm run build:permission-stub`).
+#[napi]
+pub fn set_permission_stub(stub: Option<PermissionStub>) {
+    if let Ok(mut current) = STUB.lock() {
+        *current = stub;
+    }
+}
+
+/// Scripted screen capture access, if stubbed.
+pub fn screen_capture_access() -> Option<bool> {
+    STUB.lock().ok()?.as_ref().map(|stub| stub.screen_capture_access)
+}
+
+/// Scripted `request_capture_permission` outcome, if stubbed.
+pub fn request_outcome() -> Option<PermissionRequestOutcome> {
+    let stub = STUB.lock().ok()?.clone()?;
+    Some(stub.request_outcome.unwrap_or(if stub.screen_capture_access {
+        PermissionRequestOutcome::Granted
+    } else {
+        PermissionRequestOutcome::Denied
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stub_answers_until_cleared() {
+        assert_eq!(screen_capture_access(), None);
+        set_permission_stub(Some(PermissionStub {
+            screen_capture_access: false,
+            request_outcome: None,
+        }));
+        assert_eq!(screen_capture_access(), Some(false));
+        assert!(matches!(request_outcome(), Some(PermissionRequestOutcome::Denied)));
+
+        set_permission_stub(Some(PermissionStub {
+            screen_capture_access: false,
+            request_outcome: Some(PermissionRequestOutcome::Prompted),
+        }));
+        assert!(matches!(request_outcome(), Some(PermissionRequestOutcome::Prompted)));
+
+        set_permission_stub(None);
+        assert_eq!(screen_capture_access(), None);
+        assert!(request_outcome().is_none());
+    }
+}
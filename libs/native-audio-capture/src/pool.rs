@@ -0,0 +1,218 @@
+//! Recycled backing storage for audio Buffers delivered to JS
+//!
+//! A fresh Vec per chunk means one allocation and one free for every 10ms of
+//! audio on every stream. `PooledBuffer` hands its bytes to JS as an external
+//! Buffer whose finalizer returns the allocation to a small free list once JS
+//! garbage-collects it, so steady-state capture cycles through the same few
+//! allocations.
+
+use std::ffi::c_void;
+use std::ptr;
+use std::sync::{Mutex, OnceLock};
+
+use napi::bindgen_prelude::*;
+use napi::sys;
+
+use crate::backlog::BacklogToken;
+use crate::base64;
+
+/// Free allocations kept around; anything beyond is released normally
+const MAX_POOLED: usize = 32;
+
+struct BufferPool {
+    free: Vec<Vec<u8>>,
+}
+
+impl BufferPool {
+    fn new() -> Self {
+        Self { free: Vec::new() }
+    }
+
+    /// An empty Vec with room for `capacity` bytes, reused when possible.
+    fn take(&mut self, capacity: usize) -> Vec<u8> {
+        match self.free.iter().position(|v| v.capacity() >= capacity) {
+            Some(i) => self.free.swap_remove(i),
+            None => Vec::with_capacity(capacity),
+        }
+    }
+
+    fn recycle(&mut self, mut data: Vec<u8>) {
+        if self.free.len() < MAX_POOLED && data.capacity() > 0 {
+            data.clear();
+            self.free.push(data);
+        }
+    }
+}
+
+static POOL: OnceLock<Mutex<BufferPool>> = OnceLock::new();
+
+fn pool() -> &'static Mutex<BufferPool> {
+    POOL.get_or_init(|| Mutex::new(BufferPool::new()))
+}
+
+/// Free the allocations held for reuse (for `shutdown`).
+pub fn release_pooled() {
+    if let Ok(mut pool) = pool().lock() {
+        pool.free = Vec::new();
+    }
+}
+
+fn recycle(data: Vec<u8>) {
+    if let Ok(mut pool) = pool().lock() {
+        pool.recycle(data);
+    }
+}
+
+/// Bytes bound for a JS `Buffer`, backed by a pooled allocation. Delivered
+/// as `{ seq, samples }` instead of a bare Buffer when a sequence number is
+/// attached, and as a base64 string instead of a Buffer if asked.
+#[derive(Default)]
+pub struct PooledBuffer {
+    data: Vec<u8>,
+    seq: Option<u32>,
+    base64: bool,
+    /// Backlog room held until the chunk reaches JS
+    token: Option<BacklogToken>,
+}
+
+impl PooledBuffer {
+    /// Int16 samples as little-endian bytes.
+    pub fn from_samples(samples: &[i16]) -> Self {
+        let mut data = match pool().lock() {
+            Ok(mut pool) => pool.take(samples.len() * 2),
+            Err(_) => Vec::with_capacity(samples.len() * 2),
+        };
+        for sample in samples {
+            data.extend_from_slice(&sample.to_le_bytes());
+        }
+        Self {
+            data,
+            ..Default::default()
+        }
+    }
+
+    /// Tag the chunk with a sequence number (`None` leaves it a bare Buffer).
+    pub fn with_seq(mut self, seq: Option<u32>) -> Self {
+        self.seq = seq;
+        self
+    }
+
+    /// Hold `token` until the chunk has been handed to JS.
+    pub fn with_token(mut self, token: BacklogToken) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    /// Deliver the bytes as a base64 string instead of a Buffer.
+    pub fn with_base64(mut self, base64: bool) -> Self {
+        self.base64 = base64;
+        self
+    }
+}
+
+impl From<Vec<u8>> for PooledBuffer {
+    fn from(data: Vec<u8>) -> Self {
+        Self {
+            data,
+            ..Default::default()
+        }
+    }
+}
+
+impl ToNapiValue for PooledBuffer {
+    unsafe fn to_napi_value(env: sys::napi_env, val: Self) -> Result<sys::napi_value> {
+        let buffer = if val.base64 {
+            let encoded = base64::encode(&val.data);
+            recycle(val.data);
+            String::to_napi_value(env, encoded)?
+        } else {
+            buffer_value(env, val.data)?
+        };
+        let Some(seq) = val.seq else {
+            return Ok(buffer);
+        };
+        let mut object = ptr::null_mut();
+        check_status!(sys::napi_create_object(env, &mut object), "Failed to create chunk object")?;
+        check_status!(
+            sys::napi_set_named_property(env, object, c"seq".as_ptr(), u32::to_napi_value(env, seq)?),
+            "Failed to set chunk seq"
+        )?;
+        check_status!(
+            sys::napi_set_named_property(env, object, c"samples".as_ptr(), buffer),
+            "Failed to set chunk samples"
+        )?;
+        Ok(object)
+    }
+}
+
+/// Hand `data` to JS as an external Buffer that recycles it when collected.
+unsafe fn buffer_value(env: sys::napi_env, mut data: Vec<u8>) -> Result<sys::napi_value> {
+    let len = data.len();
+    let mut ret = ptr::null_mut();
+    if len == 0 {
+        // External buffers can't share the dangling empty-Vec pointer
+        recycle(data);
+        check_status!(
+            sys::napi_create_buffer(env, 0, ptr::null_mut(), &mut ret),
+            "Failed to create empty buffer"
+        )?;
+        return Ok(ret);
+    }
+
+    let data_ptr = data.as_mut_ptr();
+    let hint = Box::into_raw(Box::new(data));
+    let mut status = sys::napi_create_external_buffer(
+        env,
+        len,
+        data_ptr.cast(),
+        Some(finalize_pooled),
+        hint.cast(),
+        &mut ret,
+    );
+    if status == sys::Status::napi_no_external_buffers_allowed {
+        // Electron: JS gets a copy, so the allocation is free again at once
+        let data = Box::from_raw(hint);
+        status =
+            sys::napi_create_buffer_copy(env, len, data.as_ptr().cast(), ptr::null_mut(), &mut ret);
+        recycle(*data);
+    } else if status != sys::Status::napi_ok {
+        // No Buffer took the allocation, so the finalizer won't return it
+        recycle(*Box::from_raw(hint));
+    }
+    check_status!(status, "Failed to create pooled buffer")?;
+    Ok(ret)
+}
+
+unsafe extern "C" fn finalize_pooled(_env: sys::napi_env, _data: *mut c_void, hint: *mut c_void) {
+    recycle(*Box::from_raw(hint as *mut Vec<u8>));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pool_reuses_allocations() {
+        let mut pool = BufferPool::new();
+        let first = pool.take(320);
+        let address = first.as_ptr();
+        pool.recycle(first);
+
+        let reused = pool.take(320);
+        assert_eq!(reused.as_ptr(), address);
+        assert!(reused.is_empty());
+        // Too small for the request: allocate instead
+        pool.recycle(reused);
+        let larger = pool.take(640);
+        assert_ne!(larger.as_ptr(), address);
+    }
+
+    #[test]
+    fn test_pool_is_bounded() {
+        let mut pool = BufferPool::new();
+        for _ in 0..MAX_POOLED * 2 {
+            pool.recycle(Vec::with_capacity(16));
+        }
+        assert_eq!(pool.free.len(), MAX_POOLED);
+    }
+}
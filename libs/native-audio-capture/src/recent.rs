@@ -0,0 +1,59 @@
+//! Recent-audio ring for `snapshot_recent`
+//!
+//! Keeps the last N ms of the 16kHz output as plain Int16, so a clip of the
+//! recent past can be copied out at any moment while delivery carries on.
+//! Unlike the rolling buffer nothing is encoded: a snapshot is a memcpy,
+//! at 32KB per second of retention.
+
+use std::collections::VecDeque;
+
+/// Output samples per ms
+const SAMPLES_PER_MS: usize = 16;
+
+pub struct RecentAudio {
+    samples: VecDeque<i16>,
+    capacity: usize,
+}
+
+impl RecentAudio {
+    pub fn new(retention_ms: u32) -> Self {
+        let capacity = (retention_ms as usize * SAMPLES_PER_MS).max(1);
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, samples: &[i16]) {
+        let samples = &samples[samples.len().saturating_sub(self.capacity)..];
+        let overflow = (self.samples.len() + samples.len()).saturating_sub(self.capacity);
+        self.samples.drain(..overflow);
+        self.samples.extend(samples);
+    }
+
+    /// Copy of the most recent `ms` (or everything retained, if less).
+    pub fn snapshot(&self, ms: u32) -> Vec<i16> {
+        let len = (ms as usize * SAMPLES_PER_MS).min(self.samples.len());
+        self.samples.range(self.samples.len() - len..).copied().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keeps_only_the_retention_window() {
+        let mut recent = RecentAudio::new(10);
+        recent.push(&(0..100).collect::<Vec<i16>>());
+        assert_eq!(recent.snapshot(1), (84..100).collect::<Vec<i16>>());
+        // More than retained: everything there is
+        assert_eq!(recent.snapshot(1000).len(), 100);
+
+        recent.push(&(100..200).collect::<Vec<i16>>());
+        assert_eq!(recent.snapshot(1000), (40..200).collect::<Vec<i16>>());
+        // A single chunk longer than the window
+        recent.push(&(0..1000).collect::<Vec<i16>>());
+        assert_eq!(recent.snapshot(10), (840..1000).collect::<Vec<i16>>());
+    }
+}
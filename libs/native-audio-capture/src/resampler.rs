@@ -1,6 +1,12 @@
 //! Audio resampling: 48kHz float32 stereo → 16kHz Int16 mono
 //!
 //! Pipeline: stereo→mono mixdown → low-pass filter → 3:1 decimation → float→Int16
+//! Inputs below the output rate are upsampled instead: integer ratios by
+//! zero-stuffing + FIR interpolation, anything else by linear interpolation.
+//! Inputs above it that aren't a multiple (the 44.1kHz family) go through a
+//! rational L/M polyphase FIR, e.g. 160/441 for 44.1kHz → 16kHz.
+//! The output rate defaults to 16kHz; `with_output_rate` picks another
+//! (e.g. 48kHz for mixing a telephony stream with system audio).
 
 /// Simple FIR low-pass filter coefficients for anti-aliasing before 3:1 decimation.
 /// Designed for 48kHz input, cutting off around 7.5kHz (Nyquist for 16kHz output).
@@ -11,54 +17,889 @@ const LPF_TAPS: [f32; 15] = [
     0.0694, 0.0393, 0.0177, 0.0060, 0.0024,
 ];
 
+/// Default output sample rate
+const OUTPUT_RATE: u32 = 16000;
+
+/// Telephony anti-alias filter length for 48kHz → 8kHz: long enough to go
+/// from passband at 3.4kHz to stopband before 4.6kHz, where aliases would
+/// land back below 3.4kHz
+const TELEPHONY_TAPS: usize = 127;
+/// Telephony filter cutoff, midway through the 3.4–4.6kHz transition band
+const TELEPHONY_CUTOFF_HZ: f64 = 4000.0;
+
+/// Longest custom decimation filter `with_coefficients` accepts
+const MAX_CUSTOM_TAPS: usize = 1024;
+/// How far the sum of custom coefficients may stray from unity DC gain
+const CUSTOM_DC_TOLERANCE: f64 = 1e-3;
+
+/// Interpolation filter length per polyphase branch
+const TAPS_PER_PHASE: usize = 16;
+
+/// Rational path cutoff as a fraction of the output rate (7.2kHz at 16kHz)
+const RATIONAL_CUTOFF: f64 = 0.45;
+/// Rational path taps per branch, per unit of input/output rate ratio.
+/// A Hamming-windowed sinc's transition is ~3.3/length wide, so this puts
+/// it at a tenth of the output rate: 6.4–8kHz at 16kHz, so nothing above
+/// the output Nyquist folds back into the passband.
+const RATIONAL_TAPS_PER_RATIO: u32 = 33;
+/// Largest interpolation factor L the rational path will build a filter
+/// bank for; stranger ratios fall back to linear interpolation
+const MAX_RATIONAL_UP: u32 = 640;
+
+/// How multichannel frames are folded to mono.
+///
+/// - `Average`: (L+R)/2. Never clips, but a source panned hard to one side
+///   comes out 6dB down. 5.1 and 7.1 (6 or 8 channels) are folded with
+///   ITU-R BS.775 coefficients instead, see `surround_weights`; other
+///   counts average all channels.
+/// - `Rms`: (L+R)/√2, equal-power. Hard-panned sources lose only 3dB;
+///   identical L/R content gains 3dB and may clip.
+/// - `Peak` ("peak" or "louder"): whichever channel has the larger
+///   magnitude, frame by frame. Keeps one-sided speech at full level (e.g.
+///   two speakers hard-panned L/R), but switches abruptly between channels
+///   and can add distortion on wide stereo mixes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MixdownMode {
+    #[default]
+    Average,
+    Peak,
+    Rms,
+}
+
+impl MixdownMode {
+    /// Parse the `mixdownMode` option; `None` defaults to average.
+    pub fn parse(value: Option<&str>) -> Option<Self> {
+        match value.map(str::to_ascii_lowercase).as_deref() {
+            None | Some("average") => Some(Self::Average),
+            // "louder" names what it does for hard-panned interviews
+            Some("peak") | Some("louder") => Some(Self::Peak),
+            Some("rms") => Some(Self::Rms),
+            Some(_) => None,
+        }
+    }
+}
+
+/// Corrections for misbehaving drivers, applied to the first two channels
+/// (L and R) of each frame before anything else looks at them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ChannelMap {
+    pub swap_lr: bool,
+    pub invert_l: bool,
+    pub invert_r: bool,
+}
+
+impl ChannelMap {
+    fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Apply to one frame in place; mono frames are left alone.
+    fn apply<T: Copy + std::ops::Neg<Output = T>>(&self, frame: &mut [T]) {
+        if frame.len() < 2 {
+            return;
+        }
+        if self.swap_lr {
+            frame.swap(0, 1);
+        }
+        if self.invert_l {
+            frame[0] = -frame[0];
+        }
+        if self.invert_r {
+            frame[1] = -frame[1];
+        }
+    }
+}
+
+/// Downward expander settings: below `threshold_db`, every dB of level drop
+/// becomes `ratio` dB of output drop. Softer than a hard gate, so background
+/// noise fades without pumping.
+#[derive(Clone, Copy, Debug)]
+pub struct ExpanderSettings {
+    pub threshold_db: f64,
+    pub ratio: f64,
+    pub attack_ms: f64,
+    pub release_ms: f64,
+}
+
+/// Envelope follower + gain computer for `ExpanderSettings`.
+struct Expander {
+    threshold_db: f32,
+    ratio: f32,
+    attack_coeff: f32,
+    release_coeff: f32,
+    envelope: f32,
+}
+
+impl Expander {
+    fn new(settings: ExpanderSettings, sample_rate: u32) -> Self {
+        let coeff = |ms: f64| (1.0 - (-1000.0 / (ms.max(0.01) * sample_rate as f64)).exp()) as f32;
+        Self {
+            threshold_db: settings.threshold_db as f32,
+            ratio: settings.ratio.max(1.0) as f32,
+            attack_coeff: coeff(settings.attack_ms),
+            release_coeff: coeff(settings.release_ms),
+            envelope: 0.0,
+        }
+    }
+
+    fn process(&mut self, value: f32) -> f32 {
+        let level = value.abs();
+        let coeff = if level > self.envelope { self.attack_coeff } else { self.release_coeff };
+        self.envelope += coeff * (level - self.envelope);
+
+        let envelope_db = 20.0 * self.envelope.max(1e-9).log10();
+        if envelope_db >= self.threshold_db {
+            return value;
+        }
+        let gain_db = (envelope_db - self.threshold_db) * (self.ratio - 1.0);
+        value * 10f32.powf(gain_db / 20.0)
+    }
+}
+
+/// Gain ceiling for `Normalizer`, so near-silence isn't blown up into hiss
+const NORMALIZE_MAX_GAIN_DB: f32 = 30.0;
+/// Peaks below this level (dBFS) count as silence and hold the gain
+const NORMALIZE_SILENCE_DB: f32 = -60.0;
+/// Time constant of the tracked peak's decay
+const NORMALIZE_PEAK_RELEASE_MS: f32 = 5000.0;
+/// Time constant of gain increases
+const NORMALIZE_GAIN_RISE_MS: f32 = 2000.0;
+/// DC blocker corner frequency
+const DC_BLOCK_HZ: f32 = 20.0;
+
+/// Slow peak normalization toward a target level, with DC blocking.
+///
+/// Unlike an AGC or the expander, it doesn't shape the dynamics within a
+/// phrase: the gain only drops (instantly) when a new peak would overshoot
+/// the target, and rises over seconds as the tracked peak decays. The
+/// result is a recording at a consistent overall level with its internal
+/// loud/quiet contrast intact.
+struct Normalizer {
+    target: f32,
+    max_gain: f32,
+    silence: f32,
+    dc_coeff: f32,
+    dc_prev_in: f32,
+    dc_prev_out: f32,
+    peak_release: f32,
+    gain_rise: f32,
+    peak: f32,
+    gain: f32,
+}
+
+impl Normalizer {
+    fn new(target_db: f64, sample_rate: u32) -> Self {
+        let db = |db: f32| 10f32.powf(db / 20.0);
+        let decay = |ms: f32| (-1000.0 / (ms * sample_rate as f32)).exp();
+        Self {
+            target: db(target_db.min(0.0) as f32),
+            max_gain: db(NORMALIZE_MAX_GAIN_DB),
+            silence: db(NORMALIZE_SILENCE_DB),
+            dc_coeff: 1.0 - 2.0 * std::f32::consts::PI * DC_BLOCK_HZ / sample_rate as f32,
+            dc_prev_in: 0.0,
+            dc_prev_out: 0.0,
+            peak_release: decay(NORMALIZE_PEAK_RELEASE_MS),
+            gain_rise: 1.0 - decay(NORMALIZE_GAIN_RISE_MS),
+            peak: 0.0,
+            gain: 1.0,
+        }
+    }
+
+    fn process(&mut self, input: f32) -> f32 {
+        // One-pole DC blocker: y[n] = x[n] - x[n-1] + R * y[n-1]
+        let value = input - self.dc_prev_in + self.dc_coeff * self.dc_prev_out;
+        self.dc_prev_in = input;
+        self.dc_prev_out = value;
+
+        self.peak = (self.peak * self.peak_release).max(value.abs());
+        if self.peak >= self.silence {
+            let wanted = (self.target / self.peak).min(self.max_gain);
+            if wanted < self.gain {
+                self.gain = wanted;
+            } else {
+                self.gain += self.gain_rise * (wanted - self.gain);
+            }
+        }
+        value * self.gain
+    }
+
+    fn reset(&mut self) {
+        self.dc_prev_in = 0.0;
+        self.dc_prev_out = 0.0;
+        self.peak = 0.0;
+        self.gain = 1.0;
+    }
+}
+
+/// xorshift64* generator for TPDF dither. Not cryptographic, just cheap
+/// and decorrelated from the signal.
+struct Dither {
+    state: u64,
+}
+
+impl Dither {
+    fn new(seed: u64) -> Self {
+        // xorshift never leaves an all-zero state
+        Self { state: seed.max(1) }
+    }
+
+    /// Seed from the process's random hasher keys, different every run.
+    fn from_entropy() -> Self {
+        use std::hash::{BuildHasher, Hasher};
+        Self::new(std::collections::hash_map::RandomState::new().build_hasher().finish())
+    }
+
+    fn next_unit(&mut self) -> f32 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        let bits = self.state.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 40;
+        bits as f32 / (1u64 << 24) as f32
+    }
+
+    /// Triangular noise in (-1, 1) LSB
+    fn next_tpdf(&mut self) -> f32 {
+        self.next_unit() - self.next_unit()
+    }
+}
+
+/// Frames per correlation measurement (100ms at 48kHz)
+const PHASE_WINDOW: u32 = 4800;
+/// Correlation below which the channels count as inverted copies
+const PHASE_INVERTED: f64 = -0.9;
+/// Correlation above which normal mixdown resumes
+const PHASE_RECOVERED: f64 = -0.5;
+
+/// Detects stereo input whose channels cancel when summed (dual mono with
+/// one side inverted) and switches mixdown to the left channel alone while
+/// that lasts. Measured per window with hysteresis so it doesn't flap.
+#[derive(Default)]
+struct PhaseGuard {
+    enabled: bool,
+    sum_lr: f64,
+    sum_ll: f64,
+    sum_rr: f64,
+    frames: u32,
+    inverted: bool,
+    /// Whether the guard has engaged at least once
+    engaged: bool,
+    /// Set the first time the guard engages, until taken
+    warning: bool,
+}
+
+impl PhaseGuard {
+    /// Feed one stereo frame; returns whether to use single-channel pickup.
+    fn observe(&mut self, left: f32, right: f32) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        self.sum_lr += left as f64 * right as f64;
+        self.sum_ll += left as f64 * left as f64;
+        self.sum_rr += right as f64 * right as f64;
+        self.frames += 1;
+        if self.frames == PHASE_WINDOW {
+            let energy = (self.sum_ll * self.sum_rr).sqrt();
+            // Near-silent windows say nothing about phase
+            if energy > 1e-6 * PHASE_WINDOW as f64 {
+                let correlation = self.sum_lr / energy;
+                if !self.inverted && correlation < PHASE_INVERTED {
+                    self.inverted = true;
+                    self.warning = !self.engaged;
+                    self.engaged = true;
+                } else if self.inverted && correlation > PHASE_RECOVERED {
+                    self.inverted = false;
+                }
+            }
+            self.sum_lr = 0.0;
+            self.sum_ll = 0.0;
+            self.sum_rr = 0.0;
+            self.frames = 0;
+        }
+        self.inverted
+    }
+}
+
+/// Custom processing on the resampled float samples, after the built-in
+/// stages and before the conversion to Int16 (`Resampler::set_float_processor`).
+pub trait FloatProcessor: Send {
+    /// Process one chunk of output-rate mono samples (-1..1) in place.
+    fn process(&mut self, samples: &mut [f32]);
+}
+
+/// Converts captured float frames to the mono Int16 stream the sinks consume.
+/// `Resampler` is the built-in implementation; `start_capture_with_resampler`
+/// swaps in one backed by a JS function.
+pub trait SampleProcessor: Send {
+    /// Same contract as `Resampler::process`.
+    fn process(&mut self, input: &[f32], channels: u32, input_rate: u32) -> Vec<i16>;
+
+    /// Number of clipped output samples since the previous call.
+    fn take_clipped(&mut self) -> u64 {
+        0
+    }
+
+    /// True once, the first time anti-phase protection engages.
+    fn take_phase_warning(&mut self) -> bool {
+        false
+    }
+
+    /// Channel labels for the next `process` call; see `Resampler::set_channel_layout`.
+    fn set_channel_layout(&mut self, _labels: Option<&[u32]>) {}
+
+    /// Group delay added by processing in ms, if known.
+    fn latency_ms(&self) -> Option<f64> {
+        None
+    }
+
+    /// Running state, for processors that can hand it over.
+    fn export_state(&self) -> Option<ResamplerState> {
+        None
+    }
+
+    /// Restore a state from `export_state`.
+    fn import_state(&mut self, _state: &ResamplerState) -> Result<(), String> {
+        Err("This resampler has no state to restore".to_string())
+    }
+
+    /// Install or remove a `FloatProcessor`; false if not supported.
+    fn set_float_processor(&mut self, _processor: Option<Box<dyn FloatProcessor>>) -> bool {
+        false
+    }
+
+    /// Warm the filters with earlier input; see `Resampler::prime`.
+    fn prime(&mut self, _tail: &[f32], _input_rate: u32) {}
+
+    /// Turn the raw tap on or off; false if not supported.
+    fn set_raw_tap(&mut self, _enabled: bool) -> bool {
+        false
+    }
+
+    /// Pre-stage samples since the previous call; see `Resampler::take_raw_tap`.
+    fn take_raw_tap(&mut self) -> Option<Vec<f32>> {
+        None
+    }
+}
+
+impl SampleProcessor for Resampler {
+    fn process(&mut self, input: &[f32], channels: u32, input_rate: u32) -> Vec<i16> {
+        Resampler::process(self, input, channels, input_rate)
+    }
+
+    fn take_clipped(&mut self) -> u64 {
+        Resampler::take_clipped(self)
+    }
+
+    fn take_phase_warning(&mut self) -> bool {
+        Resampler::take_phase_warning(self)
+    }
+
+    fn set_channel_layout(&mut self, labels: Option<&[u32]>) {
+        Resampler::set_channel_layout(self, labels)
+    }
+
+    fn latency_ms(&self) -> Option<f64> {
+        (self.last_input_rate != 0).then(|| Resampler::latency_ms(self))
+    }
+
+    fn export_state(&self) -> Option<ResamplerState> {
+        Some(Resampler::export_state(self))
+    }
+
+    fn import_state(&mut self, state: &ResamplerState) -> Result<(), String> {
+        Resampler::import_state(self, state)
+    }
+
+    fn prime(&mut self, tail: &[f32], input_rate: u32) {
+        Resampler::prime(self, tail, input_rate)
+    }
+
+    fn set_float_processor(&mut self, processor: Option<Box<dyn FloatProcessor>>) -> bool {
+        Resampler::set_float_processor(self, processor);
+        true
+    }
+
+    fn set_raw_tap(&mut self, enabled: bool) -> bool {
+        Resampler::set_raw_tap(self, enabled);
+        true
+    }
+
+    fn take_raw_tap(&mut self) -> Option<Vec<f32>> {
+        Resampler::take_raw_tap(self)
+    }
+}
+
 /// Resampler state — holds the filter delay line for continuity across chunks.
 pub struct Resampler {
-    /// Delay line for the FIR filter (mono samples after mixdown)
+    /// Decimation filter: `LPF_TAPS`, the telephony filter for 8kHz output,
+    /// or custom coefficients
+    taps: Vec<f32>,
+    /// `taps` in Q30 for the fixed-point path. Q30 rather than Q15 keeps
+    /// coefficient rounding well under 1 LSB summed across all taps.
+    taps_q30: Vec<i64>,
+    /// Delay line for the FIR filter (mono samples after mixdown). Both
+    /// decimation paths feed both delay lines, so switching paths mid-stream
+    /// (a frame processor or raw tap coming or going) continues seamlessly
     delay_line: Vec<f32>,
     /// Current position in the 3:1 decimation phase
     phase: usize,
+    /// Q15 delay line for the fixed-point path
+    delay_line_q15: Vec<i32>,
+    /// Run the decimation path in integer arithmetic
+    fixed_point: bool,
+    /// Decimate with a boxcar average instead of the FIR
+    low_latency: bool,
+    /// Running sum and count of the current boxcar block
+    box_sum: f32,
+    box_count: usize,
+    /// Decimation factor the boxcar block was started at (0 before the
+    /// first); a block left over from another input rate is discarded
+    box_factor: usize,
+    /// Input rate of the most recent `process` call (0 before the first)
+    last_input_rate: u32,
+    /// Last input sample seen by the upsampling path
+    up_prev: f32,
+    /// Fractional read position between `up_prev` and the next input sample
+    up_pos: f64,
+    /// Integer-ratio interpolator, built for the first matching input rate
+    interpolator: Option<Interpolator>,
+    /// Rational L/M resampler for non-integer downsampling ratios
+    rational: Option<RationalResampler>,
+    /// Optional downward expander applied before quantization
+    expander: Option<Expander>,
+    /// Optional slow normalization, applied after the expander
+    normalizer: Option<Normalizer>,
+    /// TPDF dither added before rounding to Int16 (None: plain rounding)
+    dither: Option<Dither>,
+    /// Seed from `set_dither_seed`, restored by `reset`
+    dither_seed: Option<u64>,
+    /// Keep the expander → normalizer → output chain in float, rounding to
+    /// Int16 once at the end (false: round after every stage)
+    process_in_float: bool,
+    /// Custom processing between the output stages and the Int16 conversion
+    float_processor: Option<Box<dyn FloatProcessor>>,
+    /// Resampled samples as they entered the output stages, collected while
+    /// the raw tap is on
+    raw_tap: Option<Vec<f32>>,
+    /// Optional protection against anti-phase stereo cancelling to silence
+    phase_guard: PhaseGuard,
+    /// Channel labels of the current input layout (empty if unknown)
+    layout_labels: Vec<u32>,
+    /// Mono weights derived from `layout_labels`, for surround input
+    layout_weights: Option<Vec<f32>>,
+    /// L/R swap and inversion applied before mixdown
+    channel_map: ChannelMap,
+    /// Frame copy the channel map is applied to
+    mapped: Vec<f32>,
+    /// Output samples clamped to the Int16 range since the last `take_clipped`
+    clipped: u64,
+    mixdown_mode: MixdownMode,
+    output_rate: u32,
 }
 
 impl Resampler {
     pub fn new() -> Self {
+        Self::with_output_rate(OUTPUT_RATE)
+    }
+
+    /// Resampler producing `output_rate` Hz instead of 16kHz. 8kHz output
+    /// gets a telephony-grade anti-alias filter (flat to 3.4kHz).
+    pub fn with_output_rate(output_rate: u32) -> Self {
+        let taps: Vec<f32> = if output_rate == 8000 {
+            telephony_taps(48000)
+        } else {
+            LPF_TAPS.to_vec()
+        };
+        Self::with_taps(taps, output_rate)
+    }
+
+    /// 16kHz resampler decimating with the given FIR instead of the built-in
+    /// one. The coefficients must be finite, at most `MAX_CUSTOM_TAPS` long
+    /// and sum to 1 (unity gain at DC), or the output level would shift.
+    /// They apply to integer-ratio decimation (48kHz, 32kHz); other input
+    /// rates use the interpolation and rational filters as usual.
+    pub fn with_coefficients(coefficients: &[f32]) -> Result<Self, String> {
+        if coefficients.is_empty() || coefficients.len() > MAX_CUSTOM_TAPS {
+            return Err(format!(
+                "Filter needs between 1 and {} coefficients, got {}",
+                MAX_CUSTOM_TAPS,
+                coefficients.len()
+            ));
+        }
+        if coefficients.iter().any(|c| !c.is_finite()) {
+            return Err("Filter coefficients must be finite".to_string());
+        }
+        let sum: f64 = coefficients.iter().map(|&c| c as f64).sum();
+        if (sum - 1.0).abs() > CUSTOM_DC_TOLERANCE {
+            return Err(format!("Filter coefficients must sum to 1 (unity DC gain), got {}", sum));
+        }
+        Ok(Self::with_taps(coefficients.to_vec(), OUTPUT_RATE))
+    }
+
+    fn with_taps(taps: Vec<f32>, output_rate: u32) -> Self {
+        let taps_q30 = taps
+            .iter()
+            .map(|&c| (c as f64 * (1u64 << 30) as f64).round() as i64)
+            .collect();
         Self {
-            delay_line: vec![0.0; LPF_TAPS.len()],
+            delay_line: vec![0.0; taps.len()],
             phase: 0,
+            delay_line_q15: vec![0; taps.len()],
+            taps,
+            taps_q30,
+            fixed_point: false,
+            low_latency: false,
+            box_sum: 0.0,
+            box_count: 0,
+            box_factor: 0,
+            last_input_rate: 0,
+            up_prev: 0.0,
+            up_pos: 0.0,
+            interpolator: None,
+            rational: None,
+            expander: None,
+            normalizer: None,
+            dither: None,
+            dither_seed: None,
+            process_in_float: true,
+            float_processor: None,
+            raw_tap: None,
+            phase_guard: PhaseGuard::default(),
+            layout_labels: Vec::new(),
+            layout_weights: None,
+            channel_map: ChannelMap::default(),
+            mapped: Vec::new(),
+            clipped: 0,
+            mixdown_mode: MixdownMode::Average,
+            output_rate,
+        }
+    }
+
+    /// The decimation FIR in use, oldest-sample tap first.
+    pub fn coefficients(&self) -> Vec<f32> {
+        self.taps.clone()
+    }
+
+    /// Select how multichannel input is folded to mono.
+    pub fn set_mixdown_mode(&mut self, mode: MixdownMode) {
+        self.mixdown_mode = mode;
+    }
+
+    /// Run mixdown, filtering and output in Q15 fixed point instead of float.
+    /// Output stays within ±1 LSB of the float path. Applies to decimation
+    /// only, and not while the expander, normalization or dither is enabled.
+    pub fn set_fixed_point(&mut self, enabled: bool) {
+        self.fixed_point = enabled;
+    }
+
+    /// Decimate by averaging each block of input frames instead of running
+    /// the anti-alias FIR. Group delay drops to half a block (~0.02ms at
+    /// 48kHz), but the boxcar only attenuates content near the output
+    /// Nyquist by a few dB, so sibilants and music above 8kHz alias into
+    /// the band. Fine for live monitoring, not for transcription.
+    pub fn set_low_latency(&mut self, enabled: bool) {
+        self.low_latency = enabled;
+    }
+
+    /// Group delay added by the current processing path, in ms, for the
+    /// input rate last passed to `process` (0 before any input).
+    pub fn latency_ms(&self) -> f64 {
+        let input_rate = self.last_input_rate;
+        if input_rate == 0 || input_rate == self.output_rate {
+            return 0.0;
+        }
+        let delay_frames = if input_rate < self.output_rate {
+            if self.output_rate.is_multiple_of(input_rate) {
+                TAPS_PER_PHASE as f64 / 2.0
+            } else {
+                1.0
+            }
+        } else if !input_rate.is_multiple_of(self.output_rate) {
+            match rational_ratio(input_rate, self.output_rate) {
+                // Half the prototype filter, in input frames
+                Some((up, _)) => {
+                    let taps = rational_taps_per_phase(input_rate, self.output_rate) * up as usize;
+                    (taps as f64 - 1.0) / 2.0 / up as f64
+                }
+                None => 1.0,
+            }
+        } else if self.low_latency {
+            ((input_rate / self.output_rate) as f64 - 1.0) / 2.0
+        } else {
+            (self.taps.len() as f64 - 1.0) / 2.0
+        };
+        delay_frames * 1000.0 / input_rate as f64
+    }
+
+    /// Number of samples the next `process` call would return for
+    /// `input_frames` frames at `input_rate`, given the current decimation
+    /// phase and interpolation position. Nothing is processed.
+    ///
+    /// Decimating by N, an output is due on every Nth input frame counted
+    /// from where the previous call left off, so `(phase + frames) / N`.
+    /// Integer upsampling emits N per frame; fractional upsampling emits one
+    /// per `input_rate / output_rate` step of the carried position.
+    pub fn expected_output_len(&self, input_frames: usize, input_rate: u32) -> usize {
+        if input_rate == 0 {
+            return 0;
+        }
+        if input_rate == self.output_rate {
+            return input_frames;
+        }
+        if input_rate < self.output_rate && self.output_rate.is_multiple_of(input_rate) {
+            return input_frames * (self.output_rate / input_rate) as usize;
+        }
+        if !input_rate.is_multiple_of(self.output_rate) {
+            if let Some((up, down)) = rational_ratio(input_rate, self.output_rate) {
+                // Outputs land every M of the L upsampled slots per input frame
+                let pos = self
+                    .rational
+                    .as_ref()
+                    .filter(|r| r.input_rate == input_rate)
+                    .map_or(0, |r| r.pos);
+                return (input_frames * up as usize).saturating_sub(pos).div_ceil(down as usize);
+            }
+            // Step the position exactly as `upsample` does, so float
+            // rounding can't make the two disagree
+            let step = input_rate as f64 / self.output_rate as f64;
+            let mut pos = self.up_pos;
+            let mut count = 0;
+            for _ in 0..input_frames {
+                while pos < 1.0 {
+                    count += 1;
+                    pos += step;
+                }
+                pos -= 1.0;
+            }
+            return count;
+        }
+        let decimation_factor = (input_rate / self.output_rate) as usize;
+        let pending = match self.low_latency {
+            true if self.box_factor == decimation_factor => self.box_count,
+            true => 0,
+            false => self.phase,
+        };
+        (pending + input_frames) / decimation_factor
+    }
+
+    /// Detect anti-correlated stereo (one channel an inverted copy of the
+    /// other) and pick up the left channel alone instead of summing to silence.
+    pub fn set_phase_protection(&mut self, enabled: bool) {
+        self.phase_guard.enabled = enabled;
+    }
+
+    /// True once, the first time phase protection engages.
+    pub fn take_phase_warning(&mut self) -> bool {
+        std::mem::take(&mut self.phase_guard.warning)
+    }
+
+    /// Describe the input's channel layout as CoreAudio `AudioChannelLabel`s,
+    /// one per channel (`None` if unknown). Surround input is then weighted
+    /// by what each channel carries rather than by its position. Cheap to
+    /// call per buffer: nothing is recomputed while the layout is unchanged.
+    pub fn set_channel_layout(&mut self, labels: Option<&[u32]>) {
+        let labels = labels.unwrap_or_default();
+        if labels != self.layout_labels.as_slice() {
+            self.layout_labels = labels.to_vec();
+            self.layout_weights = layout_weights(labels);
+        }
+    }
+
+    /// Swap and/or invert L and R before mixdown, to undo a driver that
+    /// delivers them swapped or with one channel inverted (which would
+    /// otherwise cancel to near-silence in the mix).
+    pub fn set_channel_map(&mut self, map: ChannelMap) {
+        self.channel_map = map;
+    }
+
+    /// Label-derived weights, if they apply to `channels`-channel input.
+    fn active_layout_weights(&self, channels: usize) -> Option<&[f32]> {
+        self.layout_weights
+            .as_deref()
+            .filter(|w| w.len() == channels && self.mixdown_mode == MixdownMode::Average)
+    }
+
+    /// Fold one interleaved frame to mono, honoring the channel map and
+    /// phase protection.
+    fn mix_frame(&mut self, input: &[f32], frame_idx: usize, channels: u32) -> f32 {
+        let ch = channels as usize;
+        if ch == 1 {
+            // Already mono (e.g. SCK asked for one channel): no mixdown
+            return input[frame_idx];
+        }
+        let frame = &input[frame_idx * ch..(frame_idx + 1) * ch];
+        if self.channel_map.is_identity() {
+            return self.fold(frame);
+        }
+        let mut mapped = std::mem::take(&mut self.mapped);
+        mapped.clear();
+        mapped.extend_from_slice(frame);
+        self.channel_map.apply(&mut mapped);
+        let mono = self.fold(&mapped);
+        self.mapped = mapped;
+        mono
+    }
+
+    fn fold(&mut self, frame: &[f32]) -> f32 {
+        if frame.len() == 2 && self.phase_guard.observe(frame[0], frame[1]) {
+            return frame[0];
+        }
+        if let Some(weights) = self.active_layout_weights(frame.len()) {
+            return weighted(frame, weights);
+        }
+        mixdown(frame, 0, frame.len() as u32, self.mixdown_mode)
+    }
+
+    /// Enable the downward expander on the output (disabled by default).
+    pub fn set_expander(&mut self, settings: ExpanderSettings) {
+        self.expander = Some(Expander::new(settings, self.output_rate));
+    }
+
+    /// Add ±1 LSB triangular (TPDF) dither before rounding to Int16, so
+    /// quiet passages get benign noise instead of correlated quantization
+    /// distortion (disabled by default). Seeded nondeterministically unless
+    /// `set_dither_seed` is called.
+    pub fn set_dither(&mut self, enabled: bool) {
+        self.dither = enabled.then(|| match self.dither_seed {
+            Some(seed) => Dither::new(seed),
+            None => Dither::from_entropy(),
+        });
+    }
+
+    /// Make dither reproducible: the same seed and input give byte-identical
+    /// output, for tests and offline processing. Takes effect for the
+    /// dither already enabled, and for later `set_dither` and `reset` calls.
+    pub fn set_dither_seed(&mut self, seed: u64) {
+        self.dither_seed = Some(seed);
+        if self.dither.is_some() {
+            self.dither = Some(Dither::new(seed));
         }
     }
 
+    /// Whether the processing stages after resampling (expander, then
+    /// normalization) hand each other float samples, with the only rounding
+    /// to Int16 at the very end (the default). Disabling it rounds to the
+    /// Int16 grid after every stage, as a chain of Int16 processors would;
+    /// the rounding error then compounds with each stage and any gain
+    /// applied after it, so this is only useful to compare against or
+    /// bit-match such a pipeline. Doesn't apply to the fixed-point path.
+    pub fn set_process_in_float(&mut self, enabled: bool) {
+        self.process_in_float = enabled;
+    }
+
+    /// Run `processor` on each chunk of output samples after the expander
+    /// and normalization, just before the conversion to Int16 (`None`
+    /// removes it). Disables the fixed-point path while installed.
+    pub fn set_float_processor(&mut self, processor: Option<Box<dyn FloatProcessor>>) {
+        self.float_processor = processor;
+    }
+
+    /// Collect each output sample as it was resampled, before the expander,
+    /// normalization, float processor and dither, for `take_raw_tap`.
+    /// Disables the fixed-point path while on.
+    pub fn set_raw_tap(&mut self, enabled: bool) {
+        self.raw_tap = enabled.then(Vec::new);
+    }
+
+    /// Samples collected by the raw tap since the previous call, if it's on.
+    pub fn take_raw_tap(&mut self) -> Option<Vec<f32>> {
+        self.raw_tap.as_mut().map(std::mem::take)
+    }
+
+    /// Normalize the output toward `target_db` dBFS peak (disabled by
+    /// default). See `Normalizer` for how this differs from the expander.
+    pub fn set_normalize_target(&mut self, target_db: f64) {
+        self.normalizer = Some(Normalizer::new(target_db, self.output_rate));
+    }
+
     /// Resample a buffer of interleaved float32 audio.
     ///
-    /// - `input`: interleaved float32 samples (1 or 2 channels)
-    /// - `channels`: number of channels (1 or 2)
-    /// - `input_rate`: input sample rate (must be a multiple of 16000)
+    /// - `input`: interleaved float32 samples
+    /// - `channels`: number of channels (1 or 2; more are averaged, 0 yields nothing)
+    /// - `input_rate`: input sample rate, any rate (multiples of the output
+    ///   rate and the 44.1kHz family get proper anti-alias filtering)
     ///
-    /// Returns: Vec<i16> of 16kHz mono Int16 samples.
+    /// Returns: Vec<i16> of mono Int16 samples at the output rate (16kHz by default).
     pub fn process(&mut self, input: &[f32], channels: u32, input_rate: u32) -> Vec<i16> {
-        let decimation_factor = (input_rate / 16000) as usize;
-        if decimation_factor == 0 {
+        if input_rate == 0 || channels == 0 {
+            return Vec::new();
+        }
+        self.last_input_rate = input_rate;
+        let decimates = input_rate > self.output_rate && input_rate.is_multiple_of(self.output_rate);
+        if decimates
+            && !self.low_latency
+            && self.fixed_point
+            && self.expander.is_none()
+            && self.normalizer.is_none()
+            && self.dither.is_none()
+            && self.float_processor.is_none()
+            && self.raw_tap.is_none()
+        {
+            let decimation_factor = (input_rate / self.output_rate) as usize;
+            return self.decimate_fixed(input, channels, decimation_factor);
+        }
+
+        let output = self.process_to_float(input, channels, input_rate);
+        output.into_iter().map(|value| self.quantize(value)).collect()
+    }
+
+    /// `process` up to, but not including, the Int16 conversion: output is
+    /// on the -1..1 scale and may exceed it. Always takes the float path.
+    pub fn process_to_float(&mut self, input: &[f32], channels: u32, input_rate: u32) -> Vec<f32> {
+        if input_rate == 0 || channels == 0 {
             return Vec::new();
         }
+        self.last_input_rate = input_rate;
+        let mut output = self.process_float(input, channels, input_rate);
+        if let Some(processor) = &mut self.float_processor {
+            processor.process(&mut output);
+        }
+        output
+    }
+
+    /// Everything up to the Int16 conversion: resampling, then the expander
+    /// and normalization. Output is on the -1..1 scale.
+    fn process_float(&mut self, input: &[f32], channels: u32, input_rate: u32) -> Vec<f32> {
+        if input_rate == self.output_rate {
+            // Nothing to band-limit: just mix down. Mono input
+            // passes through with no filtering or delay at all.
+            let frame_count = input.len() / channels as usize;
+            return (0..frame_count)
+                .map(|i| {
+                    let mono = self.mix_frame(input, i, channels);
+                    self.run_stages(mono)
+                })
+                .collect();
+        }
+        if input_rate < self.output_rate {
+            if self.output_rate.is_multiple_of(input_rate) {
+                return self.interpolate(input, channels, (self.output_rate / input_rate) as usize);
+            }
+            return self.upsample(input, channels, input_rate);
+        }
 
+        if !input_rate.is_multiple_of(self.output_rate) {
+            if rational_ratio(input_rate, self.output_rate).is_some() {
+                return self.resample_rational(input, channels, input_rate);
+            }
+            return self.upsample(input, channels, input_rate);
+        }
+
+        let decimation_factor = (input_rate / self.output_rate) as usize;
+        if self.low_latency {
+            return self.decimate_boxcar(input, channels, decimation_factor);
+        }
         let frame_count = input.len() / channels as usize;
 
-        // Pre-allocate output (upper bound)
-        let max_output = frame_count / decimation_factor + 1;
-        let mut output = Vec::with_capacity(max_output);
+        let mut output = Vec::with_capacity(self.expected_output_len(frame_count, input_rate));
 
         for frame_idx in 0..frame_count {
-            // Stereo → mono mixdown
-            let mono = if channels >= 2 {
-                let left = input[frame_idx * channels as usize];
-                let right = input[frame_idx * channels as usize + 1];
-                (left + right) * 0.5
-            } else {
-                input[frame_idx * channels as usize]
-            };
+            let mono = self.mix_frame(input, frame_idx, channels);
 
             // Push into delay line (shift left, append new)
             self.delay_line.remove(0);
             self.delay_line.push(mono);
+            self.delay_line_q15.rotate_left(1);
+            *self.delay_line_q15.last_mut().unwrap() = to_q15(mono);
 
             // Decimation: only compute output every `decimation_factor` samples
             self.phase += 1;
@@ -67,57 +908,933 @@ impl Resampler {
 
                 // FIR filter convolution
                 let mut filtered = 0.0f32;
-                for (i, &coeff) in LPF_TAPS.iter().enumerate() {
+                for (i, &coeff) in self.taps.iter().enumerate() {
                     filtered += self.delay_line[i] * coeff;
                 }
 
-                // Float32 → Int16 with clamp
-                let sample = (filtered * 32767.0).round().clamp(-32768.0, 32767.0) as i16;
-                output.push(sample);
+                output.push(self.run_stages(filtered));
             }
         }
 
         output
     }
 
-    /// Reset the resampler state (e.g. when starting a new capture session).
-    #[allow(dead_code)]
-    pub fn reset(&mut self) {
-        self.delay_line.fill(0.0);
-        self.phase = 0;
+    /// Low-latency decimation: the mean of each block of `factor` frames.
+    fn decimate_boxcar(&mut self, input: &[f32], channels: u32, factor: usize) -> Vec<f32> {
+        let frame_count = input.len() / channels as usize;
+        let mut output = Vec::with_capacity(frame_count / factor + 1);
+        if self.box_factor != factor {
+            // The input rate changed: a part-filled block can't be finished
+            self.box_sum = 0.0;
+            self.box_count = 0;
+            self.box_factor = factor;
+        }
+        for frame_idx in 0..frame_count {
+            self.box_sum += self.mix_frame(input, frame_idx, channels);
+            self.box_count += 1;
+            if self.box_count >= factor {
+                let mono = self.box_sum / factor as f32;
+                self.box_sum = 0.0;
+                self.box_count = 0;
+                output.push(self.run_stages(mono));
+            }
+        }
+        output
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Fixed-point counterpart of the decimation loop in `process`: samples
+    /// are converted to Q15 once on entry, then everything stays integer.
+    fn decimate_fixed(&mut self, input: &[f32], channels: u32, decimation_factor: usize) -> Vec<i16> {
+        let ch = channels as usize;
+        let frame_count = input.len() / ch;
+        let mut output = Vec::with_capacity(frame_count / decimation_factor + 1);
+        let mut frame = Vec::with_capacity(ch);
 
-    #[test]
-    fn test_decimation_ratio() {
-        let mut r = Resampler::new();
-        // 4800 mono samples at 48kHz = 100ms → should produce ~1600 samples at 16kHz
-        let input = vec![0.0f32; 4800];
-        let output = r.process(&input, 1, 48000);
-        assert_eq!(output.len(), 1600);
-    }
+        for frame_idx in 0..frame_count {
+            frame.clear();
+            frame.extend(input[frame_idx * ch..(frame_idx + 1) * ch].iter().map(|&x| to_q15(x)));
+            self.channel_map.apply(&mut frame);
+            let mono = if ch == 2 && self.phase_guard.observe(frame[0] as f32, frame[1] as f32) {
+                frame[0]
+            } else if let Some(weights) = self.active_layout_weights(ch) {
+                weighted_q15(&frame, weights)
+            } else {
+                mixdown_q15(&frame, self.mixdown_mode)
+            };
 
-    #[test]
-    fn test_stereo_to_mono() {
-        let mut r = Resampler::new();
-        // Stereo input: left=0.5, right=-0.5 → mono should be ~0.0
-        let mut input = Vec::new();
-        for _ in 0..4800 {
-            input.push(0.5f32);
-            input.push(-0.5f32);
-        }
-        let output = r.process(&input, 2, 48000);
-        // After filter settles, output should be near 0
-        for &s in &output[10..] {
-            assert!(s.abs() < 100, "Expected near-zero, got {}", s);
+            self.delay_line_q15.rotate_left(1);
+            *self.delay_line_q15.last_mut().unwrap() = mono;
+            self.delay_line.remove(0);
+            self.delay_line.push(mono as f32 / 32767.0);
+
+            self.phase += 1;
+            if self.phase >= decimation_factor {
+                self.phase = 0;
+
+                let acc: i64 = self
+                    .taps_q30
+                    .iter()
+                    .zip(&self.delay_line_q15)
+                    .map(|(&c, &x)| c * x as i64)
+                    .sum();
+                let value = (acc + (1 << 29)) >> 30;
+                if !(i16::MIN as i64..=i16::MAX as i64).contains(&value) {
+                    self.clipped += 1;
+                }
+                output.push(value.clamp(i16::MIN as i64, i16::MAX as i64) as i16);
+            }
         }
+
+        output
     }
 
-    #[test]
+    /// Integer-ratio upsampling: each input sample is followed by `factor - 1`
+    /// zeros and the result low-pass filtered at the input Nyquist, computed
+    /// polyphase so the zeros are never multiplied.
+    fn interpolate(&mut self, input: &[f32], channels: u32, factor: usize) -> Vec<f32> {
+        if self.interpolator.as_ref().is_none_or(|i| i.factor != factor) {
+            self.interpolator = Some(Interpolator::new(factor));
+        }
+        let frame_count = input.len() / channels as usize;
+        let mut output = Vec::with_capacity(frame_count * factor);
+
+        for frame_idx in 0..frame_count {
+            let mono = self.mix_frame(input, frame_idx, channels);
+            let interpolator = self.interpolator.as_mut().unwrap();
+            interpolator.history.rotate_right(1);
+            interpolator.history[0] = mono;
+            for phase in 0..factor {
+                let value = self.interpolator.as_ref().unwrap().output(phase);
+                output.push(self.run_stages(value));
+            }
+        }
+
+        output
+    }
+
+    /// Non-integer downsampling through the rational polyphase FIR.
+    fn resample_rational(&mut self, input: &[f32], channels: u32, input_rate: u32) -> Vec<f32> {
+        if self.rational.as_ref().is_none_or(|r| r.input_rate != input_rate) {
+            self.rational = Some(RationalResampler::new(input_rate, self.output_rate));
+        }
+        let frame_count = input.len() / channels as usize;
+        let mut output = Vec::with_capacity(self.expected_output_len(frame_count, input_rate));
+
+        // Out of `self` for the loop, which also borrows it to mix and quantize
+        let mut rational = self.rational.take().unwrap();
+        for frame_idx in 0..frame_count {
+            let mono = self.mix_frame(input, frame_idx, channels);
+            rational.history.rotate_right(1);
+            rational.history[0] = mono;
+            while rational.pos < rational.up {
+                let value = rational.output(rational.pos);
+                rational.pos += rational.down;
+                output.push(self.run_stages(value));
+            }
+            rational.pos -= rational.up;
+        }
+        self.rational = Some(rational);
+
+        output
+    }
+
+    /// Linear-interpolation upsampling for non-integer ratios.
+    /// Output lags the input by one sample; the interpolation position carries
+    /// across calls so chunk boundaries are seamless.
+    fn upsample(&mut self, input: &[f32], channels: u32, input_rate: u32) -> Vec<f32> {
+        let frame_count = input.len() / channels as usize;
+        let step = input_rate as f64 / self.output_rate as f64;
+        let mut output =
+            Vec::with_capacity(frame_count * self.output_rate as usize / input_rate as usize + 1);
+
+        for frame_idx in 0..frame_count {
+            let mono = self.mix_frame(input, frame_idx, channels);
+            while self.up_pos < 1.0 {
+                let value = self.up_prev + (mono - self.up_prev) * self.up_pos as f32;
+                output.push(self.run_stages(value));
+                self.up_pos += step;
+            }
+            self.up_pos -= 1.0;
+            self.up_prev = mono;
+        }
+
+        output
+    }
+
+    /// Run the output stages (expander, then normalization) on one sample.
+    fn run_stages(&mut self, value: f32) -> f32 {
+        if let Some(raw) = &mut self.raw_tap {
+            raw.push(value);
+        }
+        let in_float = self.process_in_float;
+        let value = match &mut self.expander {
+            Some(expander) => stage_output(expander.process(value), in_float),
+            None => value,
+        };
+        match &mut self.normalizer {
+            Some(normalizer) => stage_output(normalizer.process(value), in_float),
+            None => value,
+        }
+    }
+
+    /// Float32 → Int16 with dither and clamp, counting samples that hit the
+    /// rails. Unless `process_in_float` is off, this is the only rounding.
+    fn quantize(&mut self, value: f32) -> i16 {
+        let dither = self.dither.as_mut().map_or(0.0, Dither::next_tpdf);
+        let scaled = (value * 32767.0 + dither).round();
+        if !(-32768.0..=32767.0).contains(&scaled) {
+            self.clipped += 1;
+        }
+        scaled.clamp(-32768.0, 32767.0) as i16
+    }
+
+    /// Number of clipped output samples since the previous call.
+    pub fn take_clipped(&mut self) -> u64 {
+        std::mem::take(&mut self.clipped)
+    }
+
+    /// Reset the resampler state (e.g. when starting a new capture session).
+    #[allow(dead_code)]
+    pub fn reset(&mut self) {
+        self.delay_line.fill(0.0);
+        self.delay_line_q15.fill(0);
+        self.phase = 0;
+        self.up_prev = 0.0;
+        self.up_pos = 0.0;
+        self.interpolator = None;
+        self.rational = None;
+        if let Some(expander) = &mut self.expander {
+            expander.envelope = 0.0;
+        }
+        if let Some(normalizer) = &mut self.normalizer {
+            normalizer.reset();
+        }
+        if let (Some(dither), Some(seed)) = (&mut self.dither, self.dither_seed) {
+            *dither = Dither::new(seed);
+        }
+        self.clipped = 0;
+    }
+
+    /// Feed `tail`, mono input at `input_rate` that preceded this stream
+    /// (e.g. the end of the previous session), through the filters without
+    /// producing output. The next `process` at that rate then continues from
+    /// it instead of from a zeroed delay line, so there's no seam at the join.
+    pub fn prime(&mut self, tail: &[f32], input_rate: u32) {
+        if input_rate == 0 {
+            return;
+        }
+        let _ = self.process_float(tail, 1, input_rate);
+        let _ = self.take_raw_tap();
+    }
+
+    /// Snapshot the running state: filter histories, phases, and the
+    /// expander, normalizer, dither and phase protection state. Importing it
+    /// into an identically configured resampler continues the stream
+    /// sample-for-sample.
+    pub fn export_state(&self) -> ResamplerState {
+        let guard = &self.phase_guard;
+        ResamplerState {
+            output_rate: self.output_rate,
+            delay_line: self.delay_line.clone(),
+            delay_line_q15: self.delay_line_q15.clone(),
+            phase: self.phase as u32,
+            box_sum: self.box_sum,
+            box_count: self.box_count as u32,
+            last_input_rate: self.last_input_rate,
+            up_prev: self.up_prev,
+            up_pos: self.up_pos,
+            interpolator: self.interpolator.as_ref().map(|i| (i.factor as u32, i.history.clone())),
+            rational: self.rational.as_ref().map(|r| (r.input_rate, r.history.clone(), r.pos as u32)),
+            expander_envelope: self.expander.as_ref().map(|e| e.envelope),
+            normalizer: self.normalizer.as_ref().map(|n| [n.dc_prev_in, n.dc_prev_out, n.peak, n.gain]),
+            dither: self.dither.as_ref().map(|d| d.state),
+            phase_guard: (
+                [guard.sum_lr, guard.sum_ll, guard.sum_rr],
+                guard.frames,
+                guard.inverted,
+                guard.engaged,
+            ),
+        }
+    }
+
+    /// Restore a state from `export_state`. Fails, leaving this resampler
+    /// untouched, if it was exported from one with a different output rate,
+    /// filter length, or set of stages (expander, normalization, dither), or
+    /// if its positions or dither state are out of range.
+    pub fn import_state(&mut self, state: &ResamplerState) -> Result<(), String> {
+        if state.output_rate != self.output_rate || state.delay_line.len() != self.taps.len() {
+            return Err(format!(
+                "State is for {}Hz output with a {}-tap filter, not {}Hz with {} taps",
+                state.output_rate,
+                state.delay_line.len(),
+                self.output_rate,
+                self.taps.len()
+            ));
+        }
+        if state.delay_line_q15.len() != self.taps.len() {
+            return Err("State is inconsistent".to_string());
+        }
+        if state.expander_envelope.is_some() != self.expander.is_some()
+            || state.normalizer.is_some() != self.normalizer.is_some()
+            || state.dither.is_some() != self.dither.is_some()
+        {
+            return Err("State was exported with a different expander, normalization or dither setting".to_string());
+        }
+        // The state may come from anywhere: positions out of range would
+        // stall the output or spin the audio thread
+        if let Some(factor) = decimation_factor(state.last_input_rate, self.output_rate) {
+            if state.phase as usize >= factor || state.box_count as usize >= factor {
+                return Err("State is inconsistent".to_string());
+            }
+        }
+        let step = state.last_input_rate as f64 / self.output_rate as f64;
+        if !(0.0..step + 1.0).contains(&state.up_pos) || state.dither == Some(0) {
+            return Err("State is inconsistent".to_string());
+        }
+        let interpolator = match &state.interpolator {
+            Some((factor, history)) => {
+                if *factor < 2 || !self.output_rate.is_multiple_of(*factor) {
+                    return Err(format!("State has an invalid interpolation factor {}", factor));
+                }
+                let mut interpolator = Interpolator::new(*factor as usize);
+                if history.len() != interpolator.history.len() {
+                    return Err("State is inconsistent".to_string());
+                }
+                interpolator.history.copy_from_slice(history);
+                Some(interpolator)
+            }
+            None => None,
+        };
+        let rational = match &state.rational {
+            Some((input_rate, history, pos)) => {
+                if rational_ratio(*input_rate, self.output_rate).is_none() {
+                    return Err(format!("State has an unsupported input rate {}", input_rate));
+                }
+                let mut rational = RationalResampler::new(*input_rate, self.output_rate);
+                if history.len() != rational.history.len() || *pos as usize >= rational.up {
+                    return Err("State is inconsistent".to_string());
+                }
+                rational.history.copy_from_slice(history);
+                rational.pos = *pos as usize;
+                Some(rational)
+            }
+            None => None,
+        };
+
+        self.delay_line.copy_from_slice(&state.delay_line);
+        self.delay_line_q15.copy_from_slice(&state.delay_line_q15);
+        self.phase = state.phase as usize;
+        self.box_sum = state.box_sum;
+        self.box_count = state.box_count as usize;
+        self.box_factor = decimation_factor(state.last_input_rate, self.output_rate).unwrap_or(0);
+        self.last_input_rate = state.last_input_rate;
+        self.up_prev = state.up_prev;
+        self.up_pos = state.up_pos;
+        self.interpolator = interpolator;
+        self.rational = rational;
+        if let (Some(expander), Some(envelope)) = (&mut self.expander, state.expander_envelope) {
+            expander.envelope = envelope;
+        }
+        if let (Some(normalizer), Some([dc_prev_in, dc_prev_out, peak, gain])) =
+            (&mut self.normalizer, state.normalizer)
+        {
+            normalizer.dc_prev_in = dc_prev_in;
+            normalizer.dc_prev_out = dc_prev_out;
+            normalizer.peak = peak;
+            normalizer.gain = gain;
+        }
+        if let (Some(dither), Some(seed)) = (&mut self.dither, state.dither) {
+            dither.state = seed;
+        }
+        let ([sum_lr, sum_ll, sum_rr], frames, inverted, engaged) = state.phase_guard;
+        let guard = &mut self.phase_guard;
+        (guard.sum_lr, guard.sum_ll, guard.sum_rr) = (sum_lr, sum_ll, sum_rr);
+        (guard.frames, guard.inverted, guard.engaged) = (frames, inverted, engaged);
+        Ok(())
+    }
+}
+
+/// Magic and version leading a serialized `ResamplerState`
+const STATE_MAGIC: [u8; 4] = *b"VXRS";
+const STATE_VERSION: u8 = 1;
+
+/// Running state of a `Resampler`, from `export_state`. Holds no
+/// configuration: the resampler it is imported into must be set up the same.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResamplerState {
+    output_rate: u32,
+    delay_line: Vec<f32>,
+    delay_line_q15: Vec<i32>,
+    phase: u32,
+    box_sum: f32,
+    box_count: u32,
+    last_input_rate: u32,
+    up_prev: f32,
+    up_pos: f64,
+    /// Interpolation factor and history
+    interpolator: Option<(u32, Vec<f32>)>,
+    /// Input rate, history and position of the rational resampler
+    rational: Option<(u32, Vec<f32>, u32)>,
+    expander_envelope: Option<f32>,
+    /// DC blocker input and output, tracked peak, gain
+    normalizer: Option<[f32; 4]>,
+    dither: Option<u64>,
+    /// Correlation sums, frames into the window, inverted, engaged
+    phase_guard: ([f64; 3], u32, bool, bool),
+}
+
+impl ResamplerState {
+    /// Little-endian binary form, for persisting outside the process.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = StateWriter(STATE_MAGIC.to_vec());
+        out.u8(STATE_VERSION);
+        out.u32(self.output_rate);
+        out.f32s(&self.delay_line);
+        out.u32(self.delay_line_q15.len() as u32);
+        for &x in &self.delay_line_q15 {
+            out.u32(x as u32);
+        }
+        out.u32(self.phase);
+        out.f32(self.box_sum);
+        out.u32(self.box_count);
+        out.u32(self.last_input_rate);
+        out.f32(self.up_prev);
+        out.f64(self.up_pos);
+        out.flag(self.interpolator.is_some());
+        if let Some((factor, history)) = &self.interpolator {
+            out.u32(*factor);
+            out.f32s(history);
+        }
+        out.flag(self.rational.is_some());
+        if let Some((input_rate, history, pos)) = &self.rational {
+            out.u32(*input_rate);
+            out.f32s(history);
+            out.u32(*pos);
+        }
+        out.flag(self.expander_envelope.is_some());
+        if let Some(envelope) = self.expander_envelope {
+            out.f32(envelope);
+        }
+        out.flag(self.normalizer.is_some());
+        if let Some(normalizer) = &self.normalizer {
+            normalizer.iter().for_each(|&x| out.f32(x));
+        }
+        out.flag(self.dither.is_some());
+        if let Some(dither) = self.dither {
+            out.u64(dither);
+        }
+        let (sums, frames, inverted, engaged) = &self.phase_guard;
+        sums.iter().for_each(|&x| out.f64(x));
+        out.u32(*frames);
+        out.flag(*inverted);
+        out.flag(*engaged);
+        out.0
+    }
+
+    /// Parse `to_bytes` output.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut input = StateReader { bytes, pos: 0 };
+        if input.take(4)? != STATE_MAGIC {
+            return Err("Not a resampler state".to_string());
+        }
+        let version = input.u8()?;
+        if version != STATE_VERSION {
+            return Err(format!("Unsupported resampler state version {}", version));
+        }
+        let output_rate = input.u32()?;
+        let delay_line = input.f32s()?;
+        let q15_len = input.u32()? as usize;
+        let delay_line_q15 = (0..q15_len).map(|_| input.u32().map(|x| x as i32)).collect::<Result<_, _>>()?;
+        let state = Self {
+            output_rate,
+            delay_line,
+            delay_line_q15,
+            phase: input.u32()?,
+            box_sum: input.f32()?,
+            box_count: input.u32()?,
+            last_input_rate: input.u32()?,
+            up_prev: input.f32()?,
+            up_pos: input.f64()?,
+            interpolator: match input.flag()? {
+                true => Some((input.u32()?, input.f32s()?)),
+                false => None,
+            },
+            rational: match input.flag()? {
+                true => Some((input.u32()?, input.f32s()?, input.u32()?)),
+                false => None,
+            },
+            expander_envelope: match input.flag()? {
+                true => Some(input.f32()?),
+                false => None,
+            },
+            normalizer: match input.flag()? {
+                true => Some([input.f32()?, input.f32()?, input.f32()?, input.f32()?]),
+                false => None,
+            },
+            dither: match input.flag()? {
+                true => Some(input.u64()?),
+                false => None,
+            },
+            phase_guard: (
+                [input.f64()?, input.f64()?, input.f64()?],
+                input.u32()?,
+                input.flag()?,
+                input.flag()?,
+            ),
+        };
+        if input.pos != bytes.len() {
+            return Err("Trailing bytes after resampler state".to_string());
+        }
+        Ok(state)
+    }
+}
+
+struct StateWriter(Vec<u8>);
+
+impl StateWriter {
+    fn u8(&mut self, x: u8) {
+        self.0.push(x);
+    }
+    fn flag(&mut self, x: bool) {
+        self.0.push(x as u8);
+    }
+    fn u32(&mut self, x: u32) {
+        self.0.extend_from_slice(&x.to_le_bytes());
+    }
+    fn u64(&mut self, x: u64) {
+        self.0.extend_from_slice(&x.to_le_bytes());
+    }
+    fn f32(&mut self, x: f32) {
+        self.0.extend_from_slice(&x.to_le_bytes());
+    }
+    fn f64(&mut self, x: f64) {
+        self.0.extend_from_slice(&x.to_le_bytes());
+    }
+    fn f32s(&mut self, xs: &[f32]) {
+        self.u32(xs.len() as u32);
+        xs.iter().for_each(|&x| self.f32(x));
+    }
+}
+
+struct StateReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> StateReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self.pos.checked_add(len).filter(|&end| end <= self.bytes.len());
+        let end = end.ok_or_else(|| "Truncated resampler state".to_string())?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+    fn flag(&mut self) -> Result<bool, String> {
+        Ok(self.u8()? != 0)
+    }
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+    fn f32(&mut self) -> Result<f32, String> {
+        Ok(f32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+    fn f64(&mut self) -> Result<f64, String> {
+        Ok(f64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+    fn f32s(&mut self) -> Result<Vec<f32>, String> {
+        let len = self.u32()? as usize;
+        let raw = self.take(len.saturating_mul(4))?;
+        Ok(raw.chunks_exact(4).map(|b| f32::from_le_bytes(b.try_into().unwrap())).collect())
+    }
+}
+
+/// Polyphase FIR interpolator for one upsampling factor.
+struct Interpolator {
+    factor: usize,
+    /// Coefficients split by output phase: `phases[p][k]` weights `history[k]`
+    phases: Vec<Vec<f32>>,
+    /// Most recent input samples, newest first
+    history: Vec<f32>,
+}
+
+impl Interpolator {
+    /// Hamming-windowed sinc cut off at the input Nyquist (1/(2·factor) of the
+    /// output rate). Each phase is normalized to unity DC gain, which also
+    /// makes up for the energy lost to zero-stuffing.
+    fn new(factor: usize) -> Self {
+        let taps = windowed_sinc(TAPS_PER_PHASE * factor, 0.5 / factor as f64);
+
+        let phases = (0..factor)
+            .map(|p| {
+                let branch: Vec<f64> = (0..TAPS_PER_PHASE).map(|k| taps[p + k * factor]).collect();
+                let sum: f64 = branch.iter().sum();
+                let mut coeffs: Vec<f32> = branch.iter().map(|&c| (c / sum) as f32).collect();
+                // Fold f32 rounding error into the center tap so DC passes exactly
+                let center = TAPS_PER_PHASE / 2;
+                for _ in 0..4 {
+                    coeffs[center] += 1.0 - coeffs.iter().sum::<f32>();
+                }
+                coeffs
+            })
+            .collect();
+
+        Self {
+            factor,
+            phases,
+            history: vec![0.0; TAPS_PER_PHASE],
+        }
+    }
+
+    /// Output sample for `phase` given the current history.
+    fn output(&self, phase: usize) -> f32 {
+        self.phases[phase]
+            .iter()
+            .zip(&self.history)
+            .map(|(&c, &x)| c * x)
+            .sum()
+    }
+}
+
+/// Rational resampler: conceptually zero-stuff by `up` (L), low-pass at the
+/// lower Nyquist, keep every `down`th (M) sample. Only the branch of the
+/// polyphase filter that lands on a kept sample is ever evaluated.
+struct RationalResampler {
+    input_rate: u32,
+    up: usize,
+    down: usize,
+    /// Coefficients split by phase: `phases[p][k]` weights `history[k]`
+    phases: Vec<Vec<f32>>,
+    /// Most recent input samples, newest first
+    history: Vec<f32>,
+    /// Offset of the next output within the current input sample's L
+    /// upsampled slots; carried across calls
+    pos: usize,
+}
+
+impl RationalResampler {
+    fn new(input_rate: u32, output_rate: u32) -> Self {
+        let (up, down) = rational_ratio(input_rate, output_rate).expect("unsupported rational ratio");
+        let taps_per_phase = rational_taps_per_phase(input_rate, output_rate);
+        // Cutoff relative to the upsampled rate, input_rate·L
+        let cutoff = RATIONAL_CUTOFF * output_rate as f64 / (input_rate as f64 * up as f64);
+        let taps = windowed_sinc(taps_per_phase * up as usize, cutoff);
+
+        // Unity DC gain per phase, which also makes up for zero-stuffing
+        let phases = (0..up as usize)
+            .map(|p| {
+                let branch: Vec<f64> = (0..taps_per_phase).map(|k| taps[p + k * up as usize]).collect();
+                let sum: f64 = branch.iter().sum();
+                branch.iter().map(|&c| (c / sum) as f32).collect()
+            })
+            .collect();
+
+        Self {
+            input_rate,
+            up: up as usize,
+            down: down as usize,
+            phases,
+            history: vec![0.0; taps_per_phase],
+            pos: 0,
+        }
+    }
+
+    fn output(&self, phase: usize) -> f32 {
+        self.phases[phase]
+            .iter()
+            .zip(&self.history)
+            .map(|(&c, &x)| c * x)
+            .sum()
+    }
+}
+
+/// A stage's output as handed to the next one: untouched in float mode,
+/// otherwise snapped to the Int16 grid.
+fn stage_output(value: f32, in_float: bool) -> f32 {
+    if in_float {
+        return value;
+    }
+    (value * 32767.0).round().clamp(-32768.0, 32767.0) / 32767.0
+}
+
+/// Integer decimation factor from `input_rate` down to `output_rate`, if
+/// there is one.
+fn decimation_factor(input_rate: u32, output_rate: u32) -> Option<usize> {
+    (input_rate > output_rate && input_rate.is_multiple_of(output_rate)).then(|| (input_rate / output_rate) as usize)
+}
+
+/// Reduced L/M for downsampling `input_rate` to `output_rate` by a
+/// non-integer ratio, if the filter bank for it stays a sensible size.
+fn rational_ratio(input_rate: u32, output_rate: u32) -> Option<(u32, u32)> {
+    if input_rate <= output_rate || input_rate.is_multiple_of(output_rate) {
+        return None;
+    }
+    let divisor = gcd(input_rate, output_rate);
+    let up = output_rate / divisor;
+    (up <= MAX_RATIONAL_UP).then_some((up, input_rate / divisor))
+}
+
+fn rational_taps_per_phase(input_rate: u32, output_rate: u32) -> usize {
+    (RATIONAL_TAPS_PER_RATIO * input_rate).div_ceil(output_rate) as usize
+}
+
+fn gcd(mut a: u32, mut b: u32) -> u32 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Whether `channels` is a layout `process` folds properly: mono, stereo,
+/// 5.1 or 7.1. Other non-zero counts are accepted, but without channel
+/// labels all their channels are simply averaged.
+pub fn is_expected_channel_count(channels: u32) -> bool {
+    matches!(channels, 1 | 2 | 6 | 8)
+}
+
+/// Front level in the mono fold of the ITU-R BS.775 Lo/Ro downmix
+const ITU_FRONT: f32 = 0.5;
+/// 1/√2: center level, and surround level into Lo/Ro, in ITU-R BS.775
+const ITU_LEVEL: f32 = std::f32::consts::FRAC_1_SQRT_2;
+/// Surround level in the mono fold
+const ITU_SURROUND: f32 = ITU_LEVEL / 2.0;
+
+/// Per-channel mono weights for common surround layouts, in WAVE/SMPTE
+/// channel order. Derived from the ITU-R BS.775 stereo downmix
+/// (Lo = L + C/√2 + Ls/√2, likewise Ro) then mono = (Lo + Ro)/2, so the
+/// center channel, where dialogue lives, lands at -3dB while the surrounds
+/// are 9dB down and LFE is dropped. `None` for other counts, which are
+/// averaged: 3, 4 or 5 channels could be several layouts (3 is L R C or
+/// 2.1, 4 quad or L C R S), and weighting the wrong one would be worse.
+fn surround_weights(channels: usize) -> Option<&'static [f32]> {
+    const F: f32 = ITU_FRONT;
+    const C: f32 = ITU_LEVEL;
+    const S: f32 = ITU_SURROUND;
+    match channels {
+        // L R C LFE Ls Rs
+        6 => Some(&[F, F, C, 0.0, S, S]),
+        // L R C LFE Ls Rs Lrs Rrs
+        8 => Some(&[F, F, C, 0.0, S, S, S, S]),
+        _ => None,
+    }
+}
+
+/// Mono weight for one CoreAudio `AudioChannelLabel`, per the same ITU fold.
+fn label_weight(label: u32) -> Option<f32> {
+    match label {
+        // Unused
+        0 => Some(0.0),
+        // Left, Right, LeftCenter, RightCenter, CenterSurround
+        1 | 2 | 7 | 8 | 9 => Some(ITU_FRONT),
+        // Center
+        3 => Some(ITU_LEVEL),
+        // LFEScreen, LFE2
+        4 | 37 => Some(0.0),
+        // LeftSurround, RightSurround, direct and rear surrounds
+        5 | 6 | 10 | 11 | 33 | 34 => Some(ITU_SURROUND),
+        _ => None,
+    }
+}
+
+/// Mono weights for a labeled surround layout; `None` if any label is one
+/// the downmix doesn't know, in which case channel positions are assumed.
+fn layout_weights(labels: &[u32]) -> Option<Vec<f32>> {
+    if labels.len() <= 2 {
+        return None;
+    }
+    labels.iter().map(|&label| label_weight(label)).collect()
+}
+
+fn weighted(frame: &[f32], weights: &[f32]) -> f32 {
+    frame.iter().zip(weights).map(|(s, w)| s * w).sum()
+}
+
+fn weighted_q15(frame: &[i32], weights: &[f32]) -> i32 {
+    let sum: i64 = frame
+        .iter()
+        .zip(weights)
+        .map(|(&x, w)| x as i64 * (w * 32768.0).round() as i64)
+        .sum();
+    div_round(sum, 1 << 15) as i32
+}
+
+/// Mono mixdown of one interleaved frame according to `mode`.
+fn mixdown(input: &[f32], frame_idx: usize, channels: u32, mode: MixdownMode) -> f32 {
+    let ch = channels as usize;
+    let frame = &input[frame_idx * ch..(frame_idx + 1) * ch];
+    if ch == 1 {
+        return frame[0];
+    }
+    match mode {
+        MixdownMode::Average if ch == 2 => (frame[0] + frame[1]) * 0.5,
+        MixdownMode::Average => match surround_weights(ch) {
+            Some(weights) => weighted(frame, weights),
+            None => frame.iter().sum::<f32>() / ch as f32,
+        },
+        MixdownMode::Rms => frame.iter().sum::<f32>() / (ch as f32).sqrt(),
+        MixdownMode::Peak => frame
+            .iter()
+            .copied()
+            .fold(0.0f32, |best, s| if s.abs() > best.abs() { s } else { best }),
+    }
+}
+
+/// Hamming-windowed sinc low-pass of `len` taps; `cutoff` is in cycles per
+/// sample (0.5 = Nyquist).
+fn windowed_sinc(len: usize, cutoff: f64) -> Vec<f64> {
+    use std::f64::consts::PI;
+    let center = (len - 1) as f64 / 2.0;
+    (0..len)
+        .map(|n| {
+            let x = n as f64 - center;
+            let sinc = if x == 0.0 {
+                2.0 * cutoff
+            } else {
+                (2.0 * PI * cutoff * x).sin() / (PI * x)
+            };
+            let window = 0.54 - 0.46 * (2.0 * PI * n as f64 / (len - 1) as f64).cos();
+            sinc * window
+        })
+        .collect()
+}
+
+/// Telephony anti-alias filter for decimating `input_rate` to 8kHz, with unity DC gain.
+fn telephony_taps(input_rate: u32) -> Vec<f32> {
+    let taps = windowed_sinc(TELEPHONY_TAPS, TELEPHONY_CUTOFF_HZ / input_rate as f64);
+    let sum: f64 = taps.iter().sum();
+    taps.iter().map(|&c| (c / sum) as f32).collect()
+}
+
+/// Float sample → Q15, keeping headroom above full scale (the float path
+/// only clamps after filtering).
+fn to_q15(x: f32) -> i32 {
+    (x * 32767.0).round().clamp(-(1 << 24) as f32, (1 << 24) as f32) as i32
+}
+
+/// Integer version of `mixdown` on one frame of Q15 samples.
+fn mixdown_q15(frame: &[i32], mode: MixdownMode) -> i32 {
+    /// 1/√2 in Q15
+    const INV_SQRT2_Q15: i64 = 23170;
+    let ch = frame.len() as i64;
+    if ch == 1 {
+        return frame[0];
+    }
+    let sum: i64 = frame.iter().map(|&x| x as i64).sum();
+    match mode {
+        MixdownMode::Average if ch > 2 => match surround_weights(ch as usize) {
+            Some(weights) => weighted_q15(frame, weights),
+            None => div_round(sum, ch) as i32,
+        },
+        MixdownMode::Average => div_round(sum, ch) as i32,
+        MixdownMode::Rms if ch == 2 => ((sum * INV_SQRT2_Q15 + (1 << 14)) >> 15) as i32,
+        MixdownMode::Rms => (sum as f64 / (ch as f64).sqrt()).round() as i32,
+        MixdownMode::Peak => frame
+            .iter()
+            .copied()
+            .fold(0, |best, s| if s.abs() > best.abs() { s } else { best }),
+    }
+}
+
+/// Integer division rounding half away from zero.
+fn div_round(n: i64, d: i64) -> i64 {
+    if n >= 0 {
+        (n + d / 2) / d
+    } else {
+        (n - d / 2) / d
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    // ── Deterministic test vectors ──────────────────────────────────────────
+
+    /// Mono sine tone at `freq` Hz, `len` samples at `rate` Hz.
+    fn sine(freq: f64, rate: u32, len: usize, amplitude: f64) -> Vec<f32> {
+        (0..len)
+            .map(|n| (amplitude * (2.0 * PI * freq * n as f64 / rate as f64).sin()) as f32)
+            .collect()
+    }
+
+    /// Linear sine sweep from `f0` to `f1` Hz over `len` samples at `rate` Hz.
+    fn sine_sweep(f0: f64, f1: f64, rate: u32, len: usize, amplitude: f64) -> Vec<f32> {
+        let duration = len as f64 / rate as f64;
+        (0..len)
+            .map(|n| {
+                let t = n as f64 / rate as f64;
+                let phase = 2.0 * PI * (f0 * t + (f1 - f0) * t * t / (2.0 * duration));
+                (amplitude * phase.sin()) as f32
+            })
+            .collect()
+    }
+
+    /// Reference 16kHz output: straightforward f64 convolution with
+    /// `LPF_TAPS` + decimation, independent of the delay-line bookkeeping in
+    /// `Resampler::process` (but not of the coefficients: see
+    /// `SWEEP_VECTORS` for that).
+    fn reference_output(mono: &[f32], input_rate: u32) -> Vec<i16> {
+        let factor = (input_rate / 16000) as usize;
+        let taps = LPF_TAPS.len();
+        let mut padded = vec![0.0f64; taps - 1];
+        padded.extend(mono.iter().map(|&s| s as f64));
+        (0..mono.len())
+            .filter(|n| (n + 1) % factor == 0)
+            .map(|n| {
+                let filtered: f64 = LPF_TAPS
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &c)| padded[n + i] * c as f64)
+                    .sum();
+                (filtered * 32767.0).round().clamp(-32768.0, 32767.0) as i16
+            })
+            .collect()
+    }
+
+    fn rms(samples: &[i16]) -> f64 {
+        let sum: f64 = samples.iter().map(|&s| (s as f64) * (s as f64)).sum();
+        (sum / samples.len() as f64).sqrt()
+    }
+
+    /// Output RMS for a 100ms tone, skipping the filter warm-up.
+    fn tone_rms(freq: f64) -> f64 {
+        let mut r = Resampler::new();
+        let output = r.process(&sine(freq, 48000, 4800, 0.5), 1, 48000);
+        rms(&output[LPF_TAPS.len()..])
+    }
+
+    #[test]
+    fn test_decimation_ratio() {
+        let mut r = Resampler::new();
+        // 4800 mono samples at 48kHz = 100ms → should produce ~1600 samples at 16kHz
+        let input = vec![0.0f32; 4800];
+        let output = r.process(&input, 1, 48000);
+        assert_eq!(output.len(), 1600);
+    }
+
+    #[test]
+    fn test_stereo_to_mono() {
+        let mut r = Resampler::new();
+        // Stereo input: left=0.5, right=-0.5 → mono should be ~0.0
+        let mut input = Vec::new();
+        for _ in 0..4800 {
+            input.push(0.5f32);
+            input.push(-0.5f32);
+        }
+        let output = r.process(&input, 2, 48000);
+        // After filter settles, output should be near 0
+        for &s in &output[10..] {
+            assert!(s.abs() < 100, "Expected near-zero, got {}", s);
+        }
+    }
+
+    #[test]
     fn test_clipping_protection() {
         let mut r = Resampler::new();
         let input = vec![2.0f32; 4800]; // Over-range input
@@ -126,4 +1843,1005 @@ mod tests {
             assert!(s <= 32767 && s >= -32768);
         }
     }
+
+    #[test]
+    fn test_counts_clipped_samples() {
+        let mut r = Resampler::new();
+        let quiet = r.process(&vec![0.5f32; 4800], 1, 48000);
+        assert_eq!(r.take_clipped(), 0);
+        assert!(!quiet.is_empty());
+
+        let output = r.process(&vec![2.0f32; 4800], 1, 48000);
+        let clipped = r.take_clipped();
+        // Everything past the filter warm-up is over range
+        assert!(clipped as usize > output.len() - LPF_TAPS.len());
+        assert_eq!(r.take_clipped(), 0);
+    }
+
+    /// Expected 16kHz output for `sine_sweep(20.0, 23_000.0, 48000, 48000,
+    /// 0.8)`, checked in: 16 samples from where the sweep is near 1kHz
+    /// (passband), 7.5kHz (cutoff), 12kHz and 20kHz (stopband). Any change to
+    /// the filter coefficients or decimation shows up here.
+    const SWEEP_VECTORS: [(usize, [i16; 16]); 4] = [
+        (680, [-20, -8210, -15169, -19837, -21498, -19886, -15235, -8244, 20, 8290, 15288, 19924, 21471, 19674, 14801, 7604]),
+        (5200, [661, -744, 796, -816, 804, -760, 686, -586, 464, -324, 173, -16, -140, 291, -429, 551]),
+        (8340, [5, -31, -5, 31, 5, -31, -5, 31, 5, -32, -6, 32, 6, -32, -6, 32]),
+        (13900, [37, 50, -36, -51, 36, 51, -36, -51, 35, 52, -35, -52, 35, 52, -35, -52]),
+    ];
+
+    #[test]
+    fn test_matches_checked_in_vectors() {
+        let input = sine_sweep(20.0, 23_000.0, 48000, 48000, 0.8);
+        let output = Resampler::new().process(&input, 1, 48000);
+        for (start, expected) in SWEEP_VECTORS {
+            for (i, (&got, &want)) in output[start..start + 16].iter().zip(&expected).enumerate() {
+                assert!(
+                    (got as i32 - want as i32).abs() <= 1,
+                    "sample {}: got {}, expected {}",
+                    start + i,
+                    got,
+                    want
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_matches_reference_vectors() {
+        let input = sine_sweep(20.0, 23_000.0, 48000, 48000, 0.8);
+        let expected = reference_output(&input, 48000);
+
+        // Feed in irregular chunks to exercise state carried across calls
+        let mut r = Resampler::new();
+        let mut output = Vec::new();
+        for chunk in input.chunks(1021) {
+            output.extend(r.process(chunk, 1, 48000));
+        }
+
+        assert_eq!(output.len(), expected.len());
+        for (i, (&got, &want)) in output.iter().zip(&expected).enumerate() {
+            assert!((got as i32 - want as i32).abs() <= 1, "sample {}: got {}, expected {}", i, got, want);
+        }
+    }
+
+    #[test]
+    fn test_output_independent_of_chunking() {
+        // Stereo sweep, split at pseudo-random frame counts (including 1-frame chunks)
+        let mono = sine_sweep(50.0, 20_000.0, 48000, 24_000, 0.7);
+        let input: Vec<f32> = mono.iter().flat_map(|&s| [s, -0.5 * s]).collect();
+
+        let configs: [fn() -> Resampler; 3] = [
+            Resampler::new,
+            || Resampler::with_output_rate(8000),
+            || {
+                let mut r = Resampler::new();
+                r.set_fixed_point(true);
+                r
+            },
+        ];
+        for make in configs {
+            let whole = make().process(&input, 2, 48000);
+
+            let mut r = make();
+            let mut chunked = Vec::new();
+            let mut state = 0x9E37_79B9u32;
+            let mut pos = 0;
+            while pos < input.len() {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                let frames = 1 + (state >> 16) as usize % 700;
+                let end = (pos + frames * 2).min(input.len());
+                chunked.extend(r.process(&input[pos..end], 2, 48000));
+                pos = end;
+            }
+
+            assert_eq!(whole, chunked);
+        }
+    }
+
+    #[test]
+    fn test_passband_response() {
+        // 1kHz should come through with the filter's DC gain (~0.86), minus a little rolloff
+        let expected_rms = 0.5 / 2f64.sqrt() * 32767.0;
+        let gain_db = 20.0 * (tone_rms(1000.0) / expected_rms).log10();
+        assert!(gain_db > -3.0 && gain_db < 0.0, "1kHz gain was {:.1} dB", gain_db);
+    }
+
+    #[test]
+    fn test_stopband_attenuation() {
+        // Anything above 7.5kHz would alias into the 16kHz output band. The
+        // 15-tap filter only reaches 40dB by 8.5kHz: 7.5–8.5kHz is let
+        // through at 28–40dB down and folds to 7.5–8kHz, at the very top of
+        // the output band. The thresholds hold it to that
+        let reference = tone_rms(1000.0);
+        for (freq, min_db) in [
+            (7500.0, 27.0),
+            (8000.0, 30.0),
+            (8500.0, 40.0),
+            (9000.0, 40.0),
+            (10_000.0, 40.0),
+            (12_000.0, 40.0),
+            (16_000.0, 40.0),
+            (20_000.0, 40.0),
+        ] {
+            let attenuation_db = 20.0 * (reference / tone_rms(freq).max(1e-9)).log10();
+            assert!(
+                attenuation_db > min_db,
+                "{} Hz only attenuated by {:.1} dB",
+                freq,
+                attenuation_db
+            );
+        }
+    }
+
+    /// Tone magnitude at `freq` Hz (Goertzel), normalized by length.
+    fn tone_level(samples: &[i16], freq: f64, rate: u32) -> f64 {
+        let w = 2.0 * PI * freq / rate as f64;
+        let (mut s1, mut s2) = (0.0f64, 0.0f64);
+        for &x in samples {
+            let s0 = x as f64 + 2.0 * w.cos() * s1 - s2;
+            s2 = s1;
+            s1 = s0;
+        }
+        (s1 * s1 + s2 * s2 - 2.0 * w.cos() * s1 * s2).sqrt() / samples.len() as f64
+    }
+
+    #[test]
+    fn test_upsample_from_8khz() {
+        let mut r = Resampler::new();
+        // 800 samples at 8kHz = 100ms → 1600 samples at 16kHz
+        let input = vec![0.5f32; 800];
+        let output = r.process(&input, 1, 8000);
+        assert_eq!(output.len(), 1600);
+        // Once the interpolation filter fills, the level holds exactly
+        for &s in &output[2 * TAPS_PER_PHASE..] {
+            assert_eq!(s, 16384);
+        }
+    }
+
+    #[test]
+    fn test_upsample_8khz_to_48khz_length() {
+        let mut r = Resampler::with_output_rate(48000);
+        let mut output = Vec::new();
+        for chunk in sine(440.0, 8000, 800, 0.5).chunks(77) {
+            output.extend(r.process(chunk, 1, 8000));
+        }
+        assert_eq!(output.len(), 4800);
+    }
+
+    #[test]
+    fn test_upsample_8khz_to_48khz_has_no_images() {
+        let mut r = Resampler::with_output_rate(48000);
+        let output = r.process(&sine(1000.0, 8000, 8000, 0.5), 1, 8000);
+        let settled = &output[6 * TAPS_PER_PHASE..];
+        let tone = tone_level(settled, 1000.0, 48000);
+        // Zero-stuffing mirrors 1kHz around multiples of 8kHz
+        for image in [7000.0, 9000.0, 15_000.0, 17_000.0, 23_000.0] {
+            let attenuation_db = 20.0 * (tone / tone_level(settled, image, 48000).max(1e-9)).log10();
+            assert!(
+                attenuation_db > 40.0,
+                "{} Hz image only {:.1} dB down",
+                image,
+                attenuation_db
+            );
+        }
+    }
+
+    #[test]
+    fn test_upsample_continuity_across_chunks() {
+        let input = sine(440.0, 11025, 1100, 0.5);
+        let whole = Resampler::new().process(&input, 1, 11025);
+        let mut r = Resampler::new();
+        let mut chunked = Vec::new();
+        for chunk in input.chunks(97) {
+            chunked.extend(r.process(chunk, 1, 11025));
+        }
+        assert_eq!(whole, chunked);
+    }
+
+    #[test]
+    fn test_six_channel_input() {
+        let mut r = Resampler::new();
+        // 5.1 frames with a partial trailing frame: must not index out of bounds
+        let mut input = Vec::new();
+        for _ in 0..4800 {
+            input.extend_from_slice(&[0.3f32, 0.3, 0.3, 0.0, 0.0, 0.0]);
+        }
+        input.extend_from_slice(&[1.0, 1.0, 1.0]);
+        let output = r.process(&input, 6, 48000);
+        assert_eq!(output.len(), 1600);
+        // ITU downmix: 0.3 * (0.5 + 0.5 + 1/√2), scaled by the filter's DC gain
+        let mono = 0.3 * (1.0 + ITU_LEVEL);
+        let expected = (mono * LPF_TAPS.iter().sum::<f32>() * 32767.0) as i16;
+        for &s in &output[10..] {
+            assert!((s - expected).abs() <= 2, "Expected ~{}, got {}", expected, s);
+        }
+    }
+
+    #[test]
+    fn test_unusual_channel_count_averages_all_channels() {
+        assert!(!is_expected_channel_count(3));
+        // Three channels at different DC levels, with a partial trailing frame
+        let mut input = [0.3f32, 0.6, -0.3].repeat(4800);
+        input.extend_from_slice(&[1.0, 1.0]);
+        let output = Resampler::new().process(&input, 3, 48000);
+        assert_eq!(output.len(), 1600);
+        let expected = (0.2 * LPF_TAPS.iter().sum::<f32>() * 32767.0) as i16;
+        for &s in &output[10..] {
+            assert!((s - expected).abs() <= 2, "Expected ~{}, got {}", expected, s);
+        }
+    }
+
+    #[test]
+    fn test_zero_channels_yields_nothing() {
+        let mut r = Resampler::new();
+        assert!(r.process(&[0.5f32; 96], 0, 48000).is_empty());
+    }
+
+    #[test]
+    fn test_primed_resampler_continues_without_a_seam() {
+        let input = sine(440.0, 48000, 9600, 0.5);
+        let (head, rest) = input.split_at(4800);
+        for fixed_point in [false, true] {
+            let make = || {
+                let mut r = Resampler::new();
+                r.set_fixed_point(fixed_point);
+                r
+            };
+            let whole = make().process(&input, 1, 48000);
+
+            let mut primed = make();
+            primed.prime(&head[head.len() - 960..], 48000);
+            assert_eq!(primed.process(rest, 1, 48000), whole[1600..]);
+
+            // Without the tail the first samples ramp up from silence
+            assert_ne!(make().process(rest, 1, 48000)[..40], whole[1600..1640]);
+        }
+    }
+
+    #[test]
+    fn test_zero_input_rate_yields_nothing_and_keeps_state() {
+        let input = sine(1000.0, 48000, 4800, 0.5);
+        let mut r = Resampler::new();
+        assert!(r.process(&input, 2, 0).is_empty());
+        assert_eq!(r.take_clipped(), 0);
+        // The bogus buffer leaves no trace on the filter state
+        assert_eq!(r.process(&input, 1, 48000), Resampler::new().process(&input, 1, 48000));
+    }
+
+    #[test]
+    fn test_mixdown_modes_on_hard_panned_input() {
+        // Left-only DC input: compare the settled level per mode
+        let input: Vec<f32> = (0..4800).flat_map(|_| [0.5f32, 0.0]).collect();
+        let level = |mode| {
+            let mut r = Resampler::new();
+            r.set_mixdown_mode(mode);
+            *r.process(&input, 2, 48000).last().unwrap() as f32
+        };
+        let average = level(MixdownMode::Average);
+        assert!(((level(MixdownMode::Peak) / average) - 2.0).abs() < 0.01);
+        assert!(((level(MixdownMode::Rms) / average) - 2f32.sqrt()).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_expander_keeps_speech_and_attenuates_noise() {
+        let settings = ExpanderSettings {
+            threshold_db: -40.0,
+            ratio: 3.0,
+            attack_ms: 5.0,
+            release_ms: 100.0,
+        };
+        let loud = sine(1000.0, 48000, 9600, 0.5);
+        let noise = sine(3000.0, 48000, 9600, 0.001);
+
+        let mut plain = Resampler::new();
+        let mut expanded = Resampler::new();
+        expanded.set_expander(settings);
+
+        // Well above threshold: bit-identical once the envelope has attacked
+        let reference = plain.process(&loud, 1, 48000);
+        let output = expanded.process(&loud, 1, 48000);
+        assert_eq!(output[160..], reference[160..]);
+
+        // -60dBFS noise after release: 20dB under threshold → ~40dB cut
+        let mut plain = Resampler::new();
+        let mut expanded = Resampler::new();
+        expanded.set_expander(settings);
+        let reference = rms(&plain.process(&noise, 1, 48000)[1600..]);
+        let output = rms(&expanded.process(&noise, 1, 48000)[1600..]);
+        let attenuation_db = 20.0 * (reference / output.max(1e-9)).log10();
+        assert!(attenuation_db > 30.0, "noise only attenuated by {:.1} dB", attenuation_db);
+    }
+
+    #[test]
+    fn test_seeded_dither_is_reproducible() {
+        let input = sine(1000.0, 48000, 9600, 0.001);
+        let seeded = |seed: u64| {
+            let mut resampler = Resampler::new();
+            resampler.set_dither(true);
+            resampler.set_dither_seed(seed);
+            resampler.process(&input, 1, 48000)
+        };
+        assert_eq!(seeded(42), seeded(42));
+        assert_ne!(seeded(42), seeded(43));
+
+        // Never more than 1 LSB from plain rounding
+        let plain = Resampler::new().process(&input, 1, 48000);
+        assert!(seeded(42).iter().zip(&plain).all(|(a, b)| (a - b).abs() <= 1));
+
+        // reset() rewinds the generator to the seed
+        let mut resampler = Resampler::new();
+        resampler.set_dither_seed(7);
+        resampler.set_dither(true);
+        let first = resampler.process(&input, 1, 48000);
+        resampler.reset();
+        assert_eq!(resampler.process(&input, 1, 48000), first);
+    }
+
+    #[test]
+    fn test_mono_input_skips_mixdown() {
+        let input = sine(1000.0, 48000, 4800, 0.5);
+        let mut reference = Resampler::new();
+        let expected = reference.process(&input, 1, 48000);
+        for mode in [MixdownMode::Peak, MixdownMode::Rms] {
+            let mut resampler = Resampler::new();
+            resampler.set_mixdown_mode(mode);
+            resampler.set_phase_protection(true);
+            assert_eq!(resampler.process(&input, 1, 48000), expected);
+        }
+    }
+
+    #[test]
+    fn test_normalize_raises_quiet_audio_and_removes_dc() {
+        // -30dBFS tone riding on a DC offset, 12s at 16kHz
+        let input: Vec<f32> = sine(440.0, 16000, 192_000, 0.0316).iter().map(|x| x + 0.05).collect();
+        let mut resampler = Resampler::new();
+        resampler.set_normalize_target(-6.0);
+        let output = resampler.process(&input, 1, 16000);
+
+        let tail = &output[160_000..];
+        let peak = tail.iter().map(|&s| (s as f32).abs()).fold(0.0, f32::max) / 32767.0;
+        let peak_db = 20.0 * peak.log10();
+        assert!((peak_db + 6.0).abs() < 1.0, "settled at {:.1} dBFS", peak_db);
+        let mean = tail.iter().map(|&s| s as f64).sum::<f64>() / tail.len() as f64;
+        assert!(mean.abs() < 50.0, "DC left: {}", mean);
+    }
+
+    #[test]
+    fn test_normalize_never_overshoots_on_sudden_peaks() {
+        let mut input = sine(440.0, 16000, 80_000, 0.01);
+        input.extend(sine(440.0, 16000, 16_000, 0.9));
+        let mut resampler = Resampler::new();
+        resampler.set_normalize_target(-3.0);
+        let output = resampler.process(&input, 1, 16000);
+
+        // The gain drops on the very sample that would overshoot
+        let ceiling = 32767.0 * 10f32.powf(-3.0 / 20.0) * 1.01;
+        assert!(output.iter().all(|&s| (s as f32).abs() <= ceiling));
+        assert_eq!(resampler.take_clipped(), 0);
+
+        // reset() forgets the learned gain
+        resampler.reset();
+        let mut fresh = Resampler::new();
+        fresh.set_normalize_target(-3.0);
+        assert_eq!(resampler.process(&input[..4800], 1, 16000), fresh.process(&input[..4800], 1, 16000));
+    }
+
+    #[test]
+    fn test_gate_and_agc_chain_rounds_only_once() {
+        let settings = ExpanderSettings {
+            threshold_db: -50.0,
+            ratio: 2.0,
+            attack_ms: 5.0,
+            release_ms: 100.0,
+        };
+        // -54dBFS: quiet enough that the expander cuts it and the normalizer
+        // then works toward its full +30dB, magnifying any rounding between
+        let input = sine(440.0, 16000, 64_000, 0.002);
+
+        // The same stages run in float by hand, rounded once at the end
+        let mut expander = Expander::new(settings, 16000);
+        let mut normalizer = Normalizer::new(-6.0, 16000);
+        let chained: Vec<f32> = input.iter().map(|&x| normalizer.process(expander.process(x))).collect();
+        let round_once: Vec<i16> = chained.iter().map(|&x| (x * 32767.0).round() as i16).collect();
+
+        let mut resampler = Resampler::new();
+        resampler.set_expander(settings);
+        resampler.set_normalize_target(-6.0);
+        assert_eq!(resampler.process(&input, 1, 16000), round_once);
+
+        // Rounding between the stages instead is off by many LSBs once the
+        // normalizer's gain has risen
+        let mut per_stage = Resampler::new();
+        per_stage.set_process_in_float(false);
+        per_stage.set_expander(settings);
+        per_stage.set_normalize_target(-6.0);
+        let output = per_stage.process(&input, 1, 16000);
+        let worst = output[48_000..]
+            .iter()
+            .zip(&round_once[48_000..])
+            .map(|(&a, &b)| (a as i32 - b as i32).abs())
+            .max()
+            .unwrap();
+        assert!(worst > 4, "intermediate rounding only moved samples by {} LSB", worst);
+    }
+
+    #[test]
+    fn test_float_processor_runs_before_conversion() {
+        struct Halve;
+        impl FloatProcessor for Halve {
+            fn process(&mut self, samples: &mut [f32]) {
+                samples.iter_mut().for_each(|x| *x *= 0.5);
+            }
+        }
+        // Odd values that halving in Int16 would have to round
+        let input: Vec<f32> = (0..480).map(|i| (2 * i + 1) as f32 / 32767.0).collect();
+        let mut r = Resampler::new();
+        r.set_fixed_point(true);
+        r.set_float_processor(Some(Box::new(Halve)));
+        let output = r.process(&input, 1, 16000);
+        let expected: Vec<i16> = (0..480).map(|i| ((2 * i + 1) as f32 * 0.5).round() as i16).collect();
+        assert_eq!(output, expected);
+
+        // Removed again: a 48kHz stream takes the fixed-point path as before
+        r.set_float_processor(None);
+        let tone = sine(1000.0, 48000, 4800, 0.5);
+        let mut plain = Resampler::new();
+        plain.set_fixed_point(true);
+        assert_eq!(r.process(&tone, 1, 48000), plain.process(&tone, 1, 48000));
+    }
+
+    #[test]
+    fn test_fixed_point_matches_float_path() {
+        // Deterministic white noise (LCG), stereo, in irregular chunks
+        let mut state = 0x1234_5678u32;
+        let input: Vec<f32> = (0..96_000)
+            .map(|_| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (state >> 8) as f32 / (1u32 << 23) as f32 - 1.0
+            })
+            .map(|x| x * 0.9)
+            .collect();
+
+        let mut float = Resampler::new();
+        let mut fixed = Resampler::new();
+        fixed.set_fixed_point(true);
+        let (mut a, mut b) = (Vec::new(), Vec::new());
+        for chunk in input.chunks(2 * 509) {
+            a.extend(float.process(chunk, 2, 48000));
+            b.extend(fixed.process(chunk, 2, 48000));
+        }
+
+        assert_eq!(a.len(), b.len());
+        for (i, (&x, &y)) in a.iter().zip(&b).enumerate() {
+            assert!((x as i32 - y as i32).abs() <= 1, "sample {}: float {}, fixed {}", i, x, y);
+        }
+    }
+
+    /// Output RMS at 8kHz for a 200ms tone, skipping the filter warm-up.
+    fn telephony_tone_rms(freq: f64) -> f64 {
+        let mut r = Resampler::with_output_rate(8000);
+        let output = r.process(&sine(freq, 48000, 9600, 0.5), 1, 48000);
+        rms(&output[TELEPHONY_TAPS / 6 + 1..])
+    }
+
+    #[test]
+    fn test_telephony_decimation_ratio() {
+        let mut r = Resampler::with_output_rate(8000);
+        assert_eq!(r.process(&vec![0.0f32; 4800], 2, 48000).len(), 400);
+    }
+
+    #[test]
+    fn test_telephony_passband_and_stopband() {
+        let reference = telephony_tone_rms(1000.0);
+        let expected_rms = 0.5 / 2f64.sqrt() * 32767.0;
+        let gain_db = 20.0 * (reference / expected_rms).log10();
+        assert!(gain_db.abs() < 0.5, "1kHz gain was {:.2} dB", gain_db);
+
+        let edge_db = 20.0 * (telephony_tone_rms(3400.0) / reference).log10();
+        assert!(edge_db > -3.0, "3.4kHz down {:.1} dB", -edge_db);
+
+        // Anything above 4.6kHz aliases into the 0–3.4kHz voice band
+        for freq in [4600.0, 6000.0, 8000.0, 12_000.0, 20_000.0] {
+            let attenuation_db = 20.0 * (reference / telephony_tone_rms(freq).max(1e-9)).log10();
+            assert!(
+                attenuation_db > 40.0,
+                "{} Hz only attenuated by {:.1} dB",
+                freq,
+                attenuation_db
+            );
+        }
+    }
+
+    /// 440Hz stereo with the right channel inverted (or not).
+    fn dual_mono(inverted: bool) -> Vec<f32> {
+        let sign = if inverted { -1.0 } else { 1.0 };
+        sine(440.0, 48000, 48000, 0.5)
+            .into_iter()
+            .flat_map(|s| [s, sign * s])
+            .collect()
+    }
+
+    #[test]
+    fn test_phase_protection_rescues_inverted_stereo() {
+        let input = dual_mono(true);
+
+        let mut r = Resampler::new();
+        assert!(rms(&r.process(&input, 2, 48000)) < 1.0);
+
+        let mut r = Resampler::new();
+        r.set_phase_protection(true);
+        let output = r.process(&input, 2, 48000);
+        // Only the first 100ms window goes through the cancelling mixdown;
+        // after that it matches in-phase stereo
+        let reference = rms(&Resampler::new().process(&dual_mono(false), 2, 48000)[1600..]);
+        assert!((rms(&output[1600..]) - reference).abs() < reference * 0.01);
+        assert!(r.take_phase_warning());
+        assert!(!r.take_phase_warning());
+    }
+
+    #[test]
+    fn test_phase_protection_leaves_normal_stereo_alone() {
+        let input = dual_mono(false);
+        let mut plain = Resampler::new();
+        let mut guarded = Resampler::new();
+        guarded.set_phase_protection(true);
+        assert_eq!(plain.process(&input, 2, 48000), guarded.process(&input, 2, 48000));
+        assert!(!guarded.take_phase_warning());
+    }
+
+    #[test]
+    fn test_surround_downmix_emphasizes_center() {
+        // 5.1 with a tone on the center channel only
+        let tone = sine(1000.0, 48000, 4800, 0.5);
+        let frames = |channel: usize| -> Vec<f32> {
+            tone.iter()
+                .flat_map(|&s| {
+                    let mut frame = [0.0f32; 6];
+                    frame[channel] = s;
+                    frame
+                })
+                .collect()
+        };
+        let level = |input: &[f32]| rms(&Resampler::new().process(input, 6, 48000)[200..]);
+
+        let mono_reference = rms(&Resampler::new().process(&tone, 1, 48000)[200..]);
+        let center_db = 20.0 * (level(&frames(2)) / mono_reference).log10();
+        assert!((center_db + 3.0).abs() < 0.2, "center at {:.2} dB", center_db);
+
+        // A plain average would put every channel at -15.6dB
+        let surround_db = 20.0 * (level(&frames(4)) / mono_reference).log10();
+        assert!((surround_db + 9.0).abs() < 0.2, "surround at {:.2} dB", surround_db);
+        assert!(level(&frames(3)) < 1.0, "LFE should be dropped");
+    }
+
+    #[test]
+    fn test_surround_downmix_fixed_point_matches_float() {
+        let input: Vec<f32> = sine(440.0, 48000, 4800 * 6, 0.3);
+        let float = Resampler::new().process(&input, 6, 48000);
+        let mut fixed = Resampler::new();
+        fixed.set_fixed_point(true);
+        let fixed = fixed.process(&input, 6, 48000);
+        assert_eq!(float.len(), fixed.len());
+        assert!(float.iter().zip(&fixed).all(|(a, b)| (a - b).abs() <= 1));
+    }
+
+    #[test]
+    fn test_low_latency_cuts_group_delay() {
+        let mut hq = Resampler::new();
+        let mut fast = Resampler::new();
+        fast.set_low_latency(true);
+        assert_eq!(hq.latency_ms(), 0.0);
+
+        // An impulse comes out `latency_ms` later on each path
+        let mut input = vec![0.0f32; 4800];
+        input[1200] = 1.0;
+        let peak_index = |output: Vec<i16>| {
+            (0..output.len()).max_by_key(|&i| output[i].unsigned_abs()).unwrap()
+        };
+        let hq_peak = peak_index(hq.process(&input, 1, 48000));
+        let fast_peak = peak_index(fast.process(&input, 1, 48000));
+        assert!(fast.latency_ms() < hq.latency_ms());
+        assert!((hq.latency_ms() - 7.0 / 48.0).abs() < 1e-9);
+        assert!(fast_peak < hq_peak, "low-latency peak at {}, FIR at {}", fast_peak, hq_peak);
+    }
+
+    #[test]
+    fn test_low_latency_is_chunking_invariant() {
+        let input = sine(1000.0, 48000, 4800, 0.5);
+        let mut whole = Resampler::new();
+        whole.set_low_latency(true);
+        let expected = whole.process(&input, 1, 48000);
+        assert_eq!(expected.len(), 1600);
+
+        let mut chunked = Resampler::new();
+        chunked.set_low_latency(true);
+        let output: Vec<i16> = input.chunks(487).flat_map(|c| chunked.process(c, 1, 48000)).collect();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_low_latency_survives_an_input_rate_drop() {
+        let mut resampler = Resampler::new();
+        resampler.set_low_latency(true);
+        // Leaves a block of 4 at 96kHz (factor 6), more than 48kHz's factor 3
+        assert!(resampler.process(&sine(1000.0, 96000, 4, 0.5), 1, 96000).is_empty());
+
+        let input = sine(1000.0, 48000, 4800, 0.5);
+        assert_eq!(resampler.expected_output_len(input.len(), 48000), 1600);
+        let output = resampler.process(&input, 1, 48000);
+        let mut fresh = Resampler::new();
+        fresh.set_low_latency(true);
+        assert_eq!(output, fresh.process(&input, 1, 48000));
+    }
+
+    #[test]
+    fn test_channel_layout_overrides_positions() {
+        // MPEG 5.1 "D" order: C L R Ls Rs LFE, tone on the center only
+        let labels = [3, 1, 2, 5, 6, 4];
+        let tone = sine(1000.0, 48000, 4800, 0.5);
+        let input: Vec<f32> = tone.iter().flat_map(|&s| [s, 0.0, 0.0, 0.0, 0.0, 0.0]).collect();
+        let mono_reference = rms(&Resampler::new().process(&tone, 1, 48000)[200..]);
+        let level_db = |labels: Option<&[u32]>| {
+            let mut r = Resampler::new();
+            r.set_channel_layout(labels);
+            20.0 * (rms(&r.process(&input, 6, 48000)[200..]) / mono_reference).log10()
+        };
+
+        let labeled = level_db(Some(&labels));
+        assert!((labeled + 3.0).abs() < 0.2, "center at {:.2} dB", labeled);
+        // Without a layout, index 0 is taken to be the left channel
+        let positional = level_db(None);
+        assert!((positional + 6.0).abs() < 0.2, "left at {:.2} dB", positional);
+        // Discrete (unlabeled) channels fall back to positions too
+        let discrete = level_db(Some(&[0x10000, 0x10001, 0x10002, 0x10003, 0x10004, 0x10005]));
+        assert!((discrete - positional).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_louder_mixdown_tracks_the_active_side() {
+        assert_eq!(MixdownMode::parse(Some("Louder")), Some(MixdownMode::Peak));
+        // Two speakers panned hard L and R, taking turns every 4 frames,
+        // each with the other's mic bleed at a tenth of the level
+        let input: Vec<f32> = (0..64)
+            .flat_map(|i| {
+                let voice = 0.5 * if i % 2 == 0 { 1.0 } else { -1.0 };
+                if (i / 4) % 2 == 0 { [voice, voice * 0.1] } else { [voice * 0.1, voice] }
+            })
+            .collect();
+        let mut r = Resampler::new();
+        r.set_mixdown_mode(MixdownMode::Peak);
+        let output = r.process(&input, 2, 16000);
+        // Full level throughout, where averaging would give 0.275
+        for (i, &s) in output.iter().enumerate() {
+            let expected = if i % 2 == 0 { 16384 } else { -16384 };
+            assert!((s as i32 - expected).abs() <= 1, "frame {}: {}", i, s);
+        }
+    }
+
+    #[test]
+    fn test_peak_mixdown_keeps_sign() {
+        let frame = [0.2f32, -0.7];
+        assert_eq!(mixdown(&frame, 0, 2, MixdownMode::Peak), -0.7);
+    }
+
+    #[test]
+    fn test_expected_output_len_matches_process() {
+        for (rate, low_latency) in [(48000, false), (48000, true), (44100, false), (16000, false), (8000, false), (11025, false)] {
+            let mut r = Resampler::new();
+            r.set_low_latency(low_latency);
+            for frames in [441, 480, 1, 1024, 7, 333] {
+                let expected = r.expected_output_len(frames, rate);
+                let input = vec![0.1f32; frames * 2];
+                assert_eq!(r.process(&input, 2, rate).len(), expected, "{} Hz, {} frames", rate, frames);
+            }
+        }
+    }
+
+    #[test]
+    fn test_44k1_resamples_with_correct_frequency() {
+        let mut r = Resampler::new();
+        let output = r.process(&sine(1000.0, 44100, 44100, 0.5), 1, 44100);
+        // 160/441 of the input, exactly
+        assert_eq!(output.len(), 16000);
+
+        let settled = &output[1000..];
+        // 15000 samples of 1kHz at 16kHz cross zero 1875 times
+        let crossings = settled.windows(2).filter(|w| (w[0] < 0) != (w[1] < 0)).count();
+        assert!(crossings.abs_diff(1875) <= 2, "{} zero crossings", crossings);
+        let gain_db = 20.0 * (rms(settled) / (0.5 / 2f64.sqrt() * 32767.0)).log10();
+        assert!(gain_db.abs() < 0.5, "1kHz gain was {:.2} dB", gain_db);
+    }
+
+    #[test]
+    fn test_44k1_stopband_attenuation() {
+        let level = |freq: f64| {
+            let output = Resampler::new().process(&sine(freq, 44100, 8820, 0.5), 1, 44100);
+            rms(&output[100..])
+        };
+        let reference = level(1000.0);
+        // Each of these would otherwise fold back into 0–8kHz
+        for freq in [9000.0, 10_000.0, 12_000.0, 16_000.0, 20_000.0] {
+            let attenuation_db = 20.0 * (reference / level(freq).max(1e-9)).log10();
+            assert!(
+                attenuation_db > 40.0,
+                "{} Hz only attenuated by {:.1} dB",
+                freq,
+                attenuation_db
+            );
+        }
+    }
+
+    #[test]
+    fn test_44k1_family_rates_and_chunking() {
+        for rate in [22050, 44100, 88200, 176400] {
+            let input = sine(440.0, rate, rate as usize / 10, 0.5);
+            let whole = Resampler::new().process(&input, 1, rate);
+            assert_eq!(whole.len(), 1600, "{} Hz", rate);
+
+            let mut r = Resampler::new();
+            let mut chunked = Vec::new();
+            for chunk in input.chunks(441) {
+                chunked.extend(r.process(chunk, 1, rate));
+            }
+            assert_eq!(whole, chunked, "{} Hz", rate);
+        }
+    }
+
+    #[test]
+    fn test_16khz_mono_passes_through_unfiltered() {
+        let mut input = sine(7000.0, 16000, 1600, 0.5);
+        // An impulse would be smeared across the taps by any lowpass
+        input[800] = 0.9;
+        let mut r = Resampler::new();
+        let output = r.process(&input, 1, 16000);
+
+        let quantized: Vec<i16> = input.iter().map(|&x| (x * 32767.0).round() as i16).collect();
+        assert_eq!(output, quantized);
+        assert_eq!(r.latency_ms(), 0.0);
+    }
+
+    /// Mono output for stereo `input` under `map`, after the filter settles.
+    fn mapped_output(map: ChannelMap, input: &[f32], mode: MixdownMode) -> Vec<i16> {
+        let mut r = Resampler::new();
+        r.set_mixdown_mode(mode);
+        r.set_channel_map(map);
+        r.process(input, 2, 48000).split_off(LPF_TAPS.len())
+    }
+
+    #[test]
+    fn test_channel_map_transforms() {
+        let apply = |map: ChannelMap| {
+            let mut frame = [0.5f32, -0.25, 0.1];
+            map.apply(&mut frame);
+            frame
+        };
+        assert_eq!(apply(ChannelMap::default()), [0.5, -0.25, 0.1]);
+        assert_eq!(apply(ChannelMap { swap_lr: true, ..Default::default() }), [-0.25, 0.5, 0.1]);
+        assert_eq!(apply(ChannelMap { invert_l: true, ..Default::default() }), [-0.5, -0.25, 0.1]);
+        assert_eq!(apply(ChannelMap { invert_r: true, ..Default::default() }), [0.5, 0.25, 0.1]);
+        // Inversions apply to the channels after the swap
+        let all = ChannelMap { swap_lr: true, invert_l: true, invert_r: true };
+        assert_eq!(apply(all), [0.25, -0.5, 0.1]);
+
+        let mut mono = [0.5f32];
+        all.apply(&mut mono);
+        assert_eq!(mono, [0.5]);
+    }
+
+    #[test]
+    fn test_channel_map_swap_changes_which_channel_leads() {
+        // Left loud, right quiet and inverted: after a swap, phase
+        // protection (which keeps L) picks up the quiet channel
+        let mono = sine(1000.0, 48000, 9600, 0.5);
+        let input: Vec<f32> = mono.iter().flat_map(|&s| [s, -0.5 * s]).collect();
+        let level = |map: ChannelMap| {
+            let mut r = Resampler::new();
+            r.set_phase_protection(true);
+            r.set_channel_map(map);
+            rms(&r.process(&input, 2, 48000)[1600..])
+        };
+        let straight = level(ChannelMap::default());
+        let swapped = level(ChannelMap { swap_lr: true, ..Default::default() });
+        assert!((swapped / straight - 0.5).abs() < 0.01, "{} vs {}", swapped, straight);
+    }
+
+    #[test]
+    fn test_channel_map_invert_restores_cancelled_stereo() {
+        let mono = sine(1000.0, 48000, 4800, 0.5);
+        let reference = rms(&mapped_output(
+            ChannelMap::default(),
+            &mono.iter().flat_map(|&s| [s, s]).collect::<Vec<_>>(),
+            MixdownMode::Average,
+        ));
+
+        // R inverted by the driver: sums to silence unless corrected
+        let right_inverted: Vec<f32> = mono.iter().flat_map(|&s| [s, -s]).collect();
+        assert!(rms(&mapped_output(ChannelMap::default(), &right_inverted, MixdownMode::Average)) < 1.0);
+        let invert_r = ChannelMap { invert_r: true, ..Default::default() };
+        let fixed = rms(&mapped_output(invert_r, &right_inverted, MixdownMode::Average));
+        assert!((fixed / reference - 1.0).abs() < 0.01, "{} vs {}", fixed, reference);
+
+        // Likewise for L
+        let left_inverted: Vec<f32> = mono.iter().flat_map(|&s| [-s, s]).collect();
+        let invert_l = ChannelMap { invert_l: true, ..Default::default() };
+        let fixed = rms(&mapped_output(invert_l, &left_inverted, MixdownMode::Average));
+        assert!((fixed / reference - 1.0).abs() < 0.01, "{} vs {}", fixed, reference);
+    }
+
+    #[test]
+    fn test_channel_map_fixed_point_matches_float() {
+        let mono = sine(1000.0, 48000, 4800, 0.5);
+        let input: Vec<f32> = mono.iter().flat_map(|&s| [-s, 0.3 * s]).collect();
+        let map = ChannelMap { swap_lr: true, invert_l: true, invert_r: true };
+        let float = mapped_output(map, &input, MixdownMode::Average);
+
+        let mut r = Resampler::new();
+        r.set_fixed_point(true);
+        r.set_channel_map(map);
+        let fixed = r.process(&input, 2, 48000).split_off(LPF_TAPS.len());
+        for (f, x) in float.iter().zip(&fixed) {
+            assert!((f - x).abs() <= 1, "{} vs {}", f, x);
+        }
+    }
+
+    #[test]
+    fn test_custom_coefficients_replace_builtin_filter() {
+        assert_eq!(Resampler::new().coefficients(), LPF_TAPS.to_vec());
+
+        // A 3-tap boxcar decimates 48kHz by averaging each block of 3
+        let third = 1.0 / 3.0;
+        let mut r = Resampler::with_coefficients(&[third, third, third]).unwrap();
+        assert_eq!(r.coefficients().len(), 3);
+        let output = r.process(&[0.3, 0.6, 0.9, -0.3, -0.6, -0.9], 1, 48000);
+        assert_eq!(output, vec![(0.6f32 * 32767.0).round() as i16, (-0.6f32 * 32767.0).round() as i16]);
+
+        assert!(Resampler::with_coefficients(&[]).is_err());
+        assert!(Resampler::with_coefficients(&[0.5, 0.6]).is_err());
+        assert!(Resampler::with_coefficients(&[f32::NAN, 1.0]).is_err());
+    }
+
+    #[test]
+    fn test_exported_state_resumes_mid_stream() {
+        let configured = || {
+            let mut r = Resampler::new();
+            r.set_expander(ExpanderSettings {
+                threshold_db: -40.0,
+                ratio: 2.0,
+                attack_ms: 5.0,
+                release_ms: 100.0,
+            });
+            r.set_normalize_target(-6.0);
+            r.set_dither_seed(7);
+            r.set_dither(true);
+            r
+        };
+        // Decimation, rational, integer interpolation and linear upsampling
+        for rate in [48000, 44100, 8000, 11025] {
+            let input = sine_sweep(100.0, 3000.0, rate, rate as usize, 0.3);
+            let split = input.len() / 3 + 7;
+            let expected = configured().process(&input, 1, rate);
+
+            let mut first = configured();
+            let mut output = first.process(&input[..split], 1, rate);
+            let bytes = first.export_state().to_bytes();
+            let mut second = configured();
+            second.import_state(&ResamplerState::from_bytes(&bytes).unwrap()).unwrap();
+            output.extend(second.process(&input[split..], 1, rate));
+            assert_eq!(output, expected, "{}Hz resumed differently", rate);
+        }
+    }
+
+    #[test]
+    fn test_import_rejects_mismatched_state() {
+        let mut source = Resampler::new();
+        source.process(&sine(440.0, 48000, 480, 0.5), 1, 48000);
+        let state = source.export_state();
+
+        let mut telephony = Resampler::with_output_rate(8000);
+        assert!(telephony.import_state(&state).is_err());
+        let mut expanded = Resampler::new();
+        expanded.set_normalize_target(-6.0);
+        assert!(expanded.import_state(&state).is_err());
+
+        let bytes = state.to_bytes();
+        assert!(ResamplerState::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(ResamplerState::from_bytes(b"nope").is_err());
+        assert_eq!(ResamplerState::from_bytes(&bytes).unwrap(), state);
+    }
+
+    #[test]
+    fn test_import_rejects_out_of_range_positions() {
+        let mut source = Resampler::new();
+        source.set_dither_seed(7);
+        source.set_dither(true);
+        source.process(&sine(440.0, 11025, 1100, 0.5), 1, 11025);
+        let upsampling = source.export_state();
+        source.process(&sine(440.0, 48000, 481, 0.5), 1, 48000);
+        let decimating = source.export_state();
+
+        let mut target = Resampler::new();
+        target.set_dither(true);
+        assert!(target.import_state(&upsampling).is_ok());
+        assert!(target.import_state(&decimating).is_ok());
+
+        let mut rejected = |edit: &dyn Fn(&mut ResamplerState), base: &ResamplerState| {
+            let mut state = base.clone();
+            edit(&mut state);
+            let before = target.export_state();
+            let rejected = target.import_state(&state).is_err();
+            // A rejected state leaves the resampler untouched
+            assert!(!rejected || target.export_state() == before);
+            rejected
+        };
+        assert!(rejected(&|s| s.up_pos = f64::NAN, &upsampling));
+        assert!(rejected(&|s| s.up_pos = -1e300, &upsampling));
+        assert!(rejected(&|s| s.up_pos = 11025.0 / 16000.0 + 1.0, &upsampling));
+        assert!(rejected(&|s| s.phase = 3, &decimating));
+        assert!(rejected(&|s| s.box_count = 3, &decimating));
+        assert!(rejected(&|s| s.dither = Some(0), &decimating));
+        assert!(!rejected(&|s| s.phase = 2, &decimating));
+    }
+
+    #[test]
+    fn test_raw_tap_sees_audio_before_the_output_stages() {
+        let input = sine(440.0, 48000, 4800, 0.1);
+        let mut plain = Resampler::new();
+        let expected = plain.process(&input, 1, 48000);
+
+        let mut normalized = Resampler::new();
+        normalized.set_normalize_target(-6.0);
+        normalized.set_raw_tap(true);
+        let output = normalized.process(&input, 1, 48000);
+        let raw = normalized.take_raw_tap().unwrap();
+        assert_eq!(raw.len(), output.len());
+        assert_ne!(output, expected);
+        let raw: Vec<i16> = raw.iter().map(|&x| (x * 32767.0).round() as i16).collect();
+        assert_eq!(raw, expected);
+        assert_eq!(normalized.take_raw_tap(), Some(Vec::new()));
+
+        normalized.set_raw_tap(false);
+        normalized.process(&input, 1, 48000);
+        assert_eq!(normalized.take_raw_tap(), None);
+    }
+
+    /// Identity `FloatProcessor`, to switch paths without changing the audio
+    struct PassThrough;
+
+    impl FloatProcessor for PassThrough {
+        fn process(&mut self, _samples: &mut [f32]) {}
+    }
+
+    /// Fixed-point output of `input` with `toggle` applied before `switch_at`
+    /// frames and undone before `switch_back_at`, against an untouched run.
+    fn assert_path_switch_is_seamless(toggle: impl Fn(&mut Resampler, bool)) {
+        let input = sine(440.0, 48000, 14_400, 0.5);
+        let mut reference = Resampler::new();
+        reference.set_fixed_point(true);
+        let expected = reference.process(&input, 1, 48000);
+
+        let mut switched = Resampler::new();
+        switched.set_fixed_point(true);
+        let mut output = switched.process(&input[..4800], 1, 48000);
+        toggle(&mut switched, true);
+        output.extend(switched.process(&input[4800..9600], 1, 48000));
+        toggle(&mut switched, false);
+        output.extend(switched.process(&input[9600..], 1, 48000));
+
+        assert_eq!(output.len(), expected.len());
+        for (i, (a, b)) in output.iter().zip(&expected).enumerate() {
+            assert!((*a as i32 - *b as i32).abs() <= 1, "sample {}: {} vs {}", i, a, b);
+        }
+    }
+
+    #[test]
+    fn test_raw_tap_toggled_mid_stream_keeps_fixed_point_output() {
+        assert_path_switch_is_seamless(|resampler, on| resampler.set_raw_tap(on));
+    }
+
+    #[test]
+    fn test_frame_processor_swapped_mid_stream_keeps_fixed_point_output() {
+        assert_path_switch_is_seamless(|resampler, on| {
+            resampler.set_float_processor(on.then(|| Box::new(PassThrough) as Box<dyn FloatProcessor>))
+        });
+    }
 }
@@ -0,0 +1,200 @@
+//! Rolling retention: keep the last N ms of capture as Opus packets in memory
+//!
+//! Samples are encoded 20ms at a time as they arrive, so memory stays bounded
+//! by the retention window and the Opus bitrate rather than by session length.
+//! `save` writes the retained window out as a .caf file.
+
+use std::collections::VecDeque;
+use std::ffi::{c_void, CString};
+use std::os::raw::c_char;
+use std::time::{Duration, Instant};
+
+use crate::logging::native_log;
+
+/// PCM samples per Opus packet (20ms at 16kHz); must match the ObjC bridge
+const FRAMES_PER_PACKET: usize = 320;
+/// Upper bound for a single encoded packet
+const MAX_PACKET_BYTES: usize = 1500;
+/// Minimum time between two logged encode failures
+const FAILURE_LOG_INTERVAL: Duration = Duration::from_secs(10);
+
+extern "C" {
+    fn voxtape_opus_encoder_new() -> *mut c_void;
+    fn voxtape_opus_encode_packet(
+        encoder: *mut c_void,
+        pcm: *const i16,
+        out: *mut u8,
+        out_capacity: u32,
+        out_len: *mut u32,
+    ) -> i32;
+    fn voxtape_opus_encoder_free(encoder: *mut c_void);
+    fn voxtape_opus_write_caf(
+        path: *const c_char,
+        encoder: *mut c_void,
+        data: *const u8,
+        packet_sizes: *const u32,
+        packet_count: u32,
+    ) -> i32;
+}
+
+/// Fixed-capacity FIFO of encoded packets; the oldest fall off the front.
+struct PacketRing {
+    packets: VecDeque<Vec<u8>>,
+    capacity: usize,
+}
+
+impl PacketRing {
+    fn new(capacity: usize) -> Self {
+        Self {
+            packets: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+        }
+    }
+
+    fn push(&mut self, packet: Vec<u8>) {
+        if self.packets.len() == self.capacity {
+            self.packets.pop_front();
+        }
+        self.packets.push_back(packet);
+    }
+
+    /// Concatenated packet bytes plus the size of each packet.
+    fn flatten(&self) -> (Vec<u8>, Vec<u32>) {
+        let mut data = Vec::with_capacity(self.packets.iter().map(Vec::len).sum());
+        let mut sizes = Vec::with_capacity(self.packets.len());
+        for packet in &self.packets {
+            data.extend_from_slice(packet);
+            sizes.push(packet.len() as u32);
+        }
+        (data, sizes)
+    }
+
+    fn len(&self) -> usize {
+        self.packets.len()
+    }
+}
+
+/// Opus encoder feeding a bounded packet ring.
+pub struct RollingBuffer {
+    encoder: *mut c_void,
+    pending: Vec<i16>,
+    ring: PacketRing,
+    /// Packets that failed to encode since the last logged failure, and
+    /// when that was
+    failed_packets: u64,
+    last_failure_log: Option<Instant>,
+}
+
+unsafe impl Send for RollingBuffer {}
+
+impl RollingBuffer {
+    /// Create a buffer that retains the most recent `retention_ms` of audio.
+    pub fn new(retention_ms: u32) -> Result<Self, String> {
+        let encoder = unsafe { voxtape_opus_encoder_new() };
+        if encoder.is_null() {
+            return Err("Failed to create Opus encoder".to_string());
+        }
+        let packet_ms = (FRAMES_PER_PACKET / 16) as u32;
+        Ok(Self {
+            encoder,
+            pending: Vec::with_capacity(FRAMES_PER_PACKET * 2),
+            ring: PacketRing::new(retention_ms.div_ceil(packet_ms) as usize),
+            failed_packets: 0,
+            last_failure_log: None,
+        })
+    }
+
+    /// Encode 16kHz mono samples into the ring, 20ms at a time.
+    pub fn push(&mut self, samples: &[i16]) {
+        self.pending.extend_from_slice(samples);
+        let mut out = [0u8; MAX_PACKET_BYTES];
+        let mut consumed = 0;
+        while self.pending.len() - consumed >= FRAMES_PER_PACKET {
+            let mut out_len = 0u32;
+            let status = unsafe {
+                voxtape_opus_encode_packet(
+                    self.encoder,
+                    self.pending[consumed..].as_ptr(),
+                    out.as_mut_ptr(),
+                    out.len() as u32,
+                    &mut out_len,
+                )
+            };
+            consumed += FRAMES_PER_PACKET;
+            if status != 0 {
+                self.log_failure(status);
+                continue;
+            }
+            if out_len > 0 {
+                self.ring.push(out[..out_len as usize].to_vec());
+            }
+        }
+        self.pending.drain(..consumed);
+    }
+
+    /// Log a failed packet, at most every 10s (with the count since), so a
+    /// persistent failure doesn't log 50 times a second.
+    fn log_failure(&mut self, status: i32) {
+        self.failed_packets += 1;
+        if self.last_failure_log.is_some_and(|t| t.elapsed() < FAILURE_LOG_INTERVAL) {
+            return;
+        }
+        native_log!(
+            Error,
+            "Opus encode failed with status {}; {} packet(s) lost from the rolling buffer since the last report",
+            status,
+            self.failed_packets
+        );
+        self.failed_packets = 0;
+        self.last_failure_log = Some(Instant::now());
+    }
+
+    /// Duration of audio currently retained, in milliseconds.
+    pub fn retained_ms(&self) -> f64 {
+        (self.ring.len() * FRAMES_PER_PACKET) as f64 / 16.0
+    }
+
+    /// Write the retained window to an Opus .caf file at `path`.
+    /// Returns the duration written in milliseconds.
+    pub fn save(&self, path: &str) -> Result<f64, String> {
+        let c_path = CString::new(path).map_err(|_| "Path contains a NUL byte".to_string())?;
+        let (data, sizes) = self.ring.flatten();
+        let status = unsafe {
+            voxtape_opus_write_caf(
+                c_path.as_ptr(),
+                self.encoder,
+                data.as_ptr(),
+                sizes.as_ptr(),
+                sizes.len() as u32,
+            )
+        };
+        if status != 0 {
+            return Err(format!("Failed to write {} (status {})", path, status));
+        }
+        Ok(self.retained_ms())
+    }
+}
+
+impl Drop for RollingBuffer {
+    fn drop(&mut self) {
+        unsafe { voxtape_opus_encoder_free(self.encoder) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring_keeps_most_recent_packets() {
+        let mut ring = PacketRing::new(3);
+        for i in 0..5u8 {
+            ring.push(vec![i; i as usize + 1]);
+        }
+        let (data, sizes) = ring.flatten();
+        assert_eq!(sizes, vec![3, 4, 5]);
+        assert_eq!(data.len(), 12);
+        assert_eq!(data[0], 2);
+        assert_eq!(data[11], 4);
+    }
+}
@@ -0,0 +1,106 @@
+//! Pause/resume segment bookkeeping
+//!
+//! Each run of delivered audio between pauses is one segment. Segment bounds
+//! come from the SCK buffer timestamps (relative to the first buffer of the
+//! session) and sample offsets from the count of delivered 16kHz samples, so
+//! a consumer can lay the delivered audio back onto a gapped timeline.
+
+/// Output samples per second
+const OUTPUT_RATE: u64 = 16000;
+
+/// One contiguous run of delivered audio.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Segment {
+    /// Start on the session timeline, in nanoseconds since the first buffer
+    pub start_ns: u64,
+    /// End on the session timeline, in nanoseconds since the first buffer
+    pub end_ns: u64,
+    /// Index of the segment's first sample in the delivered 16kHz stream
+    pub sample_offset: u64,
+    /// Number of 16kHz samples in the segment
+    pub sample_count: u64,
+}
+
+#[derive(Default)]
+pub struct SegmentTracker {
+    anchor_ns: Option<u64>,
+    current: Option<Segment>,
+    done: Vec<Segment>,
+    samples_out: u64,
+}
+
+impl SegmentTracker {
+    /// Record a buffer stamped `host_ns` that resampled to `len` samples.
+    /// Paused buffers close the open segment and are not counted.
+    pub fn record(&mut self, host_ns: u64, len: usize, paused: bool) {
+        let anchor = *self.anchor_ns.get_or_insert(host_ns);
+        if paused {
+            if let Some(segment) = self.current.take() {
+                self.done.push(segment);
+            }
+            return;
+        }
+
+        let start_ns = host_ns.saturating_sub(anchor);
+        let end_ns = start_ns + len as u64 * 1_000_000_000 / OUTPUT_RATE;
+        match &mut self.current {
+            Some(segment) => {
+                segment.end_ns = end_ns;
+                segment.sample_count += len as u64;
+            }
+            None => {
+                self.current = Some(Segment {
+                    start_ns,
+                    end_ns,
+                    sample_offset: self.samples_out,
+                    sample_count: len as u64,
+                })
+            }
+        }
+        self.samples_out += len as u64;
+    }
+
+    /// Samples delivered so far; the offset at which the next segment begins.
+    pub fn samples_out(&self) -> u64 {
+        self.samples_out
+    }
+
+    /// All segments, including the one still open.
+    pub fn segments(&self) -> Vec<Segment> {
+        let mut all = self.done.clone();
+        all.extend(self.current.clone());
+        all
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: u64 = 1_000_000;
+
+    #[test]
+    fn test_segments_split_on_pause() {
+        let mut t = SegmentTracker::default();
+        // 10ms buffers (160 samples) starting at an arbitrary host time
+        let base = 5_000 * MS;
+        for i in 0..10 {
+            t.record(base + i * 10 * MS, 160, false);
+        }
+        for i in 10..30 {
+            t.record(base + i * 10 * MS, 160, true);
+        }
+        for i in 30..35 {
+            t.record(base + i * 10 * MS, 160, false);
+        }
+
+        assert_eq!(
+            t.segments(),
+            vec![
+                Segment { start_ns: 0, end_ns: 100 * MS, sample_offset: 0, sample_count: 1600 },
+                Segment { start_ns: 300 * MS, end_ns: 350 * MS, sample_offset: 1600, sample_count: 800 },
+            ]
+        );
+        assert_eq!(t.samples_out(), 2400);
+    }
+}
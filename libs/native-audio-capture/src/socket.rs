@@ -0,0 +1,254 @@
+//! Socket sink: the 16kHz stream framed over a Unix or TCP socket
+//!
+//! Each chunk goes out as a fixed 32-byte little-endian header followed by
+//! its PCM payload (see README.md for the layout), so non-Node consumers
+//! can recover chunk boundaries, timing and gaps. Writes happen on a
+//! dedicated thread behind a bounded queue: a slow or stalled reader makes
+//! the sink drop frames (flagged on the next one) rather than block capture.
+
+use std::io::Write;
+use std::net::TcpStream;
+use std::os::unix::net::UnixStream;
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::logging::native_log;
+
+/// Frame magic, "VXTP"
+pub const FRAME_MAGIC: [u8; 4] = *b"VXTP";
+/// Current frame layout version
+pub const FRAME_VERSION: u8 = 1;
+/// Bytes before the payload
+pub const HEADER_LEN: usize = 32;
+/// Frames were dropped before this one
+pub const FLAG_DISCONTINUITY: u8 = 1 << 0;
+/// Last frame of the stream (no payload)
+pub const FLAG_END: u8 = 1 << 1;
+
+/// Output sample rate carried in every header
+const SAMPLE_RATE: u32 = 16000;
+/// Frames queued for the writer thread before new ones are dropped (~1.5s
+/// of typical 10ms chunks)
+const QUEUE_FRAMES: usize = 150;
+/// Longest one write may block on a reader that stopped reading; the
+/// connection is given up after that
+const WRITE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Header fields of one frame
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FrameHeader {
+    pub flags: u8,
+    pub seq: u32,
+    /// Host clock time of the chunk's first sample, in ns (0 if unknown)
+    pub host_time_ns: u64,
+    pub sample_count: u32,
+}
+
+impl FrameHeader {
+    pub fn encode(&self) -> [u8; HEADER_LEN] {
+        let mut out = [0u8; HEADER_LEN];
+        out[0..4].copy_from_slice(&FRAME_MAGIC);
+        out[4] = FRAME_VERSION;
+        out[5] = self.flags;
+        out[6..8].copy_from_slice(&(HEADER_LEN as u16).to_le_bytes());
+        out[8..12].copy_from_slice(&self.seq.to_le_bytes());
+        out[12..16].copy_from_slice(&SAMPLE_RATE.to_le_bytes());
+        out[16..24].copy_from_slice(&self.host_time_ns.to_le_bytes());
+        out[24..28].copy_from_slice(&self.sample_count.to_le_bytes());
+        out[28..30].copy_from_slice(&1u16.to_le_bytes());
+        // 30..32 reserved
+        out
+    }
+}
+
+/// Header plus little-endian Int16 payload.
+pub fn encode_frame(header: FrameHeader, samples: &[i16]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(HEADER_LEN + samples.len() * 2);
+    frame.extend_from_slice(&header.encode());
+    for &sample in samples {
+        frame.extend_from_slice(&sample.to_le_bytes());
+    }
+    frame
+}
+
+/// A connected socket sink. Sends an end frame and closes when dropped,
+/// without waiting on a stalled reader.
+pub struct SocketSink {
+    sender: Option<SyncSender<Vec<u8>>>,
+    thread: Option<JoinHandle<()>>,
+    seq: u32,
+    /// Set when a frame was dropped, until the next one goes out
+    discontinuity: bool,
+}
+
+impl SocketSink {
+    /// Connect to `target`: "unix:/path/to.sock" or "tcp:host:port".
+    pub fn connect(target: &str) -> Result<Self, String> {
+        let mut stream: Box<dyn Write + Send> = if let Some(path) = target.strip_prefix("unix:") {
+            let stream = UnixStream::connect(path).map_err(|e| format!("Failed to connect to {}: {}", target, e))?;
+            let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+            Box::new(stream)
+        } else if let Some(address) = target.strip_prefix("tcp:") {
+            let stream = TcpStream::connect(address).map_err(|e| format!("Failed to connect to {}: {}", target, e))?;
+            let _ = stream.set_nodelay(true);
+            let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+            Box::new(stream)
+        } else {
+            return Err(format!(
+                "Unsupported socket sink {}: use \"unix:/path\" or \"tcp:host:port\"",
+                target
+            ));
+        };
+
+        let (sender, receiver) = mpsc::sync_channel::<Vec<u8>>(QUEUE_FRAMES);
+        let target = target.to_string();
+        let thread = std::thread::spawn(move || {
+            for frame in receiver {
+                if let Err(e) = stream.write_all(&frame) {
+                    native_log!(Warn, "Socket sink {} closed: {}", target, e);
+                    return;
+                }
+            }
+        });
+        Ok(Self {
+            sender: Some(sender),
+            thread: Some(thread),
+            seq: 0,
+            discontinuity: false,
+        })
+    }
+
+    /// Queue one chunk. Returns false if it was dropped (queue full or the
+    /// connection gone).
+    pub fn push(&mut self, host_time_ns: u64, samples: &[i16]) -> bool {
+        let header = FrameHeader {
+            flags: if self.discontinuity { FLAG_DISCONTINUITY } else { 0 },
+            seq: self.seq,
+            host_time_ns,
+            sample_count: samples.len() as u32,
+        };
+        // Sequence numbers count dropped frames too, so gaps show downstream
+        self.seq = self.seq.wrapping_add(1);
+        let Some(sender) = &self.sender else {
+            return false;
+        };
+        match sender.try_send(encode_frame(header, samples)) {
+            Ok(()) => {
+                self.discontinuity = false;
+                true
+            }
+            Err(TrySendError::Full(_)) | Err(TrySendError::Disconnected(_)) => {
+                self.discontinuity = true;
+                false
+            }
+        }
+    }
+}
+
+impl Drop for SocketSink {
+    fn drop(&mut self) {
+        if let Some(sender) = self.sender.take() {
+            let end = FrameHeader {
+                flags: FLAG_END | if self.discontinuity { FLAG_DISCONTINUITY } else { 0 },
+                seq: self.seq,
+                host_time_ns: 0,
+                sample_count: 0,
+            };
+            if let Err(TrySendError::Full(_)) = sender.try_send(end.encode().to_vec()) {
+                // The reader has stalled: leave the writer to give up on
+                // its write timeout rather than wait for it
+                native_log!(Warn, "Socket sink reader stalled; closing without the end frame");
+                self.thread = None;
+            }
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::os::unix::net::UnixListener;
+
+    /// Reference parser, as a consumer would write it from the README
+    fn decode(bytes: &[u8]) -> Option<FrameHeader> {
+        if bytes.len() < HEADER_LEN || bytes[0..4] != FRAME_MAGIC || bytes[4] != FRAME_VERSION {
+            return None;
+        }
+        let u32_at = |i: usize| u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap());
+        Some(FrameHeader {
+            flags: bytes[5],
+            seq: u32_at(8),
+            host_time_ns: u64::from_le_bytes(bytes[16..24].try_into().unwrap()),
+            sample_count: u32_at(24),
+        })
+    }
+
+    #[test]
+    fn test_header_layout() {
+        let header = FrameHeader {
+            flags: FLAG_DISCONTINUITY,
+            seq: 7,
+            host_time_ns: 0x0102_0304_0506_0708,
+            sample_count: 160,
+        };
+        let bytes = header.encode();
+        assert_eq!(&bytes[0..4], b"VXTP");
+        assert_eq!(bytes[4], 1);
+        assert_eq!(bytes[5], FLAG_DISCONTINUITY);
+        assert_eq!(u16::from_le_bytes([bytes[6], bytes[7]]), 32);
+        assert_eq!(u32::from_le_bytes(bytes[12..16].try_into().unwrap()), 16000);
+        assert_eq!(u16::from_le_bytes([bytes[28], bytes[29]]), 1);
+        assert_eq!(decode(&bytes), Some(header));
+        assert_eq!(decode(&bytes[..31]), None);
+    }
+
+    #[test]
+    fn test_frames_arrive_in_order_with_end_marker() {
+        let path = std::env::temp_dir().join(format!("voxtape-socket-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let mut sink = SocketSink::connect(&format!("unix:{}", path.display())).unwrap();
+        let (mut reader, _) = listener.accept().unwrap();
+        assert!(sink.push(1_000, &[1, -2, 3]));
+        assert!(sink.push(2_000, &[4]));
+        drop(sink);
+
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        let first = decode(&bytes).unwrap();
+        assert_eq!((first.seq, first.host_time_ns, first.sample_count), (0, 1_000, 3));
+        assert_eq!(&bytes[HEADER_LEN..HEADER_LEN + 6], &[1, 0, 0xfe, 0xff, 3, 0]);
+        let second = decode(&bytes[HEADER_LEN + 6..]).unwrap();
+        assert_eq!((second.seq, second.sample_count), (1, 1));
+        let end = decode(&bytes[2 * HEADER_LEN + 8..]).unwrap();
+        assert_eq!((end.seq, end.flags, end.sample_count), (2, FLAG_END, 0));
+        assert_eq!(bytes.len(), 3 * HEADER_LEN + 8);
+    }
+
+    #[test]
+    fn test_stalled_reader_does_not_block_drop() {
+        let path = std::env::temp_dir().join(format!("voxtape-socket-stalled-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let mut sink = SocketSink::connect(&format!("unix:{}", path.display())).unwrap();
+        // Accepted but never read, so the socket buffer and then the queue fill
+        let (_reader, _) = listener.accept().unwrap();
+        let chunk = vec![0i16; 4800];
+        let delivered = (0..QUEUE_FRAMES * 4).filter(|_| sink.push(0, &chunk)).count();
+        assert!(delivered < QUEUE_FRAMES * 4);
+
+        let started = std::time::Instant::now();
+        drop(sink);
+        let _ = std::fs::remove_file(&path);
+        assert!(started.elapsed() < WRITE_TIMEOUT, "drop took {:?}", started.elapsed());
+    }
+}
@@ -0,0 +1,114 @@
+//! Test tone routed through the real delivery path
+//!
+//! Synthesizes a sine in the shape SCK delivers (48kHz interleaved stereo,
+//! 10ms chunks), runs it through the same `Resampler` and Buffer conversion
+//! as live capture, and hands the result to the callback. Lets consumers
+//! check their decoding end to end without any system audio or permissions.
+
+use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use napi_derive::napi;
+
+use crate::pool::PooledBuffer;
+use crate::resampler::Resampler;
+
+/// Source format, matching what SCK delivers
+const SOURCE_RATE: u32 = 48000;
+const SOURCE_CHANNELS: u32 = 2;
+/// Frames per source chunk (10ms)
+const CHUNK_FRAMES: usize = 480;
+/// Peak level of the tone (-6 dBFS)
+const AMPLITUDE: f64 = 0.5;
+/// Longest tone `emit_test_tone` will synthesize
+const MAX_DURATION_MS: u32 = 60_000;
+
+/// `duration_ms` of a sine at `freq_hz` as interleaved stereo source chunks.
+fn tone_chunks(freq_hz: f64, duration_ms: u32) -> Vec<Vec<f32>> {
+    let total_frames = SOURCE_RATE as usize * duration_ms as usize / 1000;
+    let step = 2.0 * std::f64::consts::PI * freq_hz / SOURCE_RATE as f64;
+    (0..total_frames)
+        .step_by(CHUNK_FRAMES)
+        .map(|start| {
+            let end = (start + CHUNK_FRAMES).min(total_frames);
+            (start..end)
+                .flat_map(|n| {
+                    let sample = (AMPLITUDE * (step * n as f64).sin()) as f32;
+                    [sample; SOURCE_CHANNELS as usize]
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Deliver a `duration_ms` sine at `freq_hz` to `callback` as 16kHz mono
+/// Int16 Buffers, exactly as a capture session would. Independent of any
+/// capture session. Returns the number of samples queued for delivery.
+#[napi]
+pub fn emit_test_tone(
+    callback: ThreadsafeFunction<PooledBuffer>,
+    freq_hz: f64,
+    duration_ms: u32,
+) -> Result<u32> {
+    if !(freq_hz > 0.0 && freq_hz < SOURCE_RATE as f64 / 2.0) {
+        return Err(Error::from_reason(format!(
+            "freqHz must be between 0 and {} Hz",
+            SOURCE_RATE / 2
+        )));
+    }
+    if duration_ms == 0 || duration_ms > MAX_DURATION_MS {
+        return Err(Error::from_reason(format!(
+            "durationMs must be between 1 and {}",
+            MAX_DURATION_MS
+        )));
+    }
+
+    let mut resampler = Resampler::new();
+    let mut delivered = 0u32;
+    for chunk in tone_chunks(freq_hz, duration_ms) {
+        let samples = resampler.process(&chunk, SOURCE_CHANNELS, SOURCE_RATE);
+        if samples.is_empty() {
+            continue;
+        }
+        delivered += samples.len() as u32;
+        callback.call(
+            Ok(PooledBuffer::from_samples(&samples)),
+            ThreadsafeFunctionCallMode::NonBlocking,
+        );
+    }
+    Ok(delivered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tone_chunks_match_sck_shape() {
+        let chunks = tone_chunks(440.0, 25);
+        // 1200 frames: two full 10ms chunks and a 5ms remainder
+        let lengths: Vec<usize> = chunks.iter().map(Vec::len).collect();
+        assert_eq!(lengths, [960, 960, 480]);
+        // Both channels carry the same signal
+        assert!(chunks[0].chunks(2).all(|frame| frame[0] == frame[1]));
+    }
+
+    #[test]
+    fn test_tone_survives_resampling() {
+        let mut resampler = Resampler::new();
+        let output: Vec<i16> = tone_chunks(1000.0, 500)
+            .iter()
+            .flat_map(|chunk| resampler.process(chunk, SOURCE_CHANNELS, SOURCE_RATE))
+            .collect();
+        assert_eq!(output.len(), 8000);
+
+        // Skip the filter's settling time; 7000 samples of 1kHz cross zero 875 times
+        let settled = &output[1000..];
+        let crossings = settled.windows(2).filter(|w| (w[0] < 0) != (w[1] < 0)).count();
+        assert!(crossings.abs_diff(875) <= 2, "{} zero crossings", crossings);
+
+        // Within 3dB of the source level (the anti-alias filter droops a little)
+        let peak = settled.iter().map(|s| s.unsigned_abs()).max().unwrap() as f64;
+        let expected = AMPLITUDE * 32767.0;
+        assert!(peak <= expected * 1.01 && peak > expected * 0.7, "peak {}", peak);
+    }
+}
@@ -0,0 +1,180 @@
+//! Per-app tracks: one labeled 16kHz stream per application
+//!
+//! ScreenCaptureKit mixes everything a content filter matches into a single
+//! audio stream, so separating apps takes one SCStream per app. Tracks run
+//! independently of `start_capture` (both can be active at once) and cost one
+//! stream each. Apps must be running when tracks start; an app launched later
+//! isn't picked up, and audio an app routes through a helper process with a
+//! different bundle id (some browsers and Electron apps) may be missed.
+
+use std::ffi::{c_void, CString};
+use std::os::raw::c_char;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use napi_derive::napi;
+
+use crate::logging::native_log;
+use crate::resampler::Resampler;
+use crate::{channel_layout, checked_sample_count, samples_to_buffer, too_many_captures_error, SckAudioCallback};
+
+/// Most tracks that run at once. Each is a full SCStream with its own
+/// resampler, so this is kept conservative
+const MAX_APP_TRACKS: usize = 8;
+
+extern "C" {
+    fn voxtape_sck_track_start(
+        bundle_id: *const c_char,
+        callback: SckAudioCallback,
+        user_data: *mut c_void,
+        status: *mut i32,
+    ) -> *mut c_void;
+    fn voxtape_sck_track_stop(handle: *mut c_void);
+}
+
+/// A chunk of one app's audio
+#[napi(object)]
+pub struct TrackChunk {
+    /// Bundle identifier of the app the audio came from
+    pub bundle_id: String,
+    /// 16kHz mono Int16 PCM
+    pub samples: Buffer,
+}
+
+/// Per-track state handed to the SCK callback as user_data
+struct TrackContext {
+    bundle_id: String,
+    resampler: Mutex<Resampler>,
+    callback: Arc<ThreadsafeFunction<TrackChunk>>,
+}
+
+struct Track {
+    handle: *mut c_void,
+    /// Boxed so the user_data pointer stays put; freed after the stream stops
+    context: Box<TrackContext>,
+}
+
+unsafe impl Send for Track {}
+
+static TRACKS: OnceLock<Mutex<Vec<Track>>> = OnceLock::new();
+
+fn tracks_mutex() -> &'static Mutex<Vec<Track>> {
+    TRACKS.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+unsafe extern "C" fn track_audio_callback(
+    data: *const f32,
+    data_len: u32,
+    frame_count: u32,
+    channels: u32,
+    channel_labels: *const u32,
+    sample_rate: u32,
+    _host_time: u64,
+    user_data: *mut c_void,
+) {
+    if data.is_null() || user_data.is_null() || frame_count == 0 || channels == 0 {
+        return;
+    }
+    let ctx = &*(user_data as *const TrackContext);
+    let Some(total_samples) = checked_sample_count(frame_count, channels, data_len) else {
+        return;
+    };
+    let input = std::slice::from_raw_parts(data, total_samples);
+    let samples = match ctx.resampler.lock() {
+        Ok(mut resampler) => {
+            resampler.set_channel_layout(channel_layout(channel_labels, channels));
+            resampler.process(input, channels, sample_rate)
+        }
+        Err(_) => return,
+    };
+    if samples.is_empty() {
+        return;
+    }
+    ctx.callback.call(
+        Ok(TrackChunk {
+            bundle_id: ctx.bundle_id.clone(),
+            samples: samples_to_buffer(&samples),
+        }),
+        ThreadsafeFunctionCallMode::NonBlocking,
+    );
+}
+
+/// Capture each app in `bundle_ids` as its own track. `callback` receives
+/// chunks labeled with the app they came from. Apps that aren't running are
+/// skipped; returns the bundle ids that did start. Call `stop_app_tracks` to end.
+/// At most 8 apps; more fail with an error starting with `TooManyCaptures:`.
+#[napi]
+pub fn start_app_tracks(bundle_ids: Vec<String>, callback: ThreadsafeFunction<TrackChunk>) -> Result<Vec<String>> {
+    let mut tracks = tracks_mutex()
+        .lock()
+        .map_err(|e| Error::from_reason(format!("Failed to acquire tracks lock: {}", e)))?;
+    if !tracks.is_empty() {
+        return Err(Error::from_reason("App tracks are already running"));
+    }
+    if bundle_ids.len() > MAX_APP_TRACKS {
+        return Err(too_many_captures_error(&format!(
+            "{} app tracks requested; at most {} run at once",
+            bundle_ids.len(),
+            MAX_APP_TRACKS
+        )));
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = (bundle_ids, callback);
+        return Err(Error::from_reason("App tracks are only supported on macOS 14.2+"));
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        if !crate::has_screen_capture_access() {
+            return Err(crate::permission_denied_error());
+        }
+
+        let callback = Arc::new(callback);
+        let mut started = Vec::new();
+        for bundle_id in bundle_ids {
+            let c_id = CString::new(bundle_id.as_str())
+                .map_err(|_| Error::from_reason("Bundle id contains a NUL byte"))?;
+            let context = Box::new(TrackContext {
+                bundle_id: bundle_id.clone(),
+                resampler: Mutex::new(Resampler::new()),
+                callback: Arc::clone(&callback),
+            });
+            let user_data = &*context as *const TrackContext as *mut c_void;
+
+            let mut status = 0;
+            let handle = unsafe {
+                voxtape_sck_track_start(c_id.as_ptr(), track_audio_callback, user_data, &mut status)
+            };
+            if handle.is_null() {
+                native_log!(Warn, "Track for {} not started (code {})", bundle_id, status);
+                continue;
+            }
+            native_log!(Info, "Track started for {}", bundle_id);
+            tracks.push(Track { handle, context });
+            started.push(bundle_id);
+        }
+        Ok(started)
+    }
+}
+
+/// Stop all app tracks.
+#[napi]
+pub fn stop_app_tracks() -> Result<()> {
+    let tracks = std::mem::take(
+        &mut *tracks_mutex()
+            .lock()
+            .map_err(|e| Error::from_reason(format!("Failed to acquire tracks lock: {}", e)))?,
+    );
+    for track in tracks {
+        #[cfg(target_os = "macos")]
+        unsafe {
+            voxtape_sck_track_stop(track.handle);
+        }
+        // The stream no longer calls into the context once stopped
+        drop(track.context);
+    }
+    Ok(())
+}
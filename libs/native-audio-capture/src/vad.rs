@@ -0,0 +1,212 @@
+//! Voice activity detection on the 16kHz output
+//!
+//! Each 20ms frame is classed as speech when it's loud enough and its
+//! zero-crossing rate is below that of broadband noise (hiss, fans, rain
+//! crosses zero on about half of all samples; voiced speech far less).
+//! Speech must persist for `min_speech_ms` before a speech span opens, and
+//! the span only closes after `hangover_ms` of non-speech, so pauses
+//! between words and unvoiced consonants stay inside one span. The stream
+//! is labeled as alternating speech and non-speech spans, each reported
+//! once it's closed.
+
+/// Samples per analysis frame (20ms at 16kHz)
+const FRAME_LEN: usize = 320;
+/// Zero crossings per sample above which a frame counts as noise
+const MAX_SPEECH_ZCR: f64 = 0.35;
+
+/// One labeled run of the stream, in samples of the delivered 16kHz output.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VadSpan {
+    pub is_speech: bool,
+    pub start_sample: u64,
+    pub end_sample: u64,
+}
+
+pub struct Vad {
+    /// Linear RMS threshold on the Int16 scale
+    threshold: f64,
+    min_speech_frames: usize,
+    hangover_frames: usize,
+    /// Samples waiting to fill a frame
+    pending: Vec<i16>,
+    /// Samples analyzed so far
+    position: u64,
+    speaking: bool,
+    /// Start of the open span
+    span_start: u64,
+    /// Consecutive speech frames while not speaking, and where they began
+    onset_frames: usize,
+    onset_start: u64,
+    /// Consecutive non-speech frames while speaking
+    quiet_frames: usize,
+    /// End of the last speech frame
+    speech_end: u64,
+}
+
+impl Vad {
+    /// - `threshold_db`: level in dBFS below which a frame is never speech
+    /// - `min_speech_ms`: sustained speech required to open a speech span
+    /// - `hangover_ms`: sustained non-speech required to close it
+    pub fn new(threshold_db: f64, min_speech_ms: u32, hangover_ms: u32) -> Self {
+        let frame_ms = FRAME_LEN as u32 / 16;
+        Self {
+            threshold: 32768.0 * 10f64.powf(threshold_db / 20.0),
+            min_speech_frames: min_speech_ms.div_ceil(frame_ms).max(1) as usize,
+            hangover_frames: hangover_ms.div_ceil(frame_ms).max(1) as usize,
+            pending: Vec::with_capacity(FRAME_LEN),
+            position: 0,
+            speaking: false,
+            span_start: 0,
+            onset_frames: 0,
+            onset_start: 0,
+            quiet_frames: 0,
+            speech_end: 0,
+        }
+    }
+
+    /// Analyze a chunk, returning the spans it closed.
+    pub fn push(&mut self, samples: &[i16]) -> Vec<VadSpan> {
+        let mut closed = Vec::new();
+        let mut samples = samples;
+        while !samples.is_empty() {
+            let take = (FRAME_LEN - self.pending.len()).min(samples.len());
+            self.pending.extend_from_slice(&samples[..take]);
+            samples = &samples[take..];
+            if self.pending.len() == FRAME_LEN {
+                let speech = is_speech_frame(&self.pending, self.threshold);
+                self.pending.clear();
+                self.update(speech, &mut closed);
+            }
+        }
+        closed
+    }
+
+    /// Close the open span at the end of the stream, if it has any length.
+    pub fn finish(&mut self) -> Option<VadSpan> {
+        let end = self.position + self.pending.len() as u64;
+        self.pending.clear();
+        self.position = end;
+        let span = (end > self.span_start).then_some(VadSpan {
+            is_speech: self.speaking,
+            start_sample: self.span_start,
+            end_sample: end,
+        });
+        self.span_start = end;
+        span
+    }
+
+    /// Advance the state machine by one frame.
+    fn update(&mut self, speech: bool, closed: &mut Vec<VadSpan>) {
+        let frame_start = self.position;
+        self.position += FRAME_LEN as u64;
+
+        if self.speaking {
+            if speech {
+                self.quiet_frames = 0;
+                self.speech_end = self.position;
+                return;
+            }
+            self.quiet_frames += 1;
+            if self.quiet_frames >= self.hangover_frames {
+                // The trailing quiet belongs to the next span
+                self.close(self.speech_end, closed);
+                self.speaking = false;
+                self.quiet_frames = 0;
+            }
+        } else if speech {
+            if self.onset_frames == 0 {
+                self.onset_start = frame_start;
+            }
+            self.onset_frames += 1;
+            if self.onset_frames >= self.min_speech_frames {
+                self.close(self.onset_start, closed);
+                self.speaking = true;
+                self.onset_frames = 0;
+                self.speech_end = self.position;
+            }
+        } else {
+            self.onset_frames = 0;
+        }
+    }
+
+    /// Report the open span as ending at `end` and start the next one there.
+    fn close(&mut self, end: u64, closed: &mut Vec<VadSpan>) {
+        if end > self.span_start {
+            closed.push(VadSpan {
+                is_speech: self.speaking,
+                start_sample: self.span_start,
+                end_sample: end,
+            });
+        }
+        self.span_start = end;
+    }
+}
+
+fn is_speech_frame(frame: &[i16], threshold: f64) -> bool {
+    let sum: f64 = frame.iter().map(|&s| (s as f64) * (s as f64)).sum();
+    if (sum / frame.len() as f64).sqrt() < threshold {
+        return false;
+    }
+    let crossings = frame.windows(2).filter(|w| (w[0] < 0) != (w[1] < 0)).count();
+    (crossings as f64 / frame.len() as f64) < MAX_SPEECH_ZCR
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(ms: usize) -> Vec<i16> {
+        (0..ms * 16)
+            .map(|i| (8000.0 * (2.0 * std::f64::consts::PI * 200.0 * i as f64 / 16000.0).sin()) as i16)
+            .collect()
+    }
+
+    fn silence(ms: usize) -> Vec<i16> {
+        vec![0; ms * 16]
+    }
+
+    #[test]
+    fn test_labels_speech_between_silence() {
+        let mut vad = Vad::new(-40.0, 60, 200);
+        let mut spans = vad.push(&silence(500));
+        spans.extend(vad.push(&tone(1000)));
+        spans.extend(vad.push(&silence(1000)));
+        spans.extend(vad.finish());
+
+        let ms: Vec<(bool, u64, u64)> = spans
+            .iter()
+            .map(|s| (s.is_speech, s.start_sample / 16, s.end_sample / 16))
+            .collect();
+        assert_eq!(ms, [(false, 0, 500), (true, 500, 1500), (false, 1500, 2500)]);
+    }
+
+    #[test]
+    fn test_short_pauses_and_blips_are_absorbed() {
+        let mut vad = Vad::new(-40.0, 60, 200);
+        let mut input = silence(200);
+        // A 40ms click is shorter than min_speech_ms
+        input.extend(tone(40));
+        input.extend(silence(200));
+        input.extend(tone(400));
+        // A 100ms pause is shorter than the hangover
+        input.extend(silence(100));
+        input.extend(tone(400));
+        input.extend(silence(400));
+        let spans = vad.push(&input);
+
+        let speech: Vec<&VadSpan> = spans.iter().filter(|s| s.is_speech).collect();
+        assert_eq!(speech.len(), 1);
+        assert_eq!((speech[0].start_sample / 16, speech[0].end_sample / 16), (440, 1340));
+    }
+
+    #[test]
+    fn test_noise_is_not_speech() {
+        // Loud alternating-sign noise crosses zero on every sample
+        let noise: Vec<i16> = (0..16000).map(|i| if i % 2 == 0 { 8000 } else { -8000 }).collect();
+        let mut vad = Vad::new(-40.0, 60, 200);
+        assert!(vad.push(&noise).is_empty());
+        let span = vad.finish().unwrap();
+        assert!(!span.is_speech);
+        assert_eq!((span.start_sample, span.end_sample), (0, 16000));
+    }
+}
@@ -0,0 +1,393 @@
+//! WAV file sink: 16kHz mono Int16 PCM
+//!
+//! Writes a placeholder RIFF header up front and patches the chunk sizes
+//! when finalized, so the file is valid even for open-ended captures.
+//! Recording metadata goes in a LIST/INFO chunk ahead of the samples.
+//! `SegmentedWavWriter` rotates to a new numbered file every N samples, so a
+//! long recording is a set of independently playable files and a crash
+//! loses at most the open one.
+
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::logging::native_log;
+use crate::RecordingMetadata;
+
+const SAMPLE_RATE: u32 = 16000;
+const CHANNELS: u16 = 1;
+const BITS_PER_SAMPLE: u16 = 16;
+/// Header length without a LIST chunk
+const HEADER_LEN: u32 = 44;
+
+/// An open WAV file. Finalized when dropped.
+pub struct WavWriter {
+    file: BufWriter<File>,
+    /// LIST/INFO chunk (empty without metadata), rewritten with the header
+    info: Vec<u8>,
+    samples_written: u64,
+    finalized: bool,
+}
+
+impl WavWriter {
+    /// Create (or truncate) `path` and write the header, tagged with `metadata`.
+    pub fn create(path: &str, metadata: Option<&RecordingMetadata>) -> std::io::Result<Self> {
+        let mut file = BufWriter::new(File::create(path)?);
+        let info = metadata.map(info_chunk).unwrap_or_default();
+        write_header(&mut file, &info, 0)?;
+        Ok(Self {
+            file,
+            info,
+            samples_written: 0,
+            finalized: false,
+        })
+    }
+
+    /// Append samples.
+    pub fn write(&mut self, samples: &[i16]) -> std::io::Result<()> {
+        for &sample in samples {
+            self.file.write_all(&sample.to_le_bytes())?;
+        }
+        self.samples_written += samples.len() as u64;
+        Ok(())
+    }
+
+    /// Number of samples written so far.
+    pub fn samples_written(&self) -> u64 {
+        self.samples_written
+    }
+
+    /// Patch the header sizes and flush to disk.
+    pub fn finalize(&mut self) -> std::io::Result<()> {
+        if self.finalized {
+            return Ok(());
+        }
+        self.finalized = true;
+        let header_len = HEADER_LEN + self.info.len() as u32;
+        let data_len = (self.samples_written * 2).min((u32::MAX - header_len) as u64) as u32;
+        self.file.seek(SeekFrom::Start(0))?;
+        write_header(&mut self.file, &self.info, data_len)?;
+        self.file.seek(SeekFrom::End(0))?;
+        self.file.flush()
+    }
+}
+
+impl Drop for WavWriter {
+    fn drop(&mut self) {
+        if let Err(e) = self.finalize() {
+            native_log!(Error, "Failed to finalize WAV file: {}", e);
+        }
+    }
+}
+
+/// A WAV sink rotating to `<stem>-001.wav`, `<stem>-002.wav`, ... next to
+/// the given path every `segment_samples` samples. Each file is finalized
+/// as soon as it's full; the open one when finalized or dropped.
+pub struct SegmentedWavWriter {
+    base: String,
+    /// Written into every segment
+    metadata: Option<RecordingMetadata>,
+    segment_samples: u64,
+    index: u32,
+    current: WavWriter,
+    /// Samples in finished segments
+    previous_samples: u64,
+}
+
+impl SegmentedWavWriter {
+    /// Create the first segment for `path`.
+    pub fn create(
+        path: &str,
+        segment_samples: u64,
+        metadata: Option<&RecordingMetadata>,
+    ) -> std::io::Result<Self> {
+        let current = WavWriter::create(&segment_path(path, 1), metadata)?;
+        Ok(Self {
+            base: path.to_string(),
+            metadata: metadata.cloned(),
+            segment_samples: segment_samples.max(1),
+            index: 1,
+            current,
+            previous_samples: 0,
+        })
+    }
+
+    /// Append samples, rotating whenever the open segment fills up.
+    pub fn write(&mut self, mut samples: &[i16]) -> std::io::Result<()> {
+        while !samples.is_empty() {
+            let room = self.segment_samples - self.current.samples_written();
+            if room == 0 {
+                self.rotate()?;
+                continue;
+            }
+            let take = samples.len().min(room as usize);
+            self.current.write(&samples[..take])?;
+            samples = &samples[take..];
+        }
+        Ok(())
+    }
+
+    /// Number of samples written so far, across all segments.
+    pub fn samples_written(&self) -> u64 {
+        self.previous_samples + self.current.samples_written()
+    }
+
+    /// Patch the open segment's header and flush it to disk.
+    pub fn finalize(&mut self) -> std::io::Result<()> {
+        self.current.finalize()
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.current.finalize()?;
+        self.previous_samples += self.current.samples_written();
+        self.index += 1;
+        // Dropping the old writer is a no-op: it's already finalized
+        self.current = WavWriter::create(&segment_path(&self.base, self.index), self.metadata.as_ref())?;
+        Ok(())
+    }
+}
+
+/// Either kind of WAV output a capture can write.
+pub enum WavSink {
+    Single(WavWriter),
+    Segmented(SegmentedWavWriter),
+}
+
+impl WavSink {
+    pub fn write(&mut self, samples: &[i16]) -> std::io::Result<()> {
+        match self {
+            WavSink::Single(w) => w.write(samples),
+            WavSink::Segmented(w) => w.write(samples),
+        }
+    }
+
+    pub fn samples_written(&self) -> u64 {
+        match self {
+            WavSink::Single(w) => w.samples_written(),
+            WavSink::Segmented(w) => w.samples_written(),
+        }
+    }
+
+    pub fn finalize(&mut self) -> std::io::Result<()> {
+        match self {
+            WavSink::Single(w) => w.finalize(),
+            WavSink::Segmented(w) => w.finalize(),
+        }
+    }
+}
+
+/// Recording files created for a capture that hasn't started yet. Dropped
+/// without `keep`, it deletes them, so a start that fails doesn't leave
+/// header-only files behind.
+pub struct PendingFiles {
+    paths: Vec<String>,
+}
+
+impl PendingFiles {
+    pub fn new() -> Self {
+        Self { paths: Vec::new() }
+    }
+
+    /// Delete `path` on drop; call once the file has been created.
+    pub fn add(&mut self, path: String) {
+        self.paths.push(path);
+    }
+
+    /// The capture started: leave the files be.
+    pub fn keep(mut self) {
+        self.paths.clear();
+    }
+}
+
+impl Drop for PendingFiles {
+    fn drop(&mut self) {
+        for path in &self.paths {
+            match std::fs::remove_file(path) {
+                Ok(()) => native_log!(Info, "Removed {} after the capture failed to start", path),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => native_log!(Warn, "Failed to remove {}: {}", path, e),
+            }
+        }
+    }
+}
+
+/// `dir/meeting.wav` → `dir/meeting-003.wav` for segment 3.
+pub fn segment_path(path: &str, index: u32) -> String {
+    let path = Path::new(path);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("segment");
+    let name = match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) => format!("{}-{:03}.{}", stem, index, ext),
+        None => format!("{}-{:03}.wav", stem, index),
+    };
+    path.with_file_name(name).to_string_lossy().into_owned()
+}
+
+/// LIST/INFO chunk holding whichever of `metadata`'s tags are set, or
+/// nothing if none are.
+fn info_chunk(metadata: &RecordingMetadata) -> Vec<u8> {
+    let tags = [
+        (b"INAM", &metadata.title),
+        (b"IART", &metadata.artist),
+        (b"ICMT", &metadata.comment),
+    ];
+    let mut body = Vec::new();
+    for (id, value) in tags {
+        let Some(value) = value.as_deref().filter(|v| !v.is_empty()) else {
+            continue;
+        };
+        // NUL-terminated, padded to an even length
+        let len = value.len() + 1;
+        body.extend_from_slice(id);
+        body.extend_from_slice(&(len as u32).to_le_bytes());
+        body.extend_from_slice(value.as_bytes());
+        body.push(0);
+        if len % 2 == 1 {
+            body.push(0);
+        }
+    }
+    if body.is_empty() {
+        return body;
+    }
+    let mut chunk = Vec::with_capacity(body.len() + 12);
+    chunk.extend_from_slice(b"LIST");
+    chunk.extend_from_slice(&(body.len() as u32 + 4).to_le_bytes());
+    chunk.extend_from_slice(b"INFO");
+    chunk.extend_from_slice(&body);
+    chunk
+}
+
+fn write_header<W: Write>(w: &mut W, info: &[u8], data_len: u32) -> std::io::Result<()> {
+    let block_align = CHANNELS * BITS_PER_SAMPLE / 8;
+    let byte_rate = SAMPLE_RATE * block_align as u32;
+
+    w.write_all(b"RIFF")?;
+    w.write_all(&(HEADER_LEN - 8 + info.len() as u32 + data_len).to_le_bytes())?;
+    w.write_all(b"WAVE")?;
+    w.write_all(b"fmt ")?;
+    w.write_all(&16u32.to_le_bytes())?;
+    w.write_all(&1u16.to_le_bytes())?; // PCM
+    w.write_all(&CHANNELS.to_le_bytes())?;
+    w.write_all(&SAMPLE_RATE.to_le_bytes())?;
+    w.write_all(&byte_rate.to_le_bytes())?;
+    w.write_all(&block_align.to_le_bytes())?;
+    w.write_all(&BITS_PER_SAMPLE.to_le_bytes())?;
+    w.write_all(info)?;
+    w.write_all(b"data")?;
+    w.write_all(&data_len.to_le_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_sizes_patched_on_finalize() {
+        let path = std::env::temp_dir().join(format!("voxtape-wav-test-{}.wav", std::process::id()));
+        let path_str = path.to_str().unwrap();
+        {
+            let mut w = WavWriter::create(path_str, None).unwrap();
+            w.write(&[1, -1, 2, -2]).unwrap();
+            w.write(&[3]).unwrap();
+            assert_eq!(w.samples_written(), 5);
+        }
+
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(bytes.len(), 44 + 10);
+        assert_eq!(&bytes[0..4], b"RIFF");
+        assert_eq!(u32::from_le_bytes(bytes[4..8].try_into().unwrap()), 36 + 10);
+        assert_eq!(u32::from_le_bytes(bytes[24..28].try_into().unwrap()), 16000);
+        assert_eq!(u32::from_le_bytes(bytes[40..44].try_into().unwrap()), 10);
+        assert_eq!(i16::from_le_bytes([bytes[46], bytes[47]]), -1);
+    }
+
+    #[test]
+    fn test_segment_paths_are_numbered() {
+        assert_eq!(segment_path("/tmp/meeting.wav", 1), "/tmp/meeting-001.wav");
+        assert_eq!(segment_path("/tmp/meeting.wav", 12), "/tmp/meeting-012.wav");
+        assert_eq!(segment_path("meeting", 2), "meeting-002.wav");
+    }
+
+    #[test]
+    fn test_segments_rotate_and_finalize() {
+        let dir = std::env::temp_dir();
+        let base = dir.join(format!("voxtape-seg-test-{}.wav", std::process::id()));
+        let base_str = base.to_str().unwrap();
+        {
+            let mut w = SegmentedWavWriter::create(base_str, 4, None).unwrap();
+            w.write(&[1, 2, 3]).unwrap();
+            w.write(&[4, 5, 6, 7, 8, 9]).unwrap();
+            assert_eq!(w.samples_written(), 9);
+        }
+
+        let data_lens: Vec<u32> = (1..=3)
+            .map(|i| {
+                let path = segment_path(base_str, i);
+                let bytes = std::fs::read(&path).unwrap();
+                std::fs::remove_file(&path).unwrap();
+                assert_eq!(bytes.len() as u32, 44 + u32::from_le_bytes(bytes[40..44].try_into().unwrap()));
+                u32::from_le_bytes(bytes[40..44].try_into().unwrap())
+            })
+            .collect();
+        // 4 + 4 + 1 samples, two bytes each
+        assert_eq!(data_lens, [8, 8, 2]);
+        assert!(!Path::new(&segment_path(base_str, 4)).exists());
+    }
+
+    #[test]
+    fn test_metadata_written_as_info_chunk() {
+        let path = std::env::temp_dir().join(format!("voxtape-wav-info-{}.wav", std::process::id()));
+        let path_str = path.to_str().unwrap();
+        let metadata = RecordingMetadata {
+            title: Some("Standup".to_string()),
+            artist: None,
+            comment: Some("Zoom".to_string()),
+        };
+        {
+            let mut w = WavWriter::create(path_str, Some(&metadata)).unwrap();
+            w.write(&[7, -7]).unwrap();
+        }
+
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // LIST + size + INFO, then INAM "Standup\0" and ICMT "Zoom\0" + pad
+        let list_len = 12 + (8 + 8) + (8 + 6);
+        assert_eq!(&bytes[36..40], b"LIST");
+        assert_eq!(u32::from_le_bytes(bytes[40..44].try_into().unwrap()), list_len - 8);
+        assert_eq!(&bytes[44..48], b"INFO");
+        assert_eq!(&bytes[48..52], b"INAM");
+        assert_eq!(&bytes[56..64], b"Standup\0");
+        assert_eq!(&bytes[64..68], b"ICMT");
+        assert_eq!(&bytes[72..77], b"Zoom\0");
+
+        let data = 36 + list_len as usize;
+        assert_eq!(&bytes[data..data + 4], b"data");
+        assert_eq!(u32::from_le_bytes(bytes[data + 4..data + 8].try_into().unwrap()), 4);
+        assert_eq!(bytes.len(), data + 8 + 4);
+        assert_eq!(u32::from_le_bytes(bytes[4..8].try_into().unwrap()) as usize, bytes.len() - 8);
+    }
+
+    #[test]
+    fn test_pending_files_removed_unless_kept() {
+        let dir = std::env::temp_dir();
+        let failed = dir.join(format!("voxtape-pending-failed-{}.wav", std::process::id()));
+        let started = dir.join(format!("voxtape-pending-started-{}.wav", std::process::id()));
+        for path in [&failed, &started] {
+            let path = path.to_str().unwrap();
+            let mut pending = PendingFiles::new();
+            drop(WavWriter::create(path, None).unwrap());
+            pending.add(path.to_string());
+            // A missing file is no error
+            pending.add(format!("{}.missing", path));
+            if path.contains("started") {
+                pending.keep();
+            }
+        }
+
+        assert!(!failed.exists());
+        assert!(started.exists());
+        std::fs::remove_file(&started).unwrap();
+    }
+}